@@ -0,0 +1,87 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+use tilted::{
+    ast::{evaluate_batch, Column},
+    Environment, Lexer, NodeBox, Number, Parser, Program, Vm,
+};
+
+const SOURCES: [&str; 3] = ["a*x^2 + b*x + c", "sin(x)^2 + cos(x)^2", "x / (1 + x*x)"];
+const ROWS: usize = 4096;
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn bench_batch(criterion: &mut Criterion) {
+    let x = (0..ROWS).map(|i| i as f64 / 100.0).collect::<Vec<_>>();
+    let a = vec![2.0; ROWS];
+    let b = vec![-3.0; ROWS];
+    let c = vec![0.5; ROWS];
+    let columns = [
+        ("a", Column::from(&a[..])),
+        ("b", Column::from(&b[..])),
+        ("c", Column::from(&c[..])),
+        ("x", Column::from(&x[..])),
+    ];
+    let mut output = vec![Number::Int(0); ROWS];
+
+    let mut group = criterion.benchmark_group("batch");
+    for source in SOURCES {
+        let node = parse(source);
+        let program = Program::compile(node.as_ref());
+
+        group.bench_with_input(
+            BenchmarkId::new("evaluate_batch", source),
+            &node,
+            |bench, node| {
+                bench.iter(|| {
+                    evaluate_batch(node.as_ref(), black_box(&columns), &mut output).unwrap()
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("evaluate_with", source),
+            &node,
+            |bench, node| {
+                bench.iter(|| {
+                    for (i, output) in output.iter_mut().enumerate() {
+                        let env = Environment::new()
+                            .with("a", a[i])
+                            .with("b", b[i])
+                            .with("c", c[i])
+                            .with("x", black_box(x[i]));
+                        *output = node.evaluate_with(&env).unwrap();
+                    }
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("vm", source),
+            &program,
+            |bench, program| {
+                let mut vm = Vm::new();
+                let mut values = Vec::with_capacity(program.variables().len());
+                bench.iter(|| {
+                    for (i, output) in output.iter_mut().enumerate() {
+                        values.clear();
+                        values.extend(program.variables().iter().map(|name| match name.as_str() {
+                            "a" => Number::Flt(a[i]),
+                            "b" => Number::Flt(b[i]),
+                            "c" => Number::Flt(c[i]),
+                            _ => Number::Flt(black_box(x[i])),
+                        }));
+                        *output = vm.run(program, &values).unwrap();
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_batch);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+use tilted::{Lexer, Parser, TokenKind};
+
+/// Term repeated to make long generated expressions, with every kind of
+/// token, including multi-byte characters.
+const TERM: &str = "3.25*x_1^2 - sin(y) / (12 + z) ± 0.5 km + 2π·√a² + ";
+
+/// Approximate lengths of the inputs, in bytes.
+const LENGTHS: [usize; 3] = [1_000, 10_000, 100_000];
+
+fn source(length: usize) -> String {
+    TERM.repeat(length / TERM.len()) + "1"
+}
+
+fn bench_lexer(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("lexer");
+    for length in LENGTHS {
+        let source = source(length);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("lex", length), &source, |bench, source| {
+            bench.iter(|| {
+                let mut lexer = Lexer::from_source_code(black_box(source));
+                while lexer.lex().unwrap().kind != TokenKind::Eof {}
+            })
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("parse", length),
+            &source,
+            |bench, source| {
+                bench.iter(|| {
+                    let lexer = Lexer::from_source_code(black_box(source));
+                    Parser::from_lexer(lexer).parse().unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_lexer);
+criterion_main!(benches);
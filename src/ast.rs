@@ -3,16 +3,55 @@
 //! An Abstract Syntax Tree consists of [`Node`]s, which are built by a
 //! [`Parser`](crate::Parser). AST can be evaluated or used to generate code.
 use std::{
+    collections::HashMap,
     fmt::{Debug, Display},
-    ops::{Add, Div, Mul, Neg, Sub},
+    ops::{Add, Div, Mul, Neg, RangeInclusive, Sub},
 };
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::Function;
+use crate::{
+    ntheory, quadrature,
+    solve::{self, Guess},
+    stats, Dimension, EvalError, Function, Matrix, Rng, Span, Unit,
+};
+
+pub mod batch;
+pub mod diff;
+pub mod dot;
+pub mod dual;
+pub mod exact;
+pub mod infix;
+pub mod interval;
+pub mod polynomial;
+pub mod quantity;
+pub mod render;
+pub mod simplify;
+pub mod uncertain;
+pub mod value;
+
+pub use batch::{evaluate_batch, Column};
+pub use diff::{depends_on, diff};
+pub use dot::{to_dot, DotPrinter};
+pub use dual::{evaluate_dual, Dual};
+pub use exact::{evaluate_exact, Exact};
+pub use infix::{to_infix, InfixPrinter};
+pub use interval::{evaluate_interval, Interval};
+pub use polynomial::{
+    coefficients, collect, expand, factor, factorint, from_polynomial, to_polynomial,
+};
+pub use quantity::{evaluate_quantity, Quantity};
+pub use render::{to_latex, to_mathml};
+pub use simplify::{simplify, Simplifier};
+pub use uncertain::{evaluate_uncertain, Uncertain};
+pub use value::{evaluate_value, Lambda, Shape, Value};
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
 
-/// Internal representation of numbers.
+/// Internal representation of numbers. Arithmetic on integers that
+/// overflows [`i128`] gives floats.
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Number {
@@ -22,11 +61,51 @@ pub enum Number {
 
 /// [`Node`] provides a blanket trait for both [`BinaryNode`] and [`UnaryNode`].
 #[cfg_attr(feature = "serde", typetag::serde(tag = "type"))]
-pub trait Node: Debug + Display {
+pub trait Node: Debug + Display + Send + Sync {
     /// Finds the value of this [`Node`].
-    fn evaluate(&self) -> Number;
+    ///
+    /// Variables are not bound to any value here, so a [`Node`] containing
+    /// them evaluates to NaN. Use [`Node::evaluate_with`] to bind them.
+    fn evaluate(&self) -> Number {
+        self.evaluate_with(&Environment::new())
+            .unwrap_or(Number::Flt(f64::NAN))
+    }
+
+    /// Finds the value of this [`Node`], looking up variables in `env`.
+    fn evaluate_with(&self, env: &Environment) -> Result<Number>;
 
     fn to_tree(&self) -> Vec<String>;
+
+    /// Exposes the concrete type of this [`Node`], so that passes over the AST
+    /// can inspect it.
+    fn view(&self) -> NodeView<'_>;
+
+    /// Copies this [`Node`] and all of its sub-nodes onto the heap.
+    fn clone_box(&self) -> NodeBox;
+
+    /// Gets the location in source code that this [`Node`] was parsed from.
+    /// Nodes built by passes over the AST have none.
+    fn span(&self) -> Option<Span>;
+
+    /// Sets the location in source code that this [`Node`] was parsed from.
+    fn set_span(&mut self, span: Option<Span>);
+}
+
+/// Borrowed view of a [`Node`] as one of its concrete types.
+#[derive(Debug, Clone, Copy)]
+pub enum NodeView<'a> {
+    Binary(&'a BinaryNode),
+    Unary(&'a UnaryNode),
+    Plain(&'a PlainNode),
+    Variable(&'a VariableNode),
+    Unit(&'a UnitNode),
+    Binder(&'a BinderNode),
+    Solve(&'a SolveNode),
+    Vector(&'a VectorNode),
+    Call(&'a CallNode),
+    List(&'a ListNode),
+    Range(&'a RangeNode),
+    Lambda(&'a LambdaNode),
 }
 
 /// Convenience type alias for a [`Node`] stored on the heap.
@@ -41,6 +120,23 @@ pub enum BinaryAction {
     Mul,
     Div,
     Pow,
+
+    /// An uncertain value `left ± right`. Evaluating it gives `left`, while
+    /// [`evaluate_uncertain`] propagates the uncertainty.
+    PlusMinus,
+
+    /// A conversion `left to right` of a quantity to the unit `right`.
+    /// Evaluating it gives `left / right` as a float, i.e. the value in that
+    /// unit, while [`evaluate_quantity`] also checks the dimensions.
+    Convert,
+
+    /// An equation `left = right`. Evaluating it gives the residual
+    /// `left - right`, which is zero where the equation holds.
+    Equate,
+
+    /// A left division `left \ right`, which is `right / left` for scalars
+    /// and solves a linear system for a matrix `left`, see [`value`].
+    LeftDiv,
 }
 
 /// [`BinaryNode`] is a [`Node`] that performs an action on two operands.
@@ -55,6 +151,13 @@ pub struct BinaryNode {
 
     /// Right-hand side operand of this [`BinaryNode`].
     right: NodeBox,
+
+    /// Location of this [`BinaryNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
 }
 
 /// [`BinaryAction`] is an action done by a [`Node`] using one operand.
@@ -75,12 +178,442 @@ pub struct UnaryNode {
 
     /// The sole operand of this [`UnaryNode`].
     operand: NodeBox,
+
+    /// Location of this [`UnaryNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
+}
+
+/// [`Constant`] is a named mathematical constant, such as `pi`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Constant {
+    Pi,
 }
 
 /// [`PlainNode`] simply stores the numbers without any action.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlainNode {
+    /// The number stored in this [`PlainNode`].
+    value: Number,
+
+    /// The constant this [`PlainNode`] was written as, if any, so that `pi`
+    /// is told apart from the float nearest to it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    constant: Option<Constant>,
+
+    /// Location of this [`PlainNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
+}
+
+/// [`VariableNode`] stands for a value that is looked up in an [`Environment`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VariableNode {
+    /// Name of this [`VariableNode`].
+    name: String,
+
+    /// Location of this [`VariableNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
+}
+
+/// [`UnitNode`] stands for a unit of measurement, such as `km`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UnitNode {
+    /// The unit stored in this [`UnitNode`].
+    unit: Unit,
+
+    /// Location of this [`UnitNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
+}
+
+/// [`BinderAction`] is an action done by a [`BinderNode`], which binds a
+/// variable over a range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BinderAction {
+    /// Definite integral over the real numbers from the lower to the upper
+    /// bound, computed numerically.
+    Integrate,
+
+    /// Sum over the integers from the lower to the upper bound, inclusive.
+    Sum,
+
+    /// Product over the integers from the lower to the upper bound,
+    /// inclusive.
+    Prod,
+}
+
+/// [`BinderNode`] is a [`Node`] that evaluates its body over a range of
+/// values of a variable, e.g. `sum(k^2, k, 1, 10)`. The variable is only
+/// bound within the body, not within the bounds.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BinderNode {
+    /// Action to be performed by this [`BinderNode`].
+    actor: BinderAction,
+
+    /// Name of the variable bound by this [`BinderNode`].
+    var: String,
+
+    /// Expression evaluated for each value of the variable.
+    body: NodeBox,
+
+    /// Lower bound of the variable.
+    lower: NodeBox,
+
+    /// Upper bound of the variable.
+    upper: NodeBox,
+
+    /// Location of this [`BinderNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
+}
+
+/// [`SolveNode`] is a [`Node`] that finds the roots of an equation in a
+/// variable, e.g. `solve(x^2 = 2, x)`, and evaluates to the smallest of them.
+/// The variable is only bound within the equation, not within the guess.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SolveNode {
+    /// Equation to be solved, usually a [`BinaryAction::Equate`] node. Any
+    /// other expression is solved for where it is zero.
+    equation: NodeBox,
+
+    /// Name of the variable solved for.
+    var: String,
+
+    /// Where to look for the roots.
+    guess: Guess<NodeBox>,
+
+    /// Location of this [`SolveNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
+}
+
+/// [`VectorNode`] is a [`Node`] that builds a vector from its elements, e.g.
+/// `[1, 2, 3]`, or a matrix from its rows, e.g. `[[1, 2], [3, 4]]`. Only
+/// [`evaluate_value`] gives its value, as it is never a scalar.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VectorNode {
+    /// Elements of the vector, or rows of the matrix.
+    elements: Vec<NodeBox>,
+
+    /// Location of this [`VectorNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
+}
+
+/// [`Builtin`] is a function on vectors, matrices and lists, a function of
+/// statistics and probability, a function of number theory, or a random
+/// function, called by a [`CallNode`].
+///
+/// A scalar is taken as a 1×1 matrix where that makes sense, e.g. `det(2)` is
+/// 2, but vectors are never taken as matrices. The higher-order functions
+/// `map`, `filter` and `reduce` go through the elements of a list, or of a
+/// vector, in order.
+///
+/// Statistics of data, such as `mean`, take the values either as a single
+/// list or vector, e.g. `mean(1..10)`, or as separate arguments, e.g.
+/// `mean(1, 2, 4)`. `gamma` and `erf` apply to each element of a vector or a
+/// list, like [`Function`]s do. See [`stats`] for how they are computed.
+///
+/// Functions of number theory, such as `gcd`, take exact integers and reject
+/// floats, even whole ones. They work on the whole range of [`i128`] without
+/// overflowing, and give a float only if the result itself does not fit,
+/// e.g. `lcm(2^100, 3^60)`. See [`ntheory`] for how they are computed.
+///
+/// Random functions, such as `rand`, draw from the [`Rng`] of the
+/// [`Environment`], so they are not pure (see [`Builtin::is_pure`]).
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct PlainNode(Number);
+pub enum Builtin {
+    /// Determinant `det(A)` of a square matrix, exact for integers.
+    Det,
+
+    /// Inverse `inv(A)` of a square matrix, or reciprocal of a scalar.
+    Inv,
+
+    /// Transpose `transpose(A)` of a matrix. Vectors and scalars are left as
+    /// they are.
+    Transpose,
+
+    /// Dot product `dot(a, b)` of two vectors of the same length, or of two
+    /// matrices of the same shape taken as vectors.
+    Dot,
+
+    /// Cross product `cross(a, b)` of two vectors of length 3.
+    Cross,
+
+    /// Euclidean norm `norm(v)` of a vector, or Frobenius norm of a matrix.
+    Norm,
+
+    /// List `map(f, xs)` of the values of a lambda `f` at each element of
+    /// `xs`.
+    Map,
+
+    /// List `filter(f, xs)` of the elements of `xs` at which a lambda `f` is
+    /// not zero.
+    Filter,
+
+    /// Value `reduce(f, xs, init)` of a lambda `f` of two parameters folded
+    /// over the elements of `xs` from the left, starting from `init` or, if
+    /// it is omitted, from the first element.
+    Reduce,
+
+    /// Number `len(xs)` of elements of a list or a vector, or of rows of a
+    /// matrix.
+    Len,
+
+    /// Arithmetic mean `mean(xs)`.
+    Mean,
+
+    /// Median `median(xs)`, which is the mean of the two middle values if
+    /// there is an even number of them.
+    Median,
+
+    /// Most frequent value `mode(xs)`, or the smallest of them if there are
+    /// several.
+    Mode,
+
+    /// Standard deviation `stdev(xs)` of a sample, of at least two values.
+    Stdev,
+
+    /// Standard deviation `pstdev(xs)` of a whole population.
+    Pstdev,
+
+    /// Variance `variance(xs)` of a sample, of at least two values.
+    Variance,
+
+    /// Variance `pvariance(xs)` of a whole population.
+    Pvariance,
+
+    /// Percentile `percentile(xs, p)` of a list or a vector, with `p` from 0
+    /// to 100, interpolated linearly between the nearest ranks.
+    Percentile,
+
+    /// Number of combinations `nCr(n, r)` of integers, exact unless it
+    /// overflows.
+    Ncr,
+
+    /// Number of permutations `nPr(n, r)` of integers, exact unless it
+    /// overflows.
+    Npr,
+
+    /// Gamma function `gamma(x)`, exact for positive integers unless it
+    /// overflows.
+    Gamma,
+
+    /// Error function `erf(x)`.
+    Erf,
+
+    /// Probability density `normpdf(x, mu, sigma)` of the normal
+    /// distribution, which is the standard one if `mu` and `sigma` are
+    /// omitted.
+    NormPdf,
+
+    /// Cumulative distribution function `normcdf(x, mu, sigma)` of the normal
+    /// distribution, which is the standard one if `mu` and `sigma` are
+    /// omitted.
+    NormCdf,
+
+    /// Probability `binompdf(k, n, p)` of `k` successes in `n` trials.
+    BinomPdf,
+
+    /// Probability `binomcdf(k, n, p)` of at most `k` successes in `n`
+    /// trials.
+    BinomCdf,
+
+    /// Probability `poissonpdf(k, lambda)` of `k` events of a Poisson process.
+    PoissonPdf,
+
+    /// Probability `poissoncdf(k, lambda)` of at most `k` events of a Poisson
+    /// process.
+    PoissonCdf,
+
+    /// Greatest common divisor `gcd(a, b)`, which is never negative.
+    Gcd,
+
+    /// Least common multiple `lcm(a, b)`, which is never negative.
+    Lcm,
+
+    /// Primality test `isprime(n)`, which is 1 for a prime and 0 otherwise.
+    IsPrime,
+
+    /// Smallest prime `nextprime(n)` greater than `n`.
+    NextPrime,
+
+    /// Prime factorisation `factorint(n)`, which evaluates to `n` itself, but
+    /// is written as a product of powers of primes by [`simplify()`].
+    FactorInt,
+
+    /// Euler's totient `totient(n)` of a positive integer, i.e. how many
+    /// integers from 1 to `n` are coprime to it.
+    Totient,
+
+    /// Modular power `powmod(a, b, m)`, from 0 to `|m| - 1`. A negative
+    /// exponent `b` raises the modular inverse of `a`.
+    PowMod,
+
+    /// Modular inverse `modinv(a, m)`, from 0 to `|m| - 1`, which is NaN if
+    /// `a` and `m` are not coprime.
+    ModInv,
+
+    /// Random float `rand()`, uniformly distributed in `[0, 1)`.
+    Rand,
+
+    /// Random integer `randint(a, b)`, uniformly distributed from `a` to `b`,
+    /// both included.
+    RandInt,
+
+    /// Random float `randn(mu, sigma)` from the normal distribution, which is
+    /// the standard one if `mu` and `sigma` are omitted.
+    RandN,
+
+    /// Random element `choose(xs)` of a list or a vector.
+    Choose,
+
+    /// Derivative `diff(expr, x)` of an expression with respect to a
+    /// variable, taken by [`diff()`] when the call is evaluated or
+    /// simplified, so that the AST keeps the call as it was written.
+    Diff,
+
+    /// Expression `expand(expr)` with its polynomials multiplied out by
+    /// [`expand()`], when the call is evaluated or simplified.
+    Expand,
+
+    /// Expression `factor(expr)` with its polynomials factorised by
+    /// [`factor()`], when the call is evaluated or simplified.
+    Factor,
+
+    /// Expression `collect(expr, x)` with the terms of its polynomials
+    /// collected by powers of a variable by [`collect()`], when the call is
+    /// evaluated or simplified.
+    Collect,
+}
+
+/// [`CallNode`] is a [`Node`] that calls a [`Builtin`] function on its
+/// arguments, e.g. `dot(a, b)`.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CallNode {
+    /// Function called by this [`CallNode`].
+    func: Builtin,
+
+    /// Arguments of the function.
+    args: Vec<NodeBox>,
+
+    /// Location of this [`CallNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
+}
+
+/// [`ListNode`] is a [`Node`] that builds a list from its elements, e.g.
+/// `{1, 4, 9}`. Only [`evaluate_value`] gives its value, as it is never a
+/// scalar.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ListNode {
+    /// Elements of the list.
+    elements: Vec<NodeBox>,
+
+    /// Location of this [`ListNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
+}
+
+/// [`RangeNode`] is a [`Node`] that builds the list of integers from `start`
+/// to `end` inclusive, e.g. `1..5`. Only [`evaluate_value`] gives its value,
+/// as it is never a scalar.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RangeNode {
+    /// First integer of the range.
+    start: NodeBox,
+
+    /// Last integer of the range.
+    end: NodeBox,
+
+    /// Location of this [`RangeNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
+}
+
+/// [`LambdaNode`] is a [`Node`] that builds an anonymous function, e.g.
+/// `x -> x^2` or `(a, b) -> a + b`. Its value is a [`Lambda`], which
+/// captures the [`Environment`] it is evaluated in.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LambdaNode {
+    /// Names of the parameters, which shadow variables of the same names.
+    params: Vec<String>,
+
+    /// Body of the lambda.
+    body: NodeBox,
+
+    /// Location of this [`LambdaNode`] in source code, if it was parsed.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    span: Option<Span>,
+}
+
+/// Values bound to variables, used when evaluating a [`Node`].
+///
+/// An [`Environment`] also holds the [`Rng`] that random functions such as
+/// `rand` draw from, seeded with 0 unless it is seeded otherwise. Clones of
+/// an [`Environment`] share its [`Rng`], so that lambdas draw from the same
+/// stream of numbers as the expression that created them.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Environment {
+    /// Values of variables, by name.
+    variables: HashMap<String, Number>,
+
+    /// Generator of random numbers.
+    #[cfg_attr(feature = "serde", serde(default))]
+    rng: Rng,
+}
 
 // -----------------------------------------------------------------------------
 // All impls onwards.
@@ -89,6 +622,19 @@ pub struct PlainNode(Number);
 // -----------------------------------------------------------------------------
 // 1. impls for Number.
 // -----------------------------------------------------------------------------
+impl Number {
+    /// Checks if two [`Number`]s are exactly the same, i.e. of the same kind
+    /// and with the same bits. Unlike `==`, this tells `0` from `0.0` and
+    /// `0.0` from `-0.0`, and a NaN is identical to the same NaN.
+    pub fn identical(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Int(a), Self::Int(b)) => a == b,
+            (Self::Flt(a), Self::Flt(b)) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+}
+
 impl PartialEq for Number {
     fn eq(&self, other: &Self) -> bool {
         // Integer comparison.
@@ -148,7 +694,10 @@ impl Add for Number {
     fn add(self, rhs: Self) -> Self::Output {
         match self {
             Self::Int(a) => match rhs {
-                Self::Int(b) => Self::Int(a + b),
+                Self::Int(b) => match a.checked_add(b) {
+                    Some(n) => Self::Int(n),
+                    None => Self::Flt(a as f64 + b as f64),
+                },
                 Self::Flt(b) => Self::Flt((a as f64) + b),
             },
             Self::Flt(a) => match rhs {
@@ -164,7 +713,10 @@ impl Sub for Number {
     fn sub(self, rhs: Self) -> Self::Output {
         match self {
             Self::Int(a) => match rhs {
-                Self::Int(b) => Self::Int(a - b),
+                Self::Int(b) => match a.checked_sub(b) {
+                    Some(n) => Self::Int(n),
+                    None => Self::Flt(a as f64 - b as f64),
+                },
                 Self::Flt(b) => Self::Flt((a as f64) - b),
             },
             Self::Flt(a) => match rhs {
@@ -180,7 +732,10 @@ impl Mul for Number {
     fn mul(self, rhs: Self) -> Self::Output {
         match self {
             Self::Int(a) => match rhs {
-                Self::Int(b) => Self::Int(a * b),
+                Self::Int(b) => match a.checked_mul(b) {
+                    Some(n) => Self::Int(n),
+                    None => Self::Flt(a as f64 * b as f64),
+                },
                 Self::Flt(b) => Self::Flt((a as f64) * b),
             },
             Self::Flt(a) => match rhs {
@@ -200,7 +755,11 @@ impl Div for Number {
 
         match self {
             Self::Int(a) => match rhs {
-                Self::Int(b) => Self::Int(a / b),
+                // Only `i128::MIN / -1` overflows.
+                Self::Int(b) => match a.checked_div(b) {
+                    Some(n) => Self::Int(n),
+                    None => Self::Flt(a as f64 / b as f64),
+                },
                 Self::Flt(b) => Self::Flt((a as f64) / b),
             },
             Self::Flt(a) => match rhs {
@@ -215,7 +774,10 @@ impl Neg for Number {
     type Output = Self;
     fn neg(self) -> Self::Output {
         match self {
-            Self::Int(a) => Self::Int(-a),
+            Self::Int(a) => match a.checked_neg() {
+                Some(n) => Self::Int(n),
+                None => Self::Flt(-(a as f64)),
+            },
             Self::Flt(a) => Self::Flt(-a),
         }
     }
@@ -304,18 +866,21 @@ impl BinaryAction {
     pub fn evaluate(&self, left: Number, right: Number) -> Number {
         match self {
             Self::Add => left + right,
-            Self::Sub => left - right,
+            Self::Sub | Self::Equate => left - right,
             Self::Mul => left * right,
             Self::Div => left / right,
+            Self::LeftDiv => right / left,
+            Self::PlusMinus => left,
+            Self::Convert => Number::Flt(float(left)) / Number::Flt(float(right)),
             Self::Pow => {
-                // Integer base and exponent are kept as integer.
+                // Integer base and exponent are kept as integer, unless the
+                // exponent is negative or the power overflows.
                 if let Number::Int(n) = left {
                     if let Number::Int(m) = right {
-                        if m >= 0 {
-                            return Number::Int(n.pow(m as u32));
-                        } else {
-                            return Number::Flt((n as f64).powf(m as f64));
-                        }
+                        return match checked_pow(n, m) {
+                            Some(power) => Number::Int(power),
+                            None => Number::Flt((n as f64).powf(m as f64)),
+                        };
                     }
                 }
 
@@ -332,6 +897,111 @@ impl BinaryAction {
             }
         }
     }
+
+    /// Evaluates this action on two quantities, checking their dimensions.
+    ///
+    /// Both sides of `+`, `-`, `±` and `=` must have the same dimension, and an
+    /// exponent must be dimensionless. `left to right` also requires the same
+    /// dimension, and gives `left` in the unit of `right`, if it has one.
+    pub fn evaluate_quantity(&self, left: Quantity, right: Quantity) -> Result<Quantity> {
+        let (ld, rd) = (left.dimension(), right.dimension());
+        let dimension = match self {
+            Self::Add | Self::Sub | Self::PlusMinus | Self::Convert | Self::Equate if ld != rd => {
+                return Err(EvalError::DimensionMismatch(ld, rd))
+            }
+            Self::Add | Self::Sub | Self::PlusMinus | Self::Equate => ld,
+            Self::Mul => ld * rd,
+            Self::Div => ld / rd,
+            Self::LeftDiv => rd / ld,
+            Self::Pow if !rd.is_dimensionless() => {
+                return Err(EvalError::DimensionlessExpected(rd))
+            }
+            Self::Pow if ld.is_dimensionless() => ld,
+            Self::Pow => ld
+                .powf(float(right.value()))
+                .ok_or(EvalError::FractionalDimension(ld))?,
+            Self::Convert => {
+                return match right.unit() {
+                    Some(unit) => left.with_unit(unit.clone()),
+                    None => Ok(Quantity::new(left.value(), ld)),
+                }
+            }
+        };
+
+        let value = self.evaluate(left.value(), right.value());
+        Ok(Quantity::new(value, dimension))
+    }
+
+    /// Evaluates this action on two values, as described in [`value`].
+    pub fn evaluate_value(&self, left: Value, right: Value) -> Result<Value> {
+        let evaluate = |l, r| self.evaluate(l, r);
+        match (self, left, right) {
+            // Lists work element by element, whatever their elements are.
+            (_, Value::List(l), Value::List(r)) if l.len() == r.len() => l
+                .into_iter()
+                .zip(r)
+                .map(|(l, r)| self.evaluate_value(l, r))
+                .collect::<Result<_>>()
+                .map(Value::List),
+            (_, l @ Value::List(_), r @ Value::List(_)) => {
+                Err(EvalError::ShapeMismatch(l.shape(), r.shape()))
+            }
+            (_, Value::List(l), r) => l
+                .into_iter()
+                .map(|l| self.evaluate_value(l, r.clone()))
+                .collect::<Result<_>>()
+                .map(Value::List),
+            (_, l, Value::List(r)) => r
+                .into_iter()
+                .map(|r| self.evaluate_value(l.clone(), r))
+                .collect::<Result<_>>()
+                .map(Value::List),
+
+            (Self::Mul, Value::Matrix(l), Value::Matrix(r)) => l.product(&r).map(Value::Matrix),
+            (Self::Mul, Value::Matrix(l), Value::Vector(r)) => {
+                let product = l.product(&Matrix::column(r))?;
+                Ok(Value::Vector(product.into_elements()))
+            }
+            (Self::Mul, Value::Vector(l), Value::Matrix(r)) => {
+                let product = Matrix::column(l).transpose().product(&r)?;
+                Ok(Value::Vector(product.into_elements()))
+            }
+
+            // Matrices are only divided by scalars.
+            (Self::Div, l, r)
+                if matches!((&l, &r), (Value::Matrix(_), _) | (_, Value::Matrix(_))) =>
+            {
+                match r {
+                    Value::Scalar(_) => l.zip_with(r, evaluate),
+                    r => Err(EvalError::ScalarExpected(r.shape())),
+                }
+            }
+
+            // Powers of matrices are repeated products.
+            (Self::Pow, Value::Matrix(l), Value::Scalar(Number::Int(n))) => {
+                l.pow(n).map(Value::Matrix)
+            }
+            (Self::Pow, Value::Matrix(_), Value::Scalar(n)) => Err(EvalError::IntegerExpected(n)),
+            (Self::Pow, Value::Matrix(_), r) | (Self::Pow, _, r @ Value::Matrix(_)) => {
+                Err(EvalError::ScalarExpected(r.shape()))
+            }
+
+            // Linear systems, with the vector as a column.
+            (Self::LeftDiv, Value::Matrix(l), Value::Vector(r)) => {
+                let solution = l.solve(&Matrix::column(r))?;
+                Ok(Value::Vector(solution.into_elements()))
+            }
+            (Self::LeftDiv, Value::Matrix(l), Value::Matrix(r)) => l.solve(&r).map(Value::Matrix),
+            (Self::LeftDiv, l @ Value::Matrix(_), r) => {
+                Err(EvalError::ShapeMismatch(l.shape(), r.shape()))
+            }
+            (Self::LeftDiv, l @ Value::Vector(_), _) => {
+                Err(EvalError::InvalidShape("\\", l.shape()))
+            }
+
+            (_, l, r) => l.zip_with(r, evaluate),
+        }
+    }
 }
 
 impl Display for BinaryAction {
@@ -343,6 +1013,10 @@ impl Display for BinaryAction {
             Self::Mul => write!(f, "*"),
             Self::Div => write!(f, "/"),
             Self::Pow => write!(f, "^"),
+            Self::PlusMinus => write!(f, "±"),
+            Self::Convert => write!(f, "to"),
+            Self::Equate => write!(f, "="),
+            Self::LeftDiv => write!(f, "\\"),
         }?;
         write!(f, ")")
     }
@@ -354,13 +1028,13 @@ impl Display for BinaryAction {
 
 #[cfg_attr(feature = "serde", typetag::serde)]
 impl Node for BinaryNode {
-    fn evaluate(&self) -> Number {
+    fn evaluate_with(&self, env: &Environment) -> Result<Number> {
         // Evaluate both sub-nodes.
-        let left = self.left.evaluate();
-        let right = self.right.evaluate();
+        let left = self.left.evaluate_with(env)?;
+        let right = self.right.evaluate_with(env)?;
 
         // Then evalute this node.
-        self.actor.evaluate(left, right)
+        Ok(self.actor.evaluate(left, right))
     }
 
     fn to_tree(&self) -> Vec<String> {
@@ -388,6 +1062,27 @@ impl Node for BinaryNode {
 
         tree
     }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::Binary(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(BinaryNode {
+            left: self.left.clone_box(),
+            actor: self.actor,
+            right: self.right.clone_box(),
+            span: self.span,
+        })
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
 }
 
 impl Display for BinaryNode {
@@ -404,7 +1099,22 @@ impl BinaryNode {
         actor: BinaryAction,
         right: NodeBox
     ) -> BinaryNode {
-        Self { left, actor, right }
+        Self { left, actor, right, span: None }
+    }
+
+    /// Gets the left-hand side operand of this [`BinaryNode`].
+    pub fn left(&self) -> &dyn Node {
+        self.left.as_ref()
+    }
+
+    /// Gets the action performed by this [`BinaryNode`].
+    pub fn actor(&self) -> BinaryAction {
+        self.actor
+    }
+
+    /// Gets the right-hand side operand of this [`BinaryNode`].
+    pub fn right(&self) -> &dyn Node {
+        self.right.as_ref()
     }
 }
 
@@ -417,67 +1127,60 @@ impl UnaryAction {
         match self {
             Self::Neg => -operand,
             Self::Iden => operand,
-            Self::Func(f) => UnaryAction::evaluate_function(f, operand),
+            Self::Func(f) => {
+                // Functions are evaluated over floats only.
+                let mut value = [match operand {
+                    Number::Int(n) => n as f64,
+                    Number::Flt(n) => n,
+                }];
+                f.evaluate_slice(&mut value);
+                Number::Flt(value[0])
+            }
         }
     }
 
-    fn evaluate_function(func: &Function, operand: Number) -> Number {
-        match func {
-            Function::Sin => match operand {
-                Number::Int(n) => Number::Flt((n as f64).sin()),
-                Number::Flt(n) => Number::Flt(n.sin()),
-            },
-            Function::Cos => match operand {
-                Number::Int(n) => Number::Flt((n as f64).cos()),
-                Number::Flt(n) => Number::Flt(n.cos()),
-            },
-            Function::Tan => match operand {
-                Number::Int(n) => Number::Flt((n as f64).tan()),
-                Number::Flt(n) => Number::Flt(n.tan()),
-            },
-            Function::Sec => match operand {
-                Number::Int(n) => Number::Flt((n as f64).cos().recip()),
-                Number::Flt(n) => Number::Flt(n.cos().recip()),
-            },
-            Function::Csc => match operand {
-                Number::Int(n) => Number::Flt((n as f64).sin().recip()),
-                Number::Flt(n) => Number::Flt(n.sin().recip()),
-            },
-
-            Function::Cot => match operand {
-                Number::Int(n) => Number::Flt((n as f64).tan().recip()),
-                Number::Flt(n) => Number::Flt(n.tan().recip()),
-            },
-
-            Function::Asin => match operand {
-                Number::Int(n) => Number::Flt((n as f64).asin()),
-                Number::Flt(n) => Number::Flt(n.asin()),
-            },
-
-            Function::Acos => match operand {
-                Number::Int(n) => Number::Flt((n as f64).acos()),
-                Number::Flt(n) => Number::Flt(n.acos()),
-            },
-
-            Function::Atan => match operand {
-                Number::Int(n) => Number::Flt((n as f64).atan()),
-                Number::Flt(n) => Number::Flt(n.atan()),
-            },
+    /// Evaluates this action on a quantity. Functions only take
+    /// dimensionless values, except `sqrt`, which halves the dimension.
+    pub fn evaluate_quantity(&self, operand: Quantity) -> Result<Quantity> {
+        let dimension = match (self, operand.dimension()) {
+            (Self::Func(Function::Sqrt), d) => {
+                d.powf(0.5).ok_or(EvalError::FractionalDimension(d))?
+            }
+            (Self::Func(_), d) if !d.is_dimensionless() => {
+                return Err(EvalError::DimensionlessExpected(d))
+            }
+            (_, d) => d,
+        };
 
-            Function::Asec => match operand {
-                Number::Int(n) => Number::Flt((n as f64).recip().acos()),
-                Number::Flt(n) => Number::Flt(n.recip().acos()),
-            },
+        Ok(Quantity::new(self.evaluate(operand.value()), dimension))
+    }
+}
 
-            Function::Acsc => match operand {
-                Number::Int(n) => Number::Flt((n as f64).recip().asin()),
-                Number::Flt(n) => Number::Flt(n.recip().asin()),
-            },
+impl Function {
+    /// Applies this [`Function`] to each number in place. Both scalar and
+    /// batch evaluation go through here, so that they give the same results.
+    pub(crate) fn evaluate_slice(&self, values: &mut [f64]) {
+        fn each(values: &mut [f64], f: impl Fn(f64) -> f64) {
+            for value in values {
+                *value = f(*value);
+            }
+        }
 
-            Function::Acot => match operand {
-                Number::Int(n) => Number::Flt((n as f64).recip().atan()),
-                Number::Flt(n) => Number::Flt(n.recip().atan()),
-            },
+        match self {
+            Function::Sin => each(values, f64::sin),
+            Function::Cos => each(values, f64::cos),
+            Function::Tan => each(values, f64::tan),
+            Function::Sec => each(values, |n| n.cos().recip()),
+            Function::Csc => each(values, |n| n.sin().recip()),
+            Function::Cot => each(values, |n| n.tan().recip()),
+            Function::Asin => each(values, f64::asin),
+            Function::Acos => each(values, f64::acos),
+            Function::Atan => each(values, f64::atan),
+            Function::Asec => each(values, |n| n.recip().acos()),
+            Function::Acsc => each(values, |n| n.recip().asin()),
+            Function::Acot => each(values, |n| n.recip().atan()),
+            Function::Ln => each(values, f64::ln),
+            Function::Sqrt => each(values, f64::sqrt),
         }
     }
 }
@@ -498,12 +1201,12 @@ impl Display for UnaryAction {
 
 #[cfg_attr(feature = "serde", typetag::serde)]
 impl Node for UnaryNode {
-    fn evaluate(&self) -> Number {
+    fn evaluate_with(&self, env: &Environment) -> Result<Number> {
         // Evaluate the operand.
-        let operand = self.operand.evaluate();
+        let operand = self.operand.evaluate_with(env)?;
 
         // Then evaluate this node.
-        self.actor.evaluate(operand)
+        Ok(self.actor.evaluate(operand))
     }
 
     fn to_tree(&self) -> Vec<String> {
@@ -522,6 +1225,26 @@ impl Node for UnaryNode {
 
         left_tree
     }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::Unary(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(UnaryNode {
+            actor: self.actor,
+            operand: self.operand.clone_box(),
+            span: self.span,
+        })
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
 }
 
 impl Display for UnaryNode {
@@ -533,7 +1256,21 @@ impl Display for UnaryNode {
 impl UnaryNode {
     /// Creates a new [`UnaryNode`].
     pub fn new(actor: UnaryAction, operand: NodeBox) -> UnaryNode {
-        Self { actor, operand }
+        Self {
+            actor,
+            operand,
+            span: None,
+        }
+    }
+
+    /// Gets the action performed by this [`UnaryNode`].
+    pub fn actor(&self) -> UnaryAction {
+        self.actor
+    }
+
+    /// Gets the sole operand of this [`UnaryNode`].
+    pub fn operand(&self) -> &dyn Node {
+        self.operand.as_ref()
     }
 }
 
@@ -543,12 +1280,28 @@ impl UnaryNode {
 
 #[cfg_attr(feature = "serde", typetag::serde)]
 impl Node for PlainNode {
-    fn evaluate(&self) -> Number {
-        self.0
+    fn evaluate_with(&self, _env: &Environment) -> Result<Number> {
+        Ok(self.value)
     }
 
     fn to_tree(&self) -> Vec<String> {
-        vec![self.0.to_string()]
+        vec![self.value.to_string()]
+    }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::Plain(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(*self)
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
     }
 }
 
@@ -560,6 +1313,1638 @@ impl Display for PlainNode {
 
 impl PlainNode {
     pub fn new(value: Number) -> PlainNode {
-        Self(value)
+        Self {
+            value,
+            constant: None,
+            span: None,
+        }
+    }
+
+    /// Creates a [`PlainNode`] storing the value of a named constant.
+    pub fn from_constant(constant: Constant) -> PlainNode {
+        Self {
+            value: constant.value(),
+            constant: Some(constant),
+            span: None,
+        }
+    }
+
+    /// Gets the number stored in this [`PlainNode`].
+    pub fn value(&self) -> Number {
+        self.value
+    }
+
+    /// Gets the constant this [`PlainNode`] stands for, if any.
+    pub fn constant(&self) -> Option<Constant> {
+        self.constant
+    }
+}
+
+impl PartialEq for PlainNode {
+    /// Compares the numbers and constants stored, regardless of where they
+    /// were parsed.
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.constant == other.constant
+    }
+}
+
+impl Constant {
+    /// Looks up a constant by name, e.g. `pi` or `π`.
+    pub fn from_name(name: &str) -> Option<Constant> {
+        match name {
+            "pi" | "π" => Some(Constant::Pi),
+            _ => None,
+        }
+    }
+
+    /// Gets the name of this [`Constant`], which reads back as itself.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Constant::Pi => "pi",
+        }
+    }
+
+    /// Gets the value of this [`Constant`].
+    pub fn value(&self) -> Number {
+        match self {
+            Constant::Pi => Number::Flt(std::f64::consts::PI),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 7. impls for VariableNode.
+// -----------------------------------------------------------------------------
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Node for VariableNode {
+    fn evaluate_with(&self, env: &Environment) -> Result<Number> {
+        env.get(&self.name)
+            .ok_or_else(|| EvalError::UnboundVariable(self.name.clone()))
     }
+
+    fn to_tree(&self) -> Vec<String> {
+        vec![format!("Var({})", self.name)]
+    }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::Variable(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(self.clone())
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
+}
+
+impl Display for VariableNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_tree().join("\n"))
+    }
+}
+
+impl VariableNode {
+    /// Creates a new [`VariableNode`].
+    pub fn new<T: Into<String>>(name: T) -> VariableNode {
+        Self {
+            name: name.into(),
+            span: None,
+        }
+    }
+
+    /// Gets the name of this [`VariableNode`].
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl PartialEq for VariableNode {
+    /// Compares the names, regardless of where they were parsed.
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 8. impls for UnitNode.
+// -----------------------------------------------------------------------------
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Node for UnitNode {
+    /// Evaluates to the value of the unit in coherent SI units, e.g. 1000.0
+    /// for `km`. Use [`evaluate_quantity`] to keep its dimension.
+    fn evaluate_with(&self, _env: &Environment) -> Result<Number> {
+        Ok(Number::Flt(self.unit.scale()))
+    }
+
+    fn to_tree(&self) -> Vec<String> {
+        vec![format!("Unit({})", self.unit)]
+    }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::Unit(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(self.clone())
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
+}
+
+impl Display for UnitNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_tree().join("\n"))
+    }
+}
+
+impl UnitNode {
+    /// Creates a new [`UnitNode`].
+    pub fn new(unit: Unit) -> UnitNode {
+        Self { unit, span: None }
+    }
+
+    /// Gets the unit stored in this [`UnitNode`].
+    pub fn unit(&self) -> &Unit {
+        &self.unit
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 9. impls for BinderAction.
+// -----------------------------------------------------------------------------
+
+impl BinderAction {
+    /// Evaluates this action from `lower` to `upper`, with `body` giving the
+    /// value of the body for a value of the variable.
+    ///
+    /// Sums and products take integer bounds, and are exact as long as the
+    /// body is an integer and the result fits in an [`i128`]. An empty range
+    /// gives 0 for sums and 1 for products. Integrals are computed with
+    /// [`quadrature::integrate`] to [`quadrature::TOLERANCE`].
+    pub fn evaluate(
+        &self,
+        lower: Number,
+        upper: Number,
+        mut body: impl FnMut(Number) -> Result<Number>,
+    ) -> Result<Number> {
+        match self {
+            Self::Integrate => {
+                let f = |x| body(Number::Flt(x)).map(float);
+                let integral =
+                    quadrature::integrate(f, float(lower), float(upper), quadrature::TOLERANCE)?;
+                Ok(Number::Flt(integral.value()))
+            }
+            Self::Sum | Self::Prod => {
+                let mut result = self.identity();
+                for k in Self::range(lower, upper)? {
+                    result = self.accumulate(result, body(Number::Int(k))?);
+                }
+                Ok(result)
+            }
+        }
+    }
+
+    /// Evaluates this action on quantities, with `body` giving the value of
+    /// the body for a dimensionless value of the variable.
+    ///
+    /// The bounds must be dimensionless. The body must have the same
+    /// dimension throughout a sum or an integral, while the dimensions of the
+    /// factors of a product are multiplied.
+    pub fn evaluate_quantity(
+        &self,
+        lower: Quantity,
+        upper: Quantity,
+        mut body: impl FnMut(Number) -> Result<Quantity>,
+    ) -> Result<Quantity> {
+        for bound in [&lower, &upper] {
+            if !bound.dimension().is_dimensionless() {
+                return Err(EvalError::DimensionlessExpected(bound.dimension()));
+            }
+        }
+
+        let mut dimension: Option<Dimension> = None;
+        let value = self.evaluate(lower.value(), upper.value(), |value| {
+            let quantity = body(value)?;
+            let actual = quantity.dimension();
+            dimension = match (dimension, self) {
+                (None, _) => Some(actual),
+                (Some(d), Self::Prod) => Some(d * actual),
+                (Some(d), _) if d != actual => return Err(EvalError::DimensionMismatch(d, actual)),
+                (d, _) => d,
+            };
+            Ok(quantity.value())
+        })?;
+
+        Ok(Quantity::new(value, dimension.unwrap_or(Dimension::NONE)))
+    }
+
+    /// Gets the name of this [`BinderAction`] as written in source code.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Integrate => "integrate",
+            Self::Sum => "sum",
+            Self::Prod => "prod",
+        }
+    }
+
+    /// Gets the value of an empty sum or product.
+    pub(crate) fn identity(&self) -> Number {
+        match self {
+            Self::Prod => Number::Int(1),
+            _ => Number::Int(0),
+        }
+    }
+
+    /// Adds a term to a sum, or multiplies a factor into a product. Integers
+    /// that overflow are converted to floats.
+    pub(crate) fn accumulate(&self, result: Number, value: Number) -> Number {
+        match self {
+            Self::Prod => result * value,
+            _ => result + value,
+        }
+    }
+
+    /// Gets the integers from `lower` to `upper`, inclusive. Floats are
+    /// allowed as long as they are whole numbers.
+    pub(crate) fn range(lower: Number, upper: Number) -> Result<std::ops::RangeInclusive<i128>> {
+        Ok(integer(lower)?..=integer(upper)?)
+    }
+}
+
+impl Display for BinderAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Integrate => write!(f, "Integrate"),
+            Self::Sum => write!(f, "Sum"),
+            Self::Prod => write!(f, "Prod"),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 10. impls for BinderNode.
+// -----------------------------------------------------------------------------
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Node for BinderNode {
+    fn evaluate_with(&self, env: &Environment) -> Result<Number> {
+        // Evaluate the bounds.
+        let lower = self.lower.evaluate_with(env)?;
+        let upper = self.upper.evaluate_with(env)?;
+
+        // Then evaluate the body with the variable bound.
+        let mut env = env.clone();
+        self.actor.evaluate(lower, upper, |value| {
+            env.bind(self.var.as_str(), value);
+            self.body.evaluate_with(&env)
+        })
+    }
+
+    fn to_tree(&self) -> Vec<String> {
+        // Get actor.
+        let actor = format!("{}({})", self.actor, self.var);
+
+        // Process body and lower bound.
+        let mut tree = vec![actor];
+        for child in [&self.body, &self.lower] {
+            let mut child_tree = child.to_tree();
+            child_tree[0].insert_str(0, "`-- ");
+            for line in child_tree.iter_mut().skip(1) {
+                line.insert_str(0, "|   ");
+            }
+            tree.extend(child_tree);
+        }
+
+        // Process upper bound.
+        let mut upper_tree = self.upper.to_tree();
+        upper_tree[0].insert_str(0, "`-- ");
+        for line in upper_tree.iter_mut().skip(1) {
+            line.insert_str(0, "    ");
+        }
+        tree.extend(upper_tree);
+
+        tree
+    }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::Binder(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(BinderNode {
+            actor: self.actor,
+            var: self.var.clone(),
+            body: self.body.clone_box(),
+            lower: self.lower.clone_box(),
+            upper: self.upper.clone_box(),
+            span: self.span,
+        })
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
+}
+
+impl Display for BinderNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_tree().join("\n"))
+    }
+}
+
+impl BinderNode {
+    /// Creates a new [`BinderNode`], binding `var` in `body` from `lower` to
+    /// `upper`.
+    pub fn new<T: Into<String>>(
+        actor: BinderAction,
+        var: T,
+        body: NodeBox,
+        lower: NodeBox,
+        upper: NodeBox,
+    ) -> BinderNode {
+        Self {
+            actor,
+            var: var.into(),
+            body,
+            lower,
+            upper,
+            span: None,
+        }
+    }
+
+    /// Gets the action performed by this [`BinderNode`].
+    pub fn actor(&self) -> BinderAction {
+        self.actor
+    }
+
+    /// Gets the name of the variable bound by this [`BinderNode`].
+    pub fn var(&self) -> &str {
+        &self.var
+    }
+
+    /// Gets the expression evaluated for each value of the variable.
+    pub fn body(&self) -> &dyn Node {
+        self.body.as_ref()
+    }
+
+    /// Gets the lower bound of the variable.
+    pub fn lower(&self) -> &dyn Node {
+        self.lower.as_ref()
+    }
+
+    /// Gets the upper bound of the variable.
+    pub fn upper(&self) -> &dyn Node {
+        self.upper.as_ref()
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 11. impls for SolveNode.
+// -----------------------------------------------------------------------------
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Node for SolveNode {
+    fn evaluate_with(&self, env: &Environment) -> Result<Number> {
+        match self.roots(env)?.first() {
+            Some(&root) => Ok(Number::Flt(root)),
+            None => Err(EvalError::NoRealRoot),
+        }
+    }
+
+    fn to_tree(&self) -> Vec<String> {
+        // Get actor.
+        let actor = format!("Solve({})", self.var);
+
+        // Process the equation and the guess, the last one without a rail.
+        let children = std::iter::once(&self.equation).chain(self.guess.iter());
+        let count = 1 + self.guess.len();
+        let mut tree = vec![actor];
+        for (i, child) in children.enumerate() {
+            let rail = if i + 1 < count { "|   " } else { "    " };
+            let mut child_tree = child.to_tree();
+            child_tree[0].insert_str(0, "`-- ");
+            for line in child_tree.iter_mut().skip(1) {
+                line.insert_str(0, rail);
+            }
+            tree.extend(child_tree);
+        }
+
+        tree
+    }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::Solve(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(SolveNode {
+            equation: self.equation.clone_box(),
+            var: self.var.clone(),
+            guess: self.guess.clone(),
+            span: self.span,
+        })
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
+}
+
+impl Display for SolveNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_tree().join("\n"))
+    }
+}
+
+impl SolveNode {
+    /// Largest degree of polynomials solved in closed form.
+    pub const MAX_DEGREE: usize = 4;
+
+    /// Creates a new [`SolveNode`], solving `equation` for `var`.
+    pub fn new<T: Into<String>>(equation: NodeBox, var: T, guess: Guess<NodeBox>) -> SolveNode {
+        Self {
+            equation,
+            var: var.into(),
+            guess,
+            span: None,
+        }
+    }
+
+    /// Gets the equation solved by this [`SolveNode`].
+    pub fn equation(&self) -> &dyn Node {
+        self.equation.as_ref()
+    }
+
+    /// Gets the name of the variable solved for.
+    pub fn var(&self) -> &str {
+        &self.var
+    }
+
+    /// Gets where to look for the roots.
+    pub fn guess(&self) -> Guess<&dyn Node> {
+        self.guess.as_ref().map(|g| g.as_ref())
+    }
+
+    /// Finds the real roots of the equation in ascending order, looking up
+    /// the other variables in `env`, with [`solve::solve`].
+    ///
+    /// Newton's method uses the derivative given by [`diff`], and
+    /// polynomials are recognised by [`coefficients`].
+    pub fn roots(&self, env: &Environment) -> Result<Vec<f64>> {
+        let guess = self
+            .guess
+            .as_ref()
+            .try_map(|g| g.evaluate_with(env).map(float))?;
+
+        let coefficients = coefficients(self.equation(), &self.var, Self::MAX_DEGREE)
+            .map(|cs| {
+                cs.iter()
+                    .map(|c| c.evaluate_with(env).map(float))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?;
+
+        let derivative = diff(self.equation(), &self.var);
+        let (mut f_env, mut df_env) = (env.clone(), env.clone());
+        solve::solve(
+            |x| {
+                f_env.bind(self.var.as_str(), x);
+                self.equation.evaluate_with(&f_env).map(float)
+            },
+            |x| {
+                df_env.bind(self.var.as_str(), x);
+                derivative.evaluate_with(&df_env).map(float)
+            },
+            coefficients.as_deref(),
+            guess,
+        )
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 12. impls for Builtin.
+// -----------------------------------------------------------------------------
+
+impl Builtin {
+    /// Evaluates this function on its arguments, whose number must be within
+    /// [`Builtin::arity`], or an error is returned.
+    ///
+    /// Random functions draw from a generator seeded with 0. Use
+    /// [`Builtin::evaluate_with`] to draw from another one.
+    pub fn evaluate(&self, args: Vec<Value>) -> Result<Value> {
+        self.evaluate_with(args, &Environment::new())
+    }
+
+    /// Evaluates this function on its arguments, whose number must be within
+    /// [`Builtin::arity`], drawing random numbers from the [`Rng`] of `env`.
+    pub fn evaluate_with(&self, args: Vec<Value>, env: &Environment) -> Result<Value> {
+        let arity = self.arity();
+        if !arity.contains(&args.len()) {
+            let expected = args.len().clamp(*arity.start(), *arity.end());
+            return Err(EvalError::ArgumentCountMismatch(
+                self.name(),
+                expected,
+                args.len(),
+            ));
+        }
+
+        // The number of arguments was checked above.
+        let mut args = args.into_iter();
+        let mut next = || args.next().expect("Builtin called with too few arguments");
+        let invalid = |value: Value| EvalError::InvalidShape(self.name(), value.shape());
+
+        match self {
+            Self::Det => match next() {
+                Value::Matrix(m) => m.determinant().map(Value::Scalar),
+                Value::Scalar(n) => Ok(Value::Scalar(n)),
+                value => Err(invalid(value)),
+            },
+            Self::Inv => match next() {
+                Value::Matrix(m) => m.inverse().map(Value::Matrix),
+                Value::Scalar(n) => Ok(Value::Scalar(Number::Flt(1.0) / n)),
+                value => Err(invalid(value)),
+            },
+            Self::Transpose => match next() {
+                Value::Matrix(m) => Ok(Value::Matrix(m.transpose())),
+                value @ (Value::Scalar(_) | Value::Vector(_)) => Ok(value),
+                value => Err(invalid(value)),
+            },
+            Self::Dot => {
+                let (left, right) = (next(), next());
+                let shape = left.shape();
+                if let Shape::List(_) | Shape::Lambda(_) = shape {
+                    return Err(invalid(left));
+                }
+                match left.zip_with(right, |l, r| l * r)? {
+                    Value::Scalar(n) if shape == Shape::Scalar => Ok(Value::Scalar(n)),
+                    Value::Vector(v) if matches!(shape, Shape::Vector(_)) => {
+                        Ok(Value::Scalar(sum(&v)))
+                    }
+                    Value::Matrix(m) if matches!(shape, Shape::Matrix(..)) => {
+                        Ok(Value::Scalar(sum(m.elements())))
+                    }
+                    // A scalar was broadcast.
+                    value => Err(EvalError::ShapeMismatch(shape, value.shape())),
+                }
+            }
+            Self::Cross => match (next(), next()) {
+                (Value::Vector(a), Value::Vector(b)) if a.len() == 3 && b.len() == 3 => {
+                    Ok(Value::Vector(vec![
+                        a[1] * b[2] - a[2] * b[1],
+                        a[2] * b[0] - a[0] * b[2],
+                        a[0] * b[1] - a[1] * b[0],
+                    ]))
+                }
+                (a @ Value::Vector(_), b) if a.shape() == Shape::Vector(3) => Err(invalid(b)),
+                (a, _) => Err(invalid(a)),
+            },
+            Self::Norm => {
+                let norm = match next() {
+                    Value::Scalar(n) => float(n).abs(),
+                    Value::Vector(v) => v.iter().fold(0.0, |norm: f64, &n| norm.hypot(float(n))),
+                    Value::Matrix(m) => m
+                        .elements()
+                        .iter()
+                        .fold(0.0, |norm: f64, &n| norm.hypot(float(n))),
+                    value => return Err(invalid(value)),
+                };
+                Ok(Value::Scalar(Number::Flt(norm)))
+            }
+            Self::Map => {
+                let (f, items) = (self.lambda(next())?, self.items(next())?);
+                let values = items
+                    .into_iter()
+                    .map(|item| f.call(vec![item]))
+                    .collect::<Result<_>>()?;
+                Ok(Value::List(values))
+            }
+            Self::Filter => {
+                let (f, items) = (self.lambda(next())?, self.items(next())?);
+                let mut kept = Vec::new();
+                for item in items {
+                    if f.call(vec![item.clone()])?.into_scalar()? != Number::Int(0) {
+                        kept.push(item);
+                    }
+                }
+                Ok(Value::List(kept))
+            }
+            Self::Reduce => {
+                let (f, items) = (self.lambda(next())?, self.items(next())?);
+                let mut items = items.into_iter();
+                let mut result = match args.next().or_else(|| items.next()) {
+                    Some(init) => init,
+                    None => return Err(invalid(Value::List(vec![]))),
+                };
+                for item in items {
+                    result = f.call(vec![result, item])?;
+                }
+                Ok(result)
+            }
+            Self::Len => {
+                let len = match next() {
+                    Value::Vector(v) => v.len(),
+                    Value::Matrix(m) => m.rows(),
+                    Value::List(l) => l.len(),
+                    value => return Err(invalid(value)),
+                };
+                Ok(Value::Scalar(Number::Int(len as i128)))
+            }
+            Self::Mean => {
+                let data = floats(&self.data(args.collect(), 1)?);
+                Ok(Value::Scalar(Number::Flt(stats::mean(&data))))
+            }
+            Self::Median => {
+                let data = sorted(self.data(args.collect(), 1)?);
+                let median = match data.len() % 2 {
+                    1 => data[data.len() / 2],
+                    _ => Number::Flt(stats::percentile(&floats(&data), 50.0)),
+                };
+                Ok(Value::Scalar(median))
+            }
+            Self::Mode => {
+                // The first of the longest runs of equal values.
+                let data = sorted(self.data(args.collect(), 1)?);
+                let mut mode = (data[0], 0);
+                for run in data.chunk_by(|a, b| a == b) {
+                    if run.len() > mode.1 {
+                        mode = (run[0], run.len());
+                    }
+                }
+                Ok(Value::Scalar(mode.0))
+            }
+            Self::Stdev | Self::Variance => {
+                let variance = stats::sample_variance(&floats(&self.data(args.collect(), 2)?));
+                let value = match self {
+                    Self::Stdev => variance.sqrt(),
+                    _ => variance,
+                };
+                Ok(Value::Scalar(Number::Flt(value)))
+            }
+            Self::Pstdev | Self::Pvariance => {
+                let variance = stats::population_variance(&floats(&self.data(args.collect(), 1)?));
+                let value = match self {
+                    Self::Pstdev => variance.sqrt(),
+                    _ => variance,
+                };
+                Ok(Value::Scalar(Number::Flt(value)))
+            }
+            Self::Percentile => {
+                let data = floats(&sorted(self.data(vec![next()], 1)?));
+                let p = float(next().into_scalar()?);
+                Ok(Value::Scalar(Number::Flt(stats::percentile(&data, p))))
+            }
+            Self::Ncr | Self::Npr => {
+                let n = integer(next().into_scalar()?)?;
+                let r = integer(next().into_scalar()?)?;
+                Ok(Value::Scalar(match self {
+                    Self::Ncr => stats::choose(n, r),
+                    _ => stats::permutations(n, r),
+                }))
+            }
+            Self::Gamma => next().map(|n| match n {
+                Number::Int(n) if n > 0 => match (1..n).try_fold(1i128, i128::checked_mul) {
+                    Some(factorial) => Number::Int(factorial),
+                    None => Number::Flt(stats::gamma(n as f64)),
+                },
+                n => Number::Flt(stats::gamma(float(n))),
+            }),
+            Self::Erf => next().map(|n| Number::Flt(stats::erf(float(n)))),
+            Self::NormPdf | Self::NormCdf => {
+                let x = next();
+                let mut parameter = |default| match args.next() {
+                    Some(value) => value.into_scalar().map(float),
+                    None => Ok(default),
+                };
+                let (mu, sigma) = (parameter(0.0)?, parameter(1.0)?);
+                x.map(|x| {
+                    Number::Flt(match self {
+                        Self::NormPdf => stats::normal_pdf(float(x), mu, sigma),
+                        _ => stats::normal_cdf(float(x), mu, sigma),
+                    })
+                })
+            }
+            Self::BinomPdf | Self::BinomCdf => {
+                let k = integer(next().into_scalar()?)?;
+                let n = integer(next().into_scalar()?)?;
+                let p = float(next().into_scalar()?);
+                Ok(Value::Scalar(Number::Flt(match self {
+                    Self::BinomPdf => stats::binomial_pdf(k, n, p),
+                    _ => stats::binomial_cdf(k, n, p),
+                })))
+            }
+            Self::PoissonPdf | Self::PoissonCdf => {
+                let k = integer(next().into_scalar()?)?;
+                let lambda = float(next().into_scalar()?);
+                Ok(Value::Scalar(Number::Flt(match self {
+                    Self::PoissonPdf => stats::poisson_pdf(k, lambda),
+                    _ => stats::poisson_cdf(k, lambda),
+                })))
+            }
+            Self::Gcd | Self::Lcm => {
+                let a = exact(next().into_scalar()?)?.unsigned_abs();
+                let b = exact(next().into_scalar()?)?.unsigned_abs();
+                Ok(Value::Scalar(match self {
+                    Self::Gcd => unsigned(ntheory::gcd(a, b)),
+                    _ => match ntheory::lcm(a, b) {
+                        Some(lcm) => unsigned(lcm),
+                        None => Number::Flt((a / ntheory::gcd(a, b)) as f64 * b as f64),
+                    },
+                }))
+            }
+            Self::IsPrime => {
+                let n = exact(next().into_scalar()?)?;
+                let prime = n > 0 && ntheory::is_prime(n as u128);
+                Ok(Value::Scalar(Number::Int(prime as i128)))
+            }
+            Self::NextPrime => {
+                let n = exact(next().into_scalar()?)?.max(0) as u128;
+                Ok(Value::Scalar(
+                    ntheory::next_prime(n).map_or(Number::Flt(f64::NAN), unsigned),
+                ))
+            }
+            Self::FactorInt => Ok(Value::Scalar(Number::Int(exact(next().into_scalar()?)?))),
+            Self::Totient => {
+                let n = exact(next().into_scalar()?)?;
+                Ok(Value::Scalar(match n > 0 {
+                    true => unsigned(ntheory::totient(n as u128)),
+                    false => Number::Flt(f64::NAN),
+                }))
+            }
+            Self::PowMod => {
+                let a = exact(next().into_scalar()?)?;
+                let b = exact(next().into_scalar()?)?;
+                let m = exact(next().into_scalar()?)?.unsigned_abs();
+                let base = match b < 0 {
+                    true => ntheory::inverse_mod(residue(a, m), m),
+                    false => (m != 0).then(|| residue(a, m)),
+                };
+                Ok(Value::Scalar(match base {
+                    Some(base) => unsigned(ntheory::pow_mod(base, b.unsigned_abs(), m)),
+                    None => Number::Flt(f64::NAN),
+                }))
+            }
+            Self::ModInv => {
+                let a = exact(next().into_scalar()?)?;
+                let m = exact(next().into_scalar()?)?.unsigned_abs();
+                Ok(Value::Scalar(
+                    ntheory::inverse_mod(residue(a, m), m).map_or(Number::Flt(f64::NAN), unsigned),
+                ))
+            }
+            Self::Rand => Ok(Value::Scalar(Number::Flt(env.rng().uniform()))),
+            Self::RandInt => {
+                let low = integer(next().into_scalar()?)?;
+                let high = integer(next().into_scalar()?)?;
+                Ok(Value::Scalar(match env.rng().integer(low, high) {
+                    Some(n) => Number::Int(n),
+                    None => Number::Flt(f64::NAN),
+                }))
+            }
+            Self::RandN => {
+                let mut parameter = |default| match args.next() {
+                    Some(value) => value.into_scalar().map(float),
+                    None => Ok(default),
+                };
+                let (mu, sigma) = (parameter(0.0)?, parameter(1.0)?);
+                Ok(Value::Scalar(Number::Flt(env.rng().normal(mu, sigma))))
+            }
+            Self::Choose => {
+                let mut items = self.items(next())?;
+                let last = match items.len() {
+                    0 => return Err(invalid(Value::List(items))),
+                    len => len as i128 - 1,
+                };
+                let index = env.rng().integer(0, last).unwrap_or_default();
+                Ok(items.swap_remove(index as usize))
+            }
+
+            // Symbolic functions act on the ASTs of their arguments, which
+            // their values cannot stand for.
+            Self::Diff => Err(EvalError::VariableExpected(self.name())),
+
+            // Polynomials keep their values when they are rewritten.
+            Self::Expand | Self::Factor | Self::Collect => Ok(next()),
+        }
+    }
+
+    /// Checks if this function is pure, i.e. if it always gives the same
+    /// value for the same arguments. Random functions are not.
+    pub fn is_pure(&self) -> bool {
+        !matches!(
+            self,
+            Self::Rand | Self::RandInt | Self::RandN | Self::Choose
+        )
+    }
+
+    /// Checks if this function is symbolic, i.e. if it acts on the ASTs of
+    /// its arguments rather than their values. A call of it is evaluated by
+    /// [`CallNode::rewrite`].
+    pub fn is_symbolic(&self) -> bool {
+        matches!(
+            self,
+            Self::Diff | Self::Expand | Self::Factor | Self::Collect
+        )
+    }
+
+    /// Gets the data passed to a statistical function, either as a single
+    /// list or vector, or as separate arguments. There must be at least
+    /// `min` values.
+    fn data(&self, mut args: Vec<Value>, min: usize) -> Result<Vec<Number>> {
+        let values = match args.pop() {
+            Some(value) if args.is_empty() && value.shape() != Shape::Scalar => {
+                self.items(value)?
+            }
+            Some(value) => {
+                args.push(value);
+                args
+            }
+            None => args,
+        };
+
+        let data = values
+            .into_iter()
+            .map(Value::into_scalar)
+            .collect::<Result<Vec<_>>>()?;
+        match data.len() < min {
+            true => Err(EvalError::InvalidShape(
+                self.name(),
+                Shape::List(data.len()),
+            )),
+            false => Ok(data),
+        }
+    }
+
+    /// Gets the lambda passed to a higher-order function.
+    fn lambda(&self, value: Value) -> Result<Lambda> {
+        match value {
+            Value::Lambda(f) => Ok(f),
+            value => Err(EvalError::InvalidShape(self.name(), value.shape())),
+        }
+    }
+
+    /// Gets the elements of the list or vector passed to a higher-order
+    /// function.
+    fn items(&self, value: Value) -> Result<Vec<Value>> {
+        match value {
+            Value::List(l) => Ok(l),
+            Value::Vector(v) => Ok(v.into_iter().map(Value::Scalar).collect()),
+            value => Err(EvalError::InvalidShape(self.name(), value.shape())),
+        }
+    }
+
+    /// Gets the name of this [`Builtin`] as written in source code.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Det => "det",
+            Self::Inv => "inv",
+            Self::Transpose => "transpose",
+            Self::Dot => "dot",
+            Self::Cross => "cross",
+            Self::Norm => "norm",
+            Self::Map => "map",
+            Self::Filter => "filter",
+            Self::Reduce => "reduce",
+            Self::Len => "len",
+            Self::Mean => "mean",
+            Self::Median => "median",
+            Self::Mode => "mode",
+            Self::Stdev => "stdev",
+            Self::Pstdev => "pstdev",
+            Self::Variance => "variance",
+            Self::Pvariance => "pvariance",
+            Self::Percentile => "percentile",
+            Self::Ncr => "nCr",
+            Self::Npr => "nPr",
+            Self::Gamma => "gamma",
+            Self::Erf => "erf",
+            Self::NormPdf => "normpdf",
+            Self::NormCdf => "normcdf",
+            Self::BinomPdf => "binompdf",
+            Self::BinomCdf => "binomcdf",
+            Self::PoissonPdf => "poissonpdf",
+            Self::PoissonCdf => "poissoncdf",
+            Self::Gcd => "gcd",
+            Self::Lcm => "lcm",
+            Self::IsPrime => "isprime",
+            Self::NextPrime => "nextprime",
+            Self::FactorInt => "factorint",
+            Self::Totient => "totient",
+            Self::PowMod => "powmod",
+            Self::ModInv => "modinv",
+            Self::Rand => "rand",
+            Self::RandInt => "randint",
+            Self::RandN => "randn",
+            Self::Choose => "choose",
+            Self::Diff => "diff",
+            Self::Expand => "expand",
+            Self::Factor => "factor",
+            Self::Collect => "collect",
+        }
+    }
+
+    /// Gets the numbers of arguments this [`Builtin`] takes.
+    pub fn arity(&self) -> RangeInclusive<usize> {
+        match self {
+            Self::Rand => 0..=0,
+            Self::Det | Self::Inv | Self::Transpose | Self::Norm | Self::Len => 1..=1,
+            Self::Gamma | Self::Erf | Self::Choose => 1..=1,
+            Self::IsPrime | Self::NextPrime | Self::FactorInt | Self::Totient => 1..=1,
+            Self::Expand | Self::Factor => 1..=1,
+            Self::Dot | Self::Cross | Self::Map | Self::Filter => 2..=2,
+            Self::Percentile | Self::Ncr | Self::Npr => 2..=2,
+            Self::PoissonPdf | Self::PoissonCdf | Self::RandInt => 2..=2,
+            Self::Gcd | Self::Lcm | Self::ModInv => 2..=2,
+            Self::Diff | Self::Collect => 2..=2,
+            Self::Reduce => 2..=3,
+            Self::BinomPdf | Self::BinomCdf | Self::PowMod => 3..=3,
+            Self::NormPdf | Self::NormCdf => 1..=3,
+            Self::RandN => 0..=2,
+            Self::Mean
+            | Self::Median
+            | Self::Mode
+            | Self::Stdev
+            | Self::Pstdev
+            | Self::Variance
+            | Self::Pvariance => 1..=usize::MAX,
+        }
+    }
+}
+
+impl TryFrom<&str> for Builtin {
+    type Error = ();
+    fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
+        match value {
+            "det" => Ok(Self::Det),
+            "inv" => Ok(Self::Inv),
+            "transpose" => Ok(Self::Transpose),
+            "dot" => Ok(Self::Dot),
+            "cross" => Ok(Self::Cross),
+            "norm" => Ok(Self::Norm),
+            "map" => Ok(Self::Map),
+            "filter" => Ok(Self::Filter),
+            "reduce" => Ok(Self::Reduce),
+            "len" => Ok(Self::Len),
+            "mean" => Ok(Self::Mean),
+            "median" => Ok(Self::Median),
+            "mode" => Ok(Self::Mode),
+            "stdev" => Ok(Self::Stdev),
+            "pstdev" => Ok(Self::Pstdev),
+            "variance" => Ok(Self::Variance),
+            "pvariance" => Ok(Self::Pvariance),
+            "percentile" => Ok(Self::Percentile),
+            "nCr" => Ok(Self::Ncr),
+            "nPr" => Ok(Self::Npr),
+            "gamma" => Ok(Self::Gamma),
+            "erf" => Ok(Self::Erf),
+            "normpdf" => Ok(Self::NormPdf),
+            "normcdf" => Ok(Self::NormCdf),
+            "binompdf" => Ok(Self::BinomPdf),
+            "binomcdf" => Ok(Self::BinomCdf),
+            "poissonpdf" => Ok(Self::PoissonPdf),
+            "poissoncdf" => Ok(Self::PoissonCdf),
+            "gcd" => Ok(Self::Gcd),
+            "lcm" => Ok(Self::Lcm),
+            "isprime" => Ok(Self::IsPrime),
+            "nextprime" => Ok(Self::NextPrime),
+            "factorint" => Ok(Self::FactorInt),
+            "totient" => Ok(Self::Totient),
+            "powmod" => Ok(Self::PowMod),
+            "modinv" => Ok(Self::ModInv),
+            "rand" => Ok(Self::Rand),
+            "randint" => Ok(Self::RandInt),
+            "randn" => Ok(Self::RandN),
+            "choose" => Ok(Self::Choose),
+            "diff" => Ok(Self::Diff),
+            "expand" => Ok(Self::Expand),
+            "factor" => Ok(Self::Factor),
+            "collect" => Ok(Self::Collect),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Det => write!(f, "Det"),
+            Self::Inv => write!(f, "Inv"),
+            Self::Transpose => write!(f, "Transpose"),
+            Self::Dot => write!(f, "Dot"),
+            Self::Cross => write!(f, "Cross"),
+            Self::Norm => write!(f, "Norm"),
+            Self::Map => write!(f, "Map"),
+            Self::Filter => write!(f, "Filter"),
+            Self::Reduce => write!(f, "Reduce"),
+            Self::Len => write!(f, "Len"),
+            Self::Mean => write!(f, "Mean"),
+            Self::Median => write!(f, "Median"),
+            Self::Mode => write!(f, "Mode"),
+            Self::Stdev => write!(f, "Stdev"),
+            Self::Pstdev => write!(f, "Pstdev"),
+            Self::Variance => write!(f, "Variance"),
+            Self::Pvariance => write!(f, "Pvariance"),
+            Self::Percentile => write!(f, "Percentile"),
+            Self::Ncr => write!(f, "Ncr"),
+            Self::Npr => write!(f, "Npr"),
+            Self::Gamma => write!(f, "Gamma"),
+            Self::Erf => write!(f, "Erf"),
+            Self::NormPdf => write!(f, "NormPdf"),
+            Self::NormCdf => write!(f, "NormCdf"),
+            Self::BinomPdf => write!(f, "BinomPdf"),
+            Self::BinomCdf => write!(f, "BinomCdf"),
+            Self::PoissonPdf => write!(f, "PoissonPdf"),
+            Self::PoissonCdf => write!(f, "PoissonCdf"),
+            Self::Gcd => write!(f, "Gcd"),
+            Self::Lcm => write!(f, "Lcm"),
+            Self::IsPrime => write!(f, "IsPrime"),
+            Self::NextPrime => write!(f, "NextPrime"),
+            Self::FactorInt => write!(f, "FactorInt"),
+            Self::Totient => write!(f, "Totient"),
+            Self::PowMod => write!(f, "PowMod"),
+            Self::ModInv => write!(f, "ModInv"),
+            Self::Rand => write!(f, "Rand"),
+            Self::RandInt => write!(f, "RandInt"),
+            Self::RandN => write!(f, "RandN"),
+            Self::Choose => write!(f, "Choose"),
+            Self::Diff => write!(f, "Diff"),
+            Self::Expand => write!(f, "Expand"),
+            Self::Factor => write!(f, "Factor"),
+            Self::Collect => write!(f, "Collect"),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 13. impls for VectorNode.
+// -----------------------------------------------------------------------------
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Node for VectorNode {
+    fn evaluate_with(&self, env: &Environment) -> Result<Number> {
+        evaluate_value(self, env)?.into_scalar()
+    }
+
+    fn to_tree(&self) -> Vec<String> {
+        children_tree("Vector".to_string(), self.elements())
+    }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::Vector(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(VectorNode {
+            elements: self.elements.clone(),
+            span: self.span,
+        })
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
+}
+
+impl Display for VectorNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_tree().join("\n"))
+    }
+}
+
+impl VectorNode {
+    /// Creates a new [`VectorNode`] from its elements.
+    pub fn new(elements: Vec<NodeBox>) -> VectorNode {
+        Self {
+            elements,
+            span: None,
+        }
+    }
+
+    /// Gets the elements of the vector, or the rows of the matrix.
+    pub fn elements(&self) -> impl ExactSizeIterator<Item = &dyn Node> {
+        self.elements.iter().map(|e| e.as_ref())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 14. impls for CallNode.
+// -----------------------------------------------------------------------------
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Node for CallNode {
+    fn evaluate_with(&self, env: &Environment) -> Result<Number> {
+        evaluate_value(self, env)?.into_scalar()
+    }
+
+    fn to_tree(&self) -> Vec<String> {
+        children_tree(format!("Func({})", self.func), self.args())
+    }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::Call(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(CallNode {
+            func: self.func,
+            args: self.args.clone(),
+            span: self.span,
+        })
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
+}
+
+impl Display for CallNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_tree().join("\n"))
+    }
+}
+
+impl CallNode {
+    /// Creates a new [`CallNode`], calling `func` on `args`.
+    pub fn new(func: Builtin, args: Vec<NodeBox>) -> CallNode {
+        Self {
+            func,
+            args,
+            span: None,
+        }
+    }
+
+    /// Gets the function called by this [`CallNode`].
+    pub fn func(&self) -> Builtin {
+        self.func
+    }
+
+    /// Gets the arguments of the function.
+    pub fn args(&self) -> impl ExactSizeIterator<Item = &dyn Node> {
+        self.args.iter().map(|a| a.as_ref())
+    }
+
+    /// Rewrites a call of a symbolic function (see [`Builtin::is_symbolic`])
+    /// into the expression it stands for, e.g. `2x` for `diff(x^2, x)` or
+    /// `x^2 + 2x + 1` for `expand((x + 1)^2)`, after
+    /// rewriting the calls in its arguments. A call of any other function is
+    /// kept, with its arguments rewritten.
+    pub fn rewrite(&self) -> Result<NodeBox> {
+        let args: Vec<_> = self.args().map(rewrite).collect();
+        if !self.func.is_symbolic() {
+            return Ok(Box::new(CallNode::new(self.func, args)));
+        }
+
+        let arity = self.func.arity();
+        if !arity.contains(&args.len()) {
+            let expected = args.len().clamp(*arity.start(), *arity.end());
+            return Err(EvalError::ArgumentCountMismatch(
+                self.func.name(),
+                expected,
+                args.len(),
+            ));
+        }
+
+        let var = |i: usize| match args[i].view() {
+            NodeView::Variable(v) => Ok(v.name()),
+            _ => Err(EvalError::VariableExpected(self.func.name())),
+        };
+        match self.func {
+            Builtin::Diff => Ok(diff(args[0].as_ref(), var(1)?)),
+            Builtin::Expand => Ok(expand(args[0].as_ref())),
+            Builtin::Factor => Ok(factor(args[0].as_ref())),
+            Builtin::Collect => Ok(collect(args[0].as_ref(), var(1)?)),
+            _ => unreachable!("{} is not symbolic", self.func),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 15. impls for ListNode.
+// -----------------------------------------------------------------------------
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Node for ListNode {
+    fn evaluate_with(&self, env: &Environment) -> Result<Number> {
+        evaluate_value(self, env)?.into_scalar()
+    }
+
+    fn to_tree(&self) -> Vec<String> {
+        children_tree("List".to_string(), self.elements())
+    }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::List(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(ListNode {
+            elements: self.elements.clone(),
+            span: self.span,
+        })
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
+}
+
+impl Display for ListNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_tree().join("\n"))
+    }
+}
+
+impl ListNode {
+    /// Creates a new [`ListNode`] from its elements.
+    pub fn new(elements: Vec<NodeBox>) -> ListNode {
+        Self {
+            elements,
+            span: None,
+        }
+    }
+
+    /// Gets the elements of the list.
+    pub fn elements(&self) -> impl ExactSizeIterator<Item = &dyn Node> {
+        self.elements.iter().map(|e| e.as_ref())
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 16. impls for RangeNode.
+// -----------------------------------------------------------------------------
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Node for RangeNode {
+    fn evaluate_with(&self, env: &Environment) -> Result<Number> {
+        evaluate_value(self, env)?.into_scalar()
+    }
+
+    fn to_tree(&self) -> Vec<String> {
+        children_tree("Range".to_string(), [self.start(), self.end()].into_iter())
+    }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::Range(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(RangeNode {
+            start: self.start.clone_box(),
+            end: self.end.clone_box(),
+            span: self.span,
+        })
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
+}
+
+impl Display for RangeNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_tree().join("\n"))
+    }
+}
+
+impl RangeNode {
+    /// Creates a new [`RangeNode`] from `start` to `end` inclusive.
+    pub fn new(start: NodeBox, end: NodeBox) -> RangeNode {
+        Self {
+            start,
+            end,
+            span: None,
+        }
+    }
+
+    /// Gets the first integer of the range.
+    pub fn start(&self) -> &dyn Node {
+        self.start.as_ref()
+    }
+
+    /// Gets the last integer of the range.
+    pub fn end(&self) -> &dyn Node {
+        self.end.as_ref()
+    }
+
+    /// Gets the integers of this [`RangeNode`], looking up variables in
+    /// `env`. The bounds must be whole numbers, as those of a sum are.
+    pub fn integers(&self, env: &Environment) -> Result<RangeInclusive<i128>> {
+        let start = self.start.evaluate_with(env)?;
+        let end = self.end.evaluate_with(env)?;
+        BinderAction::range(start, end)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 17. impls for LambdaNode.
+// -----------------------------------------------------------------------------
+
+#[cfg_attr(feature = "serde", typetag::serde)]
+impl Node for LambdaNode {
+    fn evaluate_with(&self, env: &Environment) -> Result<Number> {
+        evaluate_value(self, env)?.into_scalar()
+    }
+
+    fn to_tree(&self) -> Vec<String> {
+        let label = format!("Lambda({})", self.params.join(", "));
+        children_tree(label, std::iter::once(self.body()))
+    }
+
+    fn view(&self) -> NodeView<'_> {
+        NodeView::Lambda(self)
+    }
+
+    fn clone_box(&self) -> NodeBox {
+        Box::new(LambdaNode {
+            params: self.params.clone(),
+            body: self.body.clone_box(),
+            span: self.span,
+        })
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
+}
+
+impl Display for LambdaNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_tree().join("\n"))
+    }
+}
+
+impl LambdaNode {
+    /// Creates a new [`LambdaNode`] with the given parameters and body.
+    pub fn new<T: Into<String>>(params: impl IntoIterator<Item = T>, body: NodeBox) -> LambdaNode {
+        Self {
+            params: params.into_iter().map(Into::into).collect(),
+            body,
+            span: None,
+        }
+    }
+
+    /// Gets the names of the parameters of the lambda.
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+
+    /// Gets the body of the lambda.
+    pub fn body(&self) -> &dyn Node {
+        self.body.as_ref()
+    }
+}
+
+/// Draws a tree with `label` at the root and `children` below it, the last
+/// one without a rail.
+fn children_tree<'a>(
+    label: String,
+    children: impl ExactSizeIterator<Item = &'a dyn Node>,
+) -> Vec<String> {
+    let len = children.len();
+    let mut tree = vec![label];
+    for (i, child) in children.enumerate() {
+        let rail = if i + 1 < len { "|   " } else { "    " };
+        let mut child_tree = child.to_tree();
+        child_tree[0].insert_str(0, "`-- ");
+        for line in child_tree.iter_mut().skip(1) {
+            line.insert_str(0, rail);
+        }
+        tree.extend(child_tree);
+    }
+
+    tree
+}
+
+// -----------------------------------------------------------------------------
+// 18. impls for dyn Node.
+// -----------------------------------------------------------------------------
+
+impl<'a> PartialEq for dyn Node + 'a {
+    /// Compares two ASTs structurally, ignoring spans. Numbers are compared
+    /// with [`Number::identical`], so `1` and `1.0` are different nodes.
+    fn eq(&self, other: &Self) -> bool {
+        match (self.view(), other.view()) {
+            (NodeView::Binary(a), NodeView::Binary(b)) => {
+                a.actor == b.actor && a.left() == b.left() && a.right() == b.right()
+            }
+            (NodeView::Unary(a), NodeView::Unary(b)) => {
+                a.actor == b.actor && a.operand() == b.operand()
+            }
+            (NodeView::Plain(a), NodeView::Plain(b)) => a.value.identical(&b.value),
+            (NodeView::Variable(a), NodeView::Variable(b)) => a.name == b.name,
+            (NodeView::Unit(a), NodeView::Unit(b)) => a.unit.name() == b.unit.name(),
+            (NodeView::Binder(a), NodeView::Binder(b)) => {
+                a.actor == b.actor
+                    && a.var == b.var
+                    && a.body() == b.body()
+                    && a.lower() == b.lower()
+                    && a.upper() == b.upper()
+            }
+            (NodeView::Solve(a), NodeView::Solve(b)) => {
+                a.var == b.var && a.equation() == b.equation() && a.guess == b.guess
+            }
+            (NodeView::Vector(a), NodeView::Vector(b)) => a.elements == b.elements,
+            (NodeView::Call(a), NodeView::Call(b)) => a.func == b.func && a.args == b.args,
+            (NodeView::List(a), NodeView::List(b)) => a.elements == b.elements,
+            (NodeView::Range(a), NodeView::Range(b)) => {
+                a.start() == b.start() && a.end() == b.end()
+            }
+            (NodeView::Lambda(a), NodeView::Lambda(b)) => {
+                a.params == b.params && a.body() == b.body()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Clone for NodeBox {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 19. impls for Environment.
+// -----------------------------------------------------------------------------
+
+impl Environment {
+    /// Creates a new [`Environment`] without any variable.
+    pub fn new() -> Environment {
+        Self::default()
+    }
+
+    /// Binds a variable to a value, returning the previous value (if any).
+    pub fn bind<T: Into<String>, U: Into<Number>>(&mut self, name: T, value: U) -> Option<Number> {
+        self.variables.insert(name.into(), value.into())
+    }
+
+    /// Binds a variable to a value, consuming and returning this
+    /// [`Environment`] for chaining.
+    pub fn with<T: Into<String>, U: Into<Number>>(mut self, name: T, value: U) -> Environment {
+        self.bind(name, value);
+        self
+    }
+
+    /// Gets the value of a variable.
+    pub fn get(&self, name: &str) -> Option<Number> {
+        self.variables.get(name).copied()
+    }
+
+    /// Seeds the generator of random numbers with a new [`Rng`], which is no
+    /// longer shared with clones of this [`Environment`].
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// Seeds the generator of random numbers, consuming and returning this
+    /// [`Environment`] for chaining.
+    pub fn with_seed(mut self, seed: u64) -> Environment {
+        self.set_seed(seed);
+        self
+    }
+
+    /// Sets the generator of random numbers, e.g. to share one between
+    /// environments, consuming and returning this [`Environment`] for
+    /// chaining.
+    pub fn with_rng(mut self, rng: Rng) -> Environment {
+        self.rng = rng;
+        self
+    }
+
+    /// Gets the generator of random numbers.
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+}
+
+/// Checks if an AST is pure, i.e. if it evaluates to the same value every
+/// time with the same variables. An AST that calls a random function is not,
+/// so passes over the AST must neither fold, duplicate nor drop it.
+pub fn is_pure(node: &dyn Node) -> bool {
+    match node.view() {
+        NodeView::Binary(b) => is_pure(b.left()) && is_pure(b.right()),
+        NodeView::Unary(u) => is_pure(u.operand()),
+        NodeView::Plain(_) | NodeView::Variable(_) | NodeView::Unit(_) => true,
+        NodeView::Binder(b) => is_pure(b.body()) && is_pure(b.lower()) && is_pure(b.upper()),
+        NodeView::Solve(s) => is_pure(s.equation()) && s.guess().iter().all(|g| is_pure(*g)),
+        NodeView::Vector(v) => v.elements().all(is_pure),
+        NodeView::Call(c) => c.func().is_pure() && c.args().all(is_pure),
+        NodeView::List(l) => l.elements().all(is_pure),
+        NodeView::Range(r) => is_pure(r.start()) && is_pure(r.end()),
+        NodeView::Lambda(l) => is_pure(l.body()),
+    }
+}
+
+/// Rewrites the calls of symbolic functions in an AST, e.g. `diff(x^2, x) + 1`
+/// into `2x + 1`, with [`CallNode::rewrite`]. Calls whose arguments do not
+/// fit the function are kept, so that evaluating them gives the error.
+pub fn rewrite(node: &dyn Node) -> NodeBox {
+    match node.view() {
+        NodeView::Call(c) => c.rewrite().unwrap_or_else(|_| node.clone_box()),
+        _ => polynomial::map_children(node, &rewrite),
+    }
+}
+
+fn float(n: Number) -> f64 {
+    match n {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
+
+/// Converts a whole number to an integer, whether it is an integer or a
+/// float.
+fn integer(n: Number) -> Result<i128> {
+    match n {
+        Number::Int(n) => Ok(n),
+        Number::Flt(f) if f.fract() == 0.0 && f.abs() < 2f64.powi(127) => Ok(f as i128),
+        n => Err(EvalError::IntegerExpected(n)),
+    }
+}
+
+/// Gets an exact integer, rejecting floats even if they are whole numbers.
+fn exact(n: Number) -> Result<i128> {
+    match n {
+        Number::Int(n) => Ok(n),
+        n => Err(EvalError::IntegerExpected(n)),
+    }
+}
+
+/// Converts an unsigned integer to a [`Number`], which is a float if it does
+/// not fit in an [`i128`].
+fn unsigned(n: u128) -> Number {
+    match i128::try_from(n) {
+        Ok(n) => Number::Int(n),
+        Err(_) => Number::Flt(n as f64),
+    }
+}
+
+/// Finds the residue of an integer modulo `m`, from 0 to `m - 1`, or 0 if
+/// `m` is 0.
+fn residue(n: i128, m: u128) -> u128 {
+    match (n < 0, m) {
+        (_, 0) => 0,
+        (false, m) => n as u128 % m,
+        (true, m) => (m - n.unsigned_abs() % m) % m,
+    }
+}
+
+fn floats(values: &[Number]) -> Vec<f64> {
+    values.iter().map(|&n| float(n)).collect()
+}
+
+/// Raises an integer to a non-negative integer power, returning [`None`] if
+/// the exponent is negative or the power overflows.
+fn checked_pow(n: i128, m: i128) -> Option<i128> {
+    u32::try_from(m).ok().and_then(|m| n.checked_pow(m))
+}
+
+/// Sorts numbers in ascending order, with NaNs last.
+fn sorted(mut values: Vec<Number>) -> Vec<Number> {
+    values.sort_by(|a, b| float(*a).total_cmp(&float(*b)));
+    values
+}
+
+fn sum(values: &[Number]) -> Number {
+    values.iter().fold(Number::Int(0), |sum, &n| sum + n)
 }
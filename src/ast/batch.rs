@@ -0,0 +1,309 @@
+//! This module implements batch evaluation of ASTs over columns of values.
+//!
+//! Rather than evaluating an AST once per row, each node is evaluated once
+//! over a whole column, so that every [`BinaryAction`] and [`Function`] runs
+//! in a tight loop that the compiler can vectorise. The results are exactly
+//! those of [`Node::evaluate_with`] on each row:
+//!
+//! - Integer columns stay integer as long as every row does. A column whose
+//!   rows differ in kind, e.g. after an integer division by zero in some
+//!   rows, falls back to evaluating row by row.
+//! - NaN propagates through floats as in IEEE 754: any operation on NaN gives
+//!   NaN, except `1.0^NaN` and `NaN^0.0`, which are 1.
+//! - Division by zero gives NaN, for both integers and floats.
+//! - There are no null values. Rows without a value must be given one, e.g.
+//!   NaN, which then propagates as above.
+//! - Sums, products, integrals and roots of equations are evaluated row by
+//!   row, as their bodies are evaluated many times per row. So are vectors,
+//!   lists, lambdas and built-in functions such as `det`, which must give a
+//!   scalar.
+//! - An AST that is not pure (see [`is_pure`]) is evaluated row by row as a
+//!   whole, drawing random numbers in turn from a single [`Rng`] seeded
+//!   with 0.
+//!
+//! [`Function`]: crate::Function
+
+use crate::{
+    ast::{checked_pow, is_pure, Node, NodeView},
+    BinaryAction, Environment, EvalError, Number, Rng, UnaryAction,
+};
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// A column of values bound to a variable.
+#[derive(Debug, Clone, Copy)]
+pub enum Column<'a> {
+    Int(&'a [i128]),
+    Flt(&'a [f64]),
+}
+
+/// Values of a node over all rows.
+#[derive(Debug)]
+enum Values {
+    Int(Vec<i128>),
+    Flt(Vec<f64>),
+
+    /// Rows of different kinds, evaluated row by row.
+    Mixed(Vec<Number>),
+}
+
+/// Evaluates an AST over columns of values, writing the value of each row to
+/// `output`. Every column must be as long as `output`.
+pub fn evaluate_batch(
+    node: &dyn Node,
+    columns: &[(&str, Column<'_>)],
+    output: &mut [Number],
+) -> Result<()> {
+    for (_, column) in columns {
+        let len = column.len();
+        if len != output.len() {
+            return Err(EvalError::ValueCountMismatch(output.len(), len));
+        }
+    }
+
+    // Random numbers are drawn row after row, rather than node after node.
+    let values = match is_pure(node) {
+        true => evaluate(node, columns, output.len())?,
+        false => {
+            let rng = Rng::default();
+            let rows = (0..output.len())
+                .map(|row| node.evaluate_with(&environment(columns, row).with_rng(rng.clone())))
+                .collect::<Result<Vec<_>>>()?;
+            from_rows(rows)
+        }
+    };
+
+    match values {
+        Values::Int(values) => fill(output, values.into_iter().map(Number::Int)),
+        Values::Flt(values) => fill(output, values.into_iter().map(Number::Flt)),
+        Values::Mixed(values) => output.copy_from_slice(&values),
+    }
+
+    Ok(())
+}
+
+impl Column<'_> {
+    /// Gets the number of rows in this [`Column`].
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Int(values) => values.len(),
+            Self::Flt(values) => values.len(),
+        }
+    }
+
+    /// Checks if this [`Column`] has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'a> From<&'a [i128]> for Column<'a> {
+    fn from(value: &'a [i128]) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl<'a> From<&'a [f64]> for Column<'a> {
+    fn from(value: &'a [f64]) -> Self {
+        Self::Flt(value)
+    }
+}
+
+fn fill(output: &mut [Number], values: impl Iterator<Item = Number>) {
+    for (output, value) in output.iter_mut().zip(values) {
+        *output = value;
+    }
+}
+
+/// Evaluates a node over `len` rows.
+fn evaluate(node: &dyn Node, columns: &[(&str, Column<'_>)], len: usize) -> Result<Values> {
+    let values = match node.view() {
+        NodeView::Plain(p) => match p.value() {
+            Number::Int(n) => Values::Int(vec![n; len]),
+            Number::Flt(n) => Values::Flt(vec![n; len]),
+        },
+        NodeView::Variable(v) => match columns.iter().find(|(name, _)| *name == v.name()) {
+            Some((_, Column::Int(values))) => Values::Int(values.to_vec()),
+            Some((_, Column::Flt(values))) => Values::Flt(values.to_vec()),
+            None => return Err(EvalError::UnboundVariable(v.name().to_string())),
+        },
+        NodeView::Unit(u) => Values::Flt(vec![u.unit().scale(); len]),
+        NodeView::Unary(u) => {
+            let operand = evaluate(u.operand(), columns, len)?;
+            unary(u.actor(), operand)
+        }
+        NodeView::Binary(b) => {
+            let left = evaluate(b.left(), columns, len)?;
+            let right = evaluate(b.right(), columns, len)?;
+            binary(b.actor(), left, right)
+        }
+        NodeView::Binder(_)
+        | NodeView::Solve(_)
+        | NodeView::Vector(_)
+        | NodeView::Call(_)
+        | NodeView::List(_)
+        | NodeView::Range(_)
+        | NodeView::Lambda(_) => {
+            let rows = (0..len)
+                .map(|row| node.evaluate_with(&environment(columns, row)))
+                .collect::<Result<Vec<_>>>()?;
+            from_rows(rows)
+        }
+    };
+
+    Ok(values)
+}
+
+/// Binds each variable to its value in a row.
+fn environment(columns: &[(&str, Column<'_>)], row: usize) -> Environment {
+    let mut env = Environment::new();
+    for (name, column) in columns {
+        match column {
+            Column::Int(values) => env.bind(*name, values[row]),
+            Column::Flt(values) => env.bind(*name, values[row]),
+        };
+    }
+    env
+}
+
+/// Collects values evaluated row by row, keeping the kind of the column if
+/// every row has the same kind.
+fn from_rows(rows: Vec<Number>) -> Values {
+    let ints = rows.iter().map(|n| match n {
+        Number::Int(n) => Some(*n),
+        Number::Flt(_) => None,
+    });
+    if let Some(values) = ints.collect::<Option<Vec<_>>>() {
+        return Values::Int(values);
+    }
+
+    let flts = rows.iter().map(|n| match n {
+        Number::Int(_) => None,
+        Number::Flt(n) => Some(*n),
+    });
+    match flts.collect::<Option<Vec<_>>>() {
+        Some(values) => Values::Flt(values),
+        None => Values::Mixed(rows),
+    }
+}
+
+fn unary(actor: UnaryAction, operand: Values) -> Values {
+    match (actor, operand) {
+        (UnaryAction::Iden, operand) => operand,
+        // Negating `i128::MIN` gives a float in that row.
+        (UnaryAction::Neg, Values::Int(values)) if values.contains(&i128::MIN) => {
+            unary(actor, Values::Mixed(to_mixed(Values::Int(values))))
+        }
+        (UnaryAction::Neg, Values::Int(mut values)) => {
+            values.iter_mut().for_each(|n| *n = -*n);
+            Values::Int(values)
+        }
+        (UnaryAction::Neg, Values::Flt(mut values)) => {
+            values.iter_mut().for_each(|n| *n = -*n);
+            Values::Flt(values)
+        }
+        (UnaryAction::Func(func), Values::Int(values)) => {
+            let mut values = to_flt(values);
+            func.evaluate_slice(&mut values);
+            Values::Flt(values)
+        }
+        (UnaryAction::Func(func), Values::Flt(mut values)) => {
+            func.evaluate_slice(&mut values);
+            Values::Flt(values)
+        }
+        (actor, Values::Mixed(mut values)) => {
+            values.iter_mut().for_each(|n| *n = actor.evaluate(*n));
+            Values::Mixed(values)
+        }
+    }
+}
+
+fn binary(actor: BinaryAction, left: Values, right: Values) -> Values {
+    // The uncertainty is ignored, keeping the kind of the left operand.
+    if actor == BinaryAction::PlusMinus {
+        return left;
+    }
+
+    match (left, right) {
+        (Values::Int(left), Values::Int(right)) => binary_int(actor, left, right),
+        (Values::Int(left), Values::Flt(right)) => binary_flt(actor, to_flt(left), right),
+        (Values::Flt(left), Values::Int(right)) => binary_flt(actor, left, to_flt(right)),
+        (Values::Flt(left), Values::Flt(right)) => binary_flt(actor, left, right),
+        (left, right) => row_by_row(actor, left, right),
+    }
+}
+
+fn binary_int(actor: BinaryAction, left: Vec<i128>, right: Vec<i128>) -> Values {
+    // Rows that overflow give floats, and so do negative exponents, while
+    // division by zero gives NaN, so those fall back to row by row.
+    let checked: fn(i128, i128) -> Option<i128> = match actor {
+        BinaryAction::Add => i128::checked_add,
+        BinaryAction::Sub | BinaryAction::Equate => i128::checked_sub,
+        BinaryAction::Mul => i128::checked_mul,
+        BinaryAction::Div => i128::checked_div,
+        BinaryAction::LeftDiv => return binary_int(BinaryAction::Div, right, left),
+        BinaryAction::Pow if right.iter().all(|&r| r < 0) => {
+            return binary_flt(actor, to_flt(left), to_flt(right))
+        }
+        BinaryAction::Pow => checked_pow,
+        BinaryAction::PlusMinus => return Values::Int(left),
+
+        // Conversions divide as floats.
+        BinaryAction::Convert => return binary_flt(actor, to_flt(left), to_flt(right)),
+    };
+
+    let rows = left.iter().zip(&right).map(|(&l, &r)| checked(l, r));
+    match rows.collect::<Option<Vec<_>>>() {
+        Some(values) => Values::Int(values),
+        None => row_by_row(actor, Values::Int(left), Values::Int(right)),
+    }
+}
+
+/// Evaluates an action row by row, for rows of different kinds.
+fn row_by_row(actor: BinaryAction, left: Values, right: Values) -> Values {
+    let mut left = to_mixed(left);
+    for (l, r) in left.iter_mut().zip(to_mixed(right)) {
+        *l = actor.evaluate(*l, r);
+    }
+    Values::Mixed(left)
+}
+
+fn binary_flt(actor: BinaryAction, mut left: Vec<f64>, right: Vec<f64>) -> Values {
+    fn each(left: &mut [f64], right: &[f64], f: impl Fn(f64, f64) -> f64) {
+        for (l, r) in left.iter_mut().zip(right) {
+            *l = f(*l, *r);
+        }
+    }
+
+    match actor {
+        BinaryAction::Add => each(&mut left, &right, |l, r| l + r),
+        BinaryAction::Sub | BinaryAction::Equate => each(&mut left, &right, |l, r| l - r),
+        BinaryAction::Mul => each(&mut left, &right, |l, r| l * r),
+        BinaryAction::Div | BinaryAction::Convert => each(&mut left, &right, |l, r| {
+            // Same comparison with zero as scalar division.
+            if Number::Flt(r) == Number::Int(0) {
+                f64::NAN
+            } else {
+                l / r
+            }
+        }),
+        BinaryAction::LeftDiv => return binary_flt(BinaryAction::Div, right, left),
+        BinaryAction::Pow => each(&mut left, &right, f64::powf),
+        BinaryAction::PlusMinus => (),
+    }
+
+    Values::Flt(left)
+}
+
+fn to_flt(values: Vec<i128>) -> Vec<f64> {
+    values.into_iter().map(|n| n as f64).collect()
+}
+
+fn to_mixed(values: Values) -> Vec<Number> {
+    match values {
+        Values::Int(values) => values.into_iter().map(Number::Int).collect(),
+        Values::Flt(values) => values.into_iter().map(Number::Flt).collect(),
+        Values::Mixed(values) => values,
+    }
+}
@@ -0,0 +1,597 @@
+//! This module implements symbolic differentiation of ASTs.
+//!
+//! Derivatives are taken over the real numbers, so variables are assumed to be
+//! floating-point numbers: the derivative of `x` is `1.0`, which keeps
+//! divisions in the result from being truncated like integer divisions are.
+//!
+//! The bounds of sums and products are integers, so only their bodies are
+//! differentiated. Integrals are differentiated with the Leibniz rule, which
+//! also accounts for their bounds. Roots of equations are differentiated with
+//! the implicit function theorem, and do not depend on their guess as long as
+//! the same root is found.
+//!
+//! Vectors and lists are differentiated element by element, and built-in
+//! functions by their rules in closed form, e.g. `inv(A)'` is
+//! `-inv(A) A' inv(A)`. Integers are constant, and anything without a
+//! derivative in closed form, such as `median` or `rand()`, is differentiated
+//! to NaN unless it is constant.
+
+use std::f64::consts::E;
+
+use crate::{
+    ast::{
+        BinderNode, Builtin, CallNode, Constant, LambdaNode, ListNode, Node, NodeBox, NodeView,
+        RangeNode, Simplifier, SolveNode,
+    },
+    BinaryAction, BinaryNode, BinderAction, Function, Number, PlainNode, UnaryAction, UnaryNode,
+    VariableNode, VectorNode,
+};
+
+/// Differentiates an AST with respect to a variable, returning the simplified
+/// derivative.
+pub fn diff(node: &dyn Node, var: &str) -> NodeBox {
+    let derivative = derive(node, var).unwrap_or_else(|| plain(Number::Int(0)));
+    Simplifier::new()
+        .assume_real(true)
+        .simplify(derivative.as_ref())
+}
+
+/// Checks if an AST depends on a variable.
+pub fn depends_on(node: &dyn Node, var: &str) -> bool {
+    match node.view() {
+        NodeView::Binary(b) => depends_on(b.left(), var) || depends_on(b.right(), var),
+        NodeView::Unary(u) => depends_on(u.operand(), var),
+        NodeView::Plain(_) | NodeView::Unit(_) => false,
+        NodeView::Variable(v) => v.name() == var,
+        NodeView::Binder(b) => {
+            depends_on(b.lower(), var)
+                || depends_on(b.upper(), var)
+                || (b.var() != var && depends_on(b.body(), var))
+        }
+        NodeView::Solve(s) => {
+            s.guess().iter().any(|g| depends_on(*g, var))
+                || (s.var() != var && depends_on(s.equation(), var))
+        }
+        NodeView::Vector(v) => v.elements().any(|e| depends_on(e, var)),
+        NodeView::Call(c) => c.args().any(|a| depends_on(a, var)),
+        NodeView::List(l) => l.elements().any(|e| depends_on(e, var)),
+        NodeView::Range(r) => depends_on(r.start(), var) || depends_on(r.end(), var),
+        NodeView::Lambda(l) => !l.params().iter().any(|p| p == var) && depends_on(l.body(), var),
+    }
+}
+
+/// Differentiates an AST without simplification, returning [`None`] if the
+/// derivative is zero.
+fn derive(node: &dyn Node, var: &str) -> Option<NodeBox> {
+    if !depends_on(node, var) {
+        return None;
+    }
+
+    match node.view() {
+        NodeView::Plain(_) | NodeView::Unit(_) => None,
+        NodeView::Variable(_) => Some(plain(Number::Flt(1.0))),
+        NodeView::Unary(u) => {
+            let operand = u.operand();
+            let d = derive(operand, var);
+            match u.actor() {
+                UnaryAction::Iden => d,
+                UnaryAction::Neg => d.map(neg),
+
+                // Chain rule.
+                UnaryAction::Func(func) => {
+                    d.map(|d| mul(derive_function(func, operand.clone_box()), d))
+                }
+            }
+        }
+        NodeView::Binary(b) => {
+            let (f, g) = (b.left(), b.right());
+            let (df, dg) = (derive(f, var), derive(g, var));
+            match b.actor() {
+                BinaryAction::Add => match (df, dg) {
+                    (Some(df), Some(dg)) => Some(add(df, dg)),
+                    (df, dg) => df.or(dg),
+                },
+                BinaryAction::Sub => match (df, dg) {
+                    (Some(df), Some(dg)) => Some(sub(df, dg)),
+                    (df, dg) => df.or_else(|| dg.map(neg)),
+                },
+
+                // Product rule: (fg)' = f'g + fg'.
+                BinaryAction::Mul => {
+                    let left = df.map(|df| mul(df, g.clone_box()));
+                    let right = dg.map(|dg| mul(f.clone_box(), dg));
+                    match (left, right) {
+                        (Some(left), Some(right)) => Some(add(left, right)),
+                        (left, right) => left.or(right),
+                    }
+                }
+
+                // Quotient rule: (f/g)' = (f'g - fg') / g^2, also for
+                // conversions, which divide.
+                BinaryAction::Div | BinaryAction::Convert => match dg {
+                    None => df.map(|df| div(df, g.clone_box())),
+                    Some(dg) => {
+                        let right = mul(f.clone_box(), dg);
+                        let numerator = match df {
+                            Some(df) => sub(mul(df, g.clone_box()), right),
+                            None => neg(right),
+                        };
+                        Some(div(numerator, pow(g.clone_box(), int(2))))
+                    }
+                },
+
+                // (f = g)' is f' = g', which keeps the residual f' - g'.
+                BinaryAction::Equate => match (df, dg) {
+                    (None, None) => None,
+                    (df, dg) => Some(equate(
+                        df.unwrap_or_else(|| int(0)),
+                        dg.unwrap_or_else(|| int(0)),
+                    )),
+                },
+
+                // (f ± g)' = f', as f ± g evaluates to f.
+                BinaryAction::PlusMinus => df,
+
+                // x = f \ g solves f x = g, so f' x + f x' = g' gives
+                // x' = f \ (g' - f' x).
+                BinaryAction::LeftDiv => match (df, dg) {
+                    (None, dg) => dg.map(|dg| left_div(f.clone_box(), dg)),
+                    (Some(df), dg) => {
+                        let right = mul(df, node.clone_box());
+                        let rhs = match dg {
+                            Some(dg) => sub(dg, right),
+                            None => neg(right),
+                        };
+                        Some(left_div(f.clone_box(), rhs))
+                    }
+                },
+
+                BinaryAction::Pow => match (df, dg) {
+                    // Power rule: (f^c)' = c f^(c - 1) f'.
+                    (Some(df), None) => {
+                        let exponent = sub(g.clone_box(), int(1));
+                        Some(mul(mul(g.clone_box(), pow(f.clone_box(), exponent)), df))
+                    }
+
+                    // Exponential: (c^g)' = c^g ln(c) g'.
+                    (None, Some(dg)) => Some(mul(mul(node.clone_box(), ln(f.clone_box())), dg)),
+
+                    // General case: (f^g)' = f^g (g' ln(f) + g f' / f).
+                    (Some(df), Some(dg)) => {
+                        let left = mul(dg, ln(f.clone_box()));
+                        let right = div(mul(g.clone_box(), df), f.clone_box());
+                        Some(mul(node.clone_box(), add(left, right)))
+                    }
+
+                    (None, None) => None,
+                },
+            }
+        }
+        NodeView::Binder(b) => {
+            // The bound variable shadows `var` within the body.
+            let body = match b.var() == var {
+                true => None,
+                false => derive(b.body(), var),
+            };
+
+            match b.actor() {
+                BinderAction::Sum => body.map(|d| rebind(b, BinderAction::Sum, d)),
+
+                // Product rule: (prod f)' = sum f' * prod_{j < k} f * prod_{j > k} f,
+                // which unlike prod f * sum (f' / f) allows factors of zero.
+                BinderAction::Prod => body.map(|d| {
+                    let k = || Box::new(VariableNode::new(b.var())) as NodeBox;
+                    let factors = |lower, upper| {
+                        let body = b.body().clone_box();
+                        Box::new(BinderNode::new(
+                            BinderAction::Prod,
+                            b.var(),
+                            body,
+                            lower,
+                            upper,
+                        )) as NodeBox
+                    };
+                    let before = factors(b.lower().clone_box(), sub(k(), int(1)));
+                    let after = factors(add(k(), int(1)), b.upper().clone_box());
+                    rebind(b, BinderAction::Sum, mul(mul(d, before), after))
+                }),
+
+                // Leibniz rule: (int_a^b f dt)' = int_a^b f' dt + f(b) b' - f(a) a'.
+                BinderAction::Integrate => {
+                    let at = |bound: &dyn Node| substitute(b.body(), b.var(), bound);
+                    let inner = body.map(|d| rebind(b, BinderAction::Integrate, d));
+                    let upper = derive(b.upper(), var).map(|d| mul(at(b.upper()), d));
+                    let lower = derive(b.lower(), var).map(|d| mul(at(b.lower()), d));
+
+                    let sum = match (inner, upper) {
+                        (Some(inner), Some(upper)) => Some(add(inner, upper)),
+                        (inner, upper) => inner.or(upper),
+                    };
+                    match (sum, lower) {
+                        (Some(sum), Some(lower)) => Some(sub(sum, lower)),
+                        (sum, lower) => sum.or_else(|| lower.map(neg)),
+                    }
+                }
+            }
+        }
+
+        // Implicit function theorem: F(x(p), p) = 0 gives x' = -F_p / F_x at
+        // the root.
+        NodeView::Solve(s) => {
+            if s.var() == var {
+                return None;
+            }
+            let fp = derive(s.equation(), var)?;
+            let fx = derive(s.equation(), s.var()).unwrap_or_else(|| int(0));
+            let at = |d: NodeBox| substitute(d.as_ref(), s.var(), node);
+            Some(neg(div(at(fp), at(fx))))
+        }
+
+        // Element by element, with zeros of the same shape as the elements
+        // that do not depend on `var`.
+        NodeView::Vector(v) => Some(Box::new(VectorNode::new(
+            v.elements()
+                .map(|e| derive(e, var).unwrap_or_else(|| mul(int(0), e.clone_box())))
+                .collect(),
+        ))),
+
+        NodeView::List(l) => Some(Box::new(ListNode::new(
+            l.elements()
+                .map(|e| derive(e, var).unwrap_or_else(|| mul(int(0), e.clone_box())))
+                .collect(),
+        ))),
+
+        // Ranges are made of integers, which are constant.
+        NodeView::Range(_) => Some(mul(int(0), node.clone_box())),
+
+        // The parameters shadow `var`, so the body depends on it.
+        NodeView::Lambda(l) => {
+            let body = derive(l.body(), var).unwrap_or_else(|| int(0));
+            Some(Box::new(LambdaNode::new(l.params(), body)))
+        }
+
+        NodeView::Call(c) => {
+            let args: Vec<_> = c.args().collect();
+            let arg = |i: usize| args[i].clone_box();
+            let d = |i: usize| derive(args[i], var);
+            match c.func() {
+                Builtin::Transpose => d(0).map(|d| builtin(Builtin::Transpose, vec![d])),
+
+                // Jacobi's formula: det(A)' = det(A) tr(inv(A) A').
+                Builtin::Det => d(0).map(|d| {
+                    let inverse = builtin(Builtin::Inv, vec![arg(0)]);
+                    let transposed = builtin(Builtin::Transpose, vec![inverse]);
+                    mul(node.clone_box(), builtin(Builtin::Dot, vec![transposed, d]))
+                }),
+
+                // inv(A)' = -inv(A) A' inv(A).
+                Builtin::Inv => d(0).map(|d| neg(mul(mul(node.clone_box(), d), node.clone_box()))),
+
+                // Product rule, keeping the order of the operands.
+                Builtin::Dot | Builtin::Cross => {
+                    let left = d(0).map(|d| builtin(c.func(), vec![d, arg(1)]));
+                    let right = d(1).map(|d| builtin(c.func(), vec![arg(0), d]));
+                    match (left, right) {
+                        (Some(left), Some(right)) => Some(add(left, right)),
+                        (left, right) => left.or(right),
+                    }
+                }
+
+                // norm(v)' = dot(v, v') / norm(v).
+                Builtin::Norm => {
+                    d(0).map(|d| div(builtin(Builtin::Dot, vec![arg(0), d]), node.clone_box()))
+                }
+
+                // Lengths are integers, which are constant.
+                Builtin::Len => None,
+
+                // Chain rule: map(f, xs)' = map(f', xs) + map(∂f/∂x, xs) xs',
+                // where f' is taken with respect to `var` and ∂f/∂x with
+                // respect to the parameter of f.
+                Builtin::Map => {
+                    let outer = d(0).map(|d| builtin(Builtin::Map, vec![d, arg(1)]));
+                    let inner = match (args[0].view(), d(1)) {
+                        (_, None) => None,
+                        (NodeView::Lambda(f), Some(d)) if f.params().len() == 1 => {
+                            derive(f.body(), &f.params()[0]).map(|body| {
+                                let f = Box::new(LambdaNode::new(f.params(), body));
+                                mul(builtin(Builtin::Map, vec![f, arg(1)]), d)
+                            })
+                        }
+                        (_, Some(_)) => Some(nan(node)),
+                    };
+                    match (outer, inner) {
+                        (Some(outer), Some(inner)) => Some(add(outer, inner)),
+                        (outer, inner) => outer.or(inner),
+                    }
+                }
+
+                // The kept elements are constant if the list is, whatever the
+                // predicate.
+                Builtin::Filter => match d(1) {
+                    None => Some(mul(int(0), node.clone_box())),
+                    Some(_) => Some(nan(node)),
+                },
+                Builtin::Reduce => Some(nan(node)),
+
+                // The mean is linear, also when its values are a list.
+                Builtin::Mean => Some(builtin(
+                    Builtin::Mean,
+                    (0..args.len())
+                        .map(|i| d(i).unwrap_or_else(|| mul(int(0), arg(i))))
+                        .collect(),
+                )),
+
+                // erf(u)' = 2 / π^0.5 e^(-u^2).
+                Builtin::Erf => d(0).map(|d| {
+                    let scale = div(
+                        int(2),
+                        sqrt(Box::new(PlainNode::from_constant(Constant::Pi))),
+                    );
+                    let exponential = pow(plain(Number::Flt(E)), neg(pow(arg(0), int(2))));
+                    mul(mul(scale, exponential), d)
+                }),
+
+                // normcdf(x)' = normpdf(x) and normpdf(x)' = -(x - mu) /
+                // sigma^2 normpdf(x), with respect to x alone.
+                Builtin::NormPdf | Builtin::NormCdf
+                    if !args[1..].iter().any(|a| depends_on(*a, var)) =>
+                {
+                    d(0).map(|d| {
+                        let pdf = builtin(
+                            Builtin::NormPdf,
+                            args.iter().map(|a| a.clone_box()).collect(),
+                        );
+                        let factor = match c.func() {
+                            Builtin::NormCdf => pdf,
+                            _ => {
+                                let mu = args.get(1).map_or_else(|| int(0), |a| a.clone_box());
+                                let sigma = args.get(2).map_or_else(|| int(1), |a| a.clone_box());
+                                let z = div(sub(arg(0), mu), pow(sigma, int(2)));
+                                neg(mul(z, pdf))
+                            }
+                        };
+                        mul(factor, d)
+                    })
+                }
+
+                // Integers are constant.
+                Builtin::Ncr
+                | Builtin::Npr
+                | Builtin::Gcd
+                | Builtin::Lcm
+                | Builtin::IsPrime
+                | Builtin::NextPrime
+                | Builtin::FactorInt
+                | Builtin::Totient
+                | Builtin::PowMod
+                | Builtin::ModInv => None,
+
+                Builtin::Median
+                | Builtin::Mode
+                | Builtin::Stdev
+                | Builtin::Pstdev
+                | Builtin::Variance
+                | Builtin::Pvariance
+                | Builtin::Percentile
+                | Builtin::Gamma
+                | Builtin::NormPdf
+                | Builtin::NormCdf
+                | Builtin::BinomPdf
+                | Builtin::BinomCdf
+                | Builtin::PoissonPdf
+                | Builtin::PoissonCdf => Some(nan(node)),
+
+                // Random numbers have no derivative.
+                Builtin::Rand | Builtin::RandInt | Builtin::RandN | Builtin::Choose => {
+                    Some(nan(node))
+                }
+
+                // Symbolic functions are differentiated as what they stand
+                // for, e.g. a second derivative for nested `diff`.
+                Builtin::Diff | Builtin::Expand | Builtin::Factor | Builtin::Collect => {
+                    match c.rewrite() {
+                        Ok(form) => derive(form.as_ref(), var),
+                        Err(_) => Some(nan(node)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Replaces the free occurrences of a variable by `value`.
+fn substitute(node: &dyn Node, var: &str, value: &dyn Node) -> NodeBox {
+    match node.view() {
+        NodeView::Variable(v) if v.name() == var => value.clone_box(),
+        NodeView::Plain(_) | NodeView::Variable(_) | NodeView::Unit(_) => node.clone_box(),
+
+        // The variable of a symbolic function may be `var` itself, as in
+        // `diff(x^2, x)`, so what the call stands for is substituted into.
+        NodeView::Call(c) if c.func().is_symbolic() => match c.rewrite() {
+            Ok(form) => substitute(form.as_ref(), var, value),
+            Err(_) => node.clone_box(),
+        },
+        NodeView::Unary(u) => Box::new(UnaryNode::new(
+            u.actor(),
+            substitute(u.operand(), var, value),
+        )),
+        NodeView::Binary(b) => Box::new(BinaryNode::new(
+            substitute(b.left(), var, value),
+            b.actor(),
+            substitute(b.right(), var, value),
+        )),
+        NodeView::Binder(b) => {
+            let body = match b.var() == var {
+                true => b.body().clone_box(),
+                false => substitute(b.body(), var, value),
+            };
+            Box::new(BinderNode::new(
+                b.actor(),
+                b.var(),
+                body,
+                substitute(b.lower(), var, value),
+                substitute(b.upper(), var, value),
+            ))
+        }
+        NodeView::Solve(s) => {
+            let equation = match s.var() == var {
+                true => s.equation().clone_box(),
+                false => substitute(s.equation(), var, value),
+            };
+            let guess = s.guess().map(|g| substitute(g, var, value));
+            Box::new(SolveNode::new(equation, s.var(), guess))
+        }
+        NodeView::Vector(v) => Box::new(VectorNode::new(
+            v.elements().map(|e| substitute(e, var, value)).collect(),
+        )),
+        NodeView::Call(c) => Box::new(CallNode::new(
+            c.func(),
+            c.args().map(|a| substitute(a, var, value)).collect(),
+        )),
+        NodeView::List(l) => Box::new(ListNode::new(
+            l.elements().map(|e| substitute(e, var, value)).collect(),
+        )),
+        NodeView::Range(r) => Box::new(RangeNode::new(
+            substitute(r.start(), var, value),
+            substitute(r.end(), var, value),
+        )),
+        NodeView::Lambda(l) => {
+            let body = match l.params().iter().any(|p| p == var) {
+                true => l.body().clone_box(),
+                false => substitute(l.body(), var, value),
+            };
+            Box::new(LambdaNode::new(l.params(), body))
+        }
+    }
+}
+
+/// Returns the derivative of a [`Function`] at `u`, to be multiplied by the
+/// derivative of `u` by the chain rule.
+fn derive_function(func: Function, u: NodeBox) -> NodeBox {
+    match func {
+        // sin(u)' = cos(u)
+        Function::Sin => call(Function::Cos, u),
+
+        // cos(u)' = -sin(u)
+        Function::Cos => neg(call(Function::Sin, u)),
+
+        // tan(u)' = sec(u)^2
+        Function::Tan => pow(call(Function::Sec, u), int(2)),
+
+        // sec(u)' = sec(u) tan(u)
+        Function::Sec => mul(call(Function::Sec, u.clone()), call(Function::Tan, u)),
+
+        // csc(u)' = -csc(u) cot(u)
+        Function::Csc => neg(mul(call(Function::Csc, u.clone()), call(Function::Cot, u))),
+
+        // cot(u)' = -csc(u)^2
+        Function::Cot => neg(pow(call(Function::Csc, u), int(2))),
+
+        // asin(u)' = 1 / (1 - u^2)^0.5
+        Function::Asin => div(int(1), sqrt(sub(int(1), pow(u, int(2))))),
+
+        // acos(u)' = -1 / (1 - u^2)^0.5
+        Function::Acos => neg(div(int(1), sqrt(sub(int(1), pow(u, int(2)))))),
+
+        // atan(u)' = 1 / (1 + u^2)
+        Function::Atan => div(int(1), add(int(1), pow(u, int(2)))),
+
+        // asec(u) = acos(1 / u), so asec(u)' = 1 / (u^2 (1 - u^-2)^0.5)
+        Function::Asec => div(
+            int(1),
+            mul(pow(u.clone(), int(2)), sqrt(sub(int(1), pow(u, int(-2))))),
+        ),
+
+        // acsc(u) = asin(1 / u), so acsc(u)' = -1 / (u^2 (1 - u^-2)^0.5)
+        Function::Acsc => neg(div(
+            int(1),
+            mul(pow(u.clone(), int(2)), sqrt(sub(int(1), pow(u, int(-2))))),
+        )),
+
+        // acot(u) = atan(1 / u), so acot(u)' = -1 / (u^2 + 1)
+        Function::Acot => neg(div(int(1), add(pow(u, int(2)), int(1)))),
+
+        // ln(u)' = 1 / u
+        Function::Ln => div(int(1), u),
+
+        // sqrt(u)' = 1 / (2 sqrt(u))
+        Function::Sqrt => div(int(1), mul(int(2), call(Function::Sqrt, u))),
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Helpers for building nodes.
+// -----------------------------------------------------------------------------
+
+fn plain(n: Number) -> NodeBox {
+    Box::new(PlainNode::new(n))
+}
+
+/// Builds a [`BinderNode`] with the variable and bounds of `b`.
+fn rebind(b: &BinderNode, actor: BinderAction, body: NodeBox) -> NodeBox {
+    Box::new(BinderNode::new(
+        actor,
+        b.var(),
+        body,
+        b.lower().clone_box(),
+        b.upper().clone_box(),
+    ))
+}
+
+/// Builds NaN in the shape of `node`, for derivatives without a closed form.
+fn nan(node: &dyn Node) -> NodeBox {
+    mul(plain(Number::Flt(f64::NAN)), node.clone_box())
+}
+
+fn int(n: i128) -> NodeBox {
+    plain(Number::Int(n))
+}
+
+fn add(left: NodeBox, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, BinaryAction::Add, right))
+}
+
+fn sub(left: NodeBox, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, BinaryAction::Sub, right))
+}
+
+fn equate(left: NodeBox, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, BinaryAction::Equate, right))
+}
+
+fn mul(left: NodeBox, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, BinaryAction::Mul, right))
+}
+
+fn div(left: NodeBox, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, BinaryAction::Div, right))
+}
+
+fn left_div(left: NodeBox, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, BinaryAction::LeftDiv, right))
+}
+
+fn pow(left: NodeBox, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, BinaryAction::Pow, right))
+}
+
+fn sqrt(operand: NodeBox) -> NodeBox {
+    pow(operand, plain(Number::Flt(0.5)))
+}
+
+fn neg(operand: NodeBox) -> NodeBox {
+    Box::new(UnaryNode::new(UnaryAction::Neg, operand))
+}
+
+fn call(func: Function, operand: NodeBox) -> NodeBox {
+    Box::new(UnaryNode::new(UnaryAction::Func(func), operand))
+}
+
+fn ln(operand: NodeBox) -> NodeBox {
+    call(Function::Ln, operand)
+}
+
+fn builtin(func: Builtin, args: Vec<NodeBox>) -> NodeBox {
+    Box::new(CallNode::new(func, args))
+}
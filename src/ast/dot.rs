@@ -0,0 +1,127 @@
+//! This module implements exporting ASTs as Graphviz DOT graphs.
+//!
+//! The output is plain text, so rendering it is up to the caller, e.g.
+//! `tilted --dot "1 + 2x" | dot -Tsvg > ast.svg`.
+
+use std::fmt::Write;
+
+use crate::{
+    ast::{evaluate_value, Node, NodeView},
+    BinaryAction, Environment, UnaryAction,
+};
+
+/// Exports an AST as a Graphviz `digraph`.
+pub fn to_dot(node: &dyn Node) -> String {
+    DotPrinter::new().print(node)
+}
+
+/// Configurable exporter of Graphviz DOT graphs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DotPrinter<'a> {
+    /// Environment to evaluate nodes with, if they are to be annotated.
+    annotations: Option<&'a Environment>,
+}
+
+impl<'a> DotPrinter<'a> {
+    /// Creates a new [`DotPrinter`] that labels nodes with their actions and
+    /// values only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Annotates each node with its value, evaluated with `env`, and its
+    /// [`Span`](crate::Span) in source code.
+    pub fn annotate(mut self, env: &'a Environment) -> Self {
+        self.annotations = Some(env);
+        self
+    }
+
+    /// Exports an AST as a Graphviz `digraph`.
+    pub fn print(&self, node: &dyn Node) -> String {
+        let mut graph = String::from("digraph ast {\n    ordering=out;\n");
+        self.write(node, &mut graph, &mut 0);
+        graph.push_str("}\n");
+        graph
+    }
+
+    /// Writes a node and its sub-nodes, returning the ID of the node. Nodes
+    /// are numbered in pre-order, starting from `next_id`.
+    fn write(&self, node: &dyn Node, graph: &mut String, next_id: &mut usize) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+
+        let (label, children) = match node.view() {
+            NodeView::Binary(b) => {
+                let actor = match b.actor() {
+                    BinaryAction::Add => "+",
+                    BinaryAction::Sub => "-",
+                    BinaryAction::Mul => "*",
+                    BinaryAction::Div => "/",
+                    BinaryAction::Pow => "^",
+                    BinaryAction::PlusMinus => "±",
+                    BinaryAction::Convert => "to",
+                    BinaryAction::Equate => "=",
+                    BinaryAction::LeftDiv => "\\",
+                };
+                (actor.to_string(), vec![b.left(), b.right()])
+            }
+            NodeView::Unary(u) => {
+                let actor = match u.actor() {
+                    UnaryAction::Neg => "-",
+                    UnaryAction::Iden => "+",
+                    UnaryAction::Func(func) => func.name(),
+                };
+                (actor.to_string(), vec![u.operand()])
+            }
+            NodeView::Plain(p) => (p.value().to_string(), vec![]),
+            NodeView::Variable(v) => (v.name().to_string(), vec![]),
+            NodeView::Unit(u) => (u.unit().name().to_string(), vec![]),
+            NodeView::Binder(b) => (
+                format!("{} {}", b.actor().name(), b.var()),
+                vec![b.body(), b.lower(), b.upper()],
+            ),
+            NodeView::Solve(s) => (
+                format!("solve {}", s.var()),
+                std::iter::once(s.equation())
+                    .chain(s.guess().iter().copied())
+                    .collect(),
+            ),
+            NodeView::Vector(v) => ("[]".to_string(), v.elements().collect()),
+            NodeView::Call(c) => (c.func().name().to_string(), c.args().collect()),
+            NodeView::List(l) => ("{}".to_string(), l.elements().collect()),
+            NodeView::Range(r) => ("..".to_string(), vec![r.start(), r.end()]),
+            NodeView::Lambda(l) => (format!("{} ->", l.params().join(", ")), vec![l.body()]),
+        };
+
+        let label = match self.annotations {
+            Some(env) => {
+                let value = match evaluate_value(node, env) {
+                    Ok(value) => value.to_string(),
+                    Err(e) => e.to_string(),
+                };
+                let span = match node.span() {
+                    Some(span) => format!("{}..={}", span.start_index, span.end_index),
+                    None => "none".to_string(),
+                };
+                format!("{}\nvalue: {}\nspan: {}", label, value, span)
+            }
+            None => label,
+        };
+        writeln!(graph, "    n{} [label=\"{}\"];", id, escape(&label)).unwrap();
+
+        for child in children {
+            let child_id = self.write(child, graph, next_id);
+            writeln!(graph, "    n{} -> n{};", id, child_id).unwrap();
+        }
+
+        id
+    }
+}
+
+/// Escapes a label for use in a double-quoted DOT string.
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,308 @@
+//! This module implements forward-mode automatic differentiation of ASTs with
+//! dual numbers.
+//!
+//! Every node is evaluated into a [`Dual`], which carries the value of the
+//! node together with its partial derivatives with respect to some chosen
+//! variables. Unlike [`diff`](super::diff()), no derivative AST is built, so a
+//! gradient costs a single walk over the AST.
+//!
+//! Values are exactly those of [`Node::evaluate_with`]. Derivatives follow the
+//! same rules as [`diff`](super::diff()): they are taken over the real numbers,
+//! so the derivative of `x / 2` is `0.5` even though integer division
+//! truncates. The derivatives of an integral are themselves integrated
+//! numerically, and those of a root of an equation follow from the implicit
+//! function theorem. There are no dual vectors, so vectors, lists and
+//! built-in functions on them, such as `det`, are differentiated with
+//! [`diff`](super::diff()) instead.
+
+use crate::{
+    ast::{diff, BinderNode, Node, NodeView, SolveNode},
+    quadrature, BinaryAction, BinderAction, Environment, EvalError, Function, Number, UnaryAction,
+};
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// A value together with its partial derivatives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dual {
+    /// The value, as given by [`Node::evaluate_with`].
+    value: Number,
+
+    /// Partial derivatives, in the order the variables were given to
+    /// [`evaluate_dual`].
+    derivatives: Vec<f64>,
+}
+
+/// Evaluates an AST, looking up variables in `env`, together with its partial
+/// derivatives with respect to each of `vars`.
+pub fn evaluate_dual(node: &dyn Node, env: &Environment, vars: &[&str]) -> Result<Dual> {
+    match node.view() {
+        NodeView::Plain(p) => Ok(Dual::constant(p.value(), vars.len())),
+        NodeView::Variable(v) => {
+            let value = env
+                .get(v.name())
+                .ok_or_else(|| EvalError::UnboundVariable(v.name().to_string()))?;
+            let derivatives = vars
+                .iter()
+                .map(|var| if *var == v.name() { 1.0 } else { 0.0 })
+                .collect();
+            Ok(Dual { value, derivatives })
+        }
+        NodeView::Unit(u) => Ok(Dual::constant(Number::Flt(u.unit().scale()), vars.len())),
+        NodeView::Unary(u) => {
+            let operand = evaluate_dual(u.operand(), env, vars)?;
+            Ok(unary(u.actor(), operand))
+        }
+        NodeView::Binary(b) => {
+            let left = evaluate_dual(b.left(), env, vars)?;
+            let right = evaluate_dual(b.right(), env, vars)?;
+            Ok(binary(b.actor(), left, right))
+        }
+        NodeView::Binder(b) => binder(b, env, vars),
+        NodeView::Solve(s) => solve(s, env, vars),
+        NodeView::Call(c) if c.func().is_symbolic() => {
+            evaluate_dual(c.rewrite()?.as_ref(), env, vars)
+        }
+        NodeView::Vector(_)
+        | NodeView::Call(_)
+        | NodeView::List(_)
+        | NodeView::Range(_)
+        | NodeView::Lambda(_) => symbolic(node, env, vars),
+    }
+}
+
+impl Dual {
+    /// Creates a [`Dual`] whose derivatives are all zero.
+    fn constant(value: Number, len: usize) -> Dual {
+        Dual {
+            value,
+            derivatives: vec![0.0; len],
+        }
+    }
+
+    /// Gets the value.
+    pub fn value(&self) -> Number {
+        self.value
+    }
+
+    /// Gets the partial derivatives, in the order the variables were given
+    /// to [`evaluate_dual`].
+    pub fn derivatives(&self) -> &[f64] {
+        &self.derivatives
+    }
+
+    /// Checks if all derivatives are zero, i.e. the value does not depend on
+    /// any of the variables.
+    fn is_constant(&self) -> bool {
+        self.derivatives.iter().all(|d| *d == 0.0)
+    }
+
+    /// Multiplies every derivative by a factor.
+    fn scale(mut self, factor: f64) -> Self {
+        self.derivatives.iter_mut().for_each(|d| *d *= factor);
+        self
+    }
+
+    /// Sets each derivative to `f(self, other)` of the derivatives of `self`
+    /// and `other`.
+    fn zip_with(mut self, other: &Dual, f: impl Fn(f64, f64) -> f64) -> Self {
+        for (l, r) in self.derivatives.iter_mut().zip(&other.derivatives) {
+            *l = f(*l, *r);
+        }
+        self
+    }
+}
+
+fn unary(actor: UnaryAction, operand: Dual) -> Dual {
+    let value = actor.evaluate(operand.value);
+    let dual = match actor {
+        UnaryAction::Iden => operand,
+        UnaryAction::Neg => operand.scale(-1.0),
+
+        // Chain rule.
+        UnaryAction::Func(func) => {
+            let factor = derive_function(func, float(operand.value));
+            operand.scale(factor)
+        }
+    };
+
+    Dual { value, ..dual }
+}
+
+fn binary(actor: BinaryAction, left: Dual, right: Dual) -> Dual {
+    let value = actor.evaluate(left.value, right.value);
+    let (f, g) = (float(left.value), float(right.value));
+
+    let dual = match actor {
+        BinaryAction::Add => left.zip_with(&right, |df, dg| df + dg),
+        BinaryAction::Sub | BinaryAction::Equate => left.zip_with(&right, |df, dg| df - dg),
+
+        // Product rule: (fg)' = f'g + fg'.
+        BinaryAction::Mul => left.zip_with(&right, |df, dg| df * g + f * dg),
+
+        // Quotient rule: (f/g)' = (f'g - fg') / g^2, undefined where f/g is.
+        BinaryAction::Div | BinaryAction::Convert if right.value == Number::Int(0) => {
+            left.scale(f64::NAN)
+        }
+        BinaryAction::Div | BinaryAction::Convert => {
+            left.zip_with(&right, |df, dg| (df * g - f * dg) / (g * g))
+        }
+
+        // f \ g is g / f.
+        BinaryAction::LeftDiv => return binary(BinaryAction::Div, right, left),
+
+        // The uncertainty is ignored, as in evaluation.
+        BinaryAction::PlusMinus => left,
+
+        BinaryAction::Pow => match (left.is_constant(), right.is_constant()) {
+            // Power rule: (f^c)' = c f^(c - 1) f'.
+            (_, true) if g == 0.0 => left.scale(0.0),
+            (_, true) => left.scale(g * f.powf(g - 1.0)),
+
+            // Exponential: (c^g)' = c^g ln(c) g'.
+            (true, false) => right.scale(float(value) * f.ln()),
+
+            // General case: (f^g)' = f^g (g' ln(f) + g f' / f).
+            (false, false) => {
+                let h = float(value);
+                left.zip_with(&right, |df, dg| h * (dg * f.ln() + g * df / f))
+            }
+        },
+    };
+
+    Dual { value, ..dual }
+}
+
+/// Evaluates a sum, product or integral. The bound variable shadows any of
+/// `vars` of the same name within the body.
+fn binder(b: &BinderNode, env: &Environment, vars: &[&str]) -> Result<Dual> {
+    let lower = evaluate_dual(b.lower(), env, vars)?;
+    let upper = evaluate_dual(b.upper(), env, vars)?;
+
+    let shadowed = vars.iter().position(|var| *var == b.var());
+    let mut env = env.clone();
+    let mut body = |value: Number| {
+        env.bind(b.var(), value);
+        let mut dual = evaluate_dual(b.body(), &env, vars)?;
+        if let Some(i) = shadowed {
+            dual.derivatives[i] = 0.0;
+        }
+        Ok(dual)
+    };
+
+    match b.actor() {
+        actor @ (BinderAction::Sum | BinderAction::Prod) => {
+            let mut result = Dual::constant(actor.identity(), vars.len());
+            for k in BinderAction::range(lower.value, upper.value)? {
+                let term = body(Number::Int(k))?;
+                let value = actor.accumulate(result.value, term.value);
+                let (f, g) = (float(result.value), float(term.value));
+                result = match actor {
+                    BinderAction::Prod => result.zip_with(&term, |df, dg| df * g + f * dg),
+                    _ => result.zip_with(&term, |df, dg| df + dg),
+                };
+                result.value = value;
+            }
+            Ok(result)
+        }
+
+        // Leibniz rule: (int_a^b f dt)' = int_a^b f' dt + f(b) b' - f(a) a'.
+        BinderAction::Integrate => {
+            let (a, b) = (float(lower.value), float(upper.value));
+            let value = BinderAction::Integrate
+                .evaluate(lower.value, upper.value, |t| body(t).map(|dual| dual.value))?;
+
+            let mut derivatives = Vec::with_capacity(vars.len());
+            for i in 0..vars.len() {
+                let df = |t| body(Number::Flt(t)).map(|dual| dual.derivatives[i]);
+                derivatives.push(quadrature::integrate(df, a, b, quadrature::TOLERANCE)?.value());
+            }
+            let mut result = Dual { value, derivatives };
+
+            // The integrand is only needed at bounds that vary, as it may be
+            // undefined there.
+            if !upper.is_constant() {
+                let fb = float(body(Number::Flt(b))?.value);
+                result = result.zip_with(&upper, |d, db| d + fb * db);
+            }
+            if !lower.is_constant() {
+                let fa = float(body(Number::Flt(a))?.value);
+                result = result.zip_with(&lower, |d, da| d - fa * da);
+            }
+            Ok(result)
+        }
+    }
+}
+
+/// Evaluates a root of an equation. The variable solved for shadows any of
+/// `vars` of the same name within the equation.
+fn solve(s: &SolveNode, env: &Environment, vars: &[&str]) -> Result<Dual> {
+    let value = s.evaluate_with(env)?;
+
+    // Implicit function theorem: F(x(p), p) = 0 gives x' = -F_p / F_x.
+    let mut env = env.clone();
+    env.bind(s.var(), value);
+    let mut extended = vars.to_vec();
+    extended.push(s.var());
+    let equation = evaluate_dual(s.equation(), &env, &extended)?;
+    let fx = equation.derivatives[vars.len()];
+
+    let derivatives = vars
+        .iter()
+        .zip(&equation.derivatives)
+        .map(|(var, fp)| if *var == s.var() { 0.0 } else { -fp / fx })
+        .collect();
+    Ok(Dual { value, derivatives })
+}
+
+/// Evaluates a node that must give a scalar, differentiating it with
+/// [`diff`].
+fn symbolic(node: &dyn Node, env: &Environment, vars: &[&str]) -> Result<Dual> {
+    let value = node.evaluate_with(env)?;
+    let derivatives = vars
+        .iter()
+        .map(|var| diff(node, var).evaluate_with(env).map(float))
+        .collect::<Result<_>>()?;
+    Ok(Dual { value, derivatives })
+}
+
+/// Returns the derivative of a [`Function`] at `u`, to be multiplied by the
+/// derivative of `u` by the chain rule.
+pub(crate) fn derive_function(func: Function, u: f64) -> f64 {
+    match func {
+        Function::Sin => u.cos(),
+        Function::Cos => -u.sin(),
+        Function::Tan => u.cos().powi(2).recip(),
+
+        // sec(u)' = sec(u) tan(u) = sin(u) / cos(u)^2
+        Function::Sec => u.sin() / u.cos().powi(2),
+
+        // csc(u)' = -csc(u) cot(u) = -cos(u) / sin(u)^2
+        Function::Csc => -u.cos() / u.sin().powi(2),
+
+        Function::Cot => -u.sin().powi(2).recip(),
+        Function::Asin => (1.0 - u * u).sqrt().recip(),
+        Function::Acos => -(1.0 - u * u).sqrt().recip(),
+        Function::Atan => (1.0 + u * u).recip(),
+
+        // asec(u) = acos(1 / u), so asec(u)' = 1 / (u^2 (1 - u^-2)^0.5)
+        Function::Asec => (u * u * (1.0 - u.powi(-2)).sqrt()).recip(),
+
+        // acsc(u) = asin(1 / u), so acsc(u)' = -1 / (u^2 (1 - u^-2)^0.5)
+        Function::Acsc => -(u * u * (1.0 - u.powi(-2)).sqrt()).recip(),
+
+        // acot(u) = atan(1 / u), so acot(u)' = -1 / (u^2 + 1)
+        Function::Acot => -(u * u + 1.0).recip(),
+
+        Function::Ln => u.recip(),
+        Function::Sqrt => (2.0 * u.sqrt()).recip(),
+    }
+}
+
+fn float(n: Number) -> f64 {
+    match n {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
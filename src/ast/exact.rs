@@ -0,0 +1,633 @@
+//! This module implements exact evaluation of ASTs, for results such as
+//! `sqrt(2) / 2` rather than `0.7071067811865476`.
+//!
+//! Every node is evaluated into an [`Exact`] value, which is either an
+//! algebraic number, a rational multiple of π, or a float where no exact form
+//! is known. Algebraic numbers are sums of rational multiples of square roots,
+//! e.g. `(sqrt(6) - sqrt(2)) / 4`, so they are closed under arithmetic and
+//! square roots of rationals. Integers and decimals are exact, e.g. `0.1` is
+//! a tenth, and so is division, so `1 / 2` is a half rather than 0. Overflow
+//! of the integers within falls back to floats. The constant `pi` is exact,
+//! while the float `3.141592653589793` is only a float.
+//!
+//! Trigonometric functions are exact at multiples of π/12, e.g. `sin(pi / 6)`
+//! is `1 / 2`, and their poles, e.g. `tan(pi / 2)`, are errors. Sines and
+//! cosines are also exact at odd multiples of π/10, e.g. `cos(pi / 5)` is
+//! `(1 + sqrt(5)) / 4`, but not where their values are nested roots, e.g.
+//! `sin(pi / 5)`, which is a float. Inverse trigonometric functions are exact
+//! at those values, e.g. `acos(sqrt(2) / 2)` is `pi / 4`.
+//! Everything else, including units, sums, products, integrals, equations and
+//! built-in functions, is evaluated with [`Node::evaluate_with`] and is only
+//! exact if it is an integer.
+//!
+//! [`Exact::to_node`] gives an AST of the value, to be printed with
+//! [`to_infix`], [`to_latex`](super::to_latex) or [`to_mathml`](super::to_mathml).
+
+use std::{collections::BTreeMap, f64::consts::PI, fmt::Display};
+
+use crate::{
+    ast::{to_infix, Constant, Node, NodeBox, NodeView},
+    ntheory,
+    polynomial::Rational,
+    BinaryAction, BinaryNode, Environment, EvalError, Function, Number, PlainNode, UnaryAction,
+    UnaryNode,
+};
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// An exact real number, or a float where no exact form is known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exact(Repr);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Repr {
+    /// An algebraic number.
+    Surd(Surd),
+
+    /// A non-zero rational multiple of π.
+    Pi(Rational),
+
+    /// A float.
+    Float(f64),
+}
+
+/// Sum of rational multiples of square roots of distinct square-free
+/// integers, e.g. `1 + 2 sqrt(3)`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Surd {
+    /// Non-zero coefficient of each square root, by radicand. Rationals have
+    /// a radicand of 1.
+    terms: BTreeMap<u128, Rational>,
+}
+
+/// Evaluates an AST exactly, looking up variables in `env`.
+pub fn evaluate_exact(node: &dyn Node, env: &Environment) -> Result<Exact> {
+    let repr = match node.view() {
+        NodeView::Plain(p) => match p.constant() {
+            Some(Constant::Pi) => Repr::Pi(Rational::ONE),
+            None => Repr::literal(p.value()),
+        },
+        NodeView::Variable(v) => env
+            .get(v.name())
+            .map(Repr::literal)
+            .ok_or_else(|| EvalError::UnboundVariable(v.name().to_string()))?,
+        NodeView::Unary(u) => {
+            let operand = evaluate_exact(u.operand(), env)?.0;
+            unary(u.actor(), operand)?
+        }
+        NodeView::Binary(b) => match b.actor() {
+            actor @ (BinaryAction::Add
+            | BinaryAction::Sub
+            | BinaryAction::Mul
+            | BinaryAction::Div
+            | BinaryAction::LeftDiv
+            | BinaryAction::Pow) => {
+                let left = evaluate_exact(b.left(), env)?.0;
+                let right = evaluate_exact(b.right(), env)?.0;
+                binary(actor, left, right)
+            }
+            BinaryAction::PlusMinus | BinaryAction::Convert | BinaryAction::Equate => {
+                Repr::from(node.evaluate_with(env)?)
+            }
+        },
+        NodeView::Call(c) if c.func().is_symbolic() => {
+            evaluate_exact(c.rewrite()?.as_ref(), env)?.0
+        }
+        NodeView::Unit(_)
+        | NodeView::Binder(_)
+        | NodeView::Solve(_)
+        | NodeView::Vector(_)
+        | NodeView::Call(_)
+        | NodeView::List(_)
+        | NodeView::Range(_)
+        | NodeView::Lambda(_) => Repr::from(node.evaluate_with(env)?),
+    };
+    Ok(Exact(repr))
+}
+
+impl Exact {
+    /// Checks if this value is exact, rather than a float.
+    pub fn is_exact(&self) -> bool {
+        !matches!(self.0, Repr::Float(_))
+    }
+
+    /// Gets the nearest float.
+    pub fn to_f64(&self) -> f64 {
+        self.0.to_f64()
+    }
+
+    /// Builds an AST of this value, e.g. `(sqrt(6) - sqrt(2)) / 4`, `2 * pi / 3`
+    /// or a single number. The AST evaluates to this value, except that its
+    /// divisions of integers are truncated by [`Node::evaluate_with`].
+    pub fn to_node(&self) -> NodeBox {
+        match &self.0 {
+            Repr::Surd(surd) => surd.to_node(),
+            Repr::Pi(r) => {
+                let pi: NodeBox = Box::new(PlainNode::from_constant(Constant::Pi));
+                let numerator = match r.numer() {
+                    1 => pi,
+                    -1 => neg(pi),
+                    n => mul(int(n), pi),
+                };
+                match r.denom() {
+                    1 => numerator,
+                    d => div(numerator, int(d)),
+                }
+            }
+            Repr::Float(x) => plain(Number::Flt(*x)),
+        }
+    }
+}
+
+impl Display for Exact {
+    /// Formats as the source code of [`Exact::to_node`], e.g. `sqrt(2) / 2`,
+    /// or as a [`Number`] if this value is a float.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Repr::Float(x) => write!(f, "{}", Number::Flt(x)),
+            _ => write!(f, "{}", to_infix(self.to_node().as_ref())),
+        }
+    }
+}
+
+impl Repr {
+    /// Converts a number written in source code, which is exact unless it is
+    /// a float that is not a short decimal.
+    fn literal(n: Number) -> Repr {
+        match n {
+            Number::Flt(x) => match Rational::from_f64(x) {
+                Some(r) => Repr::Surd(Surd::rational(r)),
+                None => Repr::Float(x),
+            },
+            n => Repr::from(n),
+        }
+    }
+
+    /// Makes a multiple of π, which is zero if `r` is.
+    fn pi(r: Rational) -> Repr {
+        match r.is_zero() {
+            true => Repr::Surd(Surd::default()),
+            false => Repr::Pi(r),
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        match self {
+            Repr::Surd(surd) => surd.to_f64(),
+            Repr::Pi(r) => r.to_f64() * PI,
+            Repr::Float(x) => *x,
+        }
+    }
+
+    /// Gets the multiple of π that this value is, if it is one.
+    fn multiple_of_pi(&self) -> Option<Rational> {
+        match self {
+            Repr::Pi(r) => Some(*r),
+            Repr::Surd(surd) if surd.is_zero() => Some(Rational::ZERO),
+            _ => None,
+        }
+    }
+
+    /// Gets this value as a rational, if it is one.
+    fn as_rational(&self) -> Option<Rational> {
+        match self {
+            Repr::Surd(surd) => surd.as_rational(),
+            _ => None,
+        }
+    }
+}
+
+impl From<Number> for Repr {
+    /// Converts a result of [`Node::evaluate_with`], which is exact only if
+    /// it is an integer.
+    fn from(n: Number) -> Repr {
+        match n {
+            Number::Int(n) => match Rational::integer(n) {
+                Some(r) => Repr::Surd(Surd::rational(r)),
+                None => Repr::Float(n as f64),
+            },
+            Number::Flt(x) => Repr::Float(x),
+        }
+    }
+}
+
+fn unary(actor: UnaryAction, operand: Repr) -> Result<Repr> {
+    if let UnaryAction::Func(func) = actor {
+        if is_pole(func, &operand) {
+            return Err(EvalError::Pole(func.name(), Exact(operand).to_string()));
+        }
+    }
+
+    let exact = match (actor, &operand) {
+        (UnaryAction::Iden, _) => Some(operand.clone()),
+        (UnaryAction::Neg, Repr::Surd(surd)) => Some(Repr::Surd(surd.neg())),
+        (UnaryAction::Neg, Repr::Pi(r)) => Some(Repr::Pi(-*r)),
+        (UnaryAction::Func(func), _) => function(func, &operand),
+        _ => None,
+    };
+
+    Ok(exact.unwrap_or_else(|| Repr::from(actor.evaluate(Number::Flt(operand.to_f64())))))
+}
+
+fn binary(actor: BinaryAction, left: Repr, right: Repr) -> Repr {
+    exact_binary(actor, &left, &right).unwrap_or_else(|| {
+        let (l, r) = (Number::Flt(left.to_f64()), Number::Flt(right.to_f64()));
+        Repr::from(actor.evaluate(l, r))
+    })
+}
+
+/// Applies a [`BinaryAction`] exactly, returning [`None`] if the result has
+/// no exact form or does not fit.
+fn exact_binary(actor: BinaryAction, left: &Repr, right: &Repr) -> Option<Repr> {
+    match (actor, left, right) {
+        (BinaryAction::Add, Repr::Surd(l), Repr::Surd(r)) => l.checked_add(r).map(Repr::Surd),
+        (BinaryAction::Sub, Repr::Surd(l), Repr::Surd(r)) => {
+            l.checked_add(&r.neg()).map(Repr::Surd)
+        }
+        (BinaryAction::Add, _, _) => {
+            let (l, r) = (left.multiple_of_pi()?, right.multiple_of_pi()?);
+            l.checked_add(r).map(Repr::pi)
+        }
+        (BinaryAction::Sub, _, _) => {
+            let (l, r) = (left.multiple_of_pi()?, right.multiple_of_pi()?);
+            l.checked_sub(r).map(Repr::pi)
+        }
+        (BinaryAction::Mul, Repr::Surd(l), Repr::Surd(r)) => l.checked_mul(r).map(Repr::Surd),
+        (BinaryAction::Mul, Repr::Pi(l), _) => right.as_rational()?.checked_mul(*l).map(Repr::pi),
+        (BinaryAction::Mul, _, Repr::Pi(r)) => left.as_rational()?.checked_mul(*r).map(Repr::pi),
+        (BinaryAction::Div, _, _) => divide(left, right),
+        (BinaryAction::LeftDiv, _, _) => divide(right, left),
+        (BinaryAction::Pow, Repr::Surd(l), _) => power(l, right.as_rational()?),
+        _ => None,
+    }
+}
+
+/// Divides exactly, returning [`None`] if the quotient has no exact form,
+/// `right` is zero or the result does not fit.
+fn divide(left: &Repr, right: &Repr) -> Option<Repr> {
+    match (left, right) {
+        (Repr::Surd(l), Repr::Surd(r)) => l.checked_div(r).map(Repr::Surd),
+        (Repr::Pi(l), Repr::Pi(r)) => l.checked_div(*r).map(|q| Repr::Surd(Surd::rational(q))),
+        (Repr::Pi(l), _) => l.checked_div(right.as_rational()?).map(Repr::pi),
+        _ => None,
+    }
+}
+
+/// Raises an algebraic number to a rational power, which is exact for
+/// integer exponents, and for halves of integers if the base is a
+/// non-negative rational.
+fn power(base: &Surd, exponent: Rational) -> Option<Repr> {
+    let (base, exponent) = match exponent.denom() {
+        1 => (base.clone(), exponent.numer()),
+        2 => (Surd::sqrt(base.as_rational()?)?, exponent.numer()),
+        _ => return None,
+    };
+
+    let mut power = match exponent < 0 {
+        true => Surd::rational(Rational::ONE).checked_div(&base)?,
+        false => base,
+    };
+    let mut result = Surd::rational(Rational::ONE);
+    let mut exponent = exponent.unsigned_abs();
+    while exponent != 0 {
+        if exponent & 1 == 1 {
+            result = result.checked_mul(&power)?;
+        }
+        exponent >>= 1;
+        if exponent != 0 {
+            power = power.checked_mul(&power)?;
+        }
+    }
+    Some(Repr::Surd(result))
+}
+
+/// Applies a [`Function`] exactly, returning [`None`] if the result has no
+/// exact form.
+fn function(func: Function, operand: &Repr) -> Option<Repr> {
+    let one = Surd::rational(Rational::ONE);
+    match func {
+        Function::Sin
+        | Function::Cos
+        | Function::Tan
+        | Function::Csc
+        | Function::Sec
+        | Function::Cot => {
+            let k = sixtieths(operand)?;
+            let (sin, cos) = (|| sine(k), || sine(k + 30));
+            let value = match func {
+                Function::Sin => sin()?,
+                Function::Cos => cos()?,
+                Function::Tan => sin()?.checked_div(&cos()?)?,
+                Function::Csc => one.checked_div(&sin()?)?,
+                Function::Sec => one.checked_div(&cos()?)?,
+                _ => cos()?.checked_div(&sin()?)?,
+            };
+            Some(Repr::Surd(value))
+        }
+        Function::Asin
+        | Function::Acos
+        | Function::Atan
+        | Function::Acsc
+        | Function::Asec
+        | Function::Acot => {
+            let x = match operand {
+                Repr::Surd(x) => x.clone(),
+                _ => return None,
+            };
+            let x = match func {
+                Function::Acsc | Function::Asec | Function::Acot => one.checked_div(&x)?,
+                _ => x,
+            };
+
+            // The principal values are kπ/60 for k in these ranges.
+            let k = match func {
+                Function::Asin | Function::Acsc => {
+                    (-30..=30).find(|k| sine(*k).as_ref() == Some(&x))
+                }
+                Function::Acos | Function::Asec => {
+                    (0..=60).find(|k| sine(k + 30).as_ref() == Some(&x))
+                }
+                _ => (-29..=29).find(|k| {
+                    let tan = sine(*k)
+                        .zip(sine(k + 30))
+                        .and_then(|(s, c)| s.checked_div(&c));
+                    tan.as_ref() == Some(&x)
+                }),
+            }?;
+            Rational::new(k, 60).map(Repr::pi)
+        }
+        Function::Sqrt => Surd::sqrt(operand.as_rational()?).map(Repr::Surd),
+        Function::Ln => {
+            (operand.as_rational()? == Rational::ONE).then(|| Repr::Surd(Surd::default()))
+        }
+    }
+}
+
+/// Checks if a [`Function`] is undefined at an exact operand, as `tan` and
+/// `sec` are at odd multiples of π/2, and `csc` and `cot` at multiples of π.
+fn is_pole(func: Function, operand: &Repr) -> bool {
+    match (func, sixtieths(operand)) {
+        (Function::Tan | Function::Sec, Some(k)) => k % 60 == 30,
+        (Function::Csc | Function::Cot, Some(k)) => k % 60 == 0,
+        _ => false,
+    }
+}
+
+/// Finds k where an operand is kπ/60, with 0 <= k < 120, so that multiples
+/// of both π/12 and π/10 are found.
+fn sixtieths(operand: &Repr) -> Option<i128> {
+    let k = operand
+        .multiple_of_pi()?
+        .checked_mul(Rational::integer(60)?)
+        .filter(|k| k.is_integer())?;
+    Some(k.numer().rem_euclid(120))
+}
+
+/// Finds the sine of kπ/60, if it is a sum of square roots, which it is at
+/// multiples of π/12 and odd multiples of π/10.
+fn sine(k: i128) -> Option<Surd> {
+    // sin(x) = -sin(x - π) = sin(π - x).
+    let k = k.rem_euclid(120);
+    let (negative, k) = match k < 60 {
+        true => (false, k),
+        false => (true, k - 60),
+    };
+    let terms: &[(u128, i128, i128)] = match k.min(60 - k) {
+        0 => &[],
+        5 => &[(6, 1, 4), (2, -1, 4)],
+        6 => &[(5, 1, 4), (1, -1, 4)],
+        10 => &[(1, 1, 2)],
+        15 => &[(2, 1, 2)],
+        18 => &[(5, 1, 4), (1, 1, 4)],
+        20 => &[(3, 1, 2)],
+        25 => &[(6, 1, 4), (2, 1, 4)],
+        30 => &[(1, 1, 1)],
+        _ => return None,
+    };
+    let sine = Surd {
+        terms: terms
+            .iter()
+            .map(|(radicand, numer, denom)| (*radicand, Rational::new(*numer, *denom).unwrap()))
+            .collect(),
+    };
+    match negative {
+        true => Some(sine.neg()),
+        false => Some(sine),
+    }
+}
+
+impl Surd {
+    fn rational(r: Rational) -> Surd {
+        let mut surd = Surd::default();
+        if !r.is_zero() {
+            surd.terms.insert(1, r);
+        }
+        surd
+    }
+
+    /// Finds the square root of a rational, returning [`None`] if it is
+    /// negative or does not fit.
+    fn sqrt(r: Rational) -> Option<Surd> {
+        if r.signum() < 0 {
+            return None;
+        }
+
+        // sqrt(p / q) = sqrt(p q) / q, and p q = s^2 f with f square-free.
+        let product = r.numer().checked_mul(r.denom())?.unsigned_abs();
+        let (mut square, mut free) = (1u128, 1u128);
+        for (p, multiplicity) in ntheory::factorise(product) {
+            square = square.checked_mul(p.checked_pow(multiplicity / 2)?)?;
+            if multiplicity % 2 == 1 {
+                free *= p;
+            }
+        }
+
+        let coefficient = Rational::new(i128::try_from(square).ok()?, r.denom())?;
+        let mut surd = Surd::default();
+        if !coefficient.is_zero() {
+            surd.terms.insert(free, coefficient);
+        }
+        Some(surd)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    fn as_rational(&self) -> Option<Rational> {
+        match self.terms.iter().next() {
+            None => Some(Rational::ZERO),
+            Some((1, r)) if self.terms.len() == 1 => Some(*r),
+            _ => None,
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.terms
+            .iter()
+            .map(|(radicand, c)| c.to_f64() * (*radicand as f64).sqrt())
+            .sum()
+    }
+
+    fn neg(&self) -> Surd {
+        Surd {
+            terms: self
+                .terms
+                .iter()
+                .map(|(radicand, c)| (*radicand, -*c))
+                .collect(),
+        }
+    }
+
+    fn checked_add(&self, rhs: &Surd) -> Option<Surd> {
+        let mut sum = self.clone();
+        for (radicand, c) in &rhs.terms {
+            sum.add_term(*radicand, *c)?;
+        }
+        Some(sum)
+    }
+
+    /// Adds a term, removing it if the sum of its coefficients is zero.
+    fn add_term(&mut self, radicand: u128, c: Rational) -> Option<()> {
+        let sum = match self.terms.get(&radicand) {
+            Some(existing) => existing.checked_add(c)?,
+            None => c,
+        };
+        match sum.is_zero() {
+            true => self.terms.remove(&radicand),
+            false => self.terms.insert(radicand, sum),
+        };
+        Some(())
+    }
+
+    fn checked_mul(&self, rhs: &Surd) -> Option<Surd> {
+        let mut product = Surd::default();
+        for (a, c) in &self.terms {
+            for (b, d) in &rhs.terms {
+                // sqrt(a) sqrt(b) = g sqrt(a b / g^2), where g = gcd(a, b).
+                let g = ntheory::gcd(*a, *b);
+                let radicand = (a / g).checked_mul(b / g)?;
+                let c = c
+                    .checked_mul(*d)?
+                    .checked_mul(Rational::integer(i128::try_from(g).ok()?)?)?;
+                product.add_term(radicand, c)?;
+            }
+        }
+        Some(product)
+    }
+
+    /// Divides, returning [`None`] if `rhs` is zero or the result does not
+    /// fit.
+    fn checked_div(&self, rhs: &Surd) -> Option<Surd> {
+        if rhs.is_zero() {
+            return None;
+        }
+
+        // Multiplying by the conjugate with respect to a prime p, i.e. with
+        // sqrt(p) negated, removes sqrt(p) from the denominator. Once every
+        // prime is removed, the denominator is rational.
+        let mut primes: Vec<u128> = rhs
+            .terms
+            .keys()
+            .flat_map(|radicand| ntheory::factorise(*radicand))
+            .map(|(p, _)| p)
+            .collect();
+        primes.sort_unstable();
+        primes.dedup();
+
+        let (mut numerator, mut denominator) = (self.clone(), rhs.clone());
+        for p in primes {
+            let conjugate = denominator.conjugate(p);
+            numerator = numerator.checked_mul(&conjugate)?;
+            denominator = denominator.checked_mul(&conjugate)?;
+        }
+
+        let scale = Surd::rational(denominator.as_rational()?.recip()?);
+        numerator.checked_mul(&scale)
+    }
+
+    /// Negates every square root of a multiple of `p`.
+    fn conjugate(&self, p: u128) -> Surd {
+        let terms = self.terms.iter().map(|(radicand, c)| match radicand % p {
+            0 => (*radicand, -*c),
+            _ => (*radicand, *c),
+        });
+        Surd {
+            terms: terms.collect(),
+        }
+    }
+
+    /// Builds an AST over the least common denominator, with the rational
+    /// term first and positive terms before negative ones, e.g.
+    /// `(sqrt(6) - sqrt(2)) / 4`.
+    fn to_node(&self) -> NodeBox {
+        let denom = self.terms.values().fold(1, |denom, c| {
+            let c = c.denom();
+            denom / ntheory::gcd(denom as u128, c as u128) as i128 * c
+        });
+
+        let mut terms: Vec<(u128, i128)> = self
+            .terms
+            .iter()
+            .rev()
+            .map(|(radicand, c)| (*radicand, c.numer() * (denom / c.denom())))
+            .collect();
+        terms.rotate_right(usize::from(self.terms.contains_key(&1)));
+        terms.sort_by_key(|(_, n)| *n < 0);
+
+        let term = |radicand: u128, n: i128| match (radicand, n) {
+            (1, n) => int(n),
+            (radicand, 1) => sqrt(radicand),
+            (radicand, n) => mul(int(n), sqrt(radicand)),
+        };
+        let mut terms = terms.into_iter();
+        let numerator = match terms.next() {
+            Some((radicand, n)) if n < 0 && radicand != 1 => neg(term(radicand, -n)),
+            Some((radicand, n)) => term(radicand, n),
+            None => int(0),
+        };
+        let numerator = terms.fold(numerator, |sum, (radicand, n)| match n < 0 {
+            true => sub(sum, term(radicand, -n)),
+            false => add(sum, term(radicand, n)),
+        });
+
+        match denom {
+            1 => numerator,
+            denom => div(numerator, int(denom)),
+        }
+    }
+}
+
+fn plain(n: Number) -> NodeBox {
+    Box::new(PlainNode::new(n))
+}
+
+fn int(n: i128) -> NodeBox {
+    plain(Number::Int(n))
+}
+
+fn sqrt(radicand: u128) -> NodeBox {
+    let radicand = int(radicand as i128);
+    Box::new(UnaryNode::new(UnaryAction::Func(Function::Sqrt), radicand))
+}
+
+fn add(left: NodeBox, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, BinaryAction::Add, right))
+}
+
+fn sub(left: NodeBox, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, BinaryAction::Sub, right))
+}
+
+fn mul(left: NodeBox, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, BinaryAction::Mul, right))
+}
+
+fn div(left: NodeBox, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, BinaryAction::Div, right))
+}
+
+fn neg(operand: NodeBox) -> NodeBox {
+    Box::new(UnaryNode::new(UnaryAction::Neg, operand))
+}
@@ -0,0 +1,277 @@
+//! This module implements printing of ASTs back to infix source code.
+//!
+//! The output uses as few parentheses as the [`Parser`](crate::Parser)
+//! allows, and parsing it again gives back an equal AST. There are two
+//! exceptions, as they have no source form of their own:
+//!
+//! - [`UnaryAction::Iden`] nodes are printed as their operand.
+//! - Non-finite floats are printed as expressions that evaluate to them: NaN
+//!   is `(0/0)` and infinity is `2.0^1024`.
+//!
+//! The float closest to π is printed as `pi`, which is parsed as that float.
+
+use crate::{
+    ast::{Node, NodeView},
+    BinaryAction, Number, UnaryAction, Unit,
+};
+
+/// Prints an AST as infix source code with explicit multiplication.
+pub fn to_infix(node: &dyn Node) -> String {
+    InfixPrinter::new().print(node)
+}
+
+/// Configurable printer of infix source code.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InfixPrinter {
+    /// Whether multiplication may be written implicitly, e.g. `2x`.
+    implicit_mul: bool,
+}
+
+/// Binding strength of printed source code, from loosest to tightest. Each
+/// level corresponds to a production of the [`Parser`](crate::Parser).
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Level {
+    /// Lambdas, whose bodies extend as far as possible.
+    Lambda,
+
+    /// `equation`, i.e. `=`.
+    Equation,
+
+    /// `range`, i.e. `..`.
+    Range,
+
+    /// `conversion`, i.e. `to`.
+    Conversion,
+
+    /// `expr`, i.e. addition and subtraction.
+    Expr,
+
+    /// `term`, i.e. multiplication and division.
+    Term,
+
+    /// `factor`, i.e. negation and negative numbers.
+    Factor,
+
+    /// `pow`, i.e. exponentiation.
+    Pow,
+
+    /// `atomic`, i.e. numbers, variables, units, calls, vectors, lists and
+    /// parenthesised expressions.
+    Atomic,
+}
+
+impl InfixPrinter {
+    /// Creates a new [`InfixPrinter`] that writes multiplication explicitly.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes multiplication implicitly wherever the
+    /// [`Parser`](crate::Parser) reads it back as such, e.g. `2x (y + 1)`
+    /// instead of `2 * x * (y + 1)`.
+    pub fn implicit_mul(mut self, implicit_mul: bool) -> Self {
+        self.implicit_mul = implicit_mul;
+        self
+    }
+
+    /// Prints an AST as infix source code.
+    pub fn print(&self, node: &dyn Node) -> String {
+        self.write(node).0
+    }
+
+    /// Prints an AST, returning the source code and its binding strength.
+    fn write(&self, node: &dyn Node) -> (String, Level) {
+        match node.view() {
+            NodeView::Plain(p) => match p.constant() {
+                Some(constant) => (constant.name().to_string(), Level::Atomic),
+                None => number(p.value()),
+            },
+            NodeView::Variable(v) => (v.name().to_string(), Level::Atomic),
+            NodeView::Unit(u) => (u.unit().name().to_string(), Level::Atomic),
+            NodeView::Unary(u) => match u.actor() {
+                UnaryAction::Iden => self.write(u.operand()),
+                UnaryAction::Neg => {
+                    // A negated number would be read as a negative constant,
+                    // unlike a negated `pi`.
+                    let operand = match u.operand().view() {
+                        NodeView::Plain(p) if p.constant().is_none() => {
+                            format!("({})", self.print(u.operand()))
+                        }
+                        _ => self.wrap(u.operand(), Level::Pow),
+                    };
+                    (format!("-{}", operand), Level::Factor)
+                }
+                UnaryAction::Func(func) => (
+                    format!("{}({})", func.name(), self.print(u.operand())),
+                    Level::Atomic,
+                ),
+            },
+            NodeView::Binder(b) => {
+                let source = format!(
+                    "{}({}, {}, {}, {})",
+                    b.actor().name(),
+                    self.wrap(b.body(), Level::Expr),
+                    b.var(),
+                    self.wrap(b.lower(), Level::Expr),
+                    self.wrap(b.upper(), Level::Expr),
+                );
+                (source, Level::Atomic)
+            }
+            NodeView::Solve(s) => {
+                let mut args = vec![self.print(s.equation()), s.var().to_string()];
+                args.extend(s.guess().iter().map(|g| self.print(*g)));
+                (format!("solve({})", args.join(", ")), Level::Atomic)
+            }
+            NodeView::Vector(v) => {
+                let elements: Vec<_> = v.elements().map(|e| self.print(e)).collect();
+                (format!("[{}]", elements.join(", ")), Level::Atomic)
+            }
+            NodeView::List(l) => {
+                let elements: Vec<_> = l.elements().map(|e| self.print(e)).collect();
+                (format!("{{{}}}", elements.join(", ")), Level::Atomic)
+            }
+            NodeView::Range(r) => {
+                let start = self.wrap(r.start(), Level::Conversion);
+                let end = self.wrap(r.end(), Level::Conversion);
+                (format!("{}..{}", start, end), Level::Range)
+            }
+            NodeView::Lambda(l) => {
+                let params = match l.params() {
+                    [param] => param.clone(),
+                    params => format!("({})", params.join(", ")),
+                };
+                (
+                    format!("{} -> {}", params, self.print(l.body())),
+                    Level::Lambda,
+                )
+            }
+            NodeView::Call(c) => {
+                let args: Vec<_> = c.args().map(|a| self.print(a)).collect();
+                (
+                    format!("{}({})", c.func().name(), args.join(", ")),
+                    Level::Atomic,
+                )
+            }
+            NodeView::Binary(b) => {
+                let (left, right) = (b.left(), b.right());
+                match b.actor() {
+                    BinaryAction::Add => self.infix(left, " + ", right, Level::Expr, Level::Term),
+                    BinaryAction::Sub => self.infix(left, " - ", right, Level::Expr, Level::Term),
+                    BinaryAction::PlusMinus => {
+                        self.infix(left, " ± ", right, Level::Expr, Level::Term)
+                    }
+                    BinaryAction::Convert => {
+                        self.infix(left, " to ", right, Level::Conversion, Level::Expr)
+                    }
+
+                    // Equations do not chain, so neither side is one.
+                    BinaryAction::Equate => {
+                        let left = self.wrap(left, Level::Range);
+                        let right = self.wrap(right, Level::Range);
+                        (format!("{} = {}", left, right), Level::Equation)
+                    }
+                    BinaryAction::Div => self.infix(left, " / ", right, Level::Term, Level::Factor),
+                    BinaryAction::LeftDiv => {
+                        self.infix(left, " \\ ", right, Level::Term, Level::Factor)
+                    }
+                    BinaryAction::Mul => {
+                        let left = self.wrap(left, Level::Term);
+                        let right = self.wrap(right, Level::Factor);
+                        let source = match self.juxtapose(&left, &right) {
+                            Some(separator) => format!("{}{}{}", left, separator, right),
+                            None => format!("{} * {}", left, right),
+                        };
+                        (source, Level::Term)
+                    }
+                    BinaryAction::Pow => {
+                        let base = self.wrap(left, Level::Atomic);
+                        let exponent = self.wrap(right, Level::Atomic);
+                        (format!("{}^{}", base, exponent), Level::Pow)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Prints a left-associative operation at `level`, whose right operand
+    /// must bind at least as tightly as `next`.
+    fn infix(
+        &self,
+        left: &dyn Node,
+        operator: &str,
+        right: &dyn Node,
+        level: Level,
+        next: Level,
+    ) -> (String, Level) {
+        let left = self.wrap(left, level);
+        let right = self.wrap(right, next);
+        (format!("{}{}{}", left, operator, right), level)
+    }
+
+    /// Prints an AST, parenthesising it if it binds looser than `level`.
+    fn wrap(&self, node: &dyn Node, level: Level) -> String {
+        match self.write(node) {
+            (source, actual) if actual < level => format!("({})", source),
+            (source, _) => source,
+        }
+    }
+
+    /// Returns the separator for writing `left right` as an implicit
+    /// multiplication, or [`None`] if it must be written explicitly.
+    ///
+    /// The [`Parser`](crate::Parser) only multiplies implicitly before a left
+    /// parenthesis or a name, and a name directly followed by a left
+    /// parenthesis could be read as a call. A unit directly following a
+    /// number binds to that number alone, e.g. `1 / 2s` is `1 / (2 s)`.
+    fn juxtapose(&self, left: &str, right: &str) -> Option<&'static str> {
+        if !self.implicit_mul {
+            return None;
+        }
+
+        let name = right
+            .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .next()?;
+        if Unit::lookup(name).is_some() && left.parse::<f64>().is_err() && !ends_with_name(left) {
+            return None;
+        }
+
+        match (ends_with_name(left), right.chars().next()?) {
+            (false, c) if c == '(' || c.is_ascii_alphabetic() => Some(""),
+            (true, c) if c.is_ascii_alphabetic() => Some(" "),
+            _ => None,
+        }
+    }
+}
+
+/// Prints a number so that it is read back as the same [`Number`].
+fn number(n: Number) -> (String, Level) {
+    let source = match n {
+        Number::Int(n) => n.to_string(),
+        Number::Flt(n) if n.is_nan() => return ("(0/0)".to_string(), Level::Atomic),
+        Number::Flt(n) if n == f64::INFINITY => return ("2.0^1024".to_string(), Level::Pow),
+        Number::Flt(n) if n == f64::NEG_INFINITY => {
+            return ("-2.0^1024".to_string(), Level::Factor)
+        }
+        Number::Flt(n) => {
+            let mut source = n.to_string();
+            if !source.contains('.') {
+                source.push_str(".0");
+            }
+            source
+        }
+    };
+
+    if source.starts_with('-') {
+        (source, Level::Factor)
+    } else {
+        (source, Level::Atomic)
+    }
+}
+
+/// Checks if source code ends with a name, rather than a number or a
+/// parenthesis.
+fn ends_with_name(source: &str) -> bool {
+    // Trailing digits belong to a name if they follow one, e.g. `x1`.
+    let rest = source.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    rest.ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+}
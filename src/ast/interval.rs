@@ -0,0 +1,713 @@
+//! This module implements interval arithmetic over ASTs.
+//!
+//! An [`Interval`] is a closed set of real numbers `[lo, hi]`. Evaluating an
+//! AST with [`evaluate_interval`] gives an [`Interval`] that encloses the
+//! value of the AST for every choice of values of its variables within their
+//! intervals. Bounds are rounded outward, so the enclosure holds despite
+//! floating-point rounding:
+//!
+//! - `+`, `-`, `*` and `/` are exact where the result is representable, and
+//!   are otherwise rounded to the next float outward.
+//! - Integer powers are computed by repeated multiplication, rounded the same
+//!   way.
+//! - Functions and other powers are computed with the platform's math library
+//!   and widened by one float on each side.
+//!
+//! Variables stand for floats. As in scalar evaluation, an integer divided by
+//! an integer is truncated, so `7 / 2` is `[3, 3]`, while `x / 2` is divided
+//! over the real numbers. Where an operand may be either, e.g. `2^n`, which
+//! is a float for a negative integer `n`, the quotient encloses both ways of
+//! dividing. Division by an interval that contains zero
+//! gives the smallest interval that encloses the quotient over the rest of
+//! the divisor, which may be unbounded, e.g. `1 / [0, 2]` is `[0.5, inf]`.
+//! Functions are evaluated over the part of the interval within their domain,
+//! and give the empty interval if there is no such part. As in scalar
+//! evaluation, `x^0` and `1^y` are 1 even where `x` or `y` is undefined.
+//!
+//! An uncertain value `a ± b` is the interval `a + [-|b|, |b|]`, which makes
+//! tolerances add up over a whole AST.
+//!
+//! Each occurrence of a variable is treated independently, so the enclosure
+//! may be wider than the true range, e.g. `x - x` for `x ∈ [0, 1]` gives
+//! `[-1, 1]`.
+//!
+//! Sums and products add and multiply the enclosures of their terms. An
+//! integral is divided into pieces, each enclosed by its width times the
+//! enclosure of the integrand over it. Sums, products and integrals whose
+//! bounds are not single numbers give the entire real line.
+//!
+//! A root of an equation lies within its interval guess, so it is enclosed by
+//! the hull of the enclosures of the two ends. Without an interval, it may be
+//! anywhere on the real line.
+//!
+//! Vectors, lists, ranges and lambdas have no enclosures, so they are an
+//! error. Built-in functions, such as `det` and `reduce`, may be anywhere on
+//! the real line.
+
+use std::{
+    f64::consts::{FRAC_PI_2, PI, TAU},
+    fmt::Display,
+};
+
+use crate::{
+    ast::{BinderNode, Node, NodeView, VectorNode},
+    BinaryAction, BinderAction, EvalError, Function, Guess, Number, Shape, UnaryAction,
+};
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// Number of pieces an integral is divided into.
+const PIECES: usize = 64;
+
+/// A closed interval of real numbers, which may be empty or unbounded.
+#[derive(Debug, Clone, Copy)]
+pub struct Interval {
+    /// Lower bound, or NaN if empty.
+    lo: f64,
+
+    /// Upper bound, or NaN if empty.
+    hi: f64,
+}
+
+/// Evaluates an AST over intervals, with `bindings` giving the interval of
+/// each variable.
+pub fn evaluate_interval(node: &dyn Node, bindings: &[(&str, Interval)]) -> Result<Interval> {
+    let interval = match node.view() {
+        NodeView::Plain(p) => Interval::from(p.value()),
+        NodeView::Variable(v) => match bindings.iter().find(|(name, _)| *name == v.name()) {
+            Some(&(_, interval)) => interval,
+            None => return Err(EvalError::UnboundVariable(v.name().to_string())),
+        },
+        NodeView::Unit(u) => Interval::point(u.unit().scale()),
+        NodeView::Unary(u) => {
+            let operand = evaluate_interval(u.operand(), bindings)?;
+            match u.actor() {
+                UnaryAction::Iden => operand,
+                UnaryAction::Neg => operand.neg(),
+                UnaryAction::Func(func) => operand.apply(func),
+            }
+        }
+        NodeView::Binary(b) => {
+            let left = evaluate_interval(b.left(), bindings)?;
+            let right = evaluate_interval(b.right(), bindings)?;
+            match b.actor() {
+                BinaryAction::Add => left.add(right),
+                BinaryAction::Sub | BinaryAction::Equate => left.add(right.neg()),
+                BinaryAction::Mul => left.mul(right),
+                BinaryAction::Div => quotient(left, right, kind(b.left()), kind(b.right())),
+                BinaryAction::LeftDiv => quotient(right, left, kind(b.right()), kind(b.left())),
+                BinaryAction::Convert => left.div(right),
+                BinaryAction::Pow => left.pow(right),
+                BinaryAction::PlusMinus => left.add(right.spread()),
+            }
+        }
+        NodeView::Binder(b) => {
+            let lower = evaluate_interval(b.lower(), bindings)?;
+            let upper = evaluate_interval(b.upper(), bindings)?;
+            binder(b, bindings, lower, upper)?
+        }
+        NodeView::Solve(s) => match s.guess() {
+            Guess::Interval(lower, upper) => {
+                let lower = evaluate_interval(lower, bindings)?;
+                let upper = evaluate_interval(upper, bindings)?;
+                match lower.is_empty() || upper.is_empty() {
+                    true => Interval::EMPTY,
+                    false => lower.hull(upper),
+                }
+            }
+            _ => Interval::ENTIRE,
+        },
+        NodeView::Vector(v) => return Err(EvalError::ScalarExpected(shape(v))),
+        NodeView::List(l) => {
+            return Err(EvalError::ScalarExpected(Shape::List(l.elements().len())))
+        }
+        NodeView::Range(r) => {
+            // The range has at most as many integers as its widest choice of
+            // bounds.
+            let start = evaluate_interval(r.start(), bindings)?;
+            let end = evaluate_interval(r.end(), bindings)?;
+            let length = (end.hi.floor() - start.lo.ceil() + 1.0).max(0.0);
+            return Err(EvalError::ScalarExpected(Shape::List(length as usize)));
+        }
+        NodeView::Lambda(l) => {
+            return Err(EvalError::ScalarExpected(Shape::Lambda(l.params().len())))
+        }
+        NodeView::Call(c) if c.func().is_symbolic() => {
+            evaluate_interval(c.rewrite()?.as_ref(), bindings)?
+        }
+        NodeView::Call(_) => Interval::ENTIRE,
+    };
+
+    Ok(interval)
+}
+
+/// Whether the value of an AST is an integer or a float in scalar evaluation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Int,
+    Flt,
+    Either,
+}
+
+/// Gets the [`Kind`] of the value of an AST, taking variables as floats.
+fn kind(node: &dyn Node) -> Kind {
+    match node.view() {
+        NodeView::Plain(p) => match p.value() {
+            Number::Int(_) => Kind::Int,
+            Number::Flt(_) => Kind::Flt,
+        },
+        NodeView::Variable(_) | NodeView::Unit(_) | NodeView::Solve(_) => Kind::Flt,
+        NodeView::Unary(u) => match u.actor() {
+            UnaryAction::Iden | UnaryAction::Neg => kind(u.operand()),
+            UnaryAction::Func(_) => Kind::Flt,
+        },
+        NodeView::Binary(b) => match b.actor() {
+            BinaryAction::PlusMinus => kind(b.left()),
+            BinaryAction::Convert => Kind::Flt,
+            actor => match (kind(b.left()), kind(b.right())) {
+                (Kind::Flt, _) | (_, Kind::Flt) => Kind::Flt,
+                (Kind::Int, Kind::Int) if actor != BinaryAction::Pow => Kind::Int,
+
+                // An integer power is a float if the exponent is negative.
+                (Kind::Int, Kind::Int) => match b.right().view() {
+                    NodeView::Plain(p) if p.value() >= Number::Int(0) => Kind::Int,
+                    _ => Kind::Either,
+                },
+                _ => Kind::Either,
+            },
+        },
+        NodeView::Binder(_)
+        | NodeView::Vector(_)
+        | NodeView::Call(_)
+        | NodeView::List(_)
+        | NodeView::Range(_)
+        | NodeView::Lambda(_) => Kind::Either,
+    }
+}
+
+/// Divides `left` by `right`, whose values are of the given kinds, truncating
+/// the quotient of two integers.
+fn quotient(left: Interval, right: Interval, left_kind: Kind, right_kind: Kind) -> Interval {
+    let real = left.div(right);
+    match (left_kind, right_kind) {
+        (Kind::Flt, _) | (_, Kind::Flt) => real,
+        (Kind::Int, Kind::Int) => real.trunc(),
+        _ => real.hull(real.trunc()),
+    }
+}
+
+/// Gets the shape of a vector literal, taking literal rows as a matrix.
+fn shape(v: &VectorNode) -> Shape {
+    match v.elements().next().map(|e| e.view()) {
+        Some(NodeView::Vector(row)) => Shape::Matrix(v.elements().len(), row.elements().len()),
+        _ => Shape::Vector(v.elements().len()),
+    }
+}
+
+/// Evaluates a sum, product or integral over intervals, given its bounds.
+fn binder(
+    b: &BinderNode,
+    bindings: &[(&str, Interval)],
+    lower: Interval,
+    upper: Interval,
+) -> Result<Interval> {
+    if lower.is_empty() || upper.is_empty() {
+        return Ok(Interval::EMPTY);
+    }
+    if lower.lo != lower.hi || upper.lo != upper.hi {
+        return Ok(Interval::ENTIRE);
+    }
+
+    // The bound variable shadows any binding of the same name.
+    let mut local = Vec::with_capacity(bindings.len() + 1);
+    local.push((b.var(), Interval::EMPTY));
+    local.extend_from_slice(bindings);
+    let mut body = |value: Interval| {
+        local[0].1 = value;
+        evaluate_interval(b.body(), &local)
+    };
+
+    match b.actor() {
+        actor @ (BinderAction::Sum | BinderAction::Prod) => {
+            let mut result = Interval::from(actor.identity());
+            for k in BinderAction::range(Number::Flt(lower.lo), Number::Flt(upper.lo))? {
+                let term = body(Interval::from(Number::Int(k)))?;
+                result = match actor {
+                    BinderAction::Prod => result.mul(term),
+                    _ => result.add(term),
+                };
+            }
+            Ok(result)
+        }
+        BinderAction::Integrate => {
+            let (a, b) = (lower.lo.min(upper.lo), lower.lo.max(upper.lo));
+            if a == b {
+                return Ok(Interval::point(0.0));
+            }
+
+            // Adjacent pieces share their ends, so that they cover [a, b].
+            let step = (b - a) / PIECES as f64;
+            let mut result = Interval::point(0.0);
+            let mut start = a;
+            for i in 1..=PIECES {
+                let end = match i {
+                    PIECES => b,
+                    i => a + step * i as f64,
+                };
+                let width = Interval::point(end).add(Interval::point(start).neg());
+                result = result.add(width.mul(body(Interval::new(start, end))?));
+                start = end;
+            }
+
+            match lower.lo <= upper.lo {
+                true => Ok(result),
+                false => Ok(result.neg()),
+            }
+        }
+    }
+}
+
+impl Interval {
+    /// The empty interval.
+    pub const EMPTY: Interval = Interval {
+        lo: f64::NAN,
+        hi: f64::NAN,
+    };
+
+    /// The interval of all real numbers.
+    pub const ENTIRE: Interval = Interval {
+        lo: f64::NEG_INFINITY,
+        hi: f64::INFINITY,
+    };
+
+    /// Creates the interval `[lo, hi]`, which is empty if `lo > hi` or
+    /// either bound is NaN.
+    pub fn new(lo: f64, hi: f64) -> Interval {
+        if lo <= hi {
+            Interval { lo, hi }
+        } else {
+            Self::EMPTY
+        }
+    }
+
+    /// Creates the interval that only contains `value`.
+    pub fn point(value: f64) -> Interval {
+        Self::new(value, value)
+    }
+
+    /// Gets the lower bound, which is NaN if this [`Interval`] is empty.
+    pub fn lo(&self) -> f64 {
+        self.lo
+    }
+
+    /// Gets the upper bound, which is NaN if this [`Interval`] is empty.
+    pub fn hi(&self) -> f64 {
+        self.hi
+    }
+
+    /// Checks if this [`Interval`] is empty.
+    pub fn is_empty(&self) -> bool {
+        self.lo.is_nan()
+    }
+
+    /// Checks if this [`Interval`] contains a number.
+    pub fn contains(&self, value: f64) -> bool {
+        self.lo <= value && value <= self.hi
+    }
+
+    /// Gets the smallest [`Interval`] that contains both intervals.
+    pub fn hull(self, other: Interval) -> Interval {
+        if self.is_empty() {
+            other
+        } else if other.is_empty() {
+            self
+        } else {
+            Interval {
+                lo: self.lo.min(other.lo),
+                hi: self.hi.max(other.hi),
+            }
+        }
+    }
+
+    /// Gets `[-m, m]`, where `m` is the largest magnitude in this
+    /// [`Interval`].
+    fn spread(self) -> Interval {
+        let m = self.lo.abs().max(self.hi.abs());
+        Self::new(-m, m)
+    }
+
+    /// Rounds the bounds toward zero, which encloses the truncated values
+    /// since truncation is monotonic.
+    fn trunc(self) -> Interval {
+        Interval {
+            lo: self.lo.trunc(),
+            hi: self.hi.trunc(),
+        }
+    }
+
+    fn neg(self) -> Interval {
+        Interval {
+            lo: -self.hi,
+            hi: -self.lo,
+        }
+    }
+
+    fn add(self, other: Interval) -> Interval {
+        if self.is_empty() || other.is_empty() {
+            return Self::EMPTY;
+        }
+
+        // Opposite infinities only meet at unbounded ends.
+        let lo = match self.lo + other.lo {
+            lo if lo.is_nan() => f64::NEG_INFINITY,
+            lo => round_down(lo, sum_error(self.lo, other.lo, lo)),
+        };
+        let hi = match self.hi + other.hi {
+            hi if hi.is_nan() => f64::INFINITY,
+            hi => round_up(hi, sum_error(self.hi, other.hi, hi)),
+        };
+        Interval { lo, hi }
+    }
+
+    fn mul(self, other: Interval) -> Interval {
+        if self.is_empty() || other.is_empty() {
+            return Self::EMPTY;
+        }
+
+        let corners = [
+            (self.lo, other.lo),
+            (self.lo, other.hi),
+            (self.hi, other.lo),
+            (self.hi, other.hi),
+        ];
+        corners
+            .into_iter()
+            .map(|(a, b)| {
+                // Zero times anything, even infinity, is zero for intervals.
+                if a == 0.0 || b == 0.0 {
+                    return Self::point(0.0);
+                }
+                let p = a * b;
+                let error = a.mul_add(b, -p);
+                Interval {
+                    lo: round_down(p, error),
+                    hi: round_up(p, error),
+                }
+            })
+            .fold(Self::EMPTY, Self::hull)
+    }
+
+    fn div(self, other: Interval) -> Interval {
+        if self.is_empty() || other.is_empty() {
+            return Self::EMPTY;
+        }
+
+        if other.contains(0.0) {
+            return self.mul(other.recip());
+        }
+
+        let corners = [
+            (self.lo, other.lo),
+            (self.lo, other.hi),
+            (self.hi, other.lo),
+            (self.hi, other.hi),
+        ];
+        corners
+            .into_iter()
+            .map(|(a, b)| {
+                let q = a / b;
+                // The exact quotient is q + (a - qb) / b.
+                let error = -q.mul_add(b, -a) * b.signum();
+                Interval {
+                    lo: round_down(q, error),
+                    hi: round_up(q, error),
+                }
+            })
+            .fold(Self::EMPTY, Self::hull)
+    }
+
+    /// Gets the reciprocal of an interval that contains zero.
+    fn recip(self) -> Interval {
+        match (self.lo == 0.0, self.hi == 0.0) {
+            (true, true) => Self::EMPTY,
+            (true, false) => {
+                Self::new(Self::point(1.0).div(Self::point(self.hi)).lo, f64::INFINITY)
+            }
+            (false, true) => Self::new(
+                f64::NEG_INFINITY,
+                Self::point(1.0).div(Self::point(self.lo)).hi,
+            ),
+            (false, false) => Self::ENTIRE,
+        }
+    }
+
+    fn pow(self, exponent: Interval) -> Interval {
+        // As in scalar evaluation, x^0 and 1^y are 1 even where x or y is
+        // undefined.
+        if exponent == Self::point(0.0) || self == Self::point(1.0) {
+            return Self::point(1.0);
+        }
+        if self.is_empty() || exponent.is_empty() {
+            return Self::EMPTY;
+        }
+
+        let (c, d) = (exponent.lo, exponent.hi);
+        if c == d && c.fract() == 0.0 && c.abs() < u64::MAX as f64 {
+            return if c > 0.0 {
+                self.powi(c as u64)
+            } else {
+                Self::point(1.0).div(self.powi(-c as u64))
+            };
+        }
+
+        // Over non-negative bases, the extremes of x^y are at the corners,
+        // since x^y is monotonic in each of x and y.
+        let corners = |lo: f64, hi: f64| {
+            [(lo, c), (lo, d), (hi, c), (hi, d)]
+                .into_iter()
+                .map(|(x, y)| widen(x.powf(y)))
+                .fold(Self::EMPTY, Self::hull)
+        };
+
+        let mut result = Self::EMPTY;
+        if self.hi >= 0.0 {
+            result = corners(self.lo.max(0.0), self.hi);
+        }
+
+        // Negative bases only have real powers for integer exponents, of
+        // either sign.
+        if self.lo < 0.0 && c.ceil() <= d {
+            let magnitude = corners((-self.hi).max(0.0), -self.lo).hi;
+            result = result.hull(Self::new(-magnitude, magnitude));
+        }
+
+        result
+    }
+
+    /// Raises this [`Interval`] to a positive integer power.
+    fn powi(self, n: u64) -> Interval {
+        let (lo, hi) = (self.lo, self.hi);
+        if n % 2 == 1 {
+            let lo = match lo >= 0.0 {
+                true => pow_down(lo, n),
+                false => -pow_up(-lo, n),
+            };
+            let hi = match hi >= 0.0 {
+                true => pow_up(hi, n),
+                false => -pow_down(-hi, n),
+            };
+            Self::new(lo, hi)
+        } else if lo >= 0.0 {
+            Self::new(pow_down(lo, n), pow_up(hi, n))
+        } else if hi <= 0.0 {
+            Self::new(pow_down(-hi, n), pow_up(-lo, n))
+        } else {
+            Self::new(0.0, pow_up(hi.max(-lo), n))
+        }
+    }
+
+    fn apply(self, func: Function) -> Interval {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+
+        let (lo, hi) = (self.lo, self.hi);
+        match func {
+            // sin(x) peaks at pi/2 + 2k pi, and cos(x) at 2k pi.
+            Function::Sin => periodic(self, f64::sin, FRAC_PI_2),
+            Function::Cos => periodic(self, f64::cos, 0.0),
+
+            // tan(x) increases between poles at pi/2 + k pi, and cot(x)
+            // decreases between poles at k pi.
+            Function::Tan if hits(self, FRAC_PI_2, PI) => Self::ENTIRE,
+            Function::Tan => monotonic(lo.tan(), hi.tan()),
+            Function::Cot if hits(self, 0.0, PI) => Self::ENTIRE,
+            Function::Cot => monotonic(hi.tan().recip(), lo.tan().recip()),
+
+            Function::Sec => Self::point(1.0).div(self.apply(Function::Cos)),
+            Function::Csc => Self::point(1.0).div(self.apply(Function::Sin)),
+
+            Function::Asin | Function::Acos => {
+                let Interval { lo, hi } = self.intersect(-1.0, 1.0);
+                match func {
+                    Function::Asin => monotonic(lo.asin(), hi.asin()),
+                    _ => monotonic(hi.acos(), lo.acos()),
+                }
+            }
+            Function::Atan => monotonic(lo.atan(), hi.atan()),
+
+            // These are computed from the reciprocal, as in scalar evaluation.
+            Function::Asec => Self::point(1.0).div(self).apply(Function::Acos),
+            Function::Acsc => Self::point(1.0).div(self).apply(Function::Asin),
+            Function::Acot => {
+                let result = Self::point(1.0).div(self).apply(Function::Atan);
+
+                // 1 / 0 is an infinity, whose arctangent is pi/2, or -pi/2
+                // for a negative zero.
+                match self.contains(0.0) {
+                    true => result.hull(monotonic(-FRAC_PI_2, FRAC_PI_2)),
+                    false => result,
+                }
+            }
+
+            Function::Ln | Function::Sqrt => {
+                let Interval { lo, hi } = self.intersect(0.0, f64::INFINITY);
+                match func {
+                    Function::Ln => monotonic(lo.ln(), hi.ln()),
+                    _ => monotonic(lo.sqrt(), hi.sqrt()),
+                }
+            }
+        }
+    }
+
+    /// Gets the part of this [`Interval`] within `[lo, hi]`.
+    fn intersect(self, lo: f64, hi: f64) -> Interval {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+        Self::new(self.lo.max(lo), self.hi.min(hi))
+    }
+}
+
+impl PartialEq for Interval {
+    /// Compares bounds, with all empty intervals equal.
+    fn eq(&self, other: &Self) -> bool {
+        (self.is_empty() && other.is_empty()) || (self.lo == other.lo && self.hi == other.hi)
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.is_empty() {
+            true => write!(f, "[]"),
+            false => write!(f, "[{}, {}]", self.lo, self.hi),
+        }
+    }
+}
+
+impl From<f64> for Interval {
+    fn from(value: f64) -> Self {
+        Self::point(value)
+    }
+}
+
+impl From<Number> for Interval {
+    fn from(value: Number) -> Self {
+        match value {
+            Number::Flt(n) => Self::point(n),
+            Number::Int(n) => {
+                // Large integers may not be exact as floats.
+                let m = n as f64;
+                match m as i128 == n {
+                    true => Self::point(m),
+                    false => Self::new(m.next_down(), m.next_up()),
+                }
+            }
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Helpers for rounding.
+// -----------------------------------------------------------------------------
+
+/// Rounds a result down, given that the exact result is `value + error`. An
+/// unknown (NaN) error rounds outward.
+fn round_down(value: f64, error: f64) -> f64 {
+    match error >= 0.0 {
+        true => value,
+        false => value.next_down(),
+    }
+}
+
+/// Rounds a result up, given that the exact result is `value + error`. An
+/// unknown (NaN) error rounds outward.
+fn round_up(value: f64, error: f64) -> f64 {
+    match error <= 0.0 {
+        true => value,
+        false => value.next_up(),
+    }
+}
+
+/// Gets the rounding error of `sum = a + b` exactly, by Knuth's TwoSum.
+fn sum_error(a: f64, b: f64, sum: f64) -> f64 {
+    let b_virtual = sum - a;
+    let a_virtual = sum - b_virtual;
+    (a - a_virtual) + (b - b_virtual)
+}
+
+/// Widens an inexact result by one float on each side.
+fn widen(value: f64) -> Interval {
+    if value.is_nan() {
+        return Interval::EMPTY;
+    }
+    Interval {
+        lo: value.next_down(),
+        hi: value.next_up(),
+    }
+}
+
+/// Gets an [`Interval`] from the inexact values of an increasing function at
+/// the bounds of an interval.
+fn monotonic(lo: f64, hi: f64) -> Interval {
+    widen(lo).hull(widen(hi))
+}
+
+/// Raises a non-negative number to a positive integer power, rounding down.
+fn pow_down(x: f64, n: u64) -> f64 {
+    pow_rounded(x, n, round_down)
+}
+
+/// Raises a non-negative number to a positive integer power, rounding up.
+fn pow_up(x: f64, n: u64) -> f64 {
+    pow_rounded(x, n, round_up)
+}
+
+/// Raises a non-negative number to a positive integer power by squaring,
+/// rounding every product in the same direction.
+fn pow_rounded(mut x: f64, mut n: u64, round: fn(f64, f64) -> f64) -> f64 {
+    let mul = |a: f64, b: f64| {
+        if a == 0.0 || b == 0.0 {
+            return 0.0;
+        }
+        let p = a * b;
+        round(p, a.mul_add(b, -p))
+    };
+
+    let mut result = 1.0;
+    while n > 0 {
+        if n % 2 == 1 {
+            result = mul(result, x);
+        }
+        x = mul(x, x);
+        n /= 2;
+    }
+    result
+}
+
+/// Checks if an [`Interval`] may contain `offset + k * period` for some
+/// integer `k`. Bounds are widened slightly first, so that rounding errors
+/// can only make this say yes.
+fn hits(interval: Interval, offset: f64, period: f64) -> bool {
+    let margin = 1e-15 * interval.lo.abs().max(interval.hi.abs()).max(1.0);
+    let k = ((interval.lo - margin - offset) / period).ceil();
+    offset + k * period <= interval.hi + margin
+}
+
+/// Evaluates a function with period 2 pi, that peaks at 1 at `peak` and
+/// bottoms out at -1 half a period later, and is monotonic in between.
+fn periodic(interval: Interval, f: fn(f64) -> f64, peak: f64) -> Interval {
+    if interval.hi - interval.lo >= TAU {
+        return Interval::new(-1.0, 1.0);
+    }
+
+    let mut result = monotonic(f(interval.lo), f(interval.hi));
+    if hits(interval, peak, TAU) {
+        result = result.hull(Interval::point(1.0));
+    }
+    if hits(interval, peak + PI, TAU) {
+        result = result.hull(Interval::point(-1.0));
+    }
+    result.intersect(-1.0, 1.0)
+}
@@ -0,0 +1,438 @@
+//! This module implements recognition and manipulation of polynomials in
+//! ASTs.
+//!
+//! [`coefficients`] recognises a polynomial through sums, differences,
+//! equations, products, negations, divisions by expressions free of the
+//! variable, and powers with a non-negative integer exponent. Its
+//! coefficients are ASTs themselves, so that they can depend on other
+//! variables.
+//!
+//! The variable is taken to be `1.0` and missing terms `0.0`, so that every
+//! coefficient evaluates to a float, just like the polynomial does once the
+//! variable is bound to a float. For example, the constant term of
+//! `(x + 1) / 2` is `(0.0 + 1) / 2`, which is not truncated to 0.
+//!
+//! [`expand`], [`factor`] and [`collect`] convert ASTs to a [`Polynomial`]
+//! with [`to_polynomial`] and back with [`from_polynomial`]. Division is
+//! exact, so `x / 2` is half of `x`, as it is when `x` is a float, but
+//! divisions and powers of numbers are evaluated, so `1 / 2` is still 0.
+//! Sub-expressions that are not polynomials are manipulated inside, e.g.
+//! `expand(sin((x + 1)^2))` gives `sin(x^2 + 2x + 1)`, and numbers are left as
+//! they are. [`factorint`] factorises integers instead, into primes.
+
+use crate::{
+    ast::{
+        depends_on, is_pure, BinderNode, CallNode, LambdaNode, ListNode, Node, NodeBox, NodeView,
+        RangeNode, SolveNode, VectorNode,
+    },
+    ntheory,
+    polynomial::{Factorisation, Monomial, Polynomial, Rational},
+    BinaryAction, BinaryNode, Environment, Number, PlainNode, UnaryAction, UnaryNode, VariableNode,
+};
+
+/// Gets the coefficients of an AST as a polynomial in `var`, constant first,
+/// e.g. three coefficients evaluating to -1.0, 2.0 and 1.0 for `x^2 + 2x - 1`.
+///
+/// Returns [`None`] if the AST is not a polynomial in `var`, or if its degree
+/// may exceed `max_degree`. The degree is that of the expression as written,
+/// so `x^2 - x^2` has degree 2 although its leading coefficient is zero.
+/// Coefficients that are not pure (see [`is_pure`]) are not allowed either,
+/// as they would be copied into several terms.
+pub fn coefficients(node: &dyn Node, var: &str, max_degree: usize) -> Option<Vec<NodeBox>> {
+    if !depends_on(node, var) {
+        return is_pure(node).then(|| vec![node.clone_box()]);
+    }
+
+    let coefficients = match node.view() {
+        NodeView::Variable(_) => vec![flt(0.0), flt(1.0)],
+        NodeView::Unary(u) => {
+            let operand = coefficients(u.operand(), var, max_degree)?;
+            match u.actor() {
+                UnaryAction::Iden => operand,
+                UnaryAction::Neg => operand.into_iter().map(neg).collect(),
+                UnaryAction::Func(_) => return None,
+            }
+        }
+        NodeView::Binary(b) => {
+            let left = coefficients(b.left(), var, max_degree)?;
+            match b.actor() {
+                BinaryAction::Add => combine(left, coefficients(b.right(), var, max_degree)?, add),
+                BinaryAction::Sub | BinaryAction::Equate => {
+                    combine(left, coefficients(b.right(), var, max_degree)?, sub)
+                }
+                BinaryAction::Mul => {
+                    let right = coefficients(b.right(), var, max_degree)?;
+                    if left.len() + right.len() - 2 > max_degree {
+                        return None;
+                    }
+                    multiply(&left, &right)
+                }
+                BinaryAction::Div if !depends_on(b.right(), var) => left
+                    .into_iter()
+                    .map(|c| binary(c, BinaryAction::Div, b.right().clone_box()))
+                    .collect(),
+                BinaryAction::Pow => {
+                    let exponent = match b.right().view() {
+                        NodeView::Plain(p) => match p.value() {
+                            Number::Int(n) => usize::try_from(n).ok()?,
+                            _ => return None,
+                        },
+                        _ => return None,
+                    };
+                    if (left.len() - 1).checked_mul(exponent)? > max_degree {
+                        return None;
+                    }
+
+                    // x^0 is 1.0 for every float x.
+                    let mut power = vec![flt(1.0)];
+                    for _ in 0..exponent {
+                        power = multiply(&power, &left);
+                    }
+                    power
+                }
+                _ => return None,
+            }
+        }
+        NodeView::Call(c) if c.func().is_symbolic() => {
+            return coefficients(c.rewrite().ok()?.as_ref(), var, max_degree)
+        }
+        _ => return None,
+    };
+
+    match coefficients.len() - 1 > max_degree {
+        true => None,
+        false => Some(coefficients),
+    }
+}
+
+/// Converts an AST to a [`Polynomial`], returning [`None`] if it is not a
+/// polynomial with coefficients that fit.
+pub fn to_polynomial(node: &dyn Node) -> Option<Polynomial> {
+    match node.view() {
+        NodeView::Plain(p) => match p.value() {
+            Number::Int(n) => Rational::integer(n).map(Polynomial::from),
+            Number::Flt(n) => Rational::from_f64(n).map(Polynomial::from),
+        },
+        NodeView::Variable(v) => Some(Polynomial::variable(v.name())),
+        NodeView::Unary(u) => {
+            let operand = to_polynomial(u.operand())?;
+            match u.actor() {
+                UnaryAction::Iden => Some(operand),
+                UnaryAction::Neg => Some(-operand),
+                UnaryAction::Func(_) => None,
+            }
+        }
+        NodeView::Binary(b) => {
+            let left = to_polynomial(b.left())?;
+            let right = to_polynomial(b.right())?;
+            match b.actor() {
+                BinaryAction::Add => left.checked_add(&right),
+                BinaryAction::Sub => left.checked_sub(&right),
+                BinaryAction::Mul => left.checked_mul(&right),
+
+                // Numbers are divided and raised to powers as they are when
+                // evaluated, e.g. with integer division.
+                BinaryAction::Div | BinaryAction::Pow
+                    if left.as_constant().is_some() && right.as_constant().is_some() =>
+                {
+                    match node.evaluate_with(&Environment::new()).ok()? {
+                        Number::Int(n) => Rational::integer(n).map(Polynomial::from),
+                        Number::Flt(n) => Rational::from_f64(n).map(Polynomial::from),
+                    }
+                }
+                BinaryAction::Div => left.checked_scale(right.as_constant()?.recip()?),
+                BinaryAction::Pow => {
+                    let exponent = right.as_constant()?;
+                    match exponent.is_integer() {
+                        true => left.checked_pow(u32::try_from(exponent.numer()).ok()?),
+                        false => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        NodeView::Call(c) if c.func().is_symbolic() => to_polynomial(c.rewrite().ok()?.as_ref()),
+        _ => None,
+    }
+}
+
+/// Converts a [`Polynomial`] to an AST, as a sum of terms with the leading
+/// term first, e.g. `x^2 + 2 * x * y - 1`.
+///
+/// Coefficients are integers where possible, then floats where they are the
+/// shortest decimal of a float, e.g. `0.5 * x`, and divisions by a float
+/// otherwise, e.g. `2 * x / 3.0`.
+pub fn from_polynomial(polynomial: &Polynomial) -> NodeBox {
+    sum(polynomial.terms().map(|(m, c)| (c, monomial(m))))
+}
+
+/// Multiplies out every polynomial in an AST.
+pub fn expand(node: &dyn Node) -> NodeBox {
+    match to_polynomial(node) {
+        Some(p) if has_variables(node) => match p.as_constant() {
+            Some(c) => float(c),
+            None => from_polynomial(&p),
+        },
+        Some(_) => node.clone_box(),
+        None => map_children(node, &expand),
+    }
+}
+
+/// Factorises every polynomial in an AST over the rationals, with
+/// [`Polynomial::factor`].
+pub fn factor(node: &dyn Node) -> NodeBox {
+    match to_polynomial(node) {
+        Some(p) if has_variables(node) => match p.factor() {
+            Some(f) => from_factorisation(&f),
+            None => node.clone_box(),
+        },
+        Some(_) => node.clone_box(),
+        None => map_children(node, &factor),
+    }
+}
+
+/// Factorises an integer into primes with [`ntheory::factorise`], as a
+/// product of powers in ascending order, e.g. `2^3 * 3^2 * 5` for 360. A
+/// negative integer has a factor of `-1` first, and 0 and 1 are left as they
+/// are.
+pub fn factorint(n: i128) -> NodeBox {
+    let powers = ntheory::factorise(n.unsigned_abs())
+        .into_iter()
+        .map(|(p, multiplicity)| match multiplicity {
+            1 => int(p as i128),
+            _ => pow(int(p as i128), int(multiplicity as i128)),
+        });
+
+    match n {
+        -1..=1 => int(n),
+        _ if n < 0 => std::iter::once(int(-1)).chain(powers).reduce(mul).unwrap(),
+        _ => powers.reduce(mul).unwrap(),
+    }
+}
+
+/// Collects the terms of every polynomial in an AST by powers of a variable,
+/// e.g. `(a + b) * x` for `a x + b x`.
+pub fn collect(node: &dyn Node, var: &str) -> NodeBox {
+    let p = match to_polynomial(node) {
+        Some(p) if has_variables(node) => p,
+        Some(_) => return node.clone_box(),
+        None => return map_children(node, &|n| collect(n, var)),
+    };
+    if let Some(c) = p.as_constant() {
+        return float(c);
+    }
+
+    let coefficients = p.coefficients(var);
+    let mut terms = vec![];
+    for (power, coefficient) in coefficients.into_iter().enumerate().rev() {
+        let power = match power {
+            0 => None,
+            1 => Some(variable(var)),
+            _ => Some(pow(variable(var), int(power as i128))),
+        };
+
+        let mut coefficient_terms = coefficient.terms();
+        match (coefficient_terms.next(), coefficient_terms.next(), power) {
+            (None, _, _) => {}
+
+            // The constant term is added term by term.
+            (Some(_), _, None) => {
+                terms.extend(coefficient.terms().map(|(m, c)| (c, monomial(m))));
+            }
+            (Some((m, c)), None, Some(power)) => {
+                let mut factors = monomial(m);
+                factors.push(power);
+                terms.push((c, factors));
+            }
+            (Some(_), Some(_), Some(power)) => {
+                let factors = vec![from_polynomial(&coefficient), power];
+                terms.push((Rational::ONE, factors));
+            }
+        }
+    }
+    sum(terms.into_iter())
+}
+
+/// Converts a [`Factorisation`] to an AST, e.g. `2 * x * (x - 1)^2`.
+fn from_factorisation(factorisation: &Factorisation) -> NodeBox {
+    let factors: Vec<_> = factorisation
+        .factors()
+        .iter()
+        .map(|(f, multiplicity)| match multiplicity {
+            1 => from_polynomial(f),
+            _ => pow(from_polynomial(f), int(*multiplicity as i128)),
+        })
+        .collect();
+
+    match factors.is_empty() {
+        true => float(factorisation.constant()),
+        false => scaled(factorisation.constant(), factors),
+    }
+}
+
+/// Adds terms, each a coefficient times a product of factors.
+fn sum(terms: impl Iterator<Item = (Rational, Vec<NodeBox>)>) -> NodeBox {
+    let mut sum: Option<NodeBox> = None;
+    for (c, node) in terms {
+        sum = Some(match sum {
+            None => scaled(c, node),
+            Some(sum) if c.signum() < 0 => binary(sum, BinaryAction::Sub, scaled(-c, node)),
+            Some(sum) => add(sum, scaled(c, node)),
+        });
+    }
+    sum.unwrap_or_else(|| int(0))
+}
+
+/// Multiplies factors by a coefficient, which comes first, e.g. `2 * x * y`,
+/// or gets the coefficient itself if there are none.
+fn scaled(c: Rational, mut factors: Vec<NodeBox>) -> NodeBox {
+    if factors.is_empty() {
+        return match c.is_integer() {
+            true => int(c.numer()),
+            false => float(c),
+        };
+    }
+
+    match decimal(c) {
+        _ if c.is_integer() => match c.numer() {
+            1 => {}
+            -1 => factors[0] = neg(factors[0].clone()),
+            n => factors.insert(0, int(n)),
+        },
+        Some(n) => factors.insert(0, flt(n)),
+        None => {
+            let numer = Rational::integer(c.numer()).unwrap();
+            let denom = flt(c.denom() as f64);
+            return binary(scaled(numer, factors), BinaryAction::Div, denom);
+        }
+    }
+    factors.into_iter().reduce(mul).unwrap()
+}
+
+/// Gets an AST evaluating to a rational number as a float.
+fn float(c: Rational) -> NodeBox {
+    match decimal(c) {
+        Some(n) => flt(n),
+        None => binary(int(c.numer()), BinaryAction::Div, flt(c.denom() as f64)),
+    }
+}
+
+/// Gets the float whose shortest decimal is a rational number, if any.
+fn decimal(c: Rational) -> Option<f64> {
+    let n = c.to_f64();
+    match Rational::from_f64(n) == Some(c) {
+        true => Some(n),
+        false => None,
+    }
+}
+
+/// Converts a [`Monomial`] to its powers of variables, which are none for 1.
+fn monomial(m: &Monomial) -> Vec<NodeBox> {
+    m.powers()
+        .map(|(var, power)| match power {
+            1 => variable(var),
+            _ => pow(variable(var), int(power as i128)),
+        })
+        .collect()
+}
+
+/// Checks if a polynomial AST has a variable, even one that cancels out.
+fn has_variables(node: &dyn Node) -> bool {
+    match node.view() {
+        NodeView::Variable(_) => true,
+        NodeView::Unary(u) => has_variables(u.operand()),
+        NodeView::Binary(b) => has_variables(b.left()) || has_variables(b.right()),
+        _ => false,
+    }
+}
+
+/// Rebuilds an AST with `f` applied to the children of its root.
+pub(crate) fn map_children(node: &dyn Node, f: &dyn Fn(&dyn Node) -> NodeBox) -> NodeBox {
+    match node.view() {
+        NodeView::Plain(_) | NodeView::Variable(_) | NodeView::Unit(_) => node.clone_box(),
+        NodeView::Unary(u) => Box::new(UnaryNode::new(u.actor(), f(u.operand()))),
+        NodeView::Binary(b) => binary(f(b.left()), b.actor(), f(b.right())),
+        NodeView::Binder(b) => Box::new(BinderNode::new(
+            b.actor(),
+            b.var(),
+            f(b.body()),
+            f(b.lower()),
+            f(b.upper()),
+        )),
+        NodeView::Solve(s) => Box::new(SolveNode::new(f(s.equation()), s.var(), s.guess().map(f))),
+        NodeView::Vector(v) => Box::new(VectorNode::new(v.elements().map(f).collect())),
+        NodeView::Call(c) => Box::new(CallNode::new(c.func(), c.args().map(f).collect())),
+        NodeView::List(l) => Box::new(ListNode::new(l.elements().map(f).collect())),
+        NodeView::Range(r) => Box::new(RangeNode::new(f(r.start()), f(r.end()))),
+        NodeView::Lambda(l) => Box::new(LambdaNode::new(l.params(), f(l.body()))),
+    }
+}
+
+/// Combines the coefficients of two polynomials term by term.
+fn combine(
+    left: Vec<NodeBox>,
+    right: Vec<NodeBox>,
+    f: fn(NodeBox, NodeBox) -> NodeBox,
+) -> Vec<NodeBox> {
+    let length = left.len().max(right.len());
+    let mut left = left.into_iter();
+    let mut right = right.into_iter();
+    (0..length)
+        .map(|_| {
+            let l = left.next().unwrap_or_else(|| flt(0.0));
+            let r = right.next().unwrap_or_else(|| flt(0.0));
+            f(l, r)
+        })
+        .collect()
+}
+
+/// Multiplies two polynomials, i.e. convolves their coefficients.
+fn multiply(left: &[NodeBox], right: &[NodeBox]) -> Vec<NodeBox> {
+    let mut product: Vec<Option<NodeBox>> = vec![None; left.len() + right.len() - 1];
+    for (i, l) in left.iter().enumerate() {
+        for (j, r) in right.iter().enumerate() {
+            let term = binary(l.clone(), BinaryAction::Mul, r.clone());
+            product[i + j] = Some(match product[i + j].take() {
+                Some(sum) => add(sum, term),
+                None => term,
+            });
+        }
+    }
+    product.into_iter().map(Option::unwrap).collect()
+}
+
+fn int(n: i128) -> NodeBox {
+    Box::new(PlainNode::new(Number::Int(n)))
+}
+
+fn flt(n: f64) -> NodeBox {
+    Box::new(PlainNode::new(Number::Flt(n)))
+}
+
+fn binary(left: NodeBox, actor: BinaryAction, right: NodeBox) -> NodeBox {
+    Box::new(BinaryNode::new(left, actor, right))
+}
+
+fn variable(name: &str) -> NodeBox {
+    Box::new(VariableNode::new(name))
+}
+
+fn add(left: NodeBox, right: NodeBox) -> NodeBox {
+    binary(left, BinaryAction::Add, right)
+}
+
+fn sub(left: NodeBox, right: NodeBox) -> NodeBox {
+    binary(left, BinaryAction::Sub, right)
+}
+
+fn neg(operand: NodeBox) -> NodeBox {
+    Box::new(UnaryNode::new(UnaryAction::Neg, operand))
+}
+
+fn mul(left: NodeBox, right: NodeBox) -> NodeBox {
+    binary(left, BinaryAction::Mul, right)
+}
+
+fn pow(left: NodeBox, right: NodeBox) -> NodeBox {
+    binary(left, BinaryAction::Pow, right)
+}
@@ -0,0 +1,215 @@
+//! This module implements evaluation of ASTs over physical quantities.
+//!
+//! Every node is evaluated into a [`Quantity`], i.e. a value together with its
+//! [`Dimension`]. Units evaluate to their value in coherent SI units, e.g.
+//! `km` is 1000 metres, so arithmetic is done in SI units throughout, and the
+//! dimensions are checked by [`BinaryAction::evaluate_quantity`],
+//! [`UnaryAction::evaluate_quantity`] and
+//! [`BinderAction::evaluate_quantity`]. Variables and numbers are
+//! dimensionless, and so are the variables bound by sums, products and
+//! integrals, and the roots of equations. An equation is checked once, at the
+//! root it is solved for. Built-in functions on vectors and lists, such as
+//! `det` and `len`, give dimensionless values, with any units within them
+//! taken in SI units.
+//!
+//! [`UnaryAction::evaluate_quantity`]: crate::UnaryAction::evaluate_quantity
+//! [`BinderAction::evaluate_quantity`]: crate::BinderAction::evaluate_quantity
+//!
+//! The [`Quantity::magnitude`] of a result is exactly the value given by
+//! [`Node::evaluate_with`], e.g. 5.4 for `1.5 m/s to km/h`, which is displayed
+//! as `5.4 km/h`.
+
+use std::fmt::Display;
+
+use crate::{
+    ast::{to_infix, Node, NodeView},
+    BinaryAction, Dimension, Environment, EvalError, Number, Unit,
+};
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// A value with a physical dimension.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    /// The value in coherent SI units.
+    value: Number,
+
+    /// The dimension of the value.
+    dimension: Dimension,
+
+    /// The unit to display the value in, or [`None`] for SI units.
+    unit: Option<Unit>,
+}
+
+/// Evaluates an AST over quantities, looking up variables in `env`.
+pub fn evaluate_quantity(node: &dyn Node, env: &Environment) -> Result<Quantity> {
+    match node.view() {
+        NodeView::Plain(p) => Ok(Quantity::from(p.value())),
+        NodeView::Variable(v) => env
+            .get(v.name())
+            .map(Quantity::from)
+            .ok_or_else(|| EvalError::UnboundVariable(v.name().to_string())),
+        NodeView::Unit(u) => Ok(Quantity::from(u.unit().clone())),
+        NodeView::Unary(u) => {
+            let operand = evaluate_quantity(u.operand(), env)?;
+            u.actor().evaluate_quantity(operand)
+        }
+        NodeView::Binary(b) => {
+            let left = evaluate_quantity(b.left(), env)?;
+            let mut right = evaluate_quantity(b.right(), env)?;
+
+            // The target of a conversion is displayed as it was written.
+            if b.actor() == BinaryAction::Convert {
+                let scale = float(right.value);
+                right.unit = Some(Unit::new(label(b.right()), scale, right.dimension));
+            }
+
+            b.actor().evaluate_quantity(left, right)
+        }
+        NodeView::Binder(b) => {
+            let lower = evaluate_quantity(b.lower(), env)?;
+            let upper = evaluate_quantity(b.upper(), env)?;
+
+            let mut env = env.clone();
+            b.actor().evaluate_quantity(lower, upper, |value| {
+                env.bind(b.var(), value);
+                evaluate_quantity(b.body(), &env)
+            })
+        }
+        NodeView::Solve(s) => {
+            for guess in s.guess().iter() {
+                let dimension = evaluate_quantity(*guess, env)?.dimension();
+                if !dimension.is_dimensionless() {
+                    return Err(EvalError::DimensionlessExpected(dimension));
+                }
+            }
+
+            let root = s.evaluate_with(env)?;
+            let mut env = env.clone();
+            env.bind(s.var(), root);
+            evaluate_quantity(s.equation(), &env)?;
+            Ok(Quantity::from(root))
+        }
+        NodeView::Call(c) if c.func().is_symbolic() => {
+            evaluate_quantity(c.rewrite()?.as_ref(), env)
+        }
+        NodeView::Vector(_)
+        | NodeView::Call(_)
+        | NodeView::List(_)
+        | NodeView::Range(_)
+        | NodeView::Lambda(_) => node.evaluate_with(env).map(Quantity::from),
+    }
+}
+
+impl Quantity {
+    /// Creates a [`Quantity`] from its value in coherent SI units.
+    pub fn new(value: Number, dimension: Dimension) -> Quantity {
+        Self {
+            value,
+            dimension,
+            unit: None,
+        }
+    }
+
+    /// Gets the value in coherent SI units.
+    pub fn value(&self) -> Number {
+        self.value
+    }
+
+    /// Gets the dimension.
+    pub fn dimension(&self) -> Dimension {
+        self.dimension
+    }
+
+    /// Gets the unit this [`Quantity`] is displayed in, or [`None`] for SI
+    /// units.
+    pub fn unit(&self) -> Option<&Unit> {
+        self.unit.as_ref()
+    }
+
+    /// Gets the value in the unit this [`Quantity`] is displayed in.
+    pub fn magnitude(&self) -> Number {
+        match &self.unit {
+            Some(unit) => Number::Flt(float(self.value)) / Number::Flt(unit.scale()),
+            None => self.value,
+        }
+    }
+
+    /// Displays this [`Quantity`] in another unit of the same dimension.
+    pub fn with_unit(self, unit: Unit) -> Result<Quantity> {
+        if unit.dimension() != self.dimension {
+            return Err(EvalError::DimensionMismatch(
+                self.dimension,
+                unit.dimension(),
+            ));
+        }
+
+        Ok(Self {
+            unit: Some(unit),
+            ..self
+        })
+    }
+}
+
+impl Display for Quantity {
+    /// Formats as the value followed by its unit, e.g. `1.5 m/s`. Values with
+    /// a unit are rounded to 12 significant figures, hiding the rounding error
+    /// of unit conversions. Pure numbers are formatted as a [`Number`].
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.unit {
+            Some(unit) => write!(f, "{} {}", rounded(self.magnitude()), unit),
+            None if self.dimension.is_dimensionless() => write!(f, "{}", self.value),
+            None => write!(f, "{} {}", rounded(self.value), self.dimension),
+        }
+    }
+}
+
+impl From<Number> for Quantity {
+    fn from(value: Number) -> Self {
+        Self::new(value, Dimension::NONE)
+    }
+}
+
+impl From<Unit> for Quantity {
+    fn from(value: Unit) -> Self {
+        Self::new(Number::Flt(value.scale()), value.dimension())
+    }
+}
+
+/// Writes the target of a conversion as a unit, e.g. `km/h` rather than the
+/// `km / h` of [`to_infix`].
+fn label(node: &dyn Node) -> String {
+    match node.view() {
+        NodeView::Unit(u) => u.unit().name().to_string(),
+        NodeView::Binary(b) => match b.actor() {
+            BinaryAction::Mul => format!("{} {}", label(b.left()), label(b.right())),
+            BinaryAction::Div => match b.right().view() {
+                NodeView::Binary(r)
+                    if matches!(r.actor(), BinaryAction::Mul | BinaryAction::Div) =>
+                {
+                    format!("{}/({})", label(b.left()), label(b.right()))
+                }
+                _ => format!("{}/{}", label(b.left()), label(b.right())),
+            },
+            BinaryAction::Pow => format!("{}^{}", label(b.left()), to_infix(b.right())),
+            _ => format!("({})", to_infix(node)),
+        },
+        _ => to_infix(node),
+    }
+}
+
+/// Rounds a float to 12 significant figures.
+pub(crate) fn rounded(n: Number) -> Number {
+    match n {
+        Number::Flt(n) if n.is_finite() => Number::Flt(format!("{:.11e}", n).parse().unwrap()),
+        n => n,
+    }
+}
+
+fn float(n: Number) -> f64 {
+    match n {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
@@ -0,0 +1,660 @@
+//! This module implements rendering of ASTs as mathematical notation, namely
+//! LaTeX and presentation MathML.
+//!
+//! Unlike [`to_infix`](super::to_infix), the output is meant to be read, not
+//! parsed: divisions become fractions, exponents become superscripts and
+//! parentheses are only added where precedence needs them. Vectors are
+//! rendered as rows, and vectors of vector literals as matrices. Ranges are
+//! rendered as the lists of integers they stand for, e.g. `{1, …, n}`.
+
+use crate::{
+    ast::{Builtin, Constant, Node, NodeView},
+    BinaryAction, BinderAction, Function, Number, UnaryAction,
+};
+
+/// Renders an AST as LaTeX, e.g. `\frac{1}{2} \cdot \sin\left(x\right)`.
+pub fn to_latex(node: &dyn Node) -> String {
+    render(&Latex, node).0
+}
+
+/// Renders an AST as a presentation MathML `<math>` element.
+pub fn to_mathml(node: &dyn Node) -> String {
+    format!(
+        "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">{}</math>",
+        render(&MathMl, node).0
+    )
+}
+
+/// Binding strength of rendered notation, from loosest to tightest.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Level {
+    /// Lambdas, whose bodies extend as far as possible.
+    Lambda,
+
+    /// Equations.
+    Equation,
+
+    /// Conversion to a unit.
+    Conversion,
+
+    /// Addition and subtraction.
+    Sum,
+
+    /// Negation and negative numbers. These may only lead a sum, and are
+    /// parenthesised elsewhere, e.g. `a - (-b)`.
+    Negation,
+
+    /// Multiplication.
+    Product,
+
+    /// Exponentiation and fractions, which cannot be the base of another
+    /// exponentiation without parentheses.
+    Power,
+
+    /// Numbers, variables, units, calls and parenthesised notation.
+    Atom,
+}
+
+/// A notation that an AST can be rendered in. Operands are already rendered
+/// and parenthesised where needed.
+trait Notation {
+    fn number(&self, n: Number) -> String;
+    fn constant(&self, constant: Constant) -> String;
+    fn variable(&self, name: &str) -> String;
+    fn unit(&self, name: &str) -> String;
+    fn parens(&self, inner: String) -> String;
+    fn negate(&self, operand: String) -> String;
+
+    /// Renders addition, subtraction, multiplication, left division,
+    /// conversion or an equation.
+    fn infix(&self, left: String, actor: BinaryAction, right: String) -> String;
+    fn fraction(&self, numerator: String, denominator: String) -> String;
+    fn power(&self, base: String, exponent: String) -> String;
+    fn call(&self, func: Function, argument: String) -> String;
+
+    /// Renders a sum, product or integral over `var`.
+    fn binder(
+        &self,
+        actor: BinderAction,
+        var: String,
+        body: String,
+        lower: String,
+        upper: String,
+    ) -> String;
+
+    /// Renders the roots of an equation, given the equation, the variable
+    /// and the guess.
+    fn solve(&self, arguments: Vec<String>) -> String;
+
+    /// Renders a matrix, given its rows.
+    fn matrix(&self, rows: Vec<Vec<String>>) -> String;
+
+    /// Renders a list, given its elements.
+    fn list(&self, elements: Vec<String>) -> String;
+
+    /// Renders the list of integers from `start` to `end`.
+    fn range(&self, start: String, end: String) -> String;
+
+    /// Renders a lambda, given its parameters and body.
+    fn lambda(&self, params: Vec<String>, body: String) -> String;
+
+    /// Renders a call of a [`Builtin`], e.g. as a superscript for
+    /// [`Builtin::Transpose`].
+    fn builtin(&self, func: Builtin, arguments: &[String]) -> String;
+}
+
+/// Renders an AST, returning the notation and its binding strength.
+fn render(notation: &dyn Notation, node: &dyn Node) -> (String, Level) {
+    match node.view() {
+        NodeView::Plain(p) => {
+            if let Some(constant) = p.constant() {
+                return (notation.constant(constant), Level::Atom);
+            }
+            let value = p.value();
+            let level = match value {
+                Number::Int(n) if n < 0 => Level::Negation,
+                Number::Flt(n) if n.is_sign_negative() && !n.is_nan() => Level::Negation,
+                _ => Level::Atom,
+            };
+            (notation.number(value), level)
+        }
+        NodeView::Variable(v) => (notation.variable(v.name()), Level::Atom),
+        NodeView::Unit(u) => (notation.unit(u.unit().name()), Level::Atom),
+        NodeView::Unary(u) => match u.actor() {
+            UnaryAction::Iden => render(notation, u.operand()),
+            UnaryAction::Neg => {
+                let operand = wrap(notation, u.operand(), Level::Power);
+                (notation.negate(operand), Level::Negation)
+            }
+            UnaryAction::Func(func) => {
+                let argument = render(notation, u.operand()).0;
+                (notation.call(func, argument), Level::Atom)
+            }
+        },
+        NodeView::Binary(b) => {
+            let (left, right) = (b.left(), b.right());
+            match b.actor() {
+                BinaryAction::Equate => {
+                    let left = wrap(notation, left, Level::Conversion);
+                    let right = wrap(notation, right, Level::Conversion);
+                    (
+                        notation.infix(left, BinaryAction::Equate, right),
+                        Level::Equation,
+                    )
+                }
+                BinaryAction::Convert => {
+                    let left = wrap(notation, left, Level::Conversion);
+                    let right = wrap(notation, right, Level::Sum);
+                    (
+                        notation.infix(left, BinaryAction::Convert, right),
+                        Level::Conversion,
+                    )
+                }
+                actor @ (BinaryAction::Add | BinaryAction::Sub | BinaryAction::PlusMinus) => {
+                    let left = wrap(notation, left, Level::Sum);
+                    let right = wrap(notation, right, Level::Product);
+                    (notation.infix(left, actor, right), Level::Sum)
+                }
+                actor @ (BinaryAction::Mul | BinaryAction::LeftDiv) => {
+                    let left = wrap(notation, left, Level::Product);
+                    let right = wrap(notation, right, Level::Power);
+                    (notation.infix(left, actor, right), Level::Product)
+                }
+                BinaryAction::Div => {
+                    let numerator = render(notation, left).0;
+                    let denominator = render(notation, right).0;
+                    (notation.fraction(numerator, denominator), Level::Power)
+                }
+                BinaryAction::Pow => {
+                    let base = wrap(notation, left, Level::Atom);
+                    let exponent = render(notation, right).0;
+                    (notation.power(base, exponent), Level::Power)
+                }
+            }
+        }
+        NodeView::Binder(b) => {
+            let var = notation.variable(b.var());
+            let lower = render(notation, b.lower()).0;
+            let upper = render(notation, b.upper()).0;
+            match b.actor() {
+                // The differential ends the integrand, which may be a sum.
+                BinderAction::Integrate => {
+                    let body = wrap(notation, b.body(), Level::Sum);
+                    let integral = notation.binder(b.actor(), var, body, lower, upper);
+                    (integral, Level::Product)
+                }
+
+                // A sum or product extends over whatever follows it, so it is
+                // parenthesised as any operand.
+                actor => {
+                    let body = wrap(notation, b.body(), Level::Product);
+                    (
+                        notation.binder(actor, var, body, lower, upper),
+                        Level::Conversion,
+                    )
+                }
+            }
+        }
+        NodeView::Solve(s) => {
+            let mut arguments = vec![render(notation, s.equation()).0, notation.variable(s.var())];
+            arguments.extend(s.guess().iter().map(|g| render(notation, *g).0));
+            (notation.solve(arguments), Level::Atom)
+        }
+        NodeView::Vector(v) => {
+            let element = |e| render(notation, e).0;
+            let literal = |e: &dyn Node| matches!(e.view(), NodeView::Vector(_));
+            let rows = match v.elements().any(literal) {
+                true => v
+                    .elements()
+                    .map(|e| match e.view() {
+                        NodeView::Vector(row) => row.elements().map(element).collect(),
+                        _ => vec![element(e)],
+                    })
+                    .collect(),
+                false => vec![v.elements().map(element).collect()],
+            };
+            (notation.matrix(rows), Level::Atom)
+        }
+        NodeView::List(l) => {
+            let elements = l.elements().map(|e| render(notation, e).0).collect();
+            (notation.list(elements), Level::Atom)
+        }
+        NodeView::Range(r) => {
+            let start = render(notation, r.start()).0;
+            let end = render(notation, r.end()).0;
+            (notation.range(start, end), Level::Atom)
+        }
+        NodeView::Lambda(l) => {
+            let params = l.params().iter().map(|p| notation.variable(p)).collect();
+            let body = render(notation, l.body()).0;
+            (notation.lambda(params, body), Level::Lambda)
+        }
+        NodeView::Call(c) => {
+            let (operand, level) = match c.func() {
+                Builtin::Map
+                | Builtin::Filter
+                | Builtin::Reduce
+                | Builtin::Len
+                | Builtin::Mean
+                | Builtin::Median
+                | Builtin::Mode
+                | Builtin::Stdev
+                | Builtin::Pstdev
+                | Builtin::Variance
+                | Builtin::Pvariance
+                | Builtin::Percentile
+                | Builtin::Ncr
+                | Builtin::Npr
+                | Builtin::Gamma
+                | Builtin::Erf
+                | Builtin::NormPdf
+                | Builtin::NormCdf
+                | Builtin::BinomPdf
+                | Builtin::BinomCdf
+                | Builtin::PoissonPdf
+                | Builtin::PoissonCdf
+                | Builtin::Gcd
+                | Builtin::Lcm
+                | Builtin::IsPrime
+                | Builtin::NextPrime
+                | Builtin::FactorInt
+                | Builtin::Totient
+                | Builtin::PowMod
+                | Builtin::ModInv
+                | Builtin::Rand
+                | Builtin::RandInt
+                | Builtin::RandN
+                | Builtin::Choose
+                | Builtin::Diff
+                | Builtin::Expand
+                | Builtin::Factor
+                | Builtin::Collect => (Level::Lambda, Level::Atom),
+                Builtin::Det | Builtin::Norm => (Level::Equation, Level::Atom),
+                Builtin::Inv | Builtin::Transpose => (Level::Atom, Level::Power),
+                Builtin::Dot | Builtin::Cross => (Level::Power, Level::Product),
+            };
+
+            // The left operand of a product may be a product itself.
+            let arguments: Vec<_> = c
+                .args()
+                .enumerate()
+                .map(|(i, a)| match (i, level) {
+                    (0, Level::Product) => wrap(notation, a, Level::Product),
+                    _ => wrap(notation, a, operand),
+                })
+                .collect();
+            (notation.builtin(c.func(), &arguments), level)
+        }
+    }
+}
+
+/// Renders an AST, parenthesising it if it binds looser than `level`.
+fn wrap(notation: &dyn Notation, node: &dyn Node, level: Level) -> String {
+    match render(notation, node) {
+        (inner, actual) if actual < level => notation.parens(inner),
+        (inner, _) => inner,
+    }
+}
+
+// -----------------------------------------------------------------------------
+// LaTeX.
+// -----------------------------------------------------------------------------
+
+struct Latex;
+
+impl Notation for Latex {
+    fn number(&self, n: Number) -> String {
+        match n {
+            Number::Flt(n) if n.is_nan() => r"\mathrm{NaN}".to_string(),
+            Number::Flt(n) if n == f64::INFINITY => r"\infty".to_string(),
+            Number::Flt(n) if n == f64::NEG_INFINITY => r"-\infty".to_string(),
+            n => n.to_string(),
+        }
+    }
+
+    fn constant(&self, constant: Constant) -> String {
+        match constant {
+            Constant::Pi => r"\pi".to_string(),
+        }
+    }
+
+    fn variable(&self, name: &str) -> String {
+        if name.len() == 1 {
+            name.to_string()
+        } else {
+            format!(r"\mathit{{{}}}", name.replace('_', r"\_"))
+        }
+    }
+
+    fn unit(&self, name: &str) -> String {
+        format!(r"\mathrm{{{}}}", name)
+    }
+
+    fn parens(&self, inner: String) -> String {
+        format!(r"\left({}\right)", inner)
+    }
+
+    fn negate(&self, operand: String) -> String {
+        format!("-{}", operand)
+    }
+
+    fn infix(&self, left: String, actor: BinaryAction, right: String) -> String {
+        let operator = match actor {
+            BinaryAction::Add => "+",
+            BinaryAction::Sub => "-",
+            BinaryAction::PlusMinus => r"\pm",
+            BinaryAction::Convert => r"\to",
+            BinaryAction::Equate => "=",
+            BinaryAction::LeftDiv => r"\backslash",
+            _ => r"\cdot",
+        };
+        format!("{} {} {}", left, operator, right)
+    }
+
+    fn fraction(&self, numerator: String, denominator: String) -> String {
+        format!(r"\frac{{{}}}{{{}}}", numerator, denominator)
+    }
+
+    fn power(&self, base: String, exponent: String) -> String {
+        format!("{}^{{{}}}", base, exponent)
+    }
+
+    fn call(&self, func: Function, argument: String) -> String {
+        let name = match func {
+            Function::Sin => r"\sin",
+            Function::Cos => r"\cos",
+            Function::Tan => r"\tan",
+            Function::Csc => r"\csc",
+            Function::Sec => r"\sec",
+            Function::Cot => r"\cot",
+            Function::Asin => r"\arcsin",
+            Function::Acos => r"\arccos",
+            Function::Atan => r"\arctan",
+            Function::Acsc => r"\operatorname{arccsc}",
+            Function::Asec => r"\operatorname{arcsec}",
+            Function::Acot => r"\operatorname{arccot}",
+            Function::Ln => r"\ln",
+            Function::Sqrt => return format!(r"\sqrt{{{}}}", argument),
+        };
+        format!("{}{}", name, self.parens(argument))
+    }
+
+    fn binder(
+        &self,
+        actor: BinderAction,
+        var: String,
+        body: String,
+        lower: String,
+        upper: String,
+    ) -> String {
+        match actor {
+            BinderAction::Integrate => format!(
+                r"\int_{{{}}}^{{{}}} {} \, \mathrm{{d}}{}",
+                lower, upper, body, var
+            ),
+            BinderAction::Sum => format!(r"\sum_{{{} = {}}}^{{{}}} {}", var, lower, upper, body),
+            BinderAction::Prod => format!(r"\prod_{{{} = {}}}^{{{}}} {}", var, lower, upper, body),
+        }
+    }
+
+    fn solve(&self, arguments: Vec<String>) -> String {
+        format!(
+            r"\operatorname{{solve}}{}",
+            self.parens(arguments.join(", "))
+        )
+    }
+
+    fn matrix(&self, rows: Vec<Vec<String>>) -> String {
+        let rows: Vec<_> = rows.iter().map(|row| row.join(" & ")).collect();
+        format!(r"\begin{{bmatrix}} {} \end{{bmatrix}}", rows.join(r" \\ "))
+    }
+
+    fn list(&self, elements: Vec<String>) -> String {
+        format!(r"\left\{{{}\right\}}", elements.join(", "))
+    }
+
+    fn range(&self, start: String, end: String) -> String {
+        self.list(vec![start, r"\ldots".to_string(), end])
+    }
+
+    fn lambda(&self, params: Vec<String>, body: String) -> String {
+        let params = match params.len() {
+            1 => params.join(""),
+            _ => self.parens(params.join(", ")),
+        };
+        format!(r"{} \mapsto {}", params, body)
+    }
+
+    fn builtin(&self, func: Builtin, arguments: &[String]) -> String {
+        match func {
+            Builtin::Det => format!(r"\det{}", self.parens(arguments[0].clone())),
+            Builtin::Inv => self.power(arguments[0].clone(), "-1".to_string()),
+            Builtin::Transpose => self.power(arguments[0].clone(), r"\mathsf{T}".to_string()),
+            Builtin::Dot => format!(r"{} \cdot {}", arguments[0], arguments[1]),
+            Builtin::Cross => format!(r"{} \times {}", arguments[0], arguments[1]),
+            Builtin::Norm => format!(r"\left\|{}\right\|", arguments[0]),
+            Builtin::Gamma => format!(r"\Gamma{}", self.parens(arguments[0].clone())),
+            Builtin::Ncr => format!(r"\binom{{{}}}{{{}}}", arguments[0], arguments[1]),
+            Builtin::Gcd => format!(r"\gcd{}", self.parens(arguments.join(", "))),
+            Builtin::Totient => format!(r"\varphi{}", self.parens(arguments[0].clone())),
+            Builtin::Diff => format!(
+                r"\frac{{\mathrm{{d}}}}{{\mathrm{{d}}{}}}{}",
+                arguments[1],
+                self.parens(arguments[0].clone())
+            ),
+
+            // Other functions are written by name.
+            func => format!(
+                r"\operatorname{{{}}}{}",
+                func.name(),
+                self.parens(arguments.join(", "))
+            ),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// MathML.
+// -----------------------------------------------------------------------------
+
+/// Every method returns a single element, so that operands can be passed to
+/// `<mfrac>` and `<msup>` as they are.
+struct MathMl;
+
+impl MathMl {
+    /// Name of a [`Function`] as conventionally written.
+    fn name(func: Function) -> &'static str {
+        match func {
+            Function::Asin => "arcsin",
+            Function::Acos => "arccos",
+            Function::Atan => "arctan",
+            Function::Acsc => "arccsc",
+            Function::Asec => "arcsec",
+            Function::Acot => "arccot",
+            func => func.name(),
+        }
+    }
+}
+
+impl Notation for MathMl {
+    fn number(&self, n: Number) -> String {
+        match n {
+            Number::Flt(n) if n.is_nan() => "<mi>NaN</mi>".to_string(),
+            Number::Flt(n) if n.is_infinite() => {
+                let infinity = "<mi>\u{221E}</mi>".to_string();
+                if n > 0.0 {
+                    infinity
+                } else {
+                    self.negate(infinity)
+                }
+            }
+            Number::Int(n) if n < 0 => self.negate(format!("<mn>{}</mn>", n.unsigned_abs())),
+            Number::Flt(n) if n.is_sign_negative() => self.negate(format!("<mn>{}</mn>", -n)),
+            n => format!("<mn>{}</mn>", n),
+        }
+    }
+
+    fn constant(&self, constant: Constant) -> String {
+        match constant {
+            Constant::Pi => "<mi>\u{3C0}</mi>".to_string(),
+        }
+    }
+
+    fn variable(&self, name: &str) -> String {
+        format!("<mi>{}</mi>", name)
+    }
+
+    fn unit(&self, name: &str) -> String {
+        format!("<mi mathvariant=\"normal\">{}</mi>", name)
+    }
+
+    fn parens(&self, inner: String) -> String {
+        format!("<mrow><mo>(</mo>{}<mo>)</mo></mrow>", inner)
+    }
+
+    fn negate(&self, operand: String) -> String {
+        format!("<mrow><mo>\u{2212}</mo>{}</mrow>", operand)
+    }
+
+    fn infix(&self, left: String, actor: BinaryAction, right: String) -> String {
+        let operator = match actor {
+            BinaryAction::Add => "+",
+            BinaryAction::Sub => "\u{2212}",
+            BinaryAction::PlusMinus => "\u{B1}",
+            BinaryAction::Convert => "\u{2192}",
+            BinaryAction::Equate => "=",
+            BinaryAction::LeftDiv => "\\",
+            _ => "\u{22C5}",
+        };
+        format!("<mrow>{}<mo>{}</mo>{}</mrow>", left, operator, right)
+    }
+
+    fn fraction(&self, numerator: String, denominator: String) -> String {
+        format!("<mfrac>{}{}</mfrac>", numerator, denominator)
+    }
+
+    fn power(&self, base: String, exponent: String) -> String {
+        format!("<msup>{}{}</msup>", base, exponent)
+    }
+
+    fn call(&self, func: Function, argument: String) -> String {
+        if func == Function::Sqrt {
+            return format!("<msqrt>{}</msqrt>", argument);
+        }
+
+        // U+2061 is the invisible function application operator.
+        format!(
+            "<mrow><mi>{}</mi><mo>\u{2061}</mo>{}</mrow>",
+            Self::name(func),
+            self.parens(argument)
+        )
+    }
+
+    fn binder(
+        &self,
+        actor: BinderAction,
+        var: String,
+        body: String,
+        lower: String,
+        upper: String,
+    ) -> String {
+        let operator = match actor {
+            BinderAction::Integrate => {
+                return format!(
+                    "<mrow><msubsup><mo>\u{222B}</mo>{}{}</msubsup>{}<mrow><mi mathvariant=\"normal\">d</mi>{}</mrow></mrow>",
+                    lower, upper, body, var
+                )
+            }
+            BinderAction::Sum => "\u{2211}",
+            BinderAction::Prod => "\u{220F}",
+        };
+        format!(
+            "<mrow><munderover><mo>{}</mo><mrow>{}<mo>=</mo>{}</mrow>{}</munderover>{}</mrow>",
+            operator, var, lower, upper, body
+        )
+    }
+
+    fn solve(&self, arguments: Vec<String>) -> String {
+        let arguments = arguments.join("<mo>,</mo>");
+        format!(
+            "<mrow><mi>solve</mi><mo>\u{2061}</mo>{}</mrow>",
+            self.parens(arguments)
+        )
+    }
+
+    fn matrix(&self, rows: Vec<Vec<String>>) -> String {
+        let rows: String = rows
+            .iter()
+            .map(|row| format!("<mtr><mtd>{}</mtd></mtr>", row.join("</mtd><mtd>")))
+            .collect();
+        format!("<mrow><mo>[</mo><mtable>{}</mtable><mo>]</mo></mrow>", rows)
+    }
+
+    fn list(&self, elements: Vec<String>) -> String {
+        format!(
+            "<mrow><mo>{{</mo>{}<mo>}}</mo></mrow>",
+            elements.join("<mo>,</mo>")
+        )
+    }
+
+    fn range(&self, start: String, end: String) -> String {
+        self.list(vec![start, "<mo>\u{2026}</mo>".to_string(), end])
+    }
+
+    fn lambda(&self, params: Vec<String>, body: String) -> String {
+        let params = match params.len() {
+            1 => params.join(""),
+            _ => self.parens(params.join("<mo>,</mo>")),
+        };
+        format!("<mrow>{}<mo>\u{21A6}</mo>{}</mrow>", params, body)
+    }
+
+    fn builtin(&self, func: Builtin, arguments: &[String]) -> String {
+        let transpose = "<mi mathvariant=\"sans-serif\">T</mi>".to_string();
+        match func {
+            Builtin::Det => format!(
+                "<mrow><mi>det</mi><mo>\u{2061}</mo>{}</mrow>",
+                self.parens(arguments[0].clone())
+            ),
+            Builtin::Inv => self.power(arguments[0].clone(), self.number(Number::Int(-1))),
+            Builtin::Transpose => self.power(arguments[0].clone(), transpose),
+            Builtin::Dot => format!(
+                "<mrow>{}<mo>\u{22C5}</mo>{}</mrow>",
+                arguments[0], arguments[1]
+            ),
+            Builtin::Cross => format!(
+                "<mrow>{}<mo>\u{D7}</mo>{}</mrow>",
+                arguments[0], arguments[1]
+            ),
+            Builtin::Norm => format!(
+                "<mrow><mo>\u{2016}</mo>{}<mo>\u{2016}</mo></mrow>",
+                arguments[0]
+            ),
+            Builtin::Gamma => format!(
+                "<mrow><mi>\u{393}</mi><mo>\u{2061}</mo>{}</mrow>",
+                self.parens(arguments[0].clone())
+            ),
+            Builtin::Ncr => format!(
+                "<mrow><mo>(</mo><mfrac linethickness=\"0\">{}{}</mfrac><mo>)</mo></mrow>",
+                arguments[0], arguments[1]
+            ),
+            Builtin::Totient => format!(
+                "<mrow><mi>\u{3C6}</mi><mo>\u{2061}</mo>{}</mrow>",
+                self.parens(arguments[0].clone())
+            ),
+            Builtin::Diff => format!(
+                "<mrow><mfrac><mi mathvariant=\"normal\">d</mi>\
+                 <mrow><mi mathvariant=\"normal\">d</mi>{}</mrow></mfrac>{}</mrow>",
+                arguments[1],
+                self.parens(arguments[0].clone())
+            ),
+
+            // Other functions are written by name.
+            func => format!(
+                "<mrow><mi>{}</mi><mo>\u{2061}</mo>{}</mrow>",
+                func.name(),
+                self.parens(arguments.join("<mo>,</mo>"))
+            ),
+        }
+    }
+}
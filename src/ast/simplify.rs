@@ -0,0 +1,690 @@
+//! This module implements constant folding and algebraic simplification of
+//! ASTs.
+//!
+//! [`Number`] mixes integers and floating-point numbers, so an identity that
+//! holds for real numbers does not always hold here. For example, `0 * x` is
+//! `NaN` when `x` is `NaN`, and `x + 0` is `0` when `x` is `-0.0`. By default,
+//! a [`Simplifier`] only rewrites an AST into one that evaluates to an
+//! identical [`Number`] (see [`Number::identical`]) for every binding of its
+//! variables. [`Simplifier::assume_real`] relaxes this for formulas whose
+//! variables are known to be finite floating-point numbers.
+//!
+//! Sub-trees that are not pure (see [`is_pure`]), i.e. that draw random
+//! numbers, are never dropped, merged or cancelled out, so `rand() - rand()`
+//! and `0 * rand()` are kept as they are.
+//!
+//! `factorint(n)` of a constant integer is written as its prime
+//! factorisation, e.g. `2^3 * 3^2 * 5` for `factorint(360)`.
+
+use crate::{
+    ast::{
+        factorint, is_pure, CallNode, LambdaNode, ListNode, Node, NodeBox, NodeView, RangeNode,
+        VectorNode,
+    },
+    BinaryAction, BinaryNode, BinderAction, BinderNode, Builtin, Number, PlainNode, SolveNode,
+    UnaryAction, UnaryNode,
+};
+
+/// Simplifies an AST with the default [`Simplifier`].
+///
+/// The result evaluates to an identical [`Number`] as `node` for every binding
+/// of its variables.
+pub fn simplify(node: &dyn Node) -> NodeBox {
+    Simplifier::new().simplify(node)
+}
+
+/// Configurable simplification pass over an AST.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Simplifier {
+    /// Whether variables are assumed to be finite floating-point numbers.
+    assume_real: bool,
+}
+
+/// Statically known kind of the [`Number`] a sub-tree evaluates to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Int,
+    Flt,
+    Unknown,
+}
+
+impl Simplifier {
+    /// Creates a new [`Simplifier`] that keeps the exact value of an AST.
+    pub fn new() -> Simplifier {
+        Self::default()
+    }
+
+    /// Sets whether variables are assumed to be finite floating-point numbers.
+    ///
+    /// This allows identities such as `0 * x = 0`, `x - x = 0` and collecting
+    /// like terms (`2x + 3x = 5x`), which do not hold for NaN, infinities or
+    /// signed zero, and may change the rounding of the result.
+    pub fn assume_real(mut self, assume_real: bool) -> Simplifier {
+        self.assume_real = assume_real;
+        self
+    }
+
+    /// Simplifies an AST, returning a new one.
+    pub fn simplify(&self, node: &dyn Node) -> NodeBox {
+        self.rebuild(node).0
+    }
+
+    /// Simplifies a sub-tree bottom-up, also returning its [`Kind`].
+    fn rebuild(&self, node: &dyn Node) -> (NodeBox, Kind) {
+        match node.view() {
+            NodeView::Plain(p) => (Box::new(*p), kind_of(p.value())),
+            NodeView::Variable(v) => {
+                let kind = if self.assume_real {
+                    Kind::Flt
+                } else {
+                    Kind::Unknown
+                };
+                (Box::new(v.clone()), kind)
+            }
+            NodeView::Unit(u) => (Box::new(u.clone()), Kind::Flt),
+            NodeView::Unary(u) => {
+                let (operand, kind) = self.rebuild(u.operand());
+                match u.actor() {
+                    UnaryAction::Iden => (operand, kind),
+                    UnaryAction::Neg => (negate(operand), kind),
+                    func @ UnaryAction::Func(_) => match constant(operand.as_ref()) {
+                        Some(n) => (plain(func.evaluate(n)), Kind::Flt),
+                        None => (Box::new(UnaryNode::new(func, operand)), Kind::Flt),
+                    },
+                }
+            }
+            NodeView::Binary(b) => {
+                let left = self.rebuild(b.left());
+                let right = self.rebuild(b.right());
+                self.combine(left, b.actor(), right)
+            }
+
+            // Sums, products and integrals are not folded, as that could drop
+            // uncertainties and units within them.
+            NodeView::Binder(b) => {
+                let (body, kind) = match b.actor() {
+                    BinderAction::Integrate => (self.simplify(b.body()), Kind::Flt),
+
+                    // The bound variable is an integer, so the body is not
+                    // assumed to be real.
+                    _ => (simplify(b.body()), Kind::Unknown),
+                };
+                let node = BinderNode::new(
+                    b.actor(),
+                    b.var(),
+                    body,
+                    self.simplify(b.lower()),
+                    self.simplify(b.upper()),
+                );
+                (Box::new(node), kind)
+            }
+
+            // Roots are found numerically, so they are floats.
+            NodeView::Solve(s) => {
+                let node = SolveNode::new(
+                    self.simplify(s.equation()),
+                    s.var(),
+                    s.guess().map(|g| self.simplify(g)),
+                );
+                (Box::new(node), Kind::Flt)
+            }
+
+            // Vectors and matrices are not scalars of either kind.
+            NodeView::Vector(v) => {
+                let elements = v.elements().map(|e| self.simplify(e)).collect();
+                (Box::new(VectorNode::new(elements)), Kind::Unknown)
+            }
+            NodeView::Call(c) => {
+                // Symbolic functions are carried out, e.g. `diff(x^2, x)` is
+                // `2x`.
+                if c.func().is_symbolic() {
+                    if let Ok(form) = c.rewrite() {
+                        return self.rebuild(form.as_ref());
+                    }
+                }
+
+                let args: Vec<_> = c.args().map(|a| self.simplify(a)).collect();
+
+                // The prime factorisation of an integer is written out.
+                if let (Builtin::FactorInt, [arg]) = (c.func(), &args[..]) {
+                    if let Some(Number::Int(n)) = constant(arg.as_ref()) {
+                        return (factorint(n), Kind::Int);
+                    }
+                }
+                (Box::new(CallNode::new(c.func(), args)), Kind::Unknown)
+            }
+
+            // Neither are lists, ranges and lambdas.
+            NodeView::List(l) => {
+                let elements = l.elements().map(|e| self.simplify(e)).collect();
+                (Box::new(ListNode::new(elements)), Kind::Unknown)
+            }
+            NodeView::Range(r) => {
+                let node = RangeNode::new(self.simplify(r.start()), self.simplify(r.end()));
+                (Box::new(node), Kind::Unknown)
+            }
+
+            // The parameters may be bound to integers, so the body is not
+            // assumed to be real.
+            NodeView::Lambda(l) => {
+                let node = LambdaNode::new(l.params(), simplify(l.body()));
+                (Box::new(node), Kind::Unknown)
+            }
+        }
+    }
+
+    /// Builds a simplified [`BinaryNode`] from two simplified operands.
+    fn combine(
+        &self,
+        (left, lk): (NodeBox, Kind),
+        actor: BinaryAction,
+        (right, rk): (NodeBox, Kind),
+    ) -> (NodeBox, Kind) {
+        // Uncertain values are kept, as folding would drop the uncertainty.
+        if actor == BinaryAction::PlusMinus {
+            return (Box::new(BinaryNode::new(left, actor, right)), lk);
+        }
+
+        // So are conversions, as folding would drop the unit.
+        if actor == BinaryAction::Convert {
+            return (Box::new(BinaryNode::new(left, actor, right)), Kind::Flt);
+        }
+
+        // And equations, as folding would drop one side.
+        if actor == BinaryAction::Equate {
+            let kind = infer(actor, lk, rk, right.as_ref());
+            return (Box::new(BinaryNode::new(left, actor, right)), kind);
+        }
+
+        // Constant folding.
+        if let (Some(a), Some(b)) = (constant(left.as_ref()), constant(right.as_ref())) {
+            if let Some(n) = fold(actor, a, b) {
+                return (plain(n), kind_of(n));
+            }
+        }
+
+        // A float constant with an integral value is written as an integer
+        // when the other operand is a float, as it is converted back exactly.
+        let (left, lk) = match (integral(left.as_ref()), rk) {
+            (Some(n), Kind::Flt) => (plain(n), Kind::Int),
+            _ => (left, lk),
+        };
+        let (right, rk) = match (integral(right.as_ref()), lk) {
+            (Some(n), Kind::Flt) => (plain(n), Kind::Int),
+            _ => (right, rk),
+        };
+
+        let kind = infer(actor, lk, rk, right.as_ref());
+        let real = self.assume_real;
+        let l = left.as_ref();
+        let r = right.as_ref();
+
+        // Whether either operand may be dropped, or both merged into one.
+        let pure = is_pure(l) && is_pure(r);
+
+        match actor {
+            BinaryAction::Add => {
+                // x + 0 = x, exact for integers only: -0.0 + 0 is 0.0.
+                if is_zero(r, lk, real) || is_neg_zero(r, lk) {
+                    return (left, kind);
+                }
+                if is_zero(l, rk, real) || is_neg_zero(l, rk) {
+                    return (right, kind);
+                }
+
+                // x + -y = x - y, and -y + x = x - y.
+                if let Some(y) = self.flipped(r, rk, lk) {
+                    return self.combine((left, lk), BinaryAction::Sub, (y, rk));
+                }
+                if let Some(y) = self.flipped(l, lk, rk) {
+                    return self.combine((right, rk), BinaryAction::Sub, (y, lk));
+                }
+            }
+
+            BinaryAction::Sub => {
+                // x - 0 = x, exact for both integers and floats.
+                if is_int(r, 0) || (lk == Kind::Flt && is_flt(r, 0.0)) || is_zero(r, lk, real) {
+                    return (left, kind);
+                }
+
+                // 0 - x = -x, exact for integers only: 0.0 - 0.0 is 0.0.
+                if is_zero(l, rk, real) {
+                    return (negate(right), kind);
+                }
+
+                // x - -y = x + y.
+                if let Some(y) = self.flipped(r, rk, lk) {
+                    return self.combine((left, lk), BinaryAction::Add, (y, rk));
+                }
+
+                // x - x = 0, exact for integers only: inf - inf is NaN.
+                if pure && l == r {
+                    match lk {
+                        Kind::Int => return (plain(Number::Int(0)), Kind::Int),
+                        Kind::Flt if real => return (plain(Number::Flt(0.0)), Kind::Flt),
+                        _ => (),
+                    }
+                }
+            }
+
+            BinaryAction::Mul => {
+                // x * 1 = x and x * -1 = -x, exact for both kinds.
+                if is_one(r, lk) {
+                    return (left, kind);
+                }
+                if is_one(l, rk) {
+                    return (right, kind);
+                }
+                if is_minus_one(r, lk) {
+                    return (negate(left), kind);
+                }
+                if is_minus_one(l, rk) {
+                    return (negate(right), kind);
+                }
+
+                // x * 0 = 0, exact for integers only: NaN * 0 is NaN.
+                if pure && (is_zero(r, lk, real) || is_zero(l, rk, real)) {
+                    return (plain(zero(kind)), kind);
+                }
+
+                // a * (b * x) = (ab) * x, which may round differently.
+                if real {
+                    if let Some(node) = merge_factors(l, r).or_else(|| merge_factors(r, l)) {
+                        return (node, kind);
+                    }
+                }
+            }
+
+            BinaryAction::Div => {
+                // x / 1 = x and x / -1 = -x, exact for both kinds.
+                if is_one(r, lk) {
+                    return (left, kind);
+                }
+                if is_minus_one(r, lk) {
+                    return (negate(left), kind);
+                }
+            }
+
+            BinaryAction::Pow => {
+                // x ^ 1 = x, exact for both kinds.
+                if is_one(r, lk) {
+                    return (left, kind);
+                }
+
+                // x ^ 0 = 1, exact for both kinds: even NaN ^ 0 is 1.
+                if pure && is_int(r, 0) && lk != Kind::Unknown {
+                    return (plain(one(lk)), lk);
+                }
+
+                // 1.0 ^ x = 1.0, exact for both kinds: even 1.0 ^ NaN is 1.
+                if pure && is_flt(l, 1.0) {
+                    return (plain(Number::Flt(1.0)), Kind::Flt);
+                }
+            }
+
+            BinaryAction::LeftDiv
+            | BinaryAction::PlusMinus
+            | BinaryAction::Convert
+            | BinaryAction::Equate => (),
+        }
+
+        // Like terms.
+        if pure && matches!(actor, BinaryAction::Add | BinaryAction::Sub) {
+            // x + x = 2x, exact for both kinds.
+            if actor == BinaryAction::Add && l == r {
+                let two = plain(Number::Int(2));
+                return (
+                    Box::new(BinaryNode::new(two, BinaryAction::Mul, left)),
+                    kind,
+                );
+            }
+
+            if real {
+                let node = BinaryNode::new(left, actor, right);
+                if let Some(collected) = collect(&node) {
+                    return (collected, kind);
+                }
+                return (Box::new(node), kind);
+            }
+        }
+
+        (Box::new(BinaryNode::new(left, actor, right)), kind)
+    }
+
+    /// Returns `y` if `node` is `-y` and adding `-y` to an operand of kind
+    /// `other` is exactly the same as subtracting `y`, or vice versa.
+    ///
+    /// This holds for negative constants, and for floats in general. For an
+    /// integer `y = 0` and a float `x = -0.0` however, `x + -y` is `0.0` but
+    /// `x - y` is `-0.0`.
+    fn flipped(&self, node: &dyn Node, kind: Kind, other: Kind) -> Option<NodeBox> {
+        match node.view() {
+            NodeView::Plain(_) => negated(node),
+            _ if self.assume_real || kind == Kind::Flt || other == Kind::Int => negated(node),
+            _ => None,
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Helpers for building and inspecting nodes.
+// -----------------------------------------------------------------------------
+
+fn plain(n: Number) -> NodeBox {
+    Box::new(PlainNode::new(n))
+}
+
+fn constant(node: &dyn Node) -> Option<Number> {
+    match node.view() {
+        NodeView::Plain(p) => Some(p.value()),
+        _ => None,
+    }
+}
+
+/// Returns the integer equal to `node` if it is a float constant that is
+/// converted back exactly, which excludes `-0.0`.
+fn integral(node: &dyn Node) -> Option<Number> {
+    match constant(node)? {
+        Number::Flt(n) if n.fract() == 0.0 && n.abs() <= 2f64.powi(53) => {
+            (n != 0.0 || n.is_sign_positive()).then_some(Number::Int(n as i128))
+        }
+        _ => None,
+    }
+}
+
+fn kind_of(n: Number) -> Kind {
+    match n {
+        Number::Int(_) => Kind::Int,
+        Number::Flt(_) => Kind::Flt,
+    }
+}
+
+fn zero(kind: Kind) -> Number {
+    match kind {
+        Kind::Int => Number::Int(0),
+        _ => Number::Flt(0.0),
+    }
+}
+
+fn one(kind: Kind) -> Number {
+    match kind {
+        Kind::Int => Number::Int(1),
+        _ => Number::Flt(1.0),
+    }
+}
+
+fn is_int(node: &dyn Node, value: i128) -> bool {
+    constant(node).is_some_and(|n| n.identical(&Number::Int(value)))
+}
+
+fn is_flt(node: &dyn Node, value: f64) -> bool {
+    constant(node).is_some_and(|n| n.identical(&Number::Flt(value)))
+}
+
+/// Checks if `node` is `1` and multiplying or dividing an operand of kind
+/// `other` by it is exact and keeps its kind.
+fn is_one(node: &dyn Node, other: Kind) -> bool {
+    is_int(node, 1) || (other == Kind::Flt && is_flt(node, 1.0))
+}
+
+/// Same as [`is_one`], but for `-1`.
+fn is_minus_one(node: &dyn Node, other: Kind) -> bool {
+    is_int(node, -1) || (other == Kind::Flt && is_flt(node, -1.0))
+}
+
+/// Checks if `node` is a zero that can be dropped from a sum, or that
+/// annihilates a product, with an operand of kind `other`.
+///
+/// For integers this is always exact. For floats this ignores NaN, infinities
+/// and the sign of zero, so it is only allowed when assuming real numbers.
+fn is_zero(node: &dyn Node, other: Kind, real: bool) -> bool {
+    match other {
+        Kind::Int => is_int(node, 0),
+        Kind::Flt if real => is_int(node, 0) || is_flt(node, 0.0) || is_flt(node, -0.0),
+        _ => false,
+    }
+}
+
+/// Checks if `node` is `-0.0`, which is the exact additive identity of floats.
+fn is_neg_zero(node: &dyn Node, other: Kind) -> bool {
+    other == Kind::Flt && is_flt(node, -0.0)
+}
+
+/// Returns `y` if `node` is `-y`, either as a [`UnaryNode`] or as a negative
+/// constant.
+fn negated(node: &dyn Node) -> Option<NodeBox> {
+    match node.view() {
+        NodeView::Unary(u) if u.actor() == UnaryAction::Neg => Some(u.operand().clone_box()),
+        NodeView::Plain(p) => negative(p.value()).map(plain),
+        _ => None,
+    }
+}
+
+/// Returns `-n` if `n` is negative and can be negated without overflow.
+fn negative(n: Number) -> Option<Number> {
+    match n {
+        Number::Int(n) if n < 0 => n.checked_neg().map(Number::Int),
+        Number::Flt(n) if n.is_sign_negative() && !n.is_nan() => Some(Number::Flt(-n)),
+        _ => None,
+    }
+}
+
+/// Negates a simplified node, folding constants and double negation.
+fn negate(node: NodeBox) -> NodeBox {
+    if let Some(y) = negated(node.as_ref()) {
+        return y;
+    }
+
+    match constant(node.as_ref()) {
+        Some(Number::Int(n)) if n.checked_neg().is_some() => plain(Number::Int(-n)),
+        Some(Number::Flt(n)) => plain(Number::Flt(-n)),
+        _ => Box::new(UnaryNode::new(UnaryAction::Neg, node)),
+    }
+}
+
+/// Merges a constant `a` and a product `b * x` (or `x * b`) of a constant and
+/// anything else into `(ab) * x`.
+fn merge_factors(a: &dyn Node, product: &dyn Node) -> Option<NodeBox> {
+    let a = constant(a)?;
+    let (b, x) = match product.view() {
+        NodeView::Binary(p) if p.actor() == BinaryAction::Mul => {
+            match (constant(p.left()), constant(p.right())) {
+                (Some(b), _) => (b, p.right()),
+                (_, Some(b)) => (b, p.left()),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    let ab = fold(BinaryAction::Mul, a, b)?;
+
+    Some(Box::new(BinaryNode::new(
+        plain(ab),
+        BinaryAction::Mul,
+        x.clone_box(),
+    )))
+}
+
+/// Folds two constants, unless integer arithmetic would overflow.
+fn fold(actor: BinaryAction, a: Number, b: Number) -> Option<Number> {
+    if let (Number::Int(m), Number::Int(n)) = (a, b) {
+        let valid = match actor {
+            BinaryAction::Add => m.checked_add(n).is_some(),
+            BinaryAction::Sub => m.checked_sub(n).is_some(),
+            BinaryAction::Mul => m.checked_mul(n).is_some(),
+            BinaryAction::Div => n == 0 || m.checked_div(n).is_some(),
+            BinaryAction::LeftDiv => m == 0 || n.checked_div(m).is_some(),
+            BinaryAction::Pow => {
+                n < 0 || u32::try_from(n).is_ok_and(|n| m.checked_pow(n).is_some())
+            }
+            BinaryAction::PlusMinus | BinaryAction::Convert | BinaryAction::Equate => true,
+        };
+
+        if !valid {
+            return None;
+        }
+    }
+
+    Some(actor.evaluate(a, b))
+}
+
+/// Infers the [`Kind`] of a [`BinaryNode`] from those of its operands.
+fn infer(actor: BinaryAction, left: Kind, right: Kind, right_node: &dyn Node) -> Kind {
+    // Uncertain values evaluate to the left operand.
+    if actor == BinaryAction::PlusMinus {
+        return left;
+    }
+
+    match (left, right) {
+        (Kind::Flt, _) | (_, Kind::Flt) => Kind::Flt,
+        (Kind::Int, Kind::Int) => match actor {
+            BinaryAction::Add | BinaryAction::Sub | BinaryAction::Mul | BinaryAction::Equate => {
+                Kind::Int
+            }
+
+            // Division by zero is NaN.
+            BinaryAction::Div => match constant(right_node) {
+                Some(Number::Int(n)) if n != 0 => Kind::Int,
+                _ => Kind::Unknown,
+            },
+
+            // Negative exponents give floats.
+            BinaryAction::Pow => match constant(right_node) {
+                Some(Number::Int(n)) if n >= 0 => Kind::Int,
+                Some(Number::Int(_)) => Kind::Flt,
+                _ => Kind::Unknown,
+            },
+
+            BinaryAction::PlusMinus => Kind::Int,
+            BinaryAction::Convert => Kind::Flt,
+
+            // Either a division, or a linear system solved over floats.
+            BinaryAction::LeftDiv => Kind::Unknown,
+        },
+        _ => Kind::Unknown,
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Collecting like terms.
+// -----------------------------------------------------------------------------
+
+/// Collects like terms in a sum, e.g. `2x + 1 - x + 3` becomes `x + 4`.
+///
+/// Returns [`None`] if nothing was collected or if the coefficients overflow.
+fn collect(node: &BinaryNode) -> Option<NodeBox> {
+    let mut terms = Vec::new();
+    flatten(node, true, &mut terms)?;
+
+    // Sum up coefficients of like terms, keeping the order they appear in.
+    let mut constant = Number::Int(0);
+    let mut constants = 0;
+    let mut collected: Vec<(Number, NodeBox)> = Vec::new();
+    for (coef, base) in terms.iter() {
+        match base {
+            None => {
+                constant = fold(BinaryAction::Add, constant, *coef)?;
+                constants += 1;
+            }
+            Some(base) => match collected.iter_mut().find(|(_, b)| b == base) {
+                Some((c, _)) => *c = fold(BinaryAction::Add, *c, *coef)?,
+                None => collected.push((*coef, base.clone())),
+            },
+        }
+    }
+
+    // Nothing to collect.
+    if collected.len() + constants.min(1) == terms.len() {
+        return None;
+    }
+
+    // Rebuild the sum.
+    let mut sum: Option<NodeBox> = None;
+    for (coef, base) in collected.iter() {
+        if is_zero_number(*coef) {
+            continue;
+        }
+
+        let (negative, coef) = match negative(*coef) {
+            Some(abs) => (true, abs),
+            None => (false, *coef),
+        };
+        let term = if coef.identical(&Number::Int(1)) {
+            base.clone()
+        } else {
+            Box::new(BinaryNode::new(
+                plain(coef),
+                BinaryAction::Mul,
+                base.clone(),
+            ))
+        };
+
+        sum = Some(match (sum, negative) {
+            (None, false) => term,
+            (None, true) => Box::new(UnaryNode::new(UnaryAction::Neg, term)),
+            (Some(s), false) => Box::new(BinaryNode::new(s, BinaryAction::Add, term)),
+            (Some(s), true) => Box::new(BinaryNode::new(s, BinaryAction::Sub, term)),
+        });
+    }
+
+    Some(match sum {
+        // Every term is a float when assuming real numbers, and so is the sum
+        // even if they cancel out.
+        None if !collected.is_empty() => plain(constant + Number::Flt(0.0)),
+        None => plain(constant),
+        Some(s) if is_zero_number(constant) => s,
+        Some(s) => match negative(constant) {
+            Some(abs) => Box::new(BinaryNode::new(s, BinaryAction::Sub, plain(abs))),
+            None => Box::new(BinaryNode::new(s, BinaryAction::Add, plain(constant))),
+        },
+    })
+}
+
+fn is_zero_number(n: Number) -> bool {
+    match n {
+        Number::Int(n) => n == 0,
+        Number::Flt(n) => n == 0.0,
+    }
+}
+
+/// Splits a sum into terms of a coefficient and a non-constant base.
+///
+/// Returns [`None`] if negating a coefficient overflows.
+fn flatten(
+    node: &dyn Node,
+    positive: bool,
+    terms: &mut Vec<(Number, Option<NodeBox>)>,
+) -> Option<()> {
+    let sign = |n: Number| {
+        if positive {
+            Some(n)
+        } else {
+            fold(BinaryAction::Sub, Number::Int(0), n)
+        }
+    };
+
+    match node.view() {
+        NodeView::Binary(b) if b.actor() == BinaryAction::Add => {
+            flatten(b.left(), positive, terms)?;
+            flatten(b.right(), positive, terms)?;
+        }
+        NodeView::Binary(b) if b.actor() == BinaryAction::Sub => {
+            flatten(b.left(), positive, terms)?;
+            flatten(b.right(), !positive, terms)?;
+        }
+        NodeView::Unary(u) if u.actor() == UnaryAction::Neg => {
+            flatten(u.operand(), !positive, terms)?;
+        }
+        NodeView::Plain(p) => terms.push((sign(p.value())?, None)),
+        NodeView::Binary(b) if b.actor() == BinaryAction::Mul => {
+            match (constant(b.left()), constant(b.right())) {
+                (Some(c), _) => terms.push((sign(c)?, Some(b.right().clone_box()))),
+                (_, Some(c)) => terms.push((sign(c)?, Some(b.left().clone_box()))),
+                _ => terms.push((sign(Number::Int(1))?, Some(node.clone_box()))),
+            }
+        }
+        _ => terms.push((sign(Number::Int(1))?, Some(node.clone_box()))),
+    }
+
+    Some(())
+}
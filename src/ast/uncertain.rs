@@ -0,0 +1,451 @@
+//! This module implements propagation of uncertainty through ASTs.
+//!
+//! An [`Uncertain`] value is a measurement `value ± error`, where the error
+//! is a standard deviation. Uncertainty is propagated to first order: every
+//! node is evaluated into its value plus a linear combination of independent
+//! sources of error, whose standard deviations give the error of the result.
+//!
+//! The sources of error are the variables bound in [`evaluate_uncertain`] and
+//! every `±` in the AST. A variable is a single source however often it
+//! appears, so its correlation with itself is kept: `x - x` is exactly
+//! `0 ± 0`, and `x * x` has twice the relative error of `x`. Two `±` are
+//! always independent, even if they are written the same, but a single `±`
+//! is a single source however often a sum, product or integral evaluates it.
+//! The bounds of sums and products are integers, whose uncertainty is
+//! ignored. A root of an equation is propagated through the equation with the
+//! implicit function theorem, and the uncertainty of its guess is ignored.
+//!
+//! Vectors, lists and built-in functions are evaluated through their
+//! derivatives with respect to the variables bound in [`evaluate_uncertain`],
+//! so the uncertainty of any `±` within them is ignored.
+//!
+//! Values are evaluated as floats, as with [`Node::evaluate_with`] on float
+//! operands. The error of `a ± b` is the absolute value of `b`, whose own
+//! uncertainty is ignored.
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    ast::{dual::derive_function, evaluate_dual, BinderNode, Node, NodeView, SolveNode},
+    BinaryAction, BinaryNode, BinderAction, Environment, EvalError, Number, UnaryAction,
+};
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// A value with an uncertainty, i.e. `value ± error`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Uncertain {
+    /// The central value.
+    value: f64,
+
+    /// The standard deviation, which is never negative.
+    error: f64,
+}
+
+/// A value as a linear combination of sources of error.
+#[derive(Debug, Clone)]
+struct Linear {
+    /// The central value.
+    value: f64,
+
+    /// Coefficients of the sources of error, by index, in increasing order of
+    /// index. Sources that are missing have a coefficient of zero.
+    coefficients: Vec<(usize, f64)>,
+}
+
+/// State of an evaluation.
+struct Evaluator<'a> {
+    /// Variables and their values, which are the first sources of error.
+    bindings: &'a [(&'a str, Uncertain)],
+
+    /// Standard deviations of the sources of error, by index.
+    errors: Vec<f64>,
+
+    /// Sources of error of the `±` nodes evaluated so far, by address.
+    uncertain: HashMap<*const BinaryNode, usize>,
+
+    /// Values of the variables bound by sums, products, integrals and
+    /// equations, which shadow the bindings. The innermost comes last.
+    locals: Vec<(String, f64)>,
+}
+
+/// Evaluates an AST, with `bindings` giving the uncertain value of each
+/// variable, and propagates their uncertainty as well as that of every `±`.
+pub fn evaluate_uncertain(node: &dyn Node, bindings: &[(&str, Uncertain)]) -> Result<Uncertain> {
+    let mut evaluator = Evaluator {
+        bindings,
+        errors: bindings.iter().map(|(_, u)| u.error).collect(),
+        uncertain: HashMap::new(),
+        locals: Vec::new(),
+    };
+    let linear = evaluator.evaluate(node)?;
+
+    let error = linear
+        .coefficients
+        .iter()
+        .map(|&(source, c)| c * evaluator.errors[source])
+        .fold(0.0, f64::hypot);
+    Ok(Uncertain::new(linear.value, error))
+}
+
+impl Uncertain {
+    /// Creates an [`Uncertain`] value. The sign of the error is ignored.
+    pub fn new(value: f64, error: f64) -> Uncertain {
+        Uncertain {
+            value,
+            error: error.abs(),
+        }
+    }
+
+    /// Gets the central value.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Gets the standard deviation.
+    pub fn error(&self) -> f64 {
+        self.error
+    }
+
+    /// Gets the decimal place that this value is displayed to, which is
+    /// negative left of the point, or [`None`] if it is not rounded because
+    /// the error is zero or either is not finite.
+    pub(crate) fn decimals(&self) -> Option<i32> {
+        let (value, error) = (self.value, self.error);
+        if error == 0.0 || !error.is_finite() || !value.is_finite() {
+            return None;
+        }
+
+        let exponent = error.log10().floor() as i32;
+        let figures = match error / 10f64.powi(exponent) < 2.0 {
+            true => 2,
+            false => 1,
+        };
+        Some(figures - 1 - exponent)
+    }
+}
+
+impl Display for Uncertain {
+    /// Formats as `value ± error`. The error is rounded to one significant
+    /// figure, or two if the first is 1, and the value to the same decimal
+    /// place.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (value, error) = (self.value, self.error);
+        match self.decimals() {
+            None => write!(f, "{} ± {}", value, error),
+            Some(decimals) if decimals >= 0 => write!(
+                f,
+                "{:.*} ± {:.*}",
+                decimals as usize, value, decimals as usize, error
+            ),
+            Some(decimals) => {
+                let scale = 10f64.powi(-decimals);
+                let round = |n: f64| (n / scale).round() * scale;
+                write!(f, "{:.0} ± {:.0}", round(value), round(error))
+            }
+        }
+    }
+}
+
+impl From<f64> for Uncertain {
+    fn from(value: f64) -> Self {
+        Self::new(value, 0.0)
+    }
+}
+
+impl From<Number> for Uncertain {
+    fn from(value: Number) -> Self {
+        Self::new(float(value), 0.0)
+    }
+}
+
+impl Evaluator<'_> {
+    fn evaluate(&mut self, node: &dyn Node) -> Result<Linear> {
+        let linear = match node.view() {
+            NodeView::Plain(p) => Linear::exact(float(p.value())),
+            NodeView::Variable(v) => {
+                let local = self.locals.iter().rev().find(|(name, _)| name == v.name());
+                if let Some(&(_, value)) = local {
+                    return Ok(Linear::exact(value));
+                }
+
+                match self.bindings.iter().position(|(name, _)| *name == v.name()) {
+                    Some(source) => Linear {
+                        value: self.bindings[source].1.value,
+                        coefficients: vec![(source, 1.0)],
+                    },
+                    None => return Err(EvalError::UnboundVariable(v.name().to_string())),
+                }
+            }
+            NodeView::Unit(u) => Linear::exact(u.unit().scale()),
+            NodeView::Unary(u) => {
+                let operand = self.evaluate(u.operand())?;
+                let value = float(u.actor().evaluate(Number::Flt(operand.value)));
+                let coefficient = match u.actor() {
+                    UnaryAction::Iden => 1.0,
+                    UnaryAction::Neg => -1.0,
+                    UnaryAction::Func(func) => derive_function(func, operand.value),
+                };
+                Linear::combine(value, (coefficient, &operand), (0.0, &Linear::exact(0.0)))
+            }
+            NodeView::Binary(b) => {
+                let left = self.evaluate(b.left())?;
+                let right = self.evaluate(b.right())?;
+                self.binary(b, left, right)
+            }
+            NodeView::Binder(b) => {
+                let lower = self.evaluate(b.lower())?;
+                let upper = self.evaluate(b.upper())?;
+                self.binder(b, lower, upper)?
+            }
+            NodeView::Solve(s) => self.solve(s)?,
+            NodeView::Call(c) if c.func().is_symbolic() => self.evaluate(c.rewrite()?.as_ref())?,
+            NodeView::Vector(_)
+            | NodeView::Call(_)
+            | NodeView::List(_)
+            | NodeView::Range(_)
+            | NodeView::Lambda(_) => self.derive(node)?,
+        };
+
+        Ok(linear)
+    }
+
+    fn binary(&mut self, node: &BinaryNode, left: Linear, right: Linear) -> Linear {
+        let actor = node.actor();
+        let (f, g) = (left.value, right.value);
+        let value = float(actor.evaluate(Number::Flt(f), Number::Flt(g)));
+
+        // Partial derivatives with respect to the left and right operands.
+        let (df, dg) = match actor {
+            BinaryAction::Add => (1.0, 1.0),
+            BinaryAction::Sub | BinaryAction::Equate => (1.0, -1.0),
+            BinaryAction::Mul => (g, f),
+
+            // Undefined where f/g is.
+            BinaryAction::Div | BinaryAction::Convert if Number::Flt(g) == Number::Int(0) => {
+                (f64::NAN, f64::NAN)
+            }
+            BinaryAction::Div | BinaryAction::Convert => (1.0 / g, -f / (g * g)),
+            BinaryAction::LeftDiv if Number::Flt(f) == Number::Int(0) => (f64::NAN, f64::NAN),
+            BinaryAction::LeftDiv => (-g / (f * f), 1.0 / f),
+
+            BinaryAction::Pow => {
+                let df = match left.is_exact() || g == 0.0 {
+                    true => 0.0,
+                    false => g * f.powf(g - 1.0),
+                };
+                let dg = match right.is_exact() {
+                    true => 0.0,
+                    false => value * f.ln(),
+                };
+                (df, dg)
+            }
+
+            // A new source of error, independent of all others. Its standard
+            // deviation is 1, scaled by the error given here.
+            BinaryAction::PlusMinus => {
+                let errors = &mut self.errors;
+                let index = *self.uncertain.entry(node).or_insert_with(|| {
+                    errors.push(1.0);
+                    errors.len() - 1
+                });
+                let source = Linear {
+                    value: 0.0,
+                    coefficients: vec![(index, g.abs())],
+                };
+                return Linear::combine(value, (1.0, &left), (1.0, &source));
+            }
+        };
+
+        Linear::combine(value, (df, &left), (dg, &right))
+    }
+
+    /// Evaluates a sum, product or integral, given its bounds.
+    fn binder(&mut self, b: &BinderNode, lower: Linear, upper: Linear) -> Result<Linear> {
+        let (lo, hi) = (Number::Flt(lower.value), Number::Flt(upper.value));
+        let linear = match b.actor() {
+            actor @ (BinderAction::Sum | BinderAction::Prod) => {
+                let mut result = Linear::exact(float(actor.identity()));
+                for k in BinderAction::range(lo, hi)? {
+                    let term = self.body(b, k as f64)?;
+                    let (f, g) = (result.value, term.value);
+                    result = match actor {
+                        BinderAction::Prod => Linear::combine(f * g, (g, &result), (f, &term)),
+                        _ => Linear::combine(f + g, (1.0, &result), (1.0, &term)),
+                    };
+                }
+                result
+            }
+
+            // Leibniz rule: (int_a^b f dt)' = int_a^b f' dt + f(b) b' - f(a) a'.
+            BinderAction::Integrate => {
+                let mut sources = Vec::new();
+                let value = BinderAction::Integrate.evaluate(lo, hi, |t| {
+                    let linear = self.body(b, float(t))?;
+                    sources.extend(linear.coefficients.iter().map(|&(source, _)| source));
+                    Ok(Number::Flt(linear.value))
+                })?;
+                sources.sort_unstable();
+                sources.dedup();
+
+                let mut coefficients = Vec::with_capacity(sources.len());
+                for source in sources {
+                    let coefficient = BinderAction::Integrate.evaluate(lo, hi, |t| {
+                        let linear = self.body(b, float(t))?;
+                        Ok(Number::Flt(linear.coefficient(source)))
+                    })?;
+                    coefficients.push((source, float(coefficient)));
+                }
+                let mut result = Linear {
+                    value: float(value),
+                    coefficients,
+                };
+
+                // The integrand is only needed at bounds that vary, as it may
+                // be undefined there.
+                if !upper.is_exact() {
+                    let fb = self.body(b, upper.value)?.value;
+                    result = Linear::combine(result.value, (1.0, &result), (fb, &upper));
+                }
+                if !lower.is_exact() {
+                    let fa = self.body(b, lower.value)?.value;
+                    result = Linear::combine(result.value, (1.0, &result), (-fa, &lower));
+                }
+                result
+            }
+        };
+
+        Ok(linear)
+    }
+
+    /// Evaluates a node with its derivatives with respect to the variables
+    /// that are not shadowed.
+    fn derive(&mut self, node: &dyn Node) -> Result<Linear> {
+        let env = self.central();
+        let sources: Vec<_> = (0..self.bindings.len())
+            .filter(|&i| {
+                !self
+                    .locals
+                    .iter()
+                    .any(|(name, _)| name == self.bindings[i].0)
+            })
+            .collect();
+        let vars: Vec<_> = sources.iter().map(|&i| self.bindings[i].0).collect();
+
+        let dual = evaluate_dual(node, &env, &vars)?;
+        Ok(Linear {
+            value: float(dual.value()),
+            coefficients: sources
+                .into_iter()
+                .zip(dual.derivatives().iter().copied())
+                .collect(),
+        })
+    }
+
+    /// Gets an [`Environment`] with the central values of the variables.
+    fn central(&self) -> Environment {
+        let mut env = Environment::new();
+        for (name, u) in self.bindings {
+            env.bind(*name, u.value);
+        }
+        for (name, value) in &self.locals {
+            env.bind(name.as_str(), *value);
+        }
+        env
+    }
+
+    /// Evaluates a root of an equation, and propagates the uncertainty of the
+    /// equation to it: F(x, p) = 0 gives dx = -F_p dp / F_x.
+    fn solve(&mut self, s: &SolveNode) -> Result<Linear> {
+        let mut env = self.central();
+        let root = float(s.evaluate_with(&env)?);
+
+        env.bind(s.var(), root);
+        let fx = evaluate_dual(s.equation(), &env, &[s.var()])?.derivatives()[0];
+
+        self.locals.push((s.var().to_string(), root));
+        let equation = self.evaluate(s.equation());
+        self.locals.pop();
+
+        let zero = Linear::exact(0.0);
+        Ok(Linear::combine(root, (-1.0 / fx, &equation?), (0.0, &zero)))
+    }
+
+    /// Evaluates the body of a sum, product or integral with its variable
+    /// bound to `value`.
+    fn body(&mut self, b: &BinderNode, value: f64) -> Result<Linear> {
+        self.locals.push((b.var().to_string(), value));
+        let linear = self.evaluate(b.body());
+        self.locals.pop();
+        linear
+    }
+}
+
+impl Linear {
+    /// Creates a [`Linear`] value without error.
+    fn exact(value: f64) -> Linear {
+        Linear {
+            value,
+            coefficients: Vec::new(),
+        }
+    }
+
+    /// Gets the coefficient of a source of error.
+    fn coefficient(&self, source: usize) -> f64 {
+        match self.coefficients.iter().find(|&&(s, _)| s == source) {
+            Some(&(_, c)) => c,
+            None => 0.0,
+        }
+    }
+
+    /// Checks if this value has no sources of error.
+    fn is_exact(&self) -> bool {
+        self.coefficients.is_empty()
+    }
+
+    /// Creates a [`Linear`] value whose coefficients are `a * x + b * y`, by
+    /// the chain rule. An operand without sources of error is skipped, so
+    /// that its (possibly undefined) factor does not matter.
+    fn combine(value: f64, (a, x): (f64, &Linear), (b, y): (f64, &Linear)) -> Linear {
+        let mut coefficients = Vec::with_capacity(x.coefficients.len() + y.coefficients.len());
+        let (mut i, mut j) = (0, 0);
+        while i < x.coefficients.len() || j < y.coefficients.len() {
+            let left = x.coefficients.get(i);
+            let right = y.coefficients.get(j);
+            let entry = match (left, right) {
+                (Some(&(s, c)), Some(&(t, d))) if s == t => {
+                    i += 1;
+                    j += 1;
+                    (s, a * c + b * d)
+                }
+                (Some(&(s, c)), Some(&(t, _))) if s < t => {
+                    i += 1;
+                    (s, a * c)
+                }
+                (Some(&(s, c)), None) => {
+                    i += 1;
+                    (s, a * c)
+                }
+                (_, Some(&(t, d))) => {
+                    j += 1;
+                    (t, b * d)
+                }
+                (None, None) => unreachable!(),
+            };
+            coefficients.push(entry);
+        }
+
+        Linear {
+            value,
+            coefficients,
+        }
+    }
+}
+
+fn float(n: Number) -> f64 {
+    match n {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
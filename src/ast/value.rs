@@ -0,0 +1,337 @@
+//! This module implements evaluation of ASTs to vectors, matrices, lists and
+//! lambdas.
+//!
+//! [`evaluate_value`] evaluates an AST to a [`Value`], which is a scalar, a
+//! vector, a matrix, a list or a lambda. `[1, 2, 3]` is a vector, and
+//! `[[1, 2], [3, 4]]` is a matrix with the rows `[1, 2]` and `[3, 4]`, as is
+//! `[v, w]` for any vectors `v` and `w` of length 2. Vectors have no
+//! orientation: they are taken as a column or as a row as needed. Variables
+//! and units are always scalars, and so are sums, products, integrals and
+//! roots, as well as their bodies.
+//!
+//! `{1, 4, 9}` is a list, whose elements may be values of any kind, and
+//! `1..5` is the list of integers from 1 to 5. `x -> x^2` is a [`Lambda`],
+//! which is called by higher-order functions such as `map`, described in
+//! [`Builtin`](crate::ast::Builtin). Its parameters are bound to scalars, so
+//! a lambda cannot be called on a vector or a list.
+//!
+//! # Broadcasting
+//!
+//! A scalar combined with a vector or a matrix is broadcast to each of its
+//! elements, e.g. `2 * [1, 2]` is `[2, 4]` and `[1, 2] + 1` is `[2, 3]`.
+//! Otherwise, operators take values as follows:
+//!
+//! - `+`, `-`, `±`, `=` and `to` work element by element, on values of the
+//!   same shape.
+//! - `*` multiplies vectors element by element. Matrices are multiplied as
+//!   matrices, and a matrix times a vector gives a vector, taking the vector
+//!   as a column on the right and as a row on the left.
+//! - `/` divides vectors element by element, but only divides a matrix by a
+//!   scalar.
+//! - `^` raises vectors element by element, but only raises a square matrix
+//!   to an integer power, negative powers being powers of its inverse.
+//! - `A \ b` solves `A x = b` for `x`, where `A` is a square matrix and `b`
+//!   a vector or a matrix with as many rows. A scalar `a` on the left
+//!   divides instead, so `a \ b` is `b / a`.
+//!
+//! Lists work element by element with every operator, whatever their
+//! elements are, e.g. `{1, [1, 2]} * 2` is `{2, [2, 4]}`. Two lists must be
+//! of the same length, and any other value is combined with each element of
+//! a list, e.g. `{1, 2} * [1, 1]` is `{[1, 1], [2, 2]}`. Lambdas are not
+//! combined with anything.
+//!
+//! Negation and functions such as `sin` work element by element. Built-in
+//! functions on vectors, matrices and lists are described in
+//! [`Builtin`](crate::ast::Builtin).
+
+use std::fmt::Display;
+
+use crate::{
+    ast::{to_infix, LambdaNode, Node, NodeBox, NodeView},
+    Environment, EvalError, Matrix, Number,
+};
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// Value of an AST, which is a scalar, a vector, a matrix, a list or a
+/// lambda.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Scalar(Number),
+    Vector(Vec<Number>),
+    Matrix(Matrix),
+    List(Vec<Value>),
+    Lambda(Lambda),
+}
+
+/// Anonymous function, the value of a [`LambdaNode`].
+///
+/// A [`Lambda`] captures the [`Environment`] its [`LambdaNode`] was
+/// evaluated in, so its body may refer to variables other than its
+/// parameters, e.g. the `x` in `x -> map(y -> x y, 1..3)`.
+#[derive(Debug, Clone)]
+pub struct Lambda {
+    /// Names of the parameters.
+    params: Vec<String>,
+
+    /// Body of the lambda.
+    body: NodeBox,
+
+    /// Values of the variables when the lambda was created.
+    env: Environment,
+}
+
+impl PartialEq for Lambda {
+    fn eq(&self, other: &Self) -> bool {
+        self.params == other.params && *self.body == *other.body && self.env == other.env
+    }
+}
+
+/// Shape of a [`Value`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    Scalar,
+
+    /// Vector of the given length.
+    Vector(usize),
+
+    /// Matrix with the given numbers of rows and columns.
+    Matrix(usize, usize),
+
+    /// List of the given length.
+    List(usize),
+
+    /// Lambda with the given number of parameters.
+    Lambda(usize),
+}
+
+/// Evaluates an AST to a [`Value`], looking up variables in `env`.
+pub fn evaluate_value(node: &dyn Node, env: &Environment) -> Result<Value> {
+    match node.view() {
+        NodeView::Plain(_)
+        | NodeView::Variable(_)
+        | NodeView::Unit(_)
+        | NodeView::Binder(_)
+        | NodeView::Solve(_) => node.evaluate_with(env).map(Value::Scalar),
+        NodeView::Unary(u) => {
+            let operand = evaluate_value(u.operand(), env)?;
+            operand.map(|n| u.actor().evaluate(n))
+        }
+        NodeView::Binary(b) => {
+            let left = evaluate_value(b.left(), env)?;
+            let right = evaluate_value(b.right(), env)?;
+            b.actor().evaluate_value(left, right)
+        }
+        NodeView::Vector(v) => {
+            let elements = v
+                .elements()
+                .map(|e| evaluate_value(e, env))
+                .collect::<Result<Vec<_>>>()?;
+            Value::stack(elements)
+        }
+        NodeView::Call(c) if c.func().is_symbolic() => evaluate_value(c.rewrite()?.as_ref(), env),
+        NodeView::Call(c) => {
+            let args = c
+                .args()
+                .map(|a| evaluate_value(a, env))
+                .collect::<Result<Vec<_>>>()?;
+            c.func().evaluate_with(args, env)
+        }
+        NodeView::List(l) => {
+            let elements = l
+                .elements()
+                .map(|e| evaluate_value(e, env))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Value::List(elements))
+        }
+        NodeView::Range(r) => {
+            let integers = r.integers(env)?;
+            Ok(Value::List(
+                integers.map(|k| Value::Scalar(Number::Int(k))).collect(),
+            ))
+        }
+        NodeView::Lambda(l) => Ok(Value::Lambda(Lambda {
+            params: l.params().to_vec(),
+            body: l.body().clone_box(),
+            env: env.clone(),
+        })),
+    }
+}
+
+impl Value {
+    /// Gets the [`Shape`] of this [`Value`].
+    pub fn shape(&self) -> Shape {
+        match self {
+            Self::Scalar(_) => Shape::Scalar,
+            Self::Vector(v) => Shape::Vector(v.len()),
+            Self::Matrix(m) => m.shape(),
+            Self::List(l) => Shape::List(l.len()),
+            Self::Lambda(f) => Shape::Lambda(f.params.len()),
+        }
+    }
+
+    /// Gets the number this [`Value`] is, if it is a scalar.
+    pub fn into_scalar(self) -> Result<Number> {
+        match self {
+            Self::Scalar(n) => Ok(n),
+            value => Err(EvalError::ScalarExpected(value.shape())),
+        }
+    }
+
+    /// Applies `f` to each element, including those within lists. Lambdas
+    /// have no elements, so they are an error.
+    pub fn map(self, f: impl Fn(Number) -> Number) -> Result<Value> {
+        self.map_dyn(&f)
+    }
+
+    fn map_dyn(self, f: &dyn Fn(Number) -> Number) -> Result<Value> {
+        match self {
+            Self::Scalar(n) => Ok(Self::Scalar(f(n))),
+            Self::Vector(v) => Ok(Self::Vector(v.into_iter().map(f).collect())),
+            Self::Matrix(m) => Ok(Self::Matrix(m.map(f))),
+            Self::List(l) => l
+                .into_iter()
+                .map(|e| e.map_dyn(f))
+                .collect::<Result<_>>()
+                .map(Self::List),
+            f @ Self::Lambda(_) => Err(EvalError::ScalarExpected(f.shape())),
+        }
+    }
+
+    /// Applies `f` to each pair of elements of two values of the same shape,
+    /// broadcasting scalars.
+    pub fn zip_with(self, other: Value, f: impl Fn(Number, Number) -> Number) -> Result<Value> {
+        self.zip_with_dyn(other, &f)
+    }
+
+    fn zip_with_dyn(self, other: Value, f: &dyn Fn(Number, Number) -> Number) -> Result<Value> {
+        match (self, other) {
+            (Self::Scalar(l), r) => r.map(|r| f(l, r)),
+            (l, Self::Scalar(r)) => l.map(|l| f(l, r)),
+            (Self::Vector(l), Self::Vector(r)) if l.len() == r.len() => Ok(Self::Vector(
+                l.into_iter().zip(r).map(|(l, r)| f(l, r)).collect(),
+            )),
+            (Self::Matrix(l), Self::Matrix(r)) => l.zip_with(&r, f).map(Self::Matrix),
+            (Self::List(l), Self::List(r)) if l.len() == r.len() => l
+                .into_iter()
+                .zip(r)
+                .map(|(l, r)| l.zip_with_dyn(r, f))
+                .collect::<Result<_>>()
+                .map(Self::List),
+            (l, r) => Err(EvalError::ShapeMismatch(l.shape(), r.shape())),
+        }
+    }
+
+    /// Stacks the elements of a vector literal, which are either all
+    /// scalars, giving a vector, or all vectors of the same length, giving a
+    /// matrix with them as rows.
+    fn stack(elements: Vec<Value>) -> Result<Value> {
+        let first = match elements.first() {
+            Some(first) => first.shape(),
+            None => return Ok(Self::Vector(vec![])),
+        };
+
+        let mut scalars = Vec::with_capacity(elements.len());
+        let mut rows = Vec::with_capacity(elements.len());
+        for element in elements {
+            match (first, element) {
+                (Shape::Scalar, Self::Scalar(n)) => scalars.push(n),
+                (Shape::Vector(_), Self::Vector(v)) => rows.push(v),
+                (Shape::Scalar | Shape::Vector(_), element) => {
+                    return Err(EvalError::ShapeMismatch(first, element.shape()))
+                }
+                (_, element) => return Err(EvalError::InvalidShape("[]", element.shape())),
+            }
+        }
+
+        match first {
+            Shape::Scalar => Ok(Self::Vector(scalars)),
+            _ => Matrix::from_rows(rows).map(Self::Matrix),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Scalar(n) => write!(f, "{}", n),
+            Self::Vector(v) => {
+                write!(f, "[")?;
+                for (i, n) in v.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", n)?;
+                }
+                write!(f, "]")
+            }
+            Self::Matrix(m) => write!(f, "{}", m),
+            Self::List(l) => {
+                write!(f, "{{")?;
+                for (i, value) in l.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "}}")
+            }
+            Self::Lambda(l) => write!(f, "{}", l),
+        }
+    }
+}
+
+impl Lambda {
+    /// Gets the names of the parameters of this [`Lambda`].
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+
+    /// Calls this [`Lambda`], binding its parameters to `args` in the
+    /// [`Environment`] it captured. The arguments must be scalars, as many
+    /// as there are parameters.
+    pub fn call(&self, args: Vec<Value>) -> Result<Value> {
+        if args.len() != self.params.len() {
+            return Err(EvalError::ValueCountMismatch(self.params.len(), args.len()));
+        }
+
+        let mut env = self.env.clone();
+        for (param, arg) in self.params.iter().zip(args) {
+            env.bind(param.as_str(), arg.into_scalar()?);
+        }
+        evaluate_value(self.body.as_ref(), &env)
+    }
+}
+
+impl Display for Lambda {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let node = LambdaNode::new(self.params.iter().cloned(), self.body.clone());
+        write!(f, "{}", to_infix(&node))
+    }
+}
+
+impl From<Number> for Value {
+    fn from(value: Number) -> Self {
+        Self::Scalar(value)
+    }
+}
+
+impl From<Matrix> for Value {
+    fn from(value: Matrix) -> Self {
+        Self::Matrix(value)
+    }
+}
+
+impl Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Scalar => write!(f, "scalar"),
+            Self::Vector(n) => write!(f, "vector of length {}", n),
+            Self::Matrix(r, c) => write!(f, "{}×{} matrix", r, c),
+            Self::List(n) => write!(f, "list of length {}", n),
+            Self::Lambda(1) => write!(f, "lambda of 1 parameter"),
+            Self::Lambda(n) => write!(f, "lambda of {} parameters", n),
+        }
+    }
+}
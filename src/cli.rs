@@ -1,22 +1,72 @@
 //! This module implements the command-line interface for [`tilted`](crate).
 #![cfg(feature = "cli")]
 
-use crate::{Lexer, Parser};
-use std::io::Write;
+use crate::{
+    ast::{self, DotPrinter},
+    BinaryAction, Builtin, Environment, Lexer, Node, NodeView, Notation, Number, NumberFormat,
+    Parser, Rng, TilError, Value,
+};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io::Write,
+};
 
-use clap::Parser as ClapParser;
+use clap::{ArgGroup, Parser as ClapParser};
 
 #[derive(Debug, ClapParser)]
 #[command(author, version, about, long_about = None)]
+#[command(group(ArgGroup::new("output").args(["ast", "canonical", "latex", "mathml", "dot"])))]
 pub struct CliParser {
     /// print the AST instead of the result
     #[arg(short = 'p', long)]
     ast: bool,
 
+    /// print the input as parsed, in canonical infix form
+    #[arg(long)]
+    canonical: bool,
+
+    /// print the input as LaTeX
+    #[arg(long)]
+    latex: bool,
+
+    /// print the input as presentation MathML
+    #[arg(long)]
+    mathml: bool,
+
+    /// print the AST as a Graphviz graph
+    #[arg(long)]
+    dot: bool,
+
+    /// print exact results such as sqrt(2) / 2, also with --latex or --mathml
+    #[arg(long, conflicts_with_all = ["ast", "canonical", "dot"])]
+    exact: bool,
+
+    /// annotate the Graphviz graph with values and spans
+    #[arg(long, requires = "dot")]
+    annotate: bool,
+
     /// enable interactive (read-eval-print-loop) mode
     #[arg(short = 'r', long = "repl")]
     interactive: bool,
 
+    /// seed random functions such as rand(), for reproducible results
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// write numbers as auto, fixed:N, sig:N, sci[:N] or eng[:N], with N
+    /// decimals or significant figures
+    #[arg(long, value_name = "NOTATION", default_value_t)]
+    format: Notation,
+
+    /// group the digits of numbers in thousands
+    #[arg(long)]
+    thousands: bool,
+
+    /// write numbers with a decimal comma
+    #[arg(long)]
+    decimal_comma: bool,
+
     /// user input
     input: Option<String>,
 }
@@ -27,23 +77,26 @@ impl CliParser {
     }
 
     pub fn start(&self) -> u8 {
+        // Random numbers are drawn from one generator throughout, seeded
+        // randomly unless a seed is given.
+        let seed = self
+            .seed
+            .unwrap_or_else(|| RandomState::new().build_hasher().finish());
+        let env = Environment::new().with_seed(seed);
+        let format = NumberFormat::new()
+            .notation(self.format)
+            .thousands(self.thousands)
+            .decimal_comma(self.decimal_comma);
+
         // Check if the user wants to start the interactive mode.
         if self.interactive {
-            self.start_interative()
+            self.start_interative(&env, format)
         }
         // Check if the user provided any input.
         else if let Some(ref input) = self.input {
-            let lexer = Lexer::from_source_code(input);
-            let mut parser = Parser::from_lexer(lexer);
-            let result = parser.parse();
-
-            match result {
-                Ok(node) => {
-                    if self.ast {
-                        println!("{}", node);
-                    } else {
-                        println!("{}", node.evaluate());
-                    }
+            match self.run(input, &env, &format) {
+                Ok(output) => {
+                    println!("{}", output);
                     0
                 }
                 Err(e) => {
@@ -59,13 +112,13 @@ impl CliParser {
         }
     }
 
-    fn start_interative(&self) -> u8 {
+    fn start_interative(&self, env: &Environment, mut format: NumberFormat) -> u8 {
         if let Some(ref input) = self.input {
             eprintln!("Ignoring input: {}", input);
         }
 
         let mut input = String::new();
-        println!("Enter 'quit' to exit");
+        println!("Enter 'quit' to exit, or ':format', ':thousands' or ':decimal' for settings");
 
         loop {
             print!("> ");
@@ -76,23 +129,159 @@ impl CliParser {
                 break 0;
             }
 
-            let lexer = Lexer::from_source_code(&input);
-            let mut parser = Parser::from_lexer(lexer);
-            let result = parser.parse();
-
-            match result {
-                Ok(node) => {
-                    if self.ast {
-                        println!("{}", node);
-                    } else {
-                        println!("{}", node.evaluate());
-                    }
+            // Settings are changed by commands starting with a colon.
+            if let Some(command) = input.trim().strip_prefix(':') {
+                match configure(format, command) {
+                    Ok(configured) => format = configured,
+                    Err(e) => eprintln!("{}", e),
                 }
-                Err(e) => {
-                    eprintln!("{}", e);
+            } else {
+                match self.run(&input, env, &format) {
+                    Ok(output) => println!("{}", output),
+                    Err(e) => eprintln!("{}", e),
                 }
             }
             input.clear();
         }
     }
+
+    /// Parses the input, then formats it as requested by the flags,
+    /// evaluating it in `env` and writing numbers in `format`.
+    fn run(
+        &self,
+        input: &str,
+        env: &Environment,
+        format: &NumberFormat,
+    ) -> Result<String, TilError> {
+        let lexer = Lexer::from_source_code(input);
+        let mut parser = Parser::from_lexer(lexer);
+        let node = parser.parse()?;
+
+        if self.exact {
+            // The result is rendered instead of the input.
+            let exact = ast::evaluate_exact(node.as_ref(), env)?;
+            if self.latex {
+                Ok(ast::to_latex(exact.to_node().as_ref()))
+            } else if self.mathml {
+                Ok(ast::to_mathml(exact.to_node().as_ref()))
+            } else if exact.is_exact() {
+                Ok(exact.to_string())
+            } else {
+                Ok(format.format(Number::Flt(exact.to_f64())))
+            }
+        } else if self.ast {
+            Ok(node.to_string())
+        } else if self.canonical {
+            Ok(ast::to_infix(node.as_ref()))
+        } else if self.latex {
+            Ok(ast::to_latex(node.as_ref()))
+        } else if self.mathml {
+            Ok(ast::to_mathml(node.as_ref()))
+        } else if self.dot {
+            let mut printer = DotPrinter::new();
+            if self.annotate {
+                printer = printer.annotate(env);
+            }
+            Ok(printer.print(node.as_ref()).trim_end().to_string())
+        } else if matches!(node.view(), NodeView::Call(c) if c.func() == Builtin::FactorInt) {
+            // A prime factorisation is printed as a product.
+            match node.evaluate_with(env)? {
+                Number::Int(n) => Ok(ast::to_infix(ast::factorint(n).as_ref())),
+                n => Ok(format.format(n)),
+            }
+        } else if is_value(node.as_ref()) {
+            // Scalars keep their units.
+            match ast::evaluate_value(node.as_ref(), &rehearsal(env))? {
+                Value::Scalar(_) => {
+                    let quantity = ast::evaluate_quantity(node.as_ref(), env)?;
+                    Ok(format.format_quantity(&quantity))
+                }
+                value => Ok(format.format_value(&value)),
+            }
+        } else if is_uncertain(node.as_ref()) {
+            // The unit is found as for any quantity, whose magnitude is the
+            // central value.
+            let quantity = ast::evaluate_quantity(node.as_ref(), &rehearsal(env))?;
+            let uncertain = ast::evaluate_uncertain(node.as_ref(), &[])?;
+            let uncertain = format.format_uncertain(&uncertain);
+            Ok(match quantity.unit() {
+                Some(unit) => format!("{} {}", uncertain, unit),
+                None if quantity.dimension().is_dimensionless() => uncertain,
+                None => format!("{} {}", uncertain, quantity.dimension()),
+            })
+        } else if let NodeView::Solve(s) = node.view() {
+            // Every root is printed, after checking the dimensions at the
+            // smallest.
+            ast::evaluate_quantity(node.as_ref(), &rehearsal(env))?;
+            let roots = s.roots(env)?;
+            let roots = roots.into_iter().map(|x| format.format(Number::Flt(x)));
+            Ok(roots.collect::<Vec<_>>().join(format.separator()))
+        } else {
+            Ok(format.format_quantity(&ast::evaluate_quantity(node.as_ref(), env)?))
+        }
+    }
+}
+
+/// Changes a [`NumberFormat`] by a command of the interactive mode, i.e.
+/// `format NOTATION`, `thousands on|off` or `decimal point|comma`.
+fn configure(format: NumberFormat, command: &str) -> Result<NumberFormat, String> {
+    match command.split_whitespace().collect::<Vec<_>>()[..] {
+        ["format", notation] => Ok(format.notation(notation.parse()?)),
+        ["thousands", "on"] => Ok(format.thousands(true)),
+        ["thousands", "off"] => Ok(format.thousands(false)),
+        ["decimal", "point"] => Ok(format.decimal_comma(false)),
+        ["decimal", "comma"] => Ok(format.decimal_comma(true)),
+        _ => Err(format!(
+            "Invalid command :{}, expected :format, :thousands or :decimal",
+            command
+        )),
+    }
+}
+
+/// Copies an [`Environment`] with a copy of its [`Rng`], for evaluating an
+/// AST once before the result is printed without drawing random numbers
+/// twice.
+fn rehearsal(env: &Environment) -> Environment {
+    env.clone().with_rng(Rng::new(env.rng().state()))
+}
+
+/// Checks if an AST contains an uncertain value, i.e. a `±`.
+fn is_uncertain(node: &dyn Node) -> bool {
+    match node.view() {
+        NodeView::Binary(b) => {
+            b.actor() == BinaryAction::PlusMinus
+                || is_uncertain(b.left())
+                || is_uncertain(b.right())
+        }
+        NodeView::Unary(u) => is_uncertain(u.operand()),
+        NodeView::Plain(_) | NodeView::Variable(_) | NodeView::Unit(_) => false,
+        NodeView::Binder(b) => {
+            is_uncertain(b.body()) || is_uncertain(b.lower()) || is_uncertain(b.upper())
+        }
+        NodeView::Solve(s) => {
+            is_uncertain(s.equation()) || s.guess().iter().any(|g| is_uncertain(*g))
+        }
+        NodeView::Vector(v) => v.elements().any(is_uncertain),
+        NodeView::Call(c) => c.args().any(is_uncertain),
+        NodeView::List(l) => l.elements().any(is_uncertain),
+        NodeView::Range(r) => is_uncertain(r.start()) || is_uncertain(r.end()),
+        NodeView::Lambda(l) => is_uncertain(l.body()),
+    }
+}
+
+/// Checks if an AST contains a vector, list, range, lambda or built-in
+/// function, whose value may not be a scalar.
+fn is_value(node: &dyn Node) -> bool {
+    match node.view() {
+        NodeView::Vector(_)
+        | NodeView::Call(_)
+        | NodeView::List(_)
+        | NodeView::Range(_)
+        | NodeView::Lambda(_) => true,
+        NodeView::Binary(b) => is_value(b.left()) || is_value(b.right()),
+        NodeView::Unary(u) => is_value(u.operand()),
+        NodeView::Plain(_) | NodeView::Variable(_) | NodeView::Unit(_) => false,
+        NodeView::Binder(b) => is_value(b.body()) || is_value(b.lower()) || is_value(b.upper()),
+        NodeView::Solve(s) => is_value(s.equation()) || s.guess().iter().any(|g| is_value(*g)),
+    }
 }
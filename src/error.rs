@@ -1,5 +1,5 @@
 //! This module implements the error types for [`tilted`](crate).
-use crate::Token;
+use crate::{Dimension, Number, Shape, Token};
 use std::{error::Error, fmt::Display};
 
 /// Errors returned by [`tilted`](crate)
@@ -11,6 +11,9 @@ pub enum TilError {
     /// Errors returned by [`Parser`](crate::Parser).
     Parse(ParseError),
 
+    /// Errors returned when evaluating a [`Node`](crate::ast::Node).
+    Eval(EvalError),
+
     /// Errors from other sources.
     Unknown(Box<dyn Error>),
 }
@@ -40,24 +43,120 @@ pub enum ParseError {
     /// Expected an operator, found something else.
     OperatorExpected(Token),
 
+    /// Expected a left parenthesis, found something else.
+    LeftParenExpected(Token),
+
     /// Expected a right parenthesis, found something else.
     RightParenExpected(Token),
 
+    /// Expected a right bracket, found something else.
+    RightBracketExpected(Token),
+
+    /// Expected a right brace, found something else.
+    RightBraceExpected(Token),
+
+    /// Expected an arrow after the parameters of a lambda, found something
+    /// else.
+    ArrowExpected(Token),
+
+    /// Expected a variable, found something else at the index.
+    VariableExpected(usize),
+
+    /// Called a built-in function with the wrong number of arguments.
+    ArgumentCountMismatch(&'static str, usize, usize),
+
     /// Found an invalid unary operator.
     InvalidUnaryOperator(Token),
 
     /// Found a right parenthesis without a matching left parenthesis.
     MismatchRightParen(usize),
 
+    /// Found a right bracket without a matching left bracket.
+    MismatchRightBracket(usize),
+
+    /// Found a right brace without a matching left brace.
+    MismatchRightBrace(usize),
+
+    /// Found an integer that does not fit in an [`i128`].
+    IntegerOverflow(Token),
+
     /// Errors caused by parsing valid but unexpected user input.
     InternalError(&'static str),
 }
 
+/// Errors returned when evaluating a [`Node`](crate::ast::Node).
+#[derive(Debug, Clone)]
+pub enum EvalError {
+    /// Variable has no value bound in the [`Environment`](crate::Environment).
+    UnboundVariable(String),
+
+    /// Expected a number of values for variables, e.g. for the parameters of
+    /// a lambda, found a different number.
+    ValueCountMismatch(usize, usize),
+
+    /// Called a built-in function with the wrong number of arguments, with
+    /// the nearest number it takes.
+    ArgumentCountMismatch(&'static str, usize, usize),
+
+    /// Combined two quantities whose dimensions must be the same, but are not.
+    DimensionMismatch(Dimension, Dimension),
+
+    /// Expected a pure number, found a quantity with a dimension.
+    DimensionlessExpected(Dimension),
+
+    /// Raised a quantity to a power that does not give a whole power of each
+    /// unit, e.g. `m^0.5`.
+    FractionalDimension(Dimension),
+
+    /// Expected an integer, e.g. a bound of a sum, found something else.
+    IntegerExpected(Number),
+
+    /// Numerical integration stopped before reaching its tolerance, with the
+    /// estimated error at that point.
+    ToleranceNotMet(f64),
+
+    /// Root finding did not converge, with the last estimate of the root.
+    NotConverged(f64),
+
+    /// Found no real root of an equation.
+    NoRealRoot,
+
+    /// Every value is a root of an equation, e.g. `x = x`.
+    InfinitelyManyRoots,
+
+    /// Combined two values whose shapes do not fit, e.g. vectors of different
+    /// lengths.
+    ShapeMismatch(Shape, Shape),
+
+    /// Expected a scalar, found a vector, a matrix, a list or a lambda.
+    ScalarExpected(Shape),
+
+    /// Passed a value of the wrong shape to a function or an operator, e.g. a
+    /// matrix that is not square to `det`.
+    InvalidShape(&'static str, Shape),
+
+    /// Inverted a matrix that has no inverse.
+    SingularMatrix,
+
+    /// Evaluated a function at a pole, e.g. `tan` at `pi / 2`, with the name
+    /// of the function and its argument.
+    Pole(&'static str, String),
+
+    /// Passed something other than a variable to a built-in function that
+    /// takes one, e.g. the second argument of `diff`.
+    VariableExpected(&'static str),
+
+    /// Ran a [`Program`](crate::Program) that pops more values than it
+    /// pushes, which a compiled one never does.
+    StackUnderflow,
+}
+
 impl Display for TilError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Lex(e) => write!(f, "{}", e),
             Self::Parse(e) => write!(f, "{}", e),
+            Self::Eval(e) => write!(f, "{}", e),
             Self::Unknown(e) => write!(f, "{}", e),
         }
     }
@@ -68,6 +167,7 @@ impl Error for TilError {
         match self {
             Self::Lex(e) => Some(e),
             Self::Parse(e) => Some(e),
+            Self::Eval(e) => Some(e),
             Self::Unknown(e) => Some(e.as_ref()),
         }
     }
@@ -85,6 +185,12 @@ impl From<ParseError> for TilError {
     }
 }
 
+impl From<EvalError> for TilError {
+    fn from(value: EvalError) -> Self {
+        Self::Eval(value)
+    }
+}
+
 impl Display for LexError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -107,16 +213,80 @@ impl Display for ParseError {
             Self::UnexpectedEOF => write!(f, "Unexpected end-of-file"),
             Self::NumberExpected(t) => write!(f, "Expected a number, found {}", t),
             Self::OperatorExpected(t) => write!(f, "Expected an operator, found {}", t),
+            Self::LeftParenExpected(t) => write!(f, "Expected a left parenthesis, found {}", t),
             Self::RightParenExpected(t) => write!(f, "Expected a right parenthesis, found {}", t),
+            Self::RightBracketExpected(t) => write!(f, "Expected a right bracket, found {}", t),
+            Self::RightBraceExpected(t) => write!(f, "Expected a right brace, found {}", t),
+            Self::ArrowExpected(t) => write!(f, "Expected '->', found {}", t),
+            Self::VariableExpected(i) => write!(f, "Expected a variable at index {}", i),
+            Self::ArgumentCountMismatch(n, e, a) => {
+                write!(f, "Function '{}' expects {} argument(s), found {}", n, e, a)
+            }
             Self::InvalidUnaryOperator(t) => write!(f, "Found an invalid unary operator {}", t),
             Self::MismatchRightParen(i) => write!(
                 f,
                 "Found a right parenthesis without a matching left one at index {}",
                 i
             ),
+            Self::MismatchRightBracket(i) => write!(
+                f,
+                "Found a right bracket without a matching left one at index {}",
+                i
+            ),
+            Self::MismatchRightBrace(i) => write!(
+                f,
+                "Found a right brace without a matching left one at index {}",
+                i
+            ),
+            Self::IntegerOverflow(t) => write!(f, "Integer {} is too large", t),
             Self::InternalError(s) => write!(f, "{}", s),
         }
     }
 }
 
 impl Error for ParseError {}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnboundVariable(n) => write!(f, "Variable '{}' is not bound to a value", n),
+            Self::ValueCountMismatch(e, a) => write!(f, "Expected {} values, found {}", e, a),
+            Self::ArgumentCountMismatch(n, e, a) => {
+                write!(f, "Function '{}' expects {} argument(s), found {}", n, e, a)
+            }
+            Self::DimensionMismatch(l, r) => write!(f, "Units {} and {} are incompatible", l, r),
+            Self::DimensionlessExpected(d) => {
+                write!(f, "Expected a dimensionless value, found {}", d)
+            }
+            Self::FractionalDimension(d) => {
+                write!(f, "Raising {} to this power gives a fractional unit", d)
+            }
+            // A whole float would be printed like an integer, e.g. `2`.
+            Self::IntegerExpected(Number::Flt(n)) => {
+                write!(f, "Expected an integer, found the float {:?}", n)
+            }
+            Self::IntegerExpected(n) => write!(f, "Expected an integer, found {}", n),
+            Self::ToleranceNotMet(e) => write!(
+                f,
+                "Integral did not reach the tolerance, with an estimated error of {}",
+                e
+            ),
+            Self::NotConverged(x) => write!(
+                f,
+                "Root finding did not converge, with a last estimate of {}",
+                x
+            ),
+            Self::NoRealRoot => write!(f, "Found no real root of the equation"),
+            Self::InfinitelyManyRoots => write!(f, "Every value is a root of the equation"),
+            Self::ShapeMismatch(l, r) => write!(f, "Shapes {} and {} are incompatible", l, r),
+            Self::ScalarExpected(s) => write!(f, "Expected a scalar, found a {}", s),
+            Self::InvalidShape(n, s) => write!(f, "'{}' does not take a {}", n, s),
+            Self::SingularMatrix => write!(f, "Matrix is singular"),
+            Self::Pole(n, x) => write!(f, "'{}' is undefined at {}", n, x),
+            Self::VariableExpected(n) => write!(f, "'{}' expects a variable, found a value", n),
+            Self::StackUnderflow => write!(f, "Ran out of values on the stack"),
+        }
+    }
+}
+
+impl Error for EvalError {}
@@ -0,0 +1,134 @@
+//! This module implements [`Expression`], a compiled formula that can be
+//! evaluated many times with different values for its variables.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{EvalError, Lexer, Number, Parser, Program, TilError, Vm};
+
+/// A formula parsed from source code and compiled into a [`Program`].
+///
+/// An [`Expression`] is [`Send`] and [`Sync`], so it can be shared across
+/// threads. It is serialised as its source code, which is compiled again
+/// when deserialised.
+///
+/// ```
+/// use tilted::{Expression, Number};
+///
+/// let expr = Expression::compile("a*x^2 + b").unwrap();
+/// assert_eq!(expr.variables(), ["a", "x", "b"]);
+///
+/// let value = expr.eval(&[("a", 3.0), ("x", 2.0), ("b", 1.0)]).unwrap();
+/// assert_eq!(value, Number::Flt(13.0));
+///
+/// let value = expr.eval_positional(&[3, 2, 1]).unwrap();
+/// assert_eq!(value, Number::Int(13));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Expression {
+    /// Source code this [`Expression`] was compiled from.
+    source: String,
+
+    /// The compiled AST.
+    program: Program,
+}
+
+impl Expression {
+    /// Parses and compiles source code into an [`Expression`].
+    pub fn compile<T: Into<String>>(source: T) -> Result<Expression, TilError> {
+        let source = source.into();
+        let lexer = Lexer::from_source_code(&source);
+        let node = Parser::from_lexer(lexer).parse()?;
+        let program = Program::compile(node.as_ref());
+
+        Ok(Self { source, program })
+    }
+
+    /// Gets the source code this [`Expression`] was compiled from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Gets the free variables of this [`Expression`], in the order they first
+    /// appear in source code.
+    pub fn variables(&self) -> &[String] {
+        self.program.variables()
+    }
+
+    /// Gets the compiled [`Program`], e.g. to run it with a [`Vm`] that is
+    /// reused between evaluations.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Evaluates this [`Expression`], binding variables by name. Bindings of
+    /// names that are not variables of this [`Expression`] are ignored.
+    ///
+    /// Random functions draw from a new generator seeded with 0 each time,
+    /// so they give the same numbers on every call. Run the
+    /// [`Expression::program`] on a [`Vm`] instead to keep drawing new ones.
+    pub fn eval<T: Into<Number> + Copy>(
+        &self,
+        bindings: &[(&str, T)],
+    ) -> Result<Number, EvalError> {
+        let values = self
+            .variables()
+            .iter()
+            .map(|name| match bindings.iter().find(|(n, _)| n == name) {
+                Some(&(_, value)) => Ok(value.into()),
+                None => Err(EvalError::UnboundVariable(name.clone())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Vm::new().run(&self.program, &values)
+    }
+
+    /// Evaluates this [`Expression`], binding variables by position in
+    /// [`Expression::variables`].
+    pub fn eval_positional<T: Into<Number> + Copy>(
+        &self,
+        values: &[T],
+    ) -> Result<Number, EvalError> {
+        let expected = self.variables().len();
+        if values.len() != expected {
+            return Err(EvalError::ValueCountMismatch(expected, values.len()));
+        }
+
+        let values = values.iter().map(|&value| value.into()).collect::<Vec<_>>();
+        Vm::new().run(&self.program, &values)
+    }
+}
+
+impl PartialEq for Expression {
+    /// Compares the source code of two expressions.
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl From<Expression> for String {
+    fn from(value: Expression) -> Self {
+        value.source
+    }
+}
+
+impl TryFrom<String> for Expression {
+    type Error = TilError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Expression::compile(value)
+    }
+}
+
+impl std::str::FromStr for Expression {
+    type Err = TilError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expression::compile(s)
+    }
+}
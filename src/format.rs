@@ -0,0 +1,396 @@
+//! This module implements configurable formatting of results for
+//! [`tilted`](crate).
+//!
+//! [`Display`] for [`Number`] gives the shortest representation that reads
+//! back as the same number, e.g. `0.30000000000000004`. A [`NumberFormat`]
+//! instead writes numbers in one of several [`Notation`]s, e.g. rounded to 2
+//! decimals or 3 significant figures, or in scientific notation, optionally
+//! with the digits grouped in thousands and a decimal comma, e.g.
+//! `1.234.567,89`.
+//!
+//! Floats are rounded to nearest, ties to even, as [`format!`] does, so the
+//! digits are exact however many are asked for. Integers are formatted
+//! exactly too, however large, and rounded half away from zero.
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    ast::{quantity::rounded, Quantity, Uncertain},
+    Number, Value,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Largest number of decimals or significant figures that a [`Notation`]
+/// parsed from a string may ask for, which covers every digit of an
+/// [`i128`].
+const MAX_PRECISION: usize = 40;
+
+/// Notation to write numbers in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Notation {
+    /// The shortest representation that reads back as the same number, as
+    /// given by [`Display`].
+    #[default]
+    Auto,
+
+    /// Positional, with a fixed number of decimals, e.g. `3.14`.
+    Fixed(usize),
+
+    /// Positional, rounded to a number of significant figures, e.g. `0.00150`
+    /// for 3 figures.
+    Significant(usize),
+
+    /// Scientific, e.g. `1.5e-7`, rounded to a number of significant figures,
+    /// or with as many as needed.
+    Scientific(Option<usize>),
+
+    /// Engineering, i.e. scientific with an exponent that is a multiple of 3,
+    /// e.g. `150e-9`, rounded to a number of significant figures, or with as
+    /// many as needed.
+    Engineering(Option<usize>),
+}
+
+impl Display for Notation {
+    /// Writes the notation as it is parsed, e.g. `fixed:2` or `sci`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Fixed(decimals) => write!(f, "fixed:{}", decimals),
+            Self::Significant(figures) => write!(f, "sig:{}", figures),
+            Self::Scientific(None) => write!(f, "sci"),
+            Self::Scientific(Some(figures)) => write!(f, "sci:{}", figures),
+            Self::Engineering(None) => write!(f, "eng"),
+            Self::Engineering(Some(figures)) => write!(f, "eng:{}", figures),
+        }
+    }
+}
+
+impl FromStr for Notation {
+    type Err = String;
+
+    /// Parses `auto`, `fixed:N`, `sig:N`, `sci`, `sci:N`, `eng` or `eng:N`,
+    /// where `N` is the number of decimals, up to 40, or of significant
+    /// figures, from 1 to 40.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, count) = match s.split_once(':') {
+            Some((name, count)) => match count.parse::<usize>() {
+                Ok(count) => (name, Some(count)),
+                Err(_) => return Err(format!("Invalid count in notation: {}", s)),
+            },
+            None => (s, None),
+        };
+
+        match (name, count) {
+            ("fixed", Some(decimals)) if decimals > MAX_PRECISION => Err(format!(
+                "Invalid notation {}, expected at most {} decimals",
+                s, MAX_PRECISION
+            )),
+            ("sig" | "sci" | "eng", Some(0)) => Err(format!(
+                "Invalid notation {}, expected at least 1 significant figure",
+                s
+            )),
+            ("sig" | "sci" | "eng", Some(figures)) if figures > MAX_PRECISION => Err(format!(
+                "Invalid notation {}, expected at most {} significant figures",
+                s, MAX_PRECISION
+            )),
+            ("auto", None) => Ok(Self::Auto),
+            ("fixed", Some(decimals)) => Ok(Self::Fixed(decimals)),
+            ("sig", Some(figures)) => Ok(Self::Significant(figures)),
+            ("sci", figures) => Ok(Self::Scientific(figures)),
+            ("eng", figures) => Ok(Self::Engineering(figures)),
+            _ => Err(format!(
+                "Invalid notation {}, expected auto, fixed:N, sig:N, sci[:N] or eng[:N]",
+                s
+            )),
+        }
+    }
+}
+
+/// Configurable formatter of numbers and values.
+///
+/// ```
+/// use tilted::{Notation, NumberFormat, Number};
+///
+/// let format = NumberFormat::new()
+///     .notation(Notation::Fixed(2))
+///     .thousands(true);
+/// assert_eq!(format.format(Number::Flt(1234567.891)), "1,234,567.89");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NumberFormat {
+    /// Notation to write numbers in.
+    notation: Notation,
+
+    /// Whether the digits of the integer part are grouped in thousands.
+    thousands: bool,
+
+    /// Whether the decimal separator is a comma rather than a point.
+    decimal_comma: bool,
+}
+
+impl NumberFormat {
+    /// Creates a new [`NumberFormat`] that formats numbers as [`Display`]
+    /// does.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes numbers in `notation`.
+    pub fn notation(mut self, notation: Notation) -> Self {
+        self.notation = notation;
+        self
+    }
+
+    /// Groups the digits of the integer part of numbers in thousands,
+    /// separated by commas, or by points with a decimal comma.
+    pub fn thousands(mut self, thousands: bool) -> Self {
+        self.thousands = thousands;
+        self
+    }
+
+    /// Writes a decimal comma rather than a decimal point. Elements of
+    /// vectors, matrices and lists are then separated by semicolons.
+    pub fn decimal_comma(mut self, decimal_comma: bool) -> Self {
+        self.decimal_comma = decimal_comma;
+        self
+    }
+
+    /// Formats a number. NaN and infinities are written as by [`Display`].
+    pub fn format(&self, n: Number) -> String {
+        if let Number::Flt(x) = n {
+            if !x.is_finite() {
+                return x.to_string();
+            }
+        }
+
+        match (self.notation, n) {
+            (Notation::Auto, Number::Int(n)) => {
+                self.join(n < 0, &n.unsigned_abs().to_string(), "", None)
+            }
+            (Notation::Auto, Number::Flt(x)) => {
+                let positional = x.abs().to_string();
+                let (int, frac) = positional.split_once('.').unwrap_or((&positional, ""));
+                self.join(x.is_sign_negative(), int, frac, None)
+            }
+            (Notation::Fixed(decimals), Number::Int(n)) => {
+                let frac = "0".repeat(decimals);
+                self.join(n < 0, &n.unsigned_abs().to_string(), &frac, None)
+            }
+            (Notation::Fixed(decimals), Number::Flt(x)) => {
+                let positional = format!("{:.*}", decimals, x.abs());
+                let (int, frac) = positional.split_once('.').unwrap_or((&positional, ""));
+                self.join(x.is_sign_negative(), int, frac, None)
+            }
+            (Notation::Significant(figures), n) => {
+                let digits = Digits::new(n, Some(figures.max(1)));
+                let (int, frac) = digits.split(digits.exponent);
+                self.join(digits.negative, &int, &frac, None)
+            }
+            (Notation::Scientific(figures), n) => {
+                let digits = Digits::new(n, figures.map(|figures| figures.max(1)));
+                let (int, frac) = digits.split(0);
+                self.join(digits.negative, &int, &frac, Some(digits.exponent))
+            }
+            (Notation::Engineering(figures), n) => {
+                let digits = Digits::new(n, figures.map(|figures| figures.max(1)));
+                let exponent = digits.exponent.div_euclid(3) * 3;
+                let (int, frac) = digits.split(digits.exponent - exponent);
+                self.join(digits.negative, &int, &frac, Some(exponent))
+            }
+        }
+    }
+
+    /// Formats a value, with every number in it formatted by
+    /// [`NumberFormat::format`].
+    pub fn format_value(&self, value: &Value) -> String {
+        match value {
+            Value::Scalar(n) => self.format(*n),
+            Value::Vector(v) => format!("[{}]", self.list(v.iter().map(|n| self.format(*n)))),
+            Value::Matrix(m) => {
+                let rows = (0..m.rows())
+                    .map(|i| format!("[{}]", self.list(m.row(i).iter().map(|n| self.format(*n)))));
+                format!("[{}]", self.list(rows))
+            }
+            Value::List(l) => format!("{{{}}}", self.list(l.iter().map(|v| self.format_value(v)))),
+            Value::Lambda(l) => l.to_string(),
+        }
+    }
+
+    /// Formats a quantity as its value followed by its unit, rounding values
+    /// with a unit to 12 significant figures first, as [`Display`] does.
+    pub fn format_quantity(&self, quantity: &Quantity) -> String {
+        match quantity.unit() {
+            Some(unit) => format!("{} {}", self.format(rounded(quantity.magnitude())), unit),
+            None if quantity.dimension().is_dimensionless() => self.format(quantity.value()),
+            None => format!(
+                "{} {}",
+                self.format(rounded(quantity.value())),
+                quantity.dimension()
+            ),
+        }
+    }
+
+    /// Formats an uncertain value as `value ± error`. With
+    /// [`Notation::Auto`], both are rounded as [`Display`] does, to the place
+    /// of the first significant figure of the error, or the second if the
+    /// first is 1.
+    pub fn format_uncertain(&self, uncertain: &Uncertain) -> String {
+        let (value, error) = (uncertain.value(), uncertain.error());
+        let (format, value, error) = match (self.notation, uncertain.decimals()) {
+            (Notation::Auto, Some(decimals)) if decimals >= 0 => (
+                self.notation(Notation::Fixed(decimals as usize)),
+                value,
+                error,
+            ),
+            (Notation::Auto, Some(decimals)) => {
+                let scale = 10f64.powi(-decimals);
+                let round = |n: f64| (n / scale).round() * scale;
+                (
+                    self.notation(Notation::Fixed(0)),
+                    round(value),
+                    round(error),
+                )
+            }
+            _ => (*self, value, error),
+        };
+        format!(
+            "{} ± {}",
+            format.format(Number::Flt(value)),
+            format.format(Number::Flt(error))
+        )
+    }
+
+    /// Gets the separator of elements of vectors, matrices and lists, which
+    /// is `, ` or `; ` with a decimal comma.
+    pub fn separator(&self) -> &'static str {
+        match self.decimal_comma {
+            true => "; ",
+            false => ", ",
+        }
+    }
+
+    /// Joins formatted elements with the separator of this format.
+    fn list(&self, elements: impl Iterator<Item = String>) -> String {
+        elements.collect::<Vec<_>>().join(self.separator())
+    }
+
+    /// Joins the sign, the integer part and the fractional part of a number,
+    /// and its exponent if any, with the separators of this format.
+    fn join(&self, negative: bool, int: &str, frac: &str, exponent: Option<i32>) -> String {
+        let (point, group) = match self.decimal_comma {
+            true => (',', '.'),
+            false => ('.', ','),
+        };
+
+        let mut output = String::new();
+        if negative {
+            output.push('-');
+        }
+        for (i, digit) in int.chars().enumerate() {
+            if self.thousands && i > 0 && (int.len() - i).is_multiple_of(3) {
+                output.push(group);
+            }
+            output.push(digit);
+        }
+        if !frac.is_empty() {
+            output.push(point);
+            output.push_str(frac);
+        }
+        if let Some(exponent) = exponent {
+            output.push_str(&format!("e{}", exponent));
+        }
+        output
+    }
+}
+
+/// Significant digits of a finite number, `d₀.d₁d₂… × 10^exponent`.
+#[derive(Debug)]
+struct Digits {
+    /// Whether the number is negative, including negative zero.
+    negative: bool,
+
+    /// Significant digits, without trailing zeros unless they were asked for.
+    digits: String,
+
+    /// Exponent of the first digit.
+    exponent: i32,
+}
+
+impl Digits {
+    /// Finds the digits of a finite number, rounded to a number of
+    /// significant figures, or as many as needed.
+    fn new(n: Number, figures: Option<usize>) -> Self {
+        match n {
+            Number::Flt(x) => {
+                let scientific = match figures {
+                    Some(figures) => format!("{:.*e}", figures - 1, x.abs()),
+                    None => format!("{:e}", x.abs()),
+                };
+                let (mantissa, exponent) = scientific.split_once('e').unwrap();
+                Self {
+                    negative: x.is_sign_negative(),
+                    digits: mantissa.replace('.', ""),
+                    exponent: exponent.parse().unwrap(),
+                }
+            }
+            Number::Int(n) => {
+                let mut digits = n.unsigned_abs().to_string().into_bytes();
+                let mut exponent = digits.len() as i32 - 1;
+                match figures {
+                    Some(figures) if figures < digits.len() => {
+                        let round_up = digits[figures] >= b'5';
+                        digits.truncate(figures);
+                        if round_up {
+                            // Carries through trailing nines, e.g. 999 to 1000.
+                            match digits.iter().rposition(|&digit| digit != b'9') {
+                                Some(i) => {
+                                    digits[i] += 1;
+                                    digits[i + 1..].fill(b'0');
+                                }
+                                None => {
+                                    digits.fill(b'0');
+                                    digits[0] = b'1';
+                                    exponent += 1;
+                                }
+                            }
+                        }
+                    }
+                    Some(figures) => digits.resize(figures, b'0'),
+                    None => {
+                        let len = digits.iter().rposition(|&digit| digit != b'0');
+                        digits.truncate(len.unwrap_or(0) + 1);
+                    }
+                }
+                Self {
+                    negative: n < 0,
+                    digits: String::from_utf8(digits).unwrap(),
+                    exponent,
+                }
+            }
+        }
+    }
+
+    /// Splits the digits into an integer and a fractional part, where the
+    /// first digit has the place value `10^place`, padding them with zeros.
+    fn split(&self, place: i32) -> (String, String) {
+        match usize::try_from(place) {
+            Ok(place) if place < self.digits.len() => {
+                let (int, frac) = self.digits.split_at(place + 1);
+                (int.to_string(), frac.to_string())
+            }
+            Ok(place) => (format!("{:0<1$}", self.digits, place + 1), String::new()),
+            Err(_) => (
+                "0".to_string(),
+                format!(
+                    "{}{}",
+                    "0".repeat(place.unsigned_abs() as usize - 1),
+                    self.digits
+                ),
+            ),
+        }
+    }
+}
@@ -2,13 +2,29 @@
 //!
 //! A lexer's job is to generate a stream of [`Token`]s from user input, which
 //! is used by the [`Parser`] to generate an Abstract Syntax Tree.
+//!
+//! Formulas pasted from documents are accepted too: `×`, `·` and `⋅` are
+//! multiplication, `÷` is division, `−` is subtraction, `√` is the square
+//! root and `π` is pi. Superscripts are exponents, e.g. `x²` and `x⁻¹` are
+//! lexed as `x^2` and `x^(-1)`, and vulgar fractions are divisions, e.g. `½`
+//! is lexed as `(1.0 / 2)`. The numerator is a float so that the division is
+//! not truncated, which makes a fraction a float when evaluated, but a
+//! rational with [`evaluate_exact`](crate::ast::evaluate_exact). A fraction
+//! after an integer, with or without spaces between them, is a mixed number,
+//! e.g. `3½` and `3 ½` are both lexed as `(3 + 1.0 / 2)`, while `3 × ½` is a
+//! product. The tokens a character stands for all have its [`Span`].
+//!
+//! The lexer keeps a byte cursor into the source code, and every token is
+//! lexed from it without copying, so lexing takes linear time. Spans are
+//! byte offsets too, so they can be used to slice the source code even if it
+//! has multi-byte characters.
 
-use std::{fmt::Display, ops::Index, slice::SliceIndex};
+use std::{collections::VecDeque, fmt::Display, ops::Index, slice::SliceIndex};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::{eof, token, LexError};
+use crate::{eof, token, LexError, Unit};
 
 /// Special [`Result`] type for the lexer.
 type Result<T> = std::result::Result<T, LexError>;
@@ -20,8 +36,13 @@ pub struct Lexer {
     /// The original source code that is passed in.
     source_code: Box<str>,
 
-    /// The index of the current character, i.e. the one that is parsed next.
+    /// The byte index of the current character, i.e. the one that is parsed
+    /// next.
     current_index: usize,
+
+    /// Tokens lexed ahead, from a character that stands for several tokens,
+    /// e.g. `²` for `^2`.
+    pending: VecDeque<Token>,
 }
 
 /// Part of the source code tokenised. Returned by a [`Lexer`].
@@ -45,7 +66,7 @@ pub enum TokenKind {
     Eof,
 
     /// Integer, i.e. numbers without decimal places.
-    Int(u64),
+    Int(u128),
 
     /// Floating-point number, i.e. real numbers that are not integers.
     Flt(f64),
@@ -56,11 +77,34 @@ pub enum TokenKind {
     /// Function.
     Func(Function),
 
+    /// Identifier, i.e. a name that is not a [`Function`]. The name itself can
+    /// be looked up from the source code using the [`Span`] of the [`Token`].
+    Ident,
+
+    /// Unit, i.e. a name that is a built-in [`Unit`]. Like an identifier, the
+    /// name can be looked up from the source code using the [`Span`].
+    Unit,
+
     /// Left parenthesis.
     LeftParen,
 
     /// Right parenthesis.
     RightParen,
+
+    /// Comma, separating arguments and elements.
+    Comma,
+
+    /// Left bracket, opening a vector or a matrix.
+    LeftBracket,
+
+    /// Right bracket, closing a vector or a matrix.
+    RightBracket,
+
+    /// Left brace, opening a list.
+    LeftBrace,
+
+    /// Right brace, closing a list.
+    RightBrace,
 }
 
 /// Functions.
@@ -102,6 +146,12 @@ pub enum Function {
 
     /// Inverse cotangent.
     Acot,
+
+    /// Natural logarithm.
+    Ln,
+
+    /// Square root.
+    Sqrt,
 }
 
 /// Basic mathematical operators.
@@ -122,16 +172,34 @@ pub enum Operator {
 
     /// Operator `^`.
     Caret,
+
+    /// Operator `±`, also written `+/-`.
+    PlusMinus,
+
+    /// Keyword `to`, converting a quantity to a unit.
+    To,
+
+    /// Operator `=`, equating two expressions.
+    Equal,
+
+    /// Operator `\`, solving a linear system.
+    Backslash,
+
+    /// Operator `..`, giving a range of integers.
+    DotDot,
+
+    /// Operator `->`, separating the parameters of a lambda from its body.
+    Arrow,
 }
 
 /// Spatial information of a [`Token`].
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Span {
-    /// Index of the first character of this [`Span`].
+    /// Byte index of the first character of this [`Span`].
     pub start_index: usize,
 
-    /// Index of the last character of this [`Span`].
+    /// Byte index of the last byte of the last character of this [`Span`].
     pub end_index: usize,
 }
 
@@ -149,6 +217,12 @@ impl From<char> for Operator {
             '*' => Self::Star,
             '/' => Self::Slash,
             '^' => Self::Caret,
+            '×' | '·' | '⋅' => Self::Star,
+            '÷' => Self::Slash,
+            '−' => Self::Minus,
+            '±' => Self::PlusMinus,
+            '=' => Self::Equal,
+            '\\' => Self::Backslash,
 
             // This also guards against attempts to add new operators
             // without implementing its conversion.
@@ -157,6 +231,28 @@ impl From<char> for Operator {
     }
 }
 
+impl Function {
+    /// Returns the name of this [`Function`] as written in source code.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sin => "sin",
+            Self::Cos => "cos",
+            Self::Tan => "tan",
+            Self::Csc => "csc",
+            Self::Sec => "sec",
+            Self::Cot => "cot",
+            Self::Asin => "asin",
+            Self::Acos => "acos",
+            Self::Atan => "atan",
+            Self::Acsc => "acsc",
+            Self::Asec => "asec",
+            Self::Acot => "acot",
+            Self::Ln => "ln",
+            Self::Sqrt => "sqrt",
+        }
+    }
+}
+
 impl TryFrom<&str> for Function {
     type Error = ();
     fn try_from(value: &str) -> std::result::Result<Self, Self::Error> {
@@ -173,6 +269,8 @@ impl TryFrom<&str> for Function {
             "acsc" => Ok(Self::Acsc),
             "asec" => Ok(Self::Asec),
             "acot" => Ok(Self::Acot),
+            "ln" => Ok(Self::Ln),
+            "sqrt" => Ok(Self::Sqrt),
             _ => Err(()),
         }
     }
@@ -193,6 +291,8 @@ impl Display for Function {
             Function::Acsc => write!(f, "Acsc"),
             Function::Asec => write!(f, "Asec"),
             Function::Acot => write!(f, "Acot"),
+            Function::Ln => write!(f, "Ln"),
+            Function::Sqrt => write!(f, "Sqrt"),
         }
     }
 }
@@ -209,13 +309,17 @@ impl Index<Span> for Lexer {
     type Output = str;
 
     fn index(&self, index: Span) -> &Self::Output {
-        &self[index.start_index..(index.end_index - 1)]
+        // End index is inclusive, except for EOF whose span is out-of-bound.
+        let end_index = (index.end_index + 1).min(self.source_code.len());
+        &self[index.start_index..end_index]
     }
 }
 
 impl Iterator for Lexer {
     type Item = Token;
 
+    /// Gets the next [`Token`], ending before EOF or at the first error. Use
+    /// a [`TokenStream`](crate::TokenStream) to get the errors.
     fn next(&mut self) -> Option<Self::Item> {
         match self.lex() {
             Ok(token) if token.kind != TokenKind::Eof => Some(token),
@@ -231,54 +335,65 @@ impl Lexer {
         Lexer {
             source_code: Box::from(source_code.as_ref()),
             current_index: 0,
+            pending: VecDeque::new(),
         }
     }
 
     /// Gets the next [`Token`] from source.
     pub fn lex(&mut self) -> Result<Token> {
+        // Tokens lexed ahead come first.
+        if let Some(token) = self.pending.pop_front() {
+            return Ok(token);
+        }
+
         // Skip whitespaces.
-        for c in self.source_code.chars().skip(self.current_index) {
-            if c.is_whitespace() {
-                self.current_index += 1;
-            } else {
-                break;
-            }
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.current_index += c.len_utf8();
         }
 
         // Check for EOF.
-        if self.current_index >= self.source_code.len() {
-            return Ok(eof!(self.current_index));
-        }
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Ok(eof!(self.current_index)),
+        };
 
         // Assign to handlers based on the next character.
-        match self
-            .source_code
-            .chars()
-            .nth(self.current_index)
-            .ok_or(LexError::InternalError(
-                "Unable to unwrap next character in source",
-                self.current_index,
-            ))? {
+        match c {
+            // Ranges, which start with a dot like some numbers.
+            '.' if self.rest().starts_with("..") => self.handle_operator(),
+
             // Numbers (integers and reals)
             // Can start with a dot or number
             '.' | '0'..='9' => self.handle_number(),
 
-            // Operators.
-            '+' | '-' | '*' | '/' | '^' => self.handle_operator(),
+            // Operators, including their Unicode forms.
+            '+' | '-' | '*' | '/' | '^' | '±' | '=' | '\\' | '×' | '·' | '⋅' | '÷' | '−' => {
+                self.handle_operator()
+            }
+
+            // Exponents written as superscripts.
+            c if superscript_digit(c).is_some() || c == '⁻' => self.handle_superscript(),
+
+            // Vulgar fractions.
+            c if vulgar_fraction(c).is_some() => self.handle_fraction(None),
 
-            // Parentheses.
+            // The radical sign and pi, which is looked up by the parser as
+            // an identifier.
+            '√' => Ok(self.advance(TokenKind::Func(Function::Sqrt), c.len_utf8())),
+            'π' => Ok(self.advance(TokenKind::Ident, c.len_utf8())),
+
+            // Parentheses, commas, brackets and braces.
             // These are short so they are handled in-place.
-            '(' => {
-                self.current_index += 1;
-                Ok(token!(TokenKind::LeftParen, self.current_index - 1, 1))
-            }
-            ')' => {
-                self.current_index += 1;
-                Ok(token!(TokenKind::RightParen, self.current_index - 1, 1))
-            }
+            '(' => Ok(self.advance(TokenKind::LeftParen, 1)),
+            ')' => Ok(self.advance(TokenKind::RightParen, 1)),
+            ',' => Ok(self.advance(TokenKind::Comma, 1)),
+            '[' => Ok(self.advance(TokenKind::LeftBracket, 1)),
+            ']' => Ok(self.advance(TokenKind::RightBracket, 1)),
+            '{' => Ok(self.advance(TokenKind::LeftBrace, 1)),
+            '}' => Ok(self.advance(TokenKind::RightBrace, 1)),
 
-            // Functions.
-            c if c.is_ascii_alphabetic() => self.handle_function(),
+            // Functions and identifiers.
+            c if c.is_ascii_alphabetic() => self.handle_identifier(),
 
             // Any other characters.
             c => Err(LexError::UnrecognisedCharacter(c, self.current_index)),
@@ -292,71 +407,85 @@ impl Lexer {
         // Tracker for decimal place.
         let mut seen_dot = false;
 
-        // Reserve enough space for a 100-char string.
-        // Most numbers (hopefully) are within this limit. However, we still
-        // need to cover the potential cases of more than 100 digits.
-        let mut result = String::with_capacity(100);
-        for c in self.source_code[self.current_index..].chars() {
-            match c {
-                '.' => {
-                    if !seen_dot {
-                        // Dot (if not seen)
-                        self.current_index += 1;
-                        seen_dot = true;
-                        result.push('.');
-                    } else {
-                        // Dot (if already seen)
-                        return Err(LexError::UnrecognisedCharacter(c, self.current_index));
-                    }
+        // Numbers are made of ASCII digits and dots, so they are scanned byte
+        // by byte and parsed from the source code in place.
+        while let Some(&b) = self.source_code.as_bytes().get(self.current_index) {
+            match b {
+                // A range follows the number, e.g. `1..5`.
+                b'.' if self.rest().starts_with("..") => break,
+
+                // Dot (if already seen)
+                b'.' if seen_dot => {
+                    return Err(LexError::UnrecognisedCharacter('.', self.current_index))
                 }
 
+                // Dot (if not seen)
+                b'.' => seen_dot = true,
+
                 // Digit
-                c if c.is_ascii_digit() => {
-                    result.push(c);
-                    self.current_index += 1;
-                }
+                b if b.is_ascii_digit() => (),
 
                 // Anything else
                 _ => break,
             }
+            self.current_index += 1;
         }
+        let source = &self.source_code[original_index..self.current_index];
 
-        // Convert string to integer or float based on seen_dot.
+        // Convert to integer or float based on seen_dot.
         if seen_dot {
             // Float
-            let num = result
+            let num = source
                 .parse::<f64>()
                 .map_err(|_| LexError::InternalError("Parse float failed", self.current_index))?;
 
-            Ok(token!(TokenKind::Flt(num), original_index, result.len()))
+            Ok(token!(TokenKind::Flt(num), original_index, source.len()))
         } else {
             // Integer
-            let num = result
-                .parse::<u64>()
+            let num = source
+                .parse::<u128>()
                 .map_err(|_| LexError::InternalError("Parse integer failed", self.current_index))?;
-
-            Ok(token!(TokenKind::Int(num), original_index, result.len()))
+            let token = token!(TokenKind::Int(num), original_index, source.len());
+
+            // A vulgar fraction after an integer makes a mixed number, e.g.
+            // `3½` or `3 ½`.
+            let rest = self.rest();
+            let spaces = rest.len() - rest.trim_start().len();
+            match rest[spaces..].chars().next() {
+                Some(c) if vulgar_fraction(c).is_some() => {
+                    self.current_index += spaces;
+                    self.handle_fraction(Some(token))
+                }
+                _ => Ok(token),
+            }
         }
     }
 
     pub fn handle_operator(&mut self) -> Result<Token> {
-        // Operator has only one char so it should be trivial.
-        let op =
-            self.source_code
-                .chars()
-                .nth(self.current_index)
-                .ok_or(LexError::InternalError(
-                    "Unable to unwrap operator",
-                    self.current_index,
-                ))?;
+        // `+/-`, `..` and `->` are the only operators longer than one
+        // character.
+        for (source, op) in [
+            ("+/-", Operator::PlusMinus),
+            ("..", Operator::DotDot),
+            ("->", Operator::Arrow),
+        ] {
+            if self.rest().starts_with(source) {
+                return Ok(self.advance(TokenKind::Op(op), source.len()));
+            }
+        }
+
+        // Other operators have only one char so it should be trivial.
+        let op = self.peek().ok_or(LexError::InternalError(
+            "Unable to unwrap operator",
+            self.current_index,
+        ))?;
 
         // The parent match operator should have narrowed down the valid ones,
         // but I think it is still important to check here, just in case I mess
         // up somewhere else. Resources are cheap anyway :)
         match op {
-            '+' | '-' | '*' | '/' | '^' => {
-                self.current_index += 1;
-                Ok(token!(TokenKind::Op(op.into()), self.current_index - 1, 1))
+            '+' | '-' | '*' | '/' | '^' | '±' | '=' | '\\' | '×' | '·' | '⋅' | '÷' | '−' => {
+                Ok(self.advance(TokenKind::Op(op.into()), op.len_utf8()))
             }
             _ => Err(LexError::InternalError(
                 "Invalid operator inside operator handler",
@@ -365,26 +494,104 @@ impl Lexer {
         }
     }
 
-    pub fn handle_function(&mut self) -> Result<Token> {
+    pub fn handle_identifier(&mut self) -> Result<Token> {
+        // Identifiers start with a letter, followed by letters, digits or
+        // underscores, which are all ASCII. The first letter was checked by
+        // the caller.
+        let length = self
+            .rest()
+            .bytes()
+            .take_while(|b| b.is_ascii_alphanumeric() || *b == b'_')
+            .count();
+        let name = &self.rest()[..length];
+
+        // Known names are functions, keywords and units, anything else is an
+        // identifier.
+        let kind = match Function::try_from(name) {
+            Ok(func) => TokenKind::Func(func),
+            Err(_) if name == "to" => TokenKind::Op(Operator::To),
+            Err(_) if Unit::lookup(name).is_some() => TokenKind::Unit,
+            Err(_) => TokenKind::Ident,
+        };
+
+        Ok(self.advance(kind, length))
+    }
+
+    /// Lexes an exponent written as superscripts, e.g. `²` or `⁻¹`, into a
+    /// caret followed by the exponent, which is parenthesised if negative.
+    pub fn handle_superscript(&mut self) -> Result<Token> {
         // Keep track of the original index for later.
         let original_index = self.current_index;
 
-        // Trigos only contain letters.
-        let name = self.source_code[self.current_index..]
-            .chars()
-            .take_while(|c| c.is_ascii_alphabetic())
-            .collect::<String>();
+        let negative = self.rest().starts_with('⁻');
+        if negative {
+            self.current_index += '⁻'.len_utf8();
+        }
 
-        // Convert string to trigonometric function.
-        let trigo = name
-            .as_str()
-            .try_into()
-            .map_err(|_| LexError::UnrecognisedFunction(name.clone(), self.current_index))?;
+        let mut exponent = None;
+        while let Some(c) = self.peek() {
+            let digit = match superscript_digit(c) {
+                Some(digit) => digit,
+                None => break,
+            };
+            let overflow = LexError::InternalError("Parse integer failed", self.current_index);
+            exponent = Some(
+                exponent
+                    .unwrap_or(0u128)
+                    .checked_mul(10)
+                    .and_then(|exponent| exponent.checked_add(digit))
+                    .ok_or(overflow)?,
+            );
+            self.current_index += c.len_utf8();
+        }
 
-        // Update current index.
-        self.current_index += name.len();
+        // A superscript minus must be followed by digits.
+        let exponent = exponent.ok_or(LexError::UnrecognisedCharacter('⁻', original_index))?;
+
+        let length = self.current_index - original_index;
+        let token = |kind| token!(kind, original_index, length);
+        match negative {
+            true => self.pending.extend([
+                token(TokenKind::LeftParen),
+                token(TokenKind::Op(Operator::Minus)),
+                token(TokenKind::Int(exponent)),
+                token(TokenKind::RightParen),
+            ]),
+            false => self.pending.push_back(token(TokenKind::Int(exponent))),
+        }
+        Ok(token(TokenKind::Op(Operator::Caret)))
+    }
 
-        Ok(token!(TokenKind::Func(trigo), original_index, name.len()))
+    /// Lexes a vulgar fraction, e.g. `½`, into a parenthesised division of
+    /// a float by an integer, so that it is not truncated, with the integer
+    /// part of a mixed number added if any.
+    pub fn handle_fraction(&mut self, whole: Option<Token>) -> Result<Token> {
+        // Keep track of the original index for later.
+        let original_index = whole.map_or(self.current_index, |whole| whole.span.start_index);
+
+        let c = self.peek().ok_or(LexError::InternalError(
+            "Unable to unwrap fraction",
+            self.current_index,
+        ))?;
+        let (numerator, denominator) = vulgar_fraction(c).ok_or(LexError::InternalError(
+            "Invalid fraction inside fraction handler",
+            self.current_index,
+        ))?;
+        self.current_index += c.len_utf8();
+
+        let length = self.current_index - original_index;
+        let token = |kind| token!(kind, original_index, length);
+        if let Some(whole) = whole {
+            self.pending
+                .extend([whole, token(TokenKind::Op(Operator::Plus))]);
+        }
+        self.pending.extend([
+            token(TokenKind::Flt(numerator as f64)),
+            token(TokenKind::Op(Operator::Slash)),
+            token(TokenKind::Int(denominator)),
+            token(TokenKind::RightParen),
+        ]);
+        Ok(token(TokenKind::LeftParen))
     }
 
     /// Reverts this [`Lexer`] to its original state.
@@ -392,5 +599,59 @@ impl Lexer {
     pub fn reset(&mut self) {
         // Simply set the index to 0 to reset.
         self.current_index = 0;
+        self.pending.clear();
+    }
+
+    /// Gets the source code from the current character on.
+    fn rest(&self) -> &str {
+        &self.source_code[self.current_index..]
+    }
+
+    /// Gets the current character, without consuming it.
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Consumes `length` bytes from the current character as a [`Token`].
+    fn advance(&mut self, kind: TokenKind, length: usize) -> Token {
+        self.current_index += length;
+        token!(kind, self.current_index - length, length)
+    }
+}
+
+/// Gets the digit a superscript stands for.
+fn superscript_digit(c: char) -> Option<u128> {
+    match c {
+        '⁰' => Some(0),
+        '¹' => Some(1),
+        '²' => Some(2),
+        '³' => Some(3),
+        '⁴'..='⁹' => Some(c as u128 - '⁴' as u128 + 4),
+        _ => None,
+    }
+}
+
+/// Gets the numerator and the denominator of a vulgar fraction.
+fn vulgar_fraction(c: char) -> Option<(u8, u128)> {
+    match c {
+        '½' => Some((1, 2)),
+        '⅓' => Some((1, 3)),
+        '⅔' => Some((2, 3)),
+        '¼' => Some((1, 4)),
+        '¾' => Some((3, 4)),
+        '⅕' => Some((1, 5)),
+        '⅖' => Some((2, 5)),
+        '⅗' => Some((3, 5)),
+        '⅘' => Some((4, 5)),
+        '⅙' => Some((1, 6)),
+        '⅚' => Some((5, 6)),
+        '⅐' => Some((1, 7)),
+        '⅛' => Some((1, 8)),
+        '⅜' => Some((3, 8)),
+        '⅝' => Some((5, 8)),
+        '⅞' => Some((7, 8)),
+        '⅑' => Some((1, 9)),
+        '⅒' => Some((1, 10)),
+        _ => None,
     }
 }
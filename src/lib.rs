@@ -5,13 +5,39 @@ pub mod ast;
 #[cfg(feature = "cli")]
 pub mod cli;
 pub mod error;
+pub mod expression;
+pub mod format;
 pub mod lexer;
 pub mod macros;
+pub mod matrix;
+pub mod ntheory;
 pub mod parser;
+pub mod polynomial;
+pub mod quadrature;
+pub mod random;
+pub mod solve;
+pub mod stats;
+pub mod stream;
+pub mod units;
+pub mod vm;
 
-pub use ast::{BinaryAction, BinaryNode, NodeBox, Number, PlainNode, UnaryAction, UnaryNode};
+pub use ast::{
+    BinaryAction, BinaryNode, BinderAction, BinderNode, Builtin, CallNode, Constant, Environment,
+    Lambda, LambdaNode, ListNode, Node, NodeBox, NodeView, Number, PlainNode, RangeNode, Shape,
+    SolveNode, UnaryAction, UnaryNode, UnitNode, Value, VariableNode, VectorNode,
+};
 #[cfg(feature = "cli")]
 pub use cli::CliParser;
-pub use error::{LexError, ParseError, TilError};
+pub use error::{EvalError, LexError, ParseError, TilError};
+pub use expression::Expression;
+pub use format::{Notation, NumberFormat};
 pub use lexer::{Function, Lexer, Operator, Span, Token, TokenKind};
+pub use matrix::Matrix;
 pub use parser::Parser;
+pub use polynomial::{Polynomial, Rational};
+pub use quadrature::Quadrature;
+pub use random::Rng;
+pub use solve::Guess;
+pub use stream::{Checkpoint, TokenStream};
+pub use units::{Dimension, Unit};
+pub use vm::{Instruction, Program, Vm};
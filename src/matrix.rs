@@ -0,0 +1,361 @@
+//! This module implements matrices for [`tilted`](crate).
+//!
+//! A [`Matrix`] stores [`Number`]s row by row. Products, powers and
+//! transposes of integer matrices stay exact, like scalar arithmetic does.
+//! Determinants of integer matrices are exact too, with the fraction-free
+//! Bareiss algorithm, unless an intermediate value overflows, in which case
+//! they are computed over floats like those of any other matrix: by Gaussian
+//! elimination with partial pivoting. Inverses and solutions of linear
+//! systems are always floats, found by Gauss–Jordan elimination with partial
+//! pivoting.
+
+use std::fmt::Display;
+
+use crate::{EvalError, Number, Shape};
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// Matrix of [`Number`]s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    /// Number of rows.
+    rows: usize,
+
+    /// Number of columns.
+    cols: usize,
+
+    /// Elements, row by row.
+    elements: Vec<Number>,
+}
+
+impl Matrix {
+    /// Creates a new [`Matrix`] from its rows, which must all be of the same
+    /// length.
+    pub fn from_rows(rows: Vec<Vec<Number>>) -> Result<Matrix> {
+        let cols = rows.first().map_or(0, Vec::len);
+        if let Some(row) = rows.iter().find(|row| row.len() != cols) {
+            return Err(EvalError::ShapeMismatch(
+                Shape::Vector(cols),
+                Shape::Vector(row.len()),
+            ));
+        }
+
+        Ok(Self {
+            rows: rows.len(),
+            cols,
+            elements: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Creates a new column [`Matrix`] from the elements of a vector.
+    pub fn column(elements: Vec<Number>) -> Matrix {
+        Self {
+            rows: elements.len(),
+            cols: 1,
+            elements,
+        }
+    }
+
+    /// Creates the identity [`Matrix`] of size `n`, with integer elements.
+    pub fn identity(n: usize) -> Matrix {
+        Self::from_fn(n, n, |i, j| Number::Int((i == j) as i128))
+    }
+
+    fn from_fn(rows: usize, cols: usize, f: impl Fn(usize, usize) -> Number) -> Matrix {
+        Self {
+            rows,
+            cols,
+            elements: (0..rows * cols).map(|k| f(k / cols, k % cols)).collect(),
+        }
+    }
+
+    /// Gets the number of rows of this [`Matrix`].
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Gets the number of columns of this [`Matrix`].
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Gets the [`Shape`] of this [`Matrix`].
+    pub fn shape(&self) -> Shape {
+        Shape::Matrix(self.rows, self.cols)
+    }
+
+    /// Checks if this [`Matrix`] has as many rows as columns.
+    pub fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+
+    /// Gets the element in row `i` and column `j`, counting from 0.
+    pub fn get(&self, i: usize, j: usize) -> Number {
+        self.elements[i * self.cols + j]
+    }
+
+    /// Gets the elements of row `i`, counting from 0.
+    pub fn row(&self, i: usize) -> &[Number] {
+        &self.elements[i * self.cols..(i + 1) * self.cols]
+    }
+
+    /// Gets the elements of this [`Matrix`], row by row.
+    pub fn elements(&self) -> &[Number] {
+        &self.elements
+    }
+
+    /// Consumes this [`Matrix`], returning its elements row by row.
+    pub fn into_elements(self) -> Vec<Number> {
+        self.elements
+    }
+
+    /// Applies `f` to each element.
+    pub fn map(mut self, f: impl Fn(Number) -> Number) -> Matrix {
+        self.elements.iter_mut().for_each(|n| *n = f(*n));
+        self
+    }
+
+    /// Applies `f` to each pair of elements of two matrices of the same
+    /// shape.
+    pub fn zip_with(
+        mut self,
+        other: &Matrix,
+        f: impl Fn(Number, Number) -> Number,
+    ) -> Result<Matrix> {
+        if self.shape() != other.shape() {
+            return Err(EvalError::ShapeMismatch(self.shape(), other.shape()));
+        }
+
+        for (l, r) in self.elements.iter_mut().zip(&other.elements) {
+            *l = f(*l, *r);
+        }
+        Ok(self)
+    }
+
+    /// Swaps rows and columns.
+    pub fn transpose(&self) -> Matrix {
+        Self::from_fn(self.cols, self.rows, |i, j| self.get(j, i))
+    }
+
+    /// Multiplies two matrices, which requires as many columns on the left
+    /// as rows on the right.
+    pub fn product(&self, other: &Matrix) -> Result<Matrix> {
+        if self.cols != other.rows {
+            return Err(EvalError::ShapeMismatch(self.shape(), other.shape()));
+        }
+
+        Ok(Self::from_fn(self.rows, other.cols, |i, j| {
+            (0..self.cols).fold(Number::Int(0), |sum, k| {
+                sum + self.get(i, k) * other.get(k, j)
+            })
+        }))
+    }
+
+    /// Raises a square [`Matrix`] to an integer power by repeated squaring.
+    /// Negative powers are powers of the inverse.
+    pub fn pow(&self, n: i128) -> Result<Matrix> {
+        if !self.is_square() {
+            return Err(EvalError::InvalidShape("^", self.shape()));
+        }
+
+        let mut base = match n < 0 {
+            true => self.inverse()?,
+            false => self.clone(),
+        };
+        let mut n = n.unsigned_abs();
+        let mut power = Self::identity(self.rows);
+        while n > 0 {
+            if n % 2 == 1 {
+                power = power.product(&base)?;
+            }
+            n /= 2;
+            if n > 0 {
+                base = base.product(&base)?;
+            }
+        }
+        Ok(power)
+    }
+
+    /// Finds the determinant of a square [`Matrix`].
+    pub fn determinant(&self) -> Result<Number> {
+        if !self.is_square() {
+            return Err(EvalError::InvalidShape("det", self.shape()));
+        }
+
+        let ints = self.elements.iter().map(|n| match n {
+            Number::Int(n) => Some(*n),
+            Number::Flt(_) => None,
+        });
+        if let Some(det) = ints
+            .collect::<Option<Vec<_>>>()
+            .and_then(|a| bareiss(self.rows, a))
+        {
+            return Ok(Number::Int(det));
+        }
+
+        // Gaussian elimination, the determinant being the product of the
+        // pivots with the sign of the row permutation.
+        let n = self.rows;
+        let mut a = self.floats();
+        let mut det = 1.0;
+        for k in 0..n {
+            let p = pivot(&a, n, k);
+            if a[p * n + k] == 0.0 {
+                return Ok(Number::Flt(0.0));
+            }
+            if p != k {
+                swap_rows(&mut a, n, p, k);
+                det = -det;
+            }
+
+            det *= a[k * n + k];
+            for i in k + 1..n {
+                let factor = a[i * n + k] / a[k * n + k];
+                for j in k..n {
+                    a[i * n + j] -= factor * a[k * n + j];
+                }
+            }
+        }
+        Ok(Number::Flt(det))
+    }
+
+    /// Finds the inverse of a square [`Matrix`].
+    pub fn inverse(&self) -> Result<Matrix> {
+        if !self.is_square() {
+            return Err(EvalError::InvalidShape("inv", self.shape()));
+        }
+        self.solve(&Self::identity(self.rows))
+    }
+
+    /// Solves `self * x = b` for `x`, where `self` is square and `b` has as
+    /// many rows, solving for each column of `b` at once.
+    pub fn solve(&self, b: &Matrix) -> Result<Matrix> {
+        if !self.is_square() {
+            return Err(EvalError::InvalidShape("\\", self.shape()));
+        }
+        if self.rows != b.rows {
+            return Err(EvalError::ShapeMismatch(self.shape(), b.shape()));
+        }
+
+        // Pivots this much smaller than the largest element are taken to be
+        // zero, as they are most likely rounding errors.
+        let (n, m) = (self.rows, b.cols);
+        let mut a = self.floats();
+        let mut x = b.floats();
+        let scale = a.iter().fold(0.0, |max: f64, n| max.max(n.abs()));
+        let tolerance = n as f64 * f64::EPSILON * scale;
+
+        for k in 0..n {
+            let p = pivot(&a, n, k);
+            if a[p * n + k].abs() <= tolerance || !a[p * n + k].is_finite() {
+                return Err(EvalError::SingularMatrix);
+            }
+            swap_rows(&mut a, n, p, k);
+            swap_rows(&mut x, m, p, k);
+
+            // Scale the pivot row to 1, then clear the column elsewhere.
+            let inverse = a[k * n + k].recip();
+            a[k * n..(k + 1) * n].iter_mut().for_each(|v| *v *= inverse);
+            x[k * m..(k + 1) * m].iter_mut().for_each(|v| *v *= inverse);
+            for i in (0..n).filter(|&i| i != k) {
+                let factor = a[i * n + k];
+                for j in 0..n {
+                    a[i * n + j] -= factor * a[k * n + j];
+                }
+                for j in 0..m {
+                    x[i * m + j] -= factor * x[k * m + j];
+                }
+            }
+        }
+
+        Ok(Self {
+            rows: n,
+            cols: m,
+            elements: x.into_iter().map(Number::Flt).collect(),
+        })
+    }
+
+    fn floats(&self) -> Vec<f64> {
+        self.elements.iter().map(|&n| float(n)).collect()
+    }
+}
+
+impl Display for Matrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        for i in 0..self.rows {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "[")?;
+            for (j, n) in self.row(i).iter().enumerate() {
+                if j > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", n)?;
+            }
+            write!(f, "]")?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Finds the determinant of an integer matrix of size `n` with the Bareiss
+/// algorithm, where every division is exact.
+///
+/// Returns [`None`] if an intermediate value overflows.
+fn bareiss(n: usize, mut a: Vec<i128>) -> Option<i128> {
+    let mut sign = 1;
+    let mut previous = 1;
+    for k in 0..n {
+        // Any non-zero pivot will do, as there is no rounding.
+        let p = match (k..n).find(|&i| a[i * n + k] != 0) {
+            Some(p) => p,
+            None => return Some(0),
+        };
+        if p != k {
+            for j in 0..n {
+                a.swap(p * n + j, k * n + j);
+            }
+            sign = -sign;
+        }
+
+        for i in k + 1..n {
+            for j in k + 1..n {
+                let cross = a[i * n + j]
+                    .checked_mul(a[k * n + k])?
+                    .checked_sub(a[i * n + k].checked_mul(a[k * n + j])?)?;
+                a[i * n + j] = cross / previous;
+            }
+        }
+        previous = a[k * n + k];
+    }
+
+    match n {
+        0 => Some(1),
+        _ => a[n * n - 1].checked_mul(sign),
+    }
+}
+
+/// Finds the row at or below `k` with the largest element in column `k`.
+fn pivot(a: &[f64], n: usize, k: usize) -> usize {
+    (k..n)
+        .max_by(|&i, &j| a[i * n + k].abs().total_cmp(&a[j * n + k].abs()))
+        .unwrap_or(k)
+}
+
+fn swap_rows(a: &mut [f64], cols: usize, i: usize, j: usize) {
+    if i != j {
+        for k in 0..cols {
+            a.swap(i * cols + k, j * cols + k);
+        }
+    }
+}
+
+fn float(n: Number) -> f64 {
+    match n {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
@@ -0,0 +1,382 @@
+//! This module implements number theory on exact integers for
+//! [`tilted`](crate).
+//!
+//! Every function works on [`u128`], so that the magnitude of any [`i128`]
+//! fits, and never overflows: products modulo `m` are found by doubling and
+//! adding when they do not fit in 128 bits, and modular powers and inverses
+//! only ever hold numbers below `m`.
+//!
+//! Primality is tested with the Miller–Rabin test on the first 13 primes as
+//! bases, which is deterministic below 3.3 × 10²⁴. Larger numbers must also
+//! pass a strong Lucas test, which together with the base 2 makes up the
+//! Baillie–PSW test, to which no counterexample is known. Numbers are
+//! factorised by trial division by small primes, then with Brent's variant
+//! of Pollard's rho algorithm, which is fast unless a number has two or more
+//! prime factors above 10¹² or so.
+
+use std::cmp::Ordering;
+
+/// Primes used as bases of the Miller–Rabin test.
+const BASES: [u128; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+/// Smallest number for which the Miller–Rabin test on [`BASES`] is not known
+/// to be deterministic.
+const MILLER_RABIN_LIMIT: u128 = 3_317_044_064_679_887_385_961_981;
+
+/// Bound of the trial division, below which every factor is found by it.
+const TRIAL_LIMIT: u128 = 1000;
+
+/// Number of steps of Pollard's rho algorithm between gcds.
+const RHO_BATCH: usize = 128;
+
+/// Finds the greatest common divisor of two numbers, which is 0 only if both
+/// are 0.
+pub fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Finds the least common multiple of two numbers, or [`None`] if it
+/// overflows. It is 0 if either number is 0.
+pub fn lcm(a: u128, b: u128) -> Option<u128> {
+    match gcd(a, b) {
+        0 => Some(0),
+        g => (a / g).checked_mul(b),
+    }
+}
+
+/// Adds two numbers below `m` modulo `m`.
+fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    match a.checked_add(b) {
+        Some(sum) if sum < m => sum,
+        _ => a - (m - b),
+    }
+}
+
+/// Subtracts two numbers below `m` modulo `m`.
+fn sub_mod(a: u128, b: u128, m: u128) -> u128 {
+    match a.cmp(&b) {
+        Ordering::Less => a + (m - b),
+        _ => a - b,
+    }
+}
+
+/// Multiplies two numbers modulo `m`, which must not be 0.
+pub fn mul_mod(a: u128, b: u128, m: u128) -> u128 {
+    let (mut a, mut b) = (a % m, b % m);
+    if let Some(product) = a.checked_mul(b) {
+        return product % m;
+    }
+
+    // Double and add, as the product does not fit.
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            product = add_mod(product, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    product
+}
+
+/// Raises a number to a power modulo `m`, which must not be 0.
+pub fn pow_mod(base: u128, mut exponent: u128, m: u128) -> u128 {
+    let mut base = base % m;
+    let mut power = 1 % m;
+    while exponent != 0 {
+        if exponent & 1 == 1 {
+            power = mul_mod(power, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exponent >>= 1;
+    }
+    power
+}
+
+/// Finds the inverse of a number modulo `m`, i.e. `x` below `m` such that
+/// `a x = 1 (mod m)`, or [`None`] if `a` and `m` are not coprime or `m` is 0.
+pub fn inverse_mod(a: u128, m: u128) -> Option<u128> {
+    if m == 0 {
+        return None;
+    }
+
+    // The extended Euclidean algorithm, keeping the coefficients of `a`
+    // modulo `m`.
+    let (mut r0, mut r1) = (a % m, m);
+    let (mut s0, mut s1) = (1 % m, 0);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, sub_mod(s0, mul_mod(q, s1, m), m));
+    }
+
+    match r0 {
+        1 => Some(s0),
+        _ => None,
+    }
+}
+
+/// Checks if a number is prime.
+pub fn is_prime(n: u128) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for p in BASES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    // n - 1 = d 2^s, with d odd.
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    let strong_probable_prime = |a: u128| {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    };
+
+    BASES.into_iter().all(strong_probable_prime)
+        && (n < MILLER_RABIN_LIMIT || is_strong_lucas_probable_prime(n))
+}
+
+/// Performs the strong Lucas test with Selfridge's parameters on an odd
+/// number greater than 41.
+fn is_strong_lucas_probable_prime(n: u128) -> bool {
+    // There is no suitable parameter for squares.
+    let root = isqrt(n);
+    if root * root == n {
+        return false;
+    }
+
+    // The first of 5, -7, 9, -11, ... whose Jacobi symbol is -1, taken
+    // modulo n.
+    let mut k: u128 = 5;
+    let mut negative = false;
+    let d = loop {
+        let d = match negative {
+            true => n - k % n,
+            false => k % n,
+        };
+        match jacobi(d, n) {
+            -1 => break d,
+            0 if !k.is_multiple_of(n) => return false,
+            _ => (k, negative) = (k + 2, !negative),
+        }
+    };
+
+    // P = 1 and Q = (1 - D) / 4.
+    let q = match negative {
+        true => ((k + 1) / 4) % n,
+        false => n - ((k - 1) / 4) % n,
+    };
+
+    // Halves a number modulo n, which is odd.
+    let half = |x: u128| match x & 1 {
+        0 => x / 2,
+        _ => x / 2 + n / 2 + 1,
+    };
+
+    // n + 1 = e 2^s, with e odd. n is odd and less than u128::MAX, so n + 1
+    // does not overflow.
+    let s = (n + 1).trailing_zeros();
+    let e = (n + 1) >> s;
+
+    // U_e and V_e, from the most significant bit of e.
+    let (mut u, mut v, mut qk) = (1, 1, q);
+    for bit in (0..127 - e.leading_zeros()).rev() {
+        u = mul_mod(u, v, n);
+        v = sub_mod(mul_mod(v, v, n), add_mod(qk, qk, n), n);
+        qk = mul_mod(qk, qk, n);
+        if e >> bit & 1 == 1 {
+            (u, v) = (
+                half(add_mod(u, v, n)),
+                half(add_mod(mul_mod(d, u, n), v, n)),
+            );
+            qk = mul_mod(qk, q, n);
+        }
+    }
+    if u == 0 || v == 0 {
+        return true;
+    }
+
+    // V_{e 2^r} for r < s.
+    for _ in 1..s {
+        v = sub_mod(mul_mod(v, v, n), add_mod(qk, qk, n), n);
+        if v == 0 {
+            return true;
+        }
+        qk = mul_mod(qk, qk, n);
+    }
+    false
+}
+
+/// Finds the Jacobi symbol `(a / n)` for an odd `n`.
+fn jacobi(mut a: u128, mut n: u128) -> i8 {
+    let mut sign = 1;
+    a %= n;
+    while a != 0 {
+        while a.is_multiple_of(2) {
+            a /= 2;
+            if n % 8 == 3 || n % 8 == 5 {
+                sign = -sign;
+            }
+        }
+        (a, n) = (n, a);
+        if a % 4 == 3 && n % 4 == 3 {
+            sign = -sign;
+        }
+        a %= n;
+    }
+    match n {
+        1 => sign,
+        _ => 0,
+    }
+}
+
+/// Finds the integer square root of a number, rounded down.
+fn isqrt(n: u128) -> u128 {
+    // A float estimate, corrected by Newton's method.
+    let mut x = (n as f64).sqrt() as u128;
+    loop {
+        match x.checked_mul(x) {
+            Some(square) if square > n => x = (x + n / x) / 2,
+            Some(_) => break,
+            None => x -= 1,
+        }
+    }
+    while (x + 1).checked_mul(x + 1).is_some_and(|square| square <= n) {
+        x += 1;
+    }
+    x
+}
+
+/// Finds the smallest prime greater than a number, or [`None`] if it does
+/// not fit.
+pub fn next_prime(n: u128) -> Option<u128> {
+    if n < 2 {
+        return Some(2);
+    }
+
+    // Odd numbers only.
+    let mut candidate = (n + 1) | 1;
+    while !is_prime(candidate) {
+        candidate = candidate.checked_add(2)?;
+    }
+    Some(candidate)
+}
+
+/// Factorises a number into primes, in ascending order, each with its
+/// multiplicity. 0 and 1 have no prime factors.
+pub fn factorise(mut n: u128) -> Vec<(u128, u32)> {
+    let mut factors = Vec::new();
+    if n == 0 {
+        return factors;
+    }
+
+    // Trial division by 2 and odd numbers.
+    let mut divide = |n: &mut u128, p: u128| {
+        let mut multiplicity = 0;
+        while n.is_multiple_of(p) {
+            *n /= p;
+            multiplicity += 1;
+        }
+        if multiplicity > 0 {
+            factors.push((p, multiplicity));
+        }
+    };
+    divide(&mut n, 2);
+    for p in (3..TRIAL_LIMIT).step_by(2) {
+        divide(&mut n, p);
+    }
+
+    // The remaining factors are above the bound of the trial division.
+    let mut primes = Vec::new();
+    let mut composites = match n {
+        1 => vec![],
+        _ => vec![n],
+    };
+    while let Some(n) = composites.pop() {
+        match is_prime(n) || n < TRIAL_LIMIT * TRIAL_LIMIT {
+            true => primes.push(n),
+            false => {
+                let d = rho(n);
+                composites.push(d);
+                composites.push(n / d);
+            }
+        }
+    }
+    primes.sort_unstable();
+    for run in primes.chunk_by(|a, b| a == b) {
+        factors.push((run[0], run.len() as u32));
+    }
+    factors
+}
+
+/// Finds a proper divisor of an odd composite number with Brent's variant of
+/// Pollard's rho algorithm.
+fn rho(n: u128) -> u128 {
+    // Each constant gives another sequence x² + c, in case one fails.
+    for c in 1.. {
+        let f = |x: u128| add_mod(mul_mod(x, x, n), c, n);
+        let (mut x, mut y, mut ys) = (2, 2, 2);
+        let mut product = 1;
+        let mut length = 1;
+        let mut divisor = 1;
+
+        while divisor == 1 {
+            x = y;
+            for _ in 0..length {
+                y = f(y);
+            }
+
+            // The differences are multiplied together, so that a gcd is only
+            // found once per batch of steps.
+            let mut steps = 0;
+            while steps < length && divisor == 1 {
+                ys = y;
+                for _ in 0..RHO_BATCH.min(length - steps) {
+                    y = f(y);
+                    product = mul_mod(product, x.abs_diff(y), n);
+                }
+                divisor = gcd(product, n);
+                steps += RHO_BATCH;
+            }
+            length *= 2;
+        }
+
+        // The batch overshot, so it is walked again one step at a time.
+        if divisor == n {
+            loop {
+                ys = f(ys);
+                divisor = gcd(x.abs_diff(ys), n);
+                if divisor != 1 {
+                    break;
+                }
+            }
+        }
+        if divisor != n {
+            return divisor;
+        }
+    }
+    unreachable!("every constant failed to factorise {}", n)
+}
+
+/// Finds Euler's totient of a number, i.e. how many numbers from 1 to `n`
+/// are coprime to it.
+pub fn totient(n: u128) -> u128 {
+    factorise(n)
+        .into_iter()
+        .fold(n, |phi, (p, _)| phi / p * (p - 1))
+}
@@ -3,28 +3,44 @@
 //! A parser's job is to take in a stream of [`Token`] and produce an Abstract
 //! Syntax Tree. The AST can be used to generate code or evaluate in the future.
 
+use std::ops::RangeInclusive;
+
 use crate::{
-    eof, BinaryAction, BinaryNode, Lexer, NodeBox, Number, Operator, ParseError, PlainNode,
-    TilError, Token, TokenKind, UnaryAction, UnaryNode,
+    ast::{polynomial::map_children, Node, NodeView},
+    eof, BinaryAction, BinaryNode, BinderAction, BinderNode, Builtin, CallNode, Constant, Guess,
+    LambdaNode, Lexer, ListNode, NodeBox, Number, Operator, ParseError, PlainNode, RangeNode,
+    SolveNode, Span, TilError, Token, TokenKind, TokenStream, UnaryAction, UnaryNode, Unit,
+    UnitNode, VariableNode, VectorNode,
 };
 
 pub type Result<T> = std::result::Result<T, TilError>;
 
 #[derive(Debug)]
 pub struct Parser {
-    /// A [`Lexer`] used to retrieve tokens.
-    lexer: Lexer,
+    /// A [`TokenStream`] used to retrieve tokens, which can also be looked
+    /// ahead at beyond the current token.
+    tokens: TokenStream,
 
-    /// The current token, allowing look-ahead once.
+    /// The current token, i.e. the next one to be consumed.
     current_token: Token,
+
+    /// The last consumed token, marking the end of the [`Span`] of a node.
+    previous_token: Token,
 }
 
 impl Parser {
     /// Creates a new [`Parser`] from a [`Lexer`].
     pub fn from_lexer(lexer: Lexer) -> Parser {
+        Self::from_tokens(TokenStream::new(lexer))
+    }
+
+    /// Creates a new [`Parser`] from a [`TokenStream`], parsing from its
+    /// current position.
+    pub fn from_tokens(tokens: TokenStream) -> Parser {
         Self {
-            lexer,
+            tokens,
             current_token: eof!(0),
+            previous_token: eof!(0),
         }
     }
 
@@ -32,14 +48,106 @@ impl Parser {
     pub fn parse(&mut self) -> Result<NodeBox> {
         self.lex_and_store()?;
 
-        self.parse_expr()
+        let expr = self.parse_equation()?;
+
+        // Expect the whole input to be consumed.
+        match self.current_token.kind {
+            TokenKind::Eof => Ok(expr),
+            TokenKind::RightParen => {
+                Err(ParseError::MismatchRightParen(self.current_token.span.start_index).into())
+            }
+            TokenKind::RightBracket => {
+                Err(ParseError::MismatchRightBracket(self.current_token.span.start_index).into())
+            }
+            TokenKind::RightBrace => {
+                Err(ParseError::MismatchRightBrace(self.current_token.span.start_index).into())
+            }
+            _ => Err(ParseError::OperatorExpected(self.current_token).into()),
+        }
     }
 
     /// Production:
     /// ```text
-    /// expr = term ([+-] term)*
+    /// equation = range (= range)?
+    /// ```
+    fn parse_equation(&mut self) -> Result<NodeBox> {
+        let start_index = self.current_token.span.start_index;
+
+        // Get the left-hand side.
+        let left = self.parse_range()?;
+        if self.current_token.kind != TokenKind::Op(Operator::Equal) {
+            return Ok(left);
+        }
+
+        // Consume operator.
+        self.lex_and_store()?;
+
+        // Get the right-hand side.
+        let right = self.parse_range()?;
+
+        Ok(self.spanned(
+            Box::new(BinaryNode::new(left, BinaryAction::Equate, right)),
+            start_index,
+        ))
+    }
+
+    /// Production:
+    /// ```text
+    /// range = conversion (.. conversion)?
+    /// ```
+    fn parse_range(&mut self) -> Result<NodeBox> {
+        let start_index = self.current_token.span.start_index;
+
+        // Get the start.
+        let start = self.parse_conversion()?;
+        if self.current_token.kind != TokenKind::Op(Operator::DotDot) {
+            return Ok(start);
+        }
+
+        // Consume operator.
+        self.lex_and_store()?;
+
+        // Get the end.
+        let end = self.parse_conversion()?;
+
+        Ok(self.spanned(Box::new(RangeNode::new(start, end)), start_index))
+    }
+
+    /// Production:
+    /// ```text
+    /// conversion = expr (to expr)*
+    /// ```
+    fn parse_conversion(&mut self) -> Result<NodeBox> {
+        let start_index = self.current_token.span.start_index;
+
+        // Get the quantity.
+        let mut expr = self.parse_expr()?;
+
+        // Loop to get all units to convert to.
+        while self.current_token.kind == TokenKind::Op(Operator::To) {
+            // Consume keyword.
+            self.lex_and_store()?;
+
+            // Get the unit.
+            let unit = self.parse_expr()?;
+
+            // Create a new node.
+            expr = self.spanned(
+                Box::new(BinaryNode::new(expr, BinaryAction::Convert, unit)),
+                start_index,
+            );
+        }
+
+        Ok(expr)
+    }
+
+    /// Production:
+    /// ```text
+    /// expr = term ([+-±] term)*
     /// ```
     fn parse_expr(&mut self) -> Result<NodeBox> {
+        let start_index = self.current_token.span.start_index;
+
         // Get the first term.
         let mut term = self.parse_term()?;
 
@@ -55,6 +163,7 @@ impl Parser {
             let actor = match operator {
                 Operator::Plus => BinaryAction::Add,
                 Operator::Minus => BinaryAction::Sub,
+                Operator::PlusMinus => BinaryAction::PlusMinus,
                 _ => return Ok(term),
             };
 
@@ -65,15 +174,20 @@ impl Parser {
             let next_term = self.parse_term()?;
 
             // Create a new node.
-            term = Box::new(BinaryNode::new(term, actor, next_term));
+            term = self.spanned(
+                Box::new(BinaryNode::new(term, actor, next_term)),
+                start_index,
+            );
         }
     }
 
     /// Production:
     /// ```text
-    /// term = factor ([*/] factor)*
+    /// term = factor ([*/\\] factor)*
     /// ```
     fn parse_term(&mut self) -> Result<NodeBox> {
+        let start_index = self.current_token.span.start_index;
+
         // Get the first factor.
         let mut factor = self.parse_factor()?;
 
@@ -85,6 +199,7 @@ impl Parser {
                     let a = match op {
                         Operator::Star => BinaryAction::Mul,
                         Operator::Slash => BinaryAction::Div,
+                        Operator::Backslash => BinaryAction::LeftDiv,
                         _ => return Ok(factor),
                     };
 
@@ -99,6 +214,10 @@ impl Parser {
                 TokenKind::LeftParen => BinaryAction::Mul,
                 // 2. Function.
                 TokenKind::Func(_) => BinaryAction::Mul,
+                // 3. Variable.
+                TokenKind::Ident => BinaryAction::Mul,
+                // 4. Unit.
+                TokenKind::Unit => BinaryAction::Mul,
 
                 _ => return Ok(factor),
             };
@@ -107,7 +226,10 @@ impl Parser {
             let next_factor = self.parse_factor()?;
 
             // Create a new node.
-            factor = Box::new(BinaryNode::new(factor, actor, next_factor));
+            factor = self.spanned(
+                Box::new(BinaryNode::new(factor, actor, next_factor)),
+                start_index,
+            );
         }
     }
 
@@ -115,20 +237,18 @@ impl Parser {
     /// ```text
     /// factor = [+-]* pow
     /// ```
+    ///
+    /// Each minus sign negates what follows it, so `- -x` is `-(-x)`. A
+    /// negated number is folded into a negative constant, unless it is raised
+    /// to a power: `-2` is the number -2, but `-2^2` is `-(2^2)`.
     fn parse_factor(&mut self) -> Result<NodeBox> {
-        // Check for unary operator(s).
-        let mut actor = UnaryAction::Iden;
+        // Check for unary operator(s), keeping where each minus sign starts.
+        let mut minuses = Vec::new();
         loop {
             match self.current_token.kind {
                 TokenKind::Op(c) => match c {
                     Operator::Plus => (),
-                    Operator::Minus => {
-                        if actor == UnaryAction::Iden {
-                            actor = UnaryAction::Neg;
-                        } else {
-                            actor = UnaryAction::Iden;
-                        }
-                    }
+                    Operator::Minus => minuses.push(self.current_token.span.start_index),
 
                     // Invalid unary operator.
                     _ => return Err(ParseError::InvalidUnaryOperator(self.current_token).into()),
@@ -144,24 +264,78 @@ impl Parser {
             self.lex_and_store()?;
         }
 
-        // Parse atomic.
-        let operand = self.parse_pow()?;
+        // Fold negative numbers into the innermost minus sign.
+        let mut operand = match minuses.pop() {
+            Some(start_index) => match self.parse_negative_number(start_index)? {
+                Some(number) => number,
+                None => {
+                    let operand = self.parse_pow()?;
+                    let neg = Box::new(UnaryNode::new(UnaryAction::Neg, operand));
+                    self.spanned(neg, start_index)
+                }
+            },
+            None => return self.parse_pow(),
+        };
 
-        if actor == UnaryAction::Iden {
-            Ok(operand)
-        } else {
-            Ok(Box::new(UnaryNode::new(actor, operand)))
+        for start_index in minuses.into_iter().rev() {
+            let neg = Box::new(UnaryNode::new(UnaryAction::Neg, operand));
+            operand = self.spanned(neg, start_index);
         }
+        Ok(operand)
     }
 
     /// Production:
     /// ```text
-    /// pow = atomic (^ atomic)?
+    /// pow = atomic (^ atomic)* | quantity
     /// ```
     fn parse_pow(&mut self) -> Result<NodeBox> {
+        let start_index = self.current_token.span.start_index;
+        let number = matches!(
+            self.current_token.kind,
+            TokenKind::Int(_) | TokenKind::Flt(_)
+        );
+
         // Parse base.
         let base = self.parse_atomic()?;
 
+        // A number not raised to a power may be followed by units.
+        if number && self.current_token.kind != TokenKind::Op(Operator::Caret) {
+            return self.parse_units(base, start_index);
+        }
+
+        self.parse_exponent(base, start_index)
+    }
+
+    /// Production:
+    /// ```text
+    /// quantity = number (Unit (^ atomic)*)*
+    /// ```
+    ///
+    /// Parses the units following a number starting at `start_index`. Units
+    /// bind to the number more tightly than multiplication and division, so
+    /// `3 m / 2 s` is `(3 m) / (2 s)`.
+    fn parse_units(&mut self, mut quantity: NodeBox, start_index: usize) -> Result<NodeBox> {
+        while self.current_token.kind == TokenKind::Unit {
+            let unit_index = self.current_token.span.start_index;
+
+            // Parse unit and its exponent.
+            let unit = self.parse_atomic()?;
+            let unit = self.parse_exponent(unit, unit_index)?;
+
+            // Create a new node.
+            quantity = self.spanned(
+                Box::new(BinaryNode::new(quantity, BinaryAction::Mul, unit)),
+                start_index,
+            );
+        }
+
+        Ok(quantity)
+    }
+
+    /// Parses the optional exponents of a `pow` production, given its base
+    /// starting at `start_index`. Powers are right-associative, so `2^3^2`
+    /// is `2^(3^2)`.
+    fn parse_exponent(&mut self, base: NodeBox, start_index: usize) -> Result<NodeBox> {
         // Check for exponentiation.
         if self.current_token.kind != TokenKind::Op(Operator::Caret) {
             return Ok(base);
@@ -170,38 +344,158 @@ impl Parser {
         // Consume operator.
         self.lex_and_store()?;
 
-        // Parse exponent.
+        // Parse exponent, which may be raised to a power itself.
+        let exponent_index = self.current_token.span.start_index;
         let exponent = self.parse_atomic()?;
+        let exponent = self.parse_exponent(exponent, exponent_index)?;
 
         // Create a new node.
-        Ok(Box::new(BinaryNode::new(base, BinaryAction::Pow, exponent)))
+        let pow = Box::new(BinaryNode::new(base, BinaryAction::Pow, exponent));
+        Ok(self.spanned(pow, start_index))
+    }
+
+    /// Parses a number following a minus sign at `start_index`, returning the
+    /// negative constant.
+    ///
+    /// Returns [`None`] if the current token is not a number, in which case
+    /// nothing is consumed. If the number is raised to a power, the negation
+    /// of the power is returned instead.
+    fn parse_negative_number(&mut self, start_index: usize) -> Result<Option<NodeBox>> {
+        let token = self.current_token;
+        let negative = match token.kind {
+            TokenKind::Flt(f) => Number::Flt(-f),
+            TokenKind::Int(i) => match 0i128.checked_sub_unsigned(i) {
+                Some(i) => Number::Int(i),
+                None => return Err(ParseError::IntegerOverflow(token).into()),
+            },
+            _ => return Ok(None),
+        };
+
+        // Consume number.
+        self.lex_and_store()?;
+
+        if self.current_token.kind != TokenKind::Op(Operator::Caret) {
+            let negative = self.spanned(Box::new(PlainNode::new(negative)), start_index);
+            return self.parse_units(negative, start_index).map(Some);
+        }
+
+        let mut base: NodeBox = Box::new(PlainNode::new(Self::number(token)?));
+        base.set_span(Some(token.span));
+
+        let pow = self.parse_exponent(base, token.span.start_index)?;
+        let neg = Box::new(UnaryNode::new(UnaryAction::Neg, pow));
+        Ok(Some(self.spanned(neg, start_index)))
+    }
+
+    /// Looks up a named constant, which cannot be used as a variable.
+    fn constant(name: &str) -> Option<Constant> {
+        Constant::from_name(name)
+    }
+
+    /// Converts a number token to a [`Number`].
+    fn number(token: Token) -> Result<Number> {
+        match token.kind {
+            TokenKind::Flt(f) => Ok(Number::Flt(f)),
+            TokenKind::Int(i) => i128::try_from(i)
+                .map(Number::Int)
+                .map_err(|_| ParseError::IntegerOverflow(token).into()),
+            _ => Err(ParseError::NumberExpected(token).into()),
+        }
     }
 
     /// Production:
     /// ```text
-    /// atomic = Int | Flt | paren_expr | Func paren_expr
+    /// atomic = Int | Flt | Ident | Unit | paren_expr | vector | list | lambda
+    ///        | call | Func paren_expr | "√" atomic
     /// ```
     fn parse_atomic(&mut self) -> Result<NodeBox> {
+        let start_index = self.current_token.span.start_index;
+
         // Match the next token.
-        let node: NodeBox = match self.current_token.kind {
+        let mut node: NodeBox = match self.current_token.kind {
             // Numbers (parse_numbers is merged here).
-            TokenKind::Flt(f) => Box::new(PlainNode::new(Number::Flt(f))),
-            TokenKind::Int(i) => Box::new(PlainNode::new(Number::Int(i as i128))),
+            TokenKind::Flt(_) | TokenKind::Int(_) => {
+                Box::new(PlainNode::new(Self::number(self.current_token)?))
+            }
+
+            // Variables, constants, built-in functions and lambdas.
+            // Return immediately as the next token is needed to tell them apart.
+            TokenKind::Ident => {
+                let name = self.tokens[self.current_token.span].to_string();
+
+                // Consume identifier.
+                self.lex_and_store()?;
+
+                if self.current_token.kind == TokenKind::Op(Operator::Arrow)
+                    && Self::constant(&name).is_none()
+                {
+                    return self.parse_lambda(vec![name], start_index);
+                }
+
+                if self.current_token.kind == TokenKind::LeftParen {
+                    if let Some(node) = self.parse_call(&name)? {
+                        return Ok(self.spanned(node, start_index));
+                    }
+                }
+
+                let var: NodeBox = match Self::constant(&name) {
+                    Some(constant) => Box::new(PlainNode::from_constant(constant)),
+                    None => Box::new(VariableNode::new(name)),
+                };
+                return Ok(self.spanned(var, start_index));
+            }
+
+            // Units.
+            TokenKind::Unit => {
+                // A unit name may be the parameter of a lambda, e.g. `s -> s^2`.
+                if self.tokens.peek_nth(0)?.kind == TokenKind::Op(Operator::Arrow) {
+                    let name = self.tokens[self.current_token.span].to_string();
+                    self.lex_and_store()?;
+                    return self.parse_lambda(vec![name], start_index);
+                }
+
+                let unit = Unit::lookup(&self.tokens[self.current_token.span])
+                    .ok_or(ParseError::InternalError("Unable to look up unit"))?;
+                Box::new(UnitNode::new(unit))
+            }
 
             // Parenthesised expressions.
             // Return immediately to avoid consumption of current token.
             TokenKind::LeftParen => return self.parse_paren_expr(),
 
+            // Vectors and matrices.
+            // Return immediately to avoid consumption of current token.
+            TokenKind::LeftBracket => return self.parse_vector(),
+
+            // Lists.
+            // Return immediately to avoid consumption of current token.
+            TokenKind::LeftBrace => return self.parse_list(),
+
             // Functions.
+            // Return immediately as the parenthesised expression is consumed.
             TokenKind::Func(func) => {
                 // Consume function.
+                let radical = &self.tokens[self.current_token.span] == "√";
                 self.lex_and_store()?;
 
+                // A radical sign applies to the atomic after it, e.g. `√2`.
+                if radical && self.current_token.kind != TokenKind::LeftParen {
+                    let radicand = self.parse_atomic()?;
+                    let call = Box::new(UnaryNode::new(UnaryAction::Func(func), radicand));
+                    return Ok(self.spanned(call, start_index));
+                }
+
+                // Expect a left parenthesis.
+                if self.current_token.kind != TokenKind::LeftParen {
+                    return Err(ParseError::LeftParenExpected(self.current_token).into());
+                }
+
                 // Parse parenthesised expression.
                 let expr = self.parse_paren_expr()?;
 
                 // Create a new node.
-                Box::new(UnaryNode::new(UnaryAction::Func(func), expr))
+                let call = Box::new(UnaryNode::new(UnaryAction::Func(func), expr));
+                return Ok(self.spanned(call, start_index));
             }
 
             // Invalid unary operators, valid ones were handled up top.
@@ -213,6 +507,18 @@ impl Parser {
             TokenKind::Eof => return Err(ParseError::UnexpectedEOF.into()),
 
             // Invalid:
+            // RightBrace: Unmatched left brace.
+            TokenKind::RightBrace => {
+                return Err(
+                    ParseError::MismatchRightBrace(self.current_token.span.start_index).into(),
+                )
+            }
+            // RightBracket: Unmatched left bracket.
+            TokenKind::RightBracket => {
+                return Err(
+                    ParseError::MismatchRightBracket(self.current_token.span.start_index).into(),
+                )
+            }
             // RightParen: Unmatched left parenthesis.
             _ => {
                 return Err(
@@ -222,6 +528,7 @@ impl Parser {
         };
 
         // Consume token.
+        node.set_span(Some(self.current_token.span));
         self.lex_and_store()?;
 
         Ok(node)
@@ -229,21 +536,36 @@ impl Parser {
 
     /// Production:
     /// ```text
-    /// paren_expr = LeftParen expr RightParen
+    /// paren_expr = LeftParen equation RightParen
     /// ```
+    ///
+    /// The [`Span`] of the expression is widened to include the parentheses.
+    /// Parenthesised parameters followed by an arrow are parsed as a lambda,
+    /// i.e. `(a, b) -> body`, which is told apart by looking ahead.
     fn parse_paren_expr(&mut self) -> Result<NodeBox> {
+        let start_index = self.current_token.span.start_index;
+
         // Expect a left parenthesis.
         if self.current_token.kind != TokenKind::LeftParen {
             unreachable!()
         }
 
+        if let Some(params) = self.peek_params()? {
+            // Consume left parenthesis, parameters, commas and right
+            // parenthesis.
+            for _ in 0..=2 * params.len() {
+                self.lex_and_store()?;
+            }
+            return self.parse_lambda(params, start_index);
+        }
+
         // Consume left parenthesis.
         self.lex_and_store()?;
 
         // Parse expression.
         // Errors need to be return immediately as the lexer might be in an
         // unusable state.
-        let expr = self.parse_expr()?;
+        let expr = self.parse_equation()?;
 
         // Expect a right parenthesis.
         if self.current_token.kind != TokenKind::RightParen {
@@ -253,12 +575,292 @@ impl Parser {
         // Consume right parenthesis.
         self.lex_and_store()?;
 
-        Ok(expr)
+        Ok(self.spanned(expr, start_index))
+    }
+
+    /// Looks ahead of a left parenthesis for the parameters of a lambda, i.e.
+    /// `Ident (Comma Ident)* RightParen Arrow`, without consuming anything.
+    /// Unit names such as `s` are accepted as identifiers.
+    ///
+    /// Returns [`None`] if the tokens are not parameters, e.g. `(x + 1)` or
+    /// `(x)` without an arrow. Several parameters must be followed by an
+    /// arrow, and commas by a parameter.
+    fn peek_params(&mut self) -> Result<Option<Vec<String>>> {
+        let mut params = Vec::new();
+        let mut k = 0;
+        loop {
+            let token = self.tokens.peek_nth(k)?;
+            let name = &self.tokens[token.span];
+            let is_param = match token.kind {
+                TokenKind::Ident => Self::constant(name).is_none(),
+                TokenKind::Unit => true,
+                _ => false,
+            };
+            if !is_param {
+                return match params.is_empty() {
+                    true => Ok(None),
+                    false => Err(ParseError::VariableExpected(token.span.start_index).into()),
+                };
+            }
+            params.push(name.to_string());
+
+            match self.tokens.peek_nth(k + 1)?.kind {
+                TokenKind::Comma => k += 2,
+                TokenKind::RightParen => break,
+                _ => return Ok(None),
+            }
+        }
+
+        match self.tokens.peek_nth(k + 2)? {
+            token if token.kind == TokenKind::Op(Operator::Arrow) => Ok(Some(params)),
+            token if params.len() > 1 => Err(ParseError::ArrowExpected(token).into()),
+            _ => Ok(None),
+        }
+    }
+
+    /// Production:
+    /// ```text
+    /// lambda = (param | LeftParen param (Comma param)* RightParen) Arrow equation
+    /// param = Ident | Unit
+    /// ```
+    ///
+    /// Parses the body of a lambda after its parameters, which start at
+    /// `start_index`. The body extends as far as possible, so `x -> x + 1` is
+    /// `x -> (x + 1)`.
+    fn parse_lambda(&mut self, params: Vec<String>, start_index: usize) -> Result<NodeBox> {
+        // Consume arrow.
+        self.lex_and_store()?;
+
+        let body = params.iter().fold(self.parse_equation()?, |body, param| {
+            bind(body.as_ref(), param)
+        });
+        Ok(self.spanned(Box::new(LambdaNode::new(params, body)), start_index))
+    }
+
+    /// Production:
+    /// ```text
+    /// vector = LeftBracket (equation (Comma equation)*)? RightBracket
+    /// ```
+    ///
+    /// A vector whose elements are vectors is a matrix, with them as rows.
+    fn parse_vector(&mut self) -> Result<NodeBox> {
+        let start_index = self.current_token.span.start_index;
+
+        // Consume left bracket.
+        self.lex_and_store()?;
+
+        let mut elements = Vec::new();
+        if self.current_token.kind != TokenKind::RightBracket {
+            loop {
+                elements.push(self.parse_equation()?);
+
+                match self.current_token.kind {
+                    TokenKind::Comma => self.lex_and_store()?,
+                    TokenKind::RightBracket => break,
+                    _ => return Err(ParseError::RightBracketExpected(self.current_token).into()),
+                };
+            }
+        }
+
+        // Consume right bracket.
+        self.lex_and_store()?;
+
+        Ok(self.spanned(Box::new(VectorNode::new(elements)), start_index))
+    }
+
+    /// Production:
+    /// ```text
+    /// list = LeftBrace (equation (Comma equation)*)? RightBrace
+    /// ```
+    fn parse_list(&mut self) -> Result<NodeBox> {
+        let start_index = self.current_token.span.start_index;
+
+        // Consume left brace.
+        self.lex_and_store()?;
+
+        let mut elements = Vec::new();
+        if self.current_token.kind != TokenKind::RightBrace {
+            loop {
+                elements.push(self.parse_equation()?);
+
+                match self.current_token.kind {
+                    TokenKind::Comma => self.lex_and_store()?,
+                    TokenKind::RightBrace => break,
+                    _ => return Err(ParseError::RightBraceExpected(self.current_token).into()),
+                };
+            }
+        }
+
+        // Consume right brace.
+        self.lex_and_store()?;
+
+        Ok(self.spanned(Box::new(ListNode::new(elements)), start_index))
+    }
+
+    /// Production:
+    /// ```text
+    /// call = Ident args
+    /// ```
+    ///
+    /// Returns [`None`] if the identifier is not a built-in function, in which
+    /// case nothing is consumed.
+    fn parse_call(&mut self, name: &str) -> Result<Option<NodeBox>> {
+        let node: NodeBox = match name {
+            // Symbolic differentiation, i.e. diff(expr, var), which is kept
+            // as a call until it is evaluated.
+            "diff" => {
+                let mut args = self.parse_args("diff", 2..=2)?;
+                let (index, var) = args.pop().unwrap();
+                let (_, expr) = args.pop().unwrap();
+
+                let name = Self::bound_var(var.as_ref(), index)?;
+                let args = vec![bind(expr.as_ref(), &name), bind(var.as_ref(), &name)];
+                Box::new(CallNode::new(Builtin::Diff, args))
+            }
+
+            // Collection of terms by powers of a variable, i.e.
+            // collect(expr, var), which is kept as a call like diff.
+            "collect" => {
+                let mut args = self.parse_args("collect", 2..=2)?;
+                let (index, var) = args.pop().unwrap();
+                let (_, expr) = args.pop().unwrap();
+
+                let name = Self::bound_var(var.as_ref(), index)?;
+                let args = vec![bind(expr.as_ref(), &name), bind(var.as_ref(), &name)];
+                Box::new(CallNode::new(Builtin::Collect, args))
+            }
+
+            // Sums, products and integrals, e.g. sum(expr, var, lower, upper).
+            "integrate" | "sum" | "prod" => {
+                let actor = match name {
+                    "integrate" => BinderAction::Integrate,
+                    "sum" => BinderAction::Sum,
+                    _ => BinderAction::Prod,
+                };
+
+                let mut args = self.parse_args(actor.name(), 4..=4)?;
+                let (_, upper) = args.pop().unwrap();
+                let (_, lower) = args.pop().unwrap();
+                let (index, var) = args.pop().unwrap();
+                let (_, body) = args.pop().unwrap();
+
+                let var = Self::bound_var(var.as_ref(), index)?;
+
+                let body = bind(body.as_ref(), &var);
+                Box::new(BinderNode::new(actor, var, body, lower, upper))
+            }
+
+            // Roots of equations, i.e. solve(equation, var) with an optional
+            // guess or interval.
+            "solve" => {
+                let mut args = self.parse_args("solve", 2..=4)?.into_iter();
+                let (_, equation) = args.next().unwrap();
+                let (index, var) = args.next().unwrap();
+
+                let var = Self::bound_var(var.as_ref(), index)?;
+
+                let guess = match (args.next(), args.next()) {
+                    (None, _) => Guess::None,
+                    (Some((_, x)), None) => Guess::Point(x),
+                    (Some((_, lower)), Some((_, upper))) => Guess::Interval(lower, upper),
+                };
+
+                let equation = bind(equation.as_ref(), &var);
+                Box::new(SolveNode::new(equation, var, guess))
+            }
+
+            // Other built-in functions, e.g. det(A), dot(v, w) and rand().
+            _ => match Builtin::try_from(name) {
+                Ok(func) => {
+                    let args = self.parse_args(func.name(), func.arity())?;
+                    let args = args.into_iter().map(|(_, arg)| arg).collect();
+                    Box::new(CallNode::new(func, args))
+                }
+                Err(_) => return Ok(None),
+            },
+        };
+
+        Ok(Some(node))
+    }
+
+    /// Production:
+    /// ```text
+    /// args = LeftParen (equation (Comma equation)*)? RightParen
+    /// ```
+    ///
+    /// Each argument is returned with the index it starts at. If the number
+    /// of arguments is not within `count`, the nearest valid number is
+    /// reported as expected.
+    fn parse_args(
+        &mut self,
+        name: &'static str,
+        count: RangeInclusive<usize>,
+    ) -> Result<Vec<(usize, NodeBox)>> {
+        // Consume left parenthesis.
+        self.lex_and_store()?;
+
+        let mut args = Vec::new();
+        if self.current_token.kind != TokenKind::RightParen {
+            loop {
+                let index = self.current_token.span.start_index;
+                args.push((index, self.parse_equation()?));
+
+                match self.current_token.kind {
+                    TokenKind::Comma => self.lex_and_store()?,
+                    TokenKind::RightParen => break,
+                    _ => return Err(ParseError::RightParenExpected(self.current_token).into()),
+                };
+            }
+        }
+
+        // Consume right parenthesis.
+        self.lex_and_store()?;
+
+        if !count.contains(&args.len()) {
+            let expected = args.len().clamp(*count.start(), *count.end());
+            return Err(ParseError::ArgumentCountMismatch(name, expected, args.len()).into());
+        }
+
+        Ok(args)
     }
 
     fn lex_and_store(&mut self) -> Result<Token> {
-        let token = self.lexer.lex()?;
-        self.current_token = token;
+        let token = self.tokens.next_token()?;
+        self.previous_token = std::mem::replace(&mut self.current_token, token);
         Ok(token)
     }
+
+    /// Gets the name of the variable bound by a lambda, a binder, `solve`,
+    /// `diff` or `collect`, given as the argument at `index`. A unit is taken
+    /// as a variable of the same name, so that e.g. `s` can be bound.
+    fn bound_var(node: &dyn Node, index: usize) -> Result<String> {
+        match node.view() {
+            NodeView::Variable(v) => Ok(v.name().to_string()),
+            NodeView::Unit(u) => Ok(u.unit().name().to_string()),
+            _ => Err(ParseError::VariableExpected(index).into()),
+        }
+    }
+
+    /// Sets the [`Span`] of a node, from `start_index` to the end of the last
+    /// consumed token.
+    fn spanned(&self, mut node: NodeBox, start_index: usize) -> NodeBox {
+        node.set_span(Some(Span {
+            start_index,
+            end_index: self.previous_token.span.end_index,
+        }));
+        node
+    }
+}
+
+/// Turns the units named `var` in `node` into variables, where `var` is bound
+/// by a lambda, a binder, `solve`, `diff` or `collect`, e.g. `s` in
+/// `integrate(s, s, 0, 1)`. Spans are kept.
+fn bind(node: &dyn Node, var: &str) -> NodeBox {
+    let mut bound = match node.view() {
+        NodeView::Unit(u) if u.unit().name() == var => Box::new(VariableNode::new(var)),
+        NodeView::Plain(_) | NodeView::Variable(_) | NodeView::Unit(_) => return node.clone_box(),
+        _ => map_children(node, &|child| bind(child, var)),
+    };
+    bound.set_span(node.span());
+    bound
 }
@@ -0,0 +1,907 @@
+//! This module implements polynomials with rational coefficients for
+//! [`tilted`](crate).
+//!
+//! A [`Polynomial`] is a sum of terms, each a [`Rational`] coefficient times a
+//! [`Monomial`], i.e. a product of powers of variables. Arithmetic is exact,
+//! with [`i128`] numerators and denominators, and every operation returns
+//! [`None`] instead of overflowing. Division with remainder and greatest
+//! common divisors are defined for polynomials in a single variable.
+//!
+//! Polynomials in a single variable are factorised over the rationals: the
+//! linear factors are found with the rational root theorem, and the others
+//! with Kronecker's method, which tries every polynomial whose values at a
+//! few points divide those of the factorised one. Each factor is divided out
+//! as many times as it divides, which gives its multiplicity. Polynomials in
+//! several variables only have their content and common powers of variables
+//! taken out.
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    ops::Neg,
+};
+
+/// Largest number of candidates tried for each degree by Kronecker's method,
+/// beyond which a factor is left as it is.
+const MAX_CANDIDATES: usize = 100_000;
+
+/// Largest integer whose divisors are listed when looking for factors.
+const MAX_DIVIDEND: u128 = 1_000_000_000_000;
+
+/// Rational number in lowest terms, with a positive denominator.
+///
+/// The numerator is never [`i128::MIN`], so that negation never overflows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: i128,
+    denom: i128,
+}
+
+/// Product of powers of variables, e.g. `x^2 y`.
+///
+/// Monomials are ordered by total degree, then by the powers of the
+/// variables in alphabetical order, so that `x^2 > x y > y^2 > x`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Monomial {
+    /// Positive power of each variable.
+    powers: BTreeMap<String, u32>,
+}
+
+/// Polynomial in any number of variables with [`Rational`] coefficients.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Polynomial {
+    /// Non-zero coefficient of each term.
+    terms: BTreeMap<Monomial, Rational>,
+}
+
+/// Factorisation of a [`Polynomial`], as a constant times powers of factors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Factorisation {
+    /// The constant factor.
+    constant: Rational,
+
+    /// Each factor and its multiplicity. The factors have integer
+    /// coefficients without a common divisor and a positive leading
+    /// coefficient.
+    factors: Vec<(Polynomial, u32)>,
+}
+
+// -----------------------------------------------------------------------------
+// 1. Rational
+// -----------------------------------------------------------------------------
+
+impl Rational {
+    pub const ZERO: Rational = Rational { numer: 0, denom: 1 };
+
+    pub const ONE: Rational = Rational { numer: 1, denom: 1 };
+
+    /// Creates a [`Rational`] in lowest terms, returning [`None`] if `denom`
+    /// is zero or the result does not fit.
+    pub fn new(numer: i128, denom: i128) -> Option<Rational> {
+        if denom == 0 {
+            return None;
+        }
+
+        let divisor = i128::try_from(gcd(numer, denom)).ok()?;
+        let (mut numer, mut denom) = (numer / divisor, denom / divisor);
+        if denom < 0 {
+            numer = numer.checked_neg()?;
+            denom = denom.checked_neg()?;
+        }
+
+        match numer == i128::MIN {
+            true => None,
+            false => Some(Rational { numer, denom }),
+        }
+    }
+
+    /// Creates a [`Rational`] from an integer, returning [`None`] for
+    /// [`i128::MIN`].
+    pub fn integer(n: i128) -> Option<Rational> {
+        Rational::new(n, 1)
+    }
+
+    /// Creates a [`Rational`] from the shortest decimal that rounds to a
+    /// float, e.g. 1/10 for `0.1`, returning [`None`] if it is not finite
+    /// or does not fit.
+    pub fn from_f64(n: f64) -> Option<Rational> {
+        if !n.is_finite() {
+            return None;
+        }
+
+        let source = n.to_string();
+        let (whole, fraction) = source.split_once('.').unwrap_or((&source, ""));
+        let numer = format!("{}{}", whole, fraction).parse().ok()?;
+        let denom = 10i128.checked_pow(u32::try_from(fraction.len()).ok()?)?;
+        Rational::new(numer, denom)
+    }
+
+    /// Gets the nearest float.
+    pub fn to_f64(self) -> f64 {
+        self.numer as f64 / self.denom as f64
+    }
+
+    /// Gets the numerator.
+    pub fn numer(self) -> i128 {
+        self.numer
+    }
+
+    /// Gets the denominator, which is always positive.
+    pub fn denom(self) -> i128 {
+        self.denom
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.numer == 0
+    }
+
+    pub fn is_integer(self) -> bool {
+        self.denom == 1
+    }
+
+    /// Gets -1, 0 or 1 according to the sign.
+    pub fn signum(self) -> i128 {
+        self.numer.signum()
+    }
+
+    pub fn abs(self) -> Rational {
+        match self.numer < 0 {
+            true => -self,
+            false => self,
+        }
+    }
+
+    pub fn checked_add(self, rhs: Rational) -> Option<Rational> {
+        // The divisor of two denominators fits, as they are positive.
+        let divisor = gcd(self.denom, rhs.denom) as i128;
+        let numer = self
+            .numer
+            .checked_mul(rhs.denom / divisor)?
+            .checked_add(rhs.numer.checked_mul(self.denom / divisor)?)?;
+        Rational::new(numer, (self.denom / divisor).checked_mul(rhs.denom)?)
+    }
+
+    pub fn checked_sub(self, rhs: Rational) -> Option<Rational> {
+        self.checked_add(-rhs)
+    }
+
+    pub fn checked_mul(self, rhs: Rational) -> Option<Rational> {
+        // Cancel before multiplying, to overflow as late as possible.
+        let left = gcd(self.numer, rhs.denom) as i128;
+        let right = gcd(rhs.numer, self.denom) as i128;
+        let numer = (self.numer / left).checked_mul(rhs.numer / right)?;
+        let denom = (self.denom / right).checked_mul(rhs.denom / left)?;
+        Rational::new(numer, denom)
+    }
+
+    /// Divides, returning [`None`] if `rhs` is zero or the result does not
+    /// fit.
+    pub fn checked_div(self, rhs: Rational) -> Option<Rational> {
+        self.checked_mul(rhs.recip()?)
+    }
+
+    /// Gets the reciprocal, returning [`None`] for zero.
+    pub fn recip(self) -> Option<Rational> {
+        Rational::new(self.denom, self.numer)
+    }
+
+    pub fn checked_pow(self, exp: u32) -> Option<Rational> {
+        Rational::new(self.numer.checked_pow(exp)?, self.denom.checked_pow(exp)?)
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Self::Output {
+        Rational {
+            numer: -self.numer,
+            denom: self.denom,
+        }
+    }
+}
+
+impl Display for Rational {
+    /// Formats as an integer, or as a fraction, e.g. `-3/2`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.denom {
+            1 => write!(f, "{}", self.numer),
+            _ => write!(f, "{}/{}", self.numer, self.denom),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 2. Monomial
+// -----------------------------------------------------------------------------
+
+impl Monomial {
+    /// Creates the [`Monomial`] of no variables, i.e. 1.
+    pub fn one() -> Monomial {
+        Monomial::default()
+    }
+
+    /// Creates the [`Monomial`] of a single variable.
+    pub fn variable<T: Into<String>>(name: T) -> Monomial {
+        Monomial {
+            powers: BTreeMap::from([(name.into(), 1)]),
+        }
+    }
+
+    /// Gets the power of a variable, which is zero if it does not appear.
+    pub fn power(&self, var: &str) -> u32 {
+        self.powers.get(var).copied().unwrap_or(0)
+    }
+
+    /// Gets the total degree, i.e. the sum of the powers.
+    pub fn degree(&self) -> u64 {
+        self.powers.values().map(|&p| p as u64).sum()
+    }
+
+    /// Gets each variable and its power, in alphabetical order.
+    pub fn powers(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.powers.iter().map(|(v, &p)| (v.as_str(), p))
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.powers.is_empty()
+    }
+
+    fn checked_mul(&self, rhs: &Monomial) -> Option<Monomial> {
+        let mut powers = self.powers.clone();
+        for (var, &power) in &rhs.powers {
+            let entry = powers.entry(var.clone()).or_insert(0);
+            *entry = entry.checked_add(power)?;
+        }
+        Some(Monomial { powers })
+    }
+
+    /// Divides by another [`Monomial`] that divides this one.
+    fn div(&self, rhs: &Monomial) -> Monomial {
+        let mut powers = self.powers.clone();
+        for (var, &power) in &rhs.powers {
+            match powers[var] == power {
+                true => powers.remove(var),
+                false => powers.insert(var.clone(), powers[var] - power),
+            };
+        }
+        Monomial { powers }
+    }
+}
+
+impl Ord for Monomial {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let vars: BTreeSet<&String> = self.powers.keys().chain(other.powers.keys()).collect();
+        self.degree().cmp(&other.degree()).then_with(|| {
+            vars.into_iter()
+                .map(|var| self.power(var).cmp(&other.power(var)))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        })
+    }
+}
+
+impl PartialOrd for Monomial {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 3. Polynomial
+// -----------------------------------------------------------------------------
+
+impl Polynomial {
+    pub fn zero() -> Polynomial {
+        Polynomial::default()
+    }
+
+    pub fn constant(c: Rational) -> Polynomial {
+        Polynomial::term(Monomial::one(), c)
+    }
+
+    pub fn variable<T: Into<String>>(name: T) -> Polynomial {
+        Polynomial::term(Monomial::variable(name), Rational::ONE)
+    }
+
+    /// Creates a [`Polynomial`] of a single term.
+    pub fn term(monomial: Monomial, coefficient: Rational) -> Polynomial {
+        let mut terms = BTreeMap::new();
+        if !coefficient.is_zero() {
+            terms.insert(monomial, coefficient);
+        }
+        Polynomial { terms }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Gets the value of a constant [`Polynomial`], or [`None`] if it has a
+    /// variable.
+    pub fn as_constant(&self) -> Option<Rational> {
+        match self.terms.len() {
+            0 => Some(Rational::ZERO),
+            1 => self.terms.get(&Monomial::one()).copied(),
+            _ => None,
+        }
+    }
+
+    /// Gets each term as its monomial and coefficient, leading term first.
+    pub fn terms(&self) -> impl Iterator<Item = (&Monomial, Rational)> {
+        self.terms.iter().rev().map(|(m, &c)| (m, c))
+    }
+
+    /// Gets the variables in alphabetical order.
+    pub fn variables(&self) -> Vec<&str> {
+        let vars: BTreeSet<&str> = self
+            .terms
+            .keys()
+            .flat_map(|m| m.powers.keys())
+            .map(String::as_str)
+            .collect();
+        vars.into_iter().collect()
+    }
+
+    /// Gets the highest power of a variable, which is zero if it does not
+    /// appear.
+    pub fn degree(&self, var: &str) -> u32 {
+        self.terms.keys().map(|m| m.power(var)).max().unwrap_or(0)
+    }
+
+    /// Gets the coefficient of each power of a variable, constant first, as
+    /// polynomials in the other variables.
+    pub fn coefficients(&self, var: &str) -> Vec<Polynomial> {
+        let mut coefficients = vec![Polynomial::zero(); self.degree(var) as usize + 1];
+        for (monomial, &c) in &self.terms {
+            let mut monomial = monomial.clone();
+            let power = monomial.powers.remove(var).unwrap_or(0);
+            coefficients[power as usize].terms.insert(monomial, c);
+        }
+        coefficients
+    }
+
+    pub fn checked_add(&self, rhs: &Polynomial) -> Option<Polynomial> {
+        let mut terms = self.terms.clone();
+        for (monomial, &c) in &rhs.terms {
+            let sum = terms
+                .get(monomial)
+                .copied()
+                .unwrap_or(Rational::ZERO)
+                .checked_add(c)?;
+            match sum.is_zero() {
+                true => terms.remove(monomial),
+                false => terms.insert(monomial.clone(), sum),
+            };
+        }
+        Some(Polynomial { terms })
+    }
+
+    pub fn checked_sub(&self, rhs: &Polynomial) -> Option<Polynomial> {
+        self.checked_add(&-rhs.clone())
+    }
+
+    pub fn checked_mul(&self, rhs: &Polynomial) -> Option<Polynomial> {
+        let mut product = Polynomial::zero();
+        for (m, &a) in &self.terms {
+            for (n, &b) in &rhs.terms {
+                product =
+                    product.checked_add(&Polynomial::term(m.checked_mul(n)?, a.checked_mul(b)?))?;
+            }
+        }
+        Some(product)
+    }
+
+    /// Multiplies every coefficient by a constant.
+    pub fn checked_scale(&self, c: Rational) -> Option<Polynomial> {
+        self.checked_mul(&Polynomial::constant(c))
+    }
+
+    pub fn checked_pow(&self, mut exp: u32) -> Option<Polynomial> {
+        // Exponentiation by squaring.
+        let mut base = self.clone();
+        let mut power = Polynomial::constant(Rational::ONE);
+        while exp > 0 {
+            if exp % 2 == 1 {
+                power = power.checked_mul(&base)?;
+            }
+            exp /= 2;
+            if exp > 0 {
+                base = base.checked_mul(&base)?;
+            }
+        }
+        Some(power)
+    }
+
+    /// Differentiates with respect to a variable.
+    pub fn derivative(&self, var: &str) -> Option<Polynomial> {
+        let mut derivative = Polynomial::zero();
+        for (monomial, &c) in &self.terms {
+            let power = monomial.power(var);
+            if power > 0 {
+                let c = c.checked_mul(Rational::integer(power as i128)?)?;
+                let monomial = monomial.div(&Monomial::variable(var));
+                derivative = derivative.checked_add(&Polynomial::term(monomial, c))?;
+            }
+        }
+        Some(derivative)
+    }
+
+    /// Divides by another [`Polynomial`], returning the quotient and the
+    /// remainder, whose degree is less than that of `divisor`.
+    ///
+    /// Returns [`None`] if `divisor` is zero, if the polynomials have more
+    /// than one variable between them, or if the result does not fit.
+    pub fn checked_div_rem(&self, divisor: &Polynomial) -> Option<(Polynomial, Polynomial)> {
+        let var = single_variable(&[self, divisor])?;
+        let (quotient, remainder) = div_rem(&self.dense(var), &divisor.dense(var))?;
+        Some((sparse(var, &quotient), sparse(var, &remainder)))
+    }
+
+    /// Gets the monic greatest common divisor with another [`Polynomial`],
+    /// which is zero only if both are.
+    ///
+    /// Returns [`None`] if the polynomials have more than one variable
+    /// between them, or if the result does not fit.
+    pub fn gcd(&self, rhs: &Polynomial) -> Option<Polynomial> {
+        let var = single_variable(&[self, rhs])?;
+        Some(sparse(var, &gcd_dense(&self.dense(var), &rhs.dense(var))?))
+    }
+
+    /// Factorises over the rationals, returning [`None`] if the result does
+    /// not fit.
+    ///
+    /// Polynomials in a single variable are split into irreducible factors,
+    /// sorted by degree and then by root, except where Kronecker's method
+    /// would have to try too many candidates, in which case a factor is left
+    /// as it is. Polynomials in several variables are only split into their
+    /// content, powers of each variable common to every term, and the rest.
+    pub fn factor(&self) -> Option<Factorisation> {
+        let constant = content(self.terms().map(|(_, c)| c))?;
+        let primitive = self.checked_scale(constant.recip().unwrap_or(Rational::ONE))?;
+
+        let mut factors = vec![];
+        let var = match single_variable(&[self]) {
+            Some(Some(var)) => var,
+
+            // Constants.
+            Some(None) => return Some(Factorisation { constant, factors }),
+
+            // Common powers of each variable.
+            None => {
+                let common = primitive
+                    .terms
+                    .keys()
+                    .map(|m| m.powers.clone())
+                    .reduce(|a, b| {
+                        a.into_iter()
+                            .filter_map(|(v, p)| {
+                                let q = *b.get(&v)?;
+                                Some((v, p.min(q)))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                for (var, &power) in &common {
+                    factors.push((Polynomial::variable(var), power));
+                }
+
+                let common = Monomial { powers: common };
+                let terms = primitive
+                    .terms
+                    .into_iter()
+                    .map(|(m, c)| (m.div(&common), c));
+                let rest = Polynomial {
+                    terms: terms.collect(),
+                };
+                if rest.as_constant().is_none() {
+                    factors.push((rest, 1));
+                }
+                return Some(Factorisation { constant, factors });
+            }
+        };
+
+        // Powers of the variable.
+        let mut dense = primitive.dense(Some(var));
+        let power = dense.iter().position(|c| !c.is_zero()).unwrap();
+        dense.drain(..power);
+
+        let mut others = match dense.len() {
+            1 => vec![],
+            _ => factor_dense(dense)?,
+        };
+
+        // Linear factors are sorted by root.
+        others.sort_by(|(a, _), (b, _)| {
+            let root = |f: &[Rational]| match f.len() {
+                2 => -f[0].to_f64() / f[1].to_f64(),
+                _ => 0.0,
+            };
+            a.len().cmp(&b.len()).then(root(a).total_cmp(&root(b)))
+        });
+
+        if power > 0 {
+            factors.push((Polynomial::variable(var), power as u32));
+        }
+        for (factor, multiplicity) in others {
+            factors.push((sparse(Some(var), &factor), multiplicity));
+        }
+        Some(Factorisation { constant, factors })
+    }
+
+    /// Gets the coefficients as a polynomial in `var`, constant first,
+    /// without trailing zeros.
+    fn dense(&self, var: Option<&str>) -> Vec<Rational> {
+        let degree = var.map_or(0, |var| self.degree(var));
+        let mut dense = vec![Rational::ZERO; degree as usize + 1];
+        for (monomial, &c) in &self.terms {
+            dense[var.map_or(0, |var| monomial.power(var)) as usize] = c;
+        }
+        trim(dense)
+    }
+}
+
+impl Neg for Polynomial {
+    type Output = Polynomial;
+
+    fn neg(self) -> Self::Output {
+        let terms = self.terms.into_iter().map(|(m, c)| (m, -c));
+        Polynomial {
+            terms: terms.collect(),
+        }
+    }
+}
+
+impl From<Rational> for Polynomial {
+    fn from(value: Rational) -> Self {
+        Polynomial::constant(value)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 4. Factorisation
+// -----------------------------------------------------------------------------
+
+impl Factorisation {
+    /// Gets the constant factor.
+    pub fn constant(&self) -> Rational {
+        self.constant
+    }
+
+    /// Gets each factor and its multiplicity.
+    pub fn factors(&self) -> &[(Polynomial, u32)] {
+        &self.factors
+    }
+
+    /// Multiplies the factors back together.
+    pub fn expand(&self) -> Option<Polynomial> {
+        let mut product = Polynomial::constant(self.constant);
+        for (factor, multiplicity) in &self.factors {
+            product = product.checked_mul(&factor.checked_pow(*multiplicity)?)?;
+        }
+        Some(product)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 5. Polynomials in a single variable
+// -----------------------------------------------------------------------------
+
+/// Finds the only variable of some polynomials, returning `Some(None)` if
+/// they are constants and [`None`] if they have more than one variable.
+fn single_variable<'a>(polynomials: &[&'a Polynomial]) -> Option<Option<&'a str>> {
+    let vars: BTreeSet<&str> = polynomials.iter().flat_map(|p| p.variables()).collect();
+    match vars.len() {
+        0 => Some(None),
+        1 => Some(vars.into_iter().next()),
+        _ => None,
+    }
+}
+
+/// Creates a [`Polynomial`] in `var` from its coefficients, constant first.
+fn sparse(var: Option<&str>, dense: &[Rational]) -> Polynomial {
+    let mut polynomial = Polynomial::zero();
+    for (power, &c) in dense.iter().enumerate().filter(|(_, c)| !c.is_zero()) {
+        let monomial = match (var, power) {
+            (Some(var), 1..) => Monomial {
+                powers: BTreeMap::from([(var.to_string(), power as u32)]),
+            },
+            _ => Monomial::one(),
+        };
+        polynomial.terms.insert(monomial, c);
+    }
+    polynomial
+}
+
+/// Removes trailing zeros, so that the zero polynomial is empty.
+fn trim(mut dense: Vec<Rational>) -> Vec<Rational> {
+    while dense.last().is_some_and(|c| c.is_zero()) {
+        dense.pop();
+    }
+    dense
+}
+
+fn add_dense(a: &[Rational], b: &[Rational]) -> Option<Vec<Rational>> {
+    let length = a.len().max(b.len());
+    let sum = (0..length).map(|i| {
+        let a = a.get(i).copied().unwrap_or(Rational::ZERO);
+        a.checked_add(b.get(i).copied().unwrap_or(Rational::ZERO))
+    });
+    Some(trim(sum.collect::<Option<_>>()?))
+}
+
+fn mul_dense(a: &[Rational], b: &[Rational]) -> Option<Vec<Rational>> {
+    if a.is_empty() || b.is_empty() {
+        return Some(vec![]);
+    }
+
+    let mut product = vec![Rational::ZERO; a.len() + b.len() - 1];
+    for (i, &a) in a.iter().enumerate() {
+        for (j, &b) in b.iter().enumerate() {
+            product[i + j] = product[i + j].checked_add(a.checked_mul(b)?)?;
+        }
+    }
+    Some(trim(product))
+}
+
+fn scale_dense(a: &[Rational], c: Rational) -> Option<Vec<Rational>> {
+    let scaled = a.iter().map(|a| a.checked_mul(c)).collect::<Option<_>>()?;
+    Some(trim(scaled))
+}
+
+/// Evaluates a polynomial with Horner's method.
+fn evaluate_dense(a: &[Rational], x: Rational) -> Option<Rational> {
+    a.iter()
+        .rev()
+        .try_fold(Rational::ZERO, |sum, &c| sum.checked_mul(x)?.checked_add(c))
+}
+
+/// Long division, returning [`None`] if `b` is zero.
+fn div_rem(a: &[Rational], b: &[Rational]) -> Option<(Vec<Rational>, Vec<Rational>)> {
+    let leading = *b.last()?;
+    let mut remainder = a.to_vec();
+    let mut quotient = vec![Rational::ZERO; a.len().saturating_sub(b.len() - 1)];
+    while remainder.len() >= b.len() {
+        let shift = remainder.len() - b.len();
+        let c = remainder.last().unwrap().checked_div(leading)?;
+        quotient[shift] = c;
+        for (i, &b) in b.iter().enumerate() {
+            remainder[shift + i] = remainder[shift + i].checked_sub(c.checked_mul(b)?)?;
+        }
+
+        // The leading term cancels exactly.
+        remainder.pop();
+        remainder = trim(remainder);
+    }
+    Some((trim(quotient), remainder))
+}
+
+/// Divides by a polynomial known to be a divisor.
+fn div_exact(a: &[Rational], b: &[Rational]) -> Option<Vec<Rational>> {
+    div_rem(a, b).map(|(quotient, _)| quotient)
+}
+
+/// Euclid's algorithm, returning a monic divisor, or zero if both are zero.
+///
+/// Each remainder is divided by its content, which keeps the coefficients
+/// from growing exponentially.
+fn gcd_dense(a: &[Rational], b: &[Rational]) -> Option<Vec<Rational>> {
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    while !b.is_empty() {
+        let (_, remainder) = div_rem(&a, &b)?;
+        a = std::mem::replace(&mut b, primitive_dense(&remainder)?);
+    }
+    match a.last() {
+        Some(&leading) => scale_dense(&a, leading.recip()?),
+        None => Some(a),
+    }
+}
+
+/// Gets the content of some coefficients, i.e. the positive rational whose
+/// quotients by them are integers without a common divisor, with the sign of
+/// the first coefficient. The content of zero is zero.
+fn content(mut coefficients: impl Iterator<Item = Rational>) -> Option<Rational> {
+    let Some(leading) = coefficients.next() else {
+        return Some(Rational::ZERO);
+    };
+
+    let (mut numer, mut denom) = (leading.numer, leading.denom);
+    for c in coefficients {
+        numer = i128::try_from(gcd(numer, c.numer)).ok()?;
+        denom = (denom / gcd(denom, c.denom) as i128).checked_mul(c.denom)?;
+    }
+    Rational::new(numer.abs() * leading.signum(), denom)
+}
+
+/// Divides a polynomial by its content, unless it is zero.
+fn primitive_dense(a: &[Rational]) -> Option<Vec<Rational>> {
+    let c = content(a.iter().rev().copied())?;
+    scale_dense(a, c.recip().unwrap_or(Rational::ONE))
+}
+
+/// Splits a primitive polynomial of positive degree, whose constant term is
+/// not zero, into primitive factors that are irreducible over the rationals,
+/// with their multiplicities.
+fn factor_dense(mut f: Vec<Rational>) -> Option<Vec<(Vec<Rational>, u32)>> {
+    let mut factors = vec![];
+
+    // Each rational root p/q gives a factor q x - p, where p divides the
+    // constant term and q the leading coefficient.
+    let numers = divisors(f[0].numer);
+    let denoms = divisors(f.last().unwrap().numer);
+    if let (Some(numers), Some(denoms)) = (numers, denoms) {
+        let mut roots = BTreeSet::new();
+        for &p in &numers {
+            for &q in &denoms {
+                roots.insert((p, q));
+                roots.insert((-p, q));
+            }
+        }
+
+        for (p, q) in roots {
+            let Some(root) = Rational::new(p, q) else {
+                continue;
+            };
+            let factor = vec![
+                -Rational::integer(root.numer)?,
+                Rational::integer(root.denom)?,
+            ];
+            let multiplicity = divide_out(&mut f, &factor, |f| {
+                evaluate_dense(f, root) == Some(Rational::ZERO)
+            })?;
+            if multiplicity > 0 {
+                factors.push((factor, multiplicity));
+            }
+        }
+    }
+
+    // Polynomials of degree 2 or 3 without a root are irreducible.
+    if f.len() > 1 {
+        factors.extend(kronecker(f)?);
+    }
+    Some(factors)
+}
+
+/// Divides `f` by `factor` for as long as `divides` holds and `f` is not
+/// constant, returning the number of times it did.
+fn divide_out(
+    f: &mut Vec<Rational>,
+    factor: &[Rational],
+    divides: impl Fn(&[Rational]) -> bool,
+) -> Option<u32> {
+    let mut multiplicity = 0;
+    while f.len() > 1 && divides(f) {
+        *f = div_exact(f, factor)?;
+        multiplicity += 1;
+    }
+    Some(multiplicity)
+}
+
+/// Splits a primitive polynomial without rational roots into irreducible
+/// factors with Kronecker's method.
+///
+/// A factor of degree `d` is determined by its values at `d + 1` points,
+/// each of which divides the value of `f` there. Every such factor is tried,
+/// lowest degree first, so that the first one found is irreducible.
+fn kronecker(mut f: Vec<Rational>) -> Option<Vec<(Vec<Rational>, u32)>> {
+    let degree = f.len() - 1;
+    for d in 2..=degree / 2 {
+        // Integer points 0, 1, -1, 2, ... with their values, which are not
+        // zero as f has no rational roots.
+        let points: Vec<(Rational, i128)> = (0..2 * degree as i128)
+            .map(|i| match i % 2 {
+                0 => -i / 2,
+                _ => i / 2 + 1,
+            })
+            .filter_map(|x| {
+                let x = Rational::integer(x)?;
+                let y = evaluate_dense(&f, x)?;
+                Some((x, y.numer))
+            })
+            .take(d + 1)
+            .collect();
+        if points.len() <= d {
+            break;
+        }
+
+        // The divisors of the values of f at the points.
+        let mut choices = vec![];
+        let mut count = 1usize;
+        for (i, &(_, y)) in points.iter().enumerate() {
+            let Some(positive) = divisors(y) else {
+                return Some(vec![(f, 1)]);
+            };
+
+            // The sign of a factor is chosen by its value at the first point.
+            let divisors: Vec<i128> = match i {
+                0 => positive,
+                _ => positive.iter().flat_map(|&d| [d, -d]).collect(),
+            };
+            count = count.saturating_mul(divisors.len());
+            choices.push(divisors);
+        }
+        if count > MAX_CANDIDATES {
+            break;
+        }
+
+        let basis = lagrange(&points.iter().map(|&(x, _)| x).collect::<Vec<_>>())?;
+        for index in 0..count {
+            // Picks a divisor at each point.
+            let mut rest = index;
+            let mut candidate = vec![];
+            for (divisors, basis) in choices.iter().zip(&basis) {
+                let value = Rational::integer(divisors[rest % divisors.len()])?;
+                rest /= divisors.len();
+                candidate = add_dense(&candidate, &scale_dense(basis, value)?)?;
+            }
+
+            if candidate.len() != d + 1 || !candidate.iter().all(|c| c.is_integer()) {
+                continue;
+            }
+
+            let candidate = primitive_dense(&candidate)?;
+            let multiplicity = divide_out(&mut f, &candidate, |f| {
+                div_rem(f, &candidate).is_some_and(|(_, remainder)| remainder.is_empty())
+            })?;
+            if multiplicity > 0 {
+                let mut factors = vec![(candidate, multiplicity)];
+                if f.len() > 1 {
+                    factors.extend(kronecker(f)?);
+                }
+                return Some(factors);
+            }
+        }
+    }
+    Some(vec![(f, 1)])
+}
+
+/// Gets the Lagrange basis polynomials for some points, i.e. the
+/// polynomials that are one at a point and zero at the others.
+fn lagrange(points: &[Rational]) -> Option<Vec<Vec<Rational>>> {
+    let mut basis = vec![];
+    for (i, &xi) in points.iter().enumerate() {
+        let mut polynomial = vec![Rational::ONE];
+        for (j, &xj) in points.iter().enumerate() {
+            if i != j {
+                let scale = xi.checked_sub(xj)?.recip()?;
+                let factor = vec![-xj.checked_mul(scale)?, scale];
+                polynomial = mul_dense(&polynomial, &factor)?;
+            }
+        }
+        basis.push(polynomial);
+    }
+    Some(basis)
+}
+
+/// Lists the positive divisors of an integer, returning [`None`] if it is
+/// zero or too large.
+fn divisors(n: i128) -> Option<Vec<i128>> {
+    let n = n.unsigned_abs();
+    if n == 0 || n > MAX_DIVIDEND {
+        return None;
+    }
+
+    let (mut small, mut large) = (vec![], vec![]);
+    let mut d = 1;
+    while d * d <= n {
+        if n.is_multiple_of(d) {
+            small.push(d as i128);
+            if d * d != n {
+                large.push((n / d) as i128);
+            }
+        }
+        d += 1;
+    }
+    small.extend(large.into_iter().rev());
+    Some(small)
+}
+
+/// Greatest common divisor of the magnitudes, which is zero only if both are.
+fn gcd(a: i128, b: i128) -> u128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
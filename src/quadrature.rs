@@ -0,0 +1,174 @@
+//! This module implements numerical integration for [`tilted`](crate).
+//!
+//! Integrals are computed with adaptive Gauss–Kronrod quadrature: each
+//! segment of the interval is integrated with the 15-point Kronrod rule, and
+//! the difference from the embedded 7-point Gauss rule estimates its error.
+//! The segment with the largest error is bisected until the total error is
+//! within the tolerance. The integrand is never evaluated at the bounds, so
+//! integrable singularities there, e.g. `1 / x^0.5` from 0, are allowed.
+
+use crate::EvalError;
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// Tolerance used when evaluating `integrate(expr, var, lower, upper)`.
+pub const TOLERANCE: f64 = 1e-10;
+
+/// Largest number of segments the interval is divided into.
+const MAX_SEGMENTS: usize = 1000;
+
+/// Nodes of the 15-point Kronrod rule on `[-1, 1]`, from the outside in. The
+/// odd ones are the nodes of the 7-point Gauss rule.
+#[rustfmt::skip]
+const KRONROD_NODES: [f64; 8] = [
+    0.9914553711208126,
+    0.9491079123427585,
+    0.8648644233597691,
+    0.7415311855993945,
+    0.5860872354676911,
+    0.4058451513773972,
+    0.20778495500789848,
+    0.0,
+];
+
+/// Weights of the 15-point Kronrod rule.
+#[rustfmt::skip]
+const KRONROD_WEIGHTS: [f64; 8] = [
+    0.022935322010529224,
+    0.06309209262997856,
+    0.10479001032225019,
+    0.14065325971552592,
+    0.1690047266392679,
+    0.19035057806478542,
+    0.20443294007529889,
+    0.20948214108472782,
+];
+
+/// Weights of the 7-point Gauss rule, for the odd Kronrod nodes.
+#[rustfmt::skip]
+const GAUSS_WEIGHTS: [f64; 4] = [
+    0.1294849661688697,
+    0.27970539148927664,
+    0.3818300505051189,
+    0.4179591836734694,
+];
+
+/// Value of an integral together with an estimate of its error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quadrature {
+    /// The value.
+    value: f64,
+
+    /// The estimated absolute error, which is never negative.
+    error: f64,
+}
+
+/// A segment of the interval and its integral.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    lower: f64,
+    upper: f64,
+    quadrature: Quadrature,
+}
+
+/// Integrates `f` from `lower` to `upper`, until the estimated error is at
+/// most `tolerance` times the magnitude of the integral, or `tolerance`
+/// itself for integrals smaller than 1.
+///
+/// Returns [`EvalError::ToleranceNotMet`] if the tolerance cannot be reached,
+/// e.g. because the integral diverges or `f` is not finite somewhere, and
+/// stops at the first error returned by `f`.
+pub fn integrate(
+    mut f: impl FnMut(f64) -> Result<f64>,
+    lower: f64,
+    upper: f64,
+    tolerance: f64,
+) -> Result<Quadrature> {
+    if lower == upper {
+        return Ok(Quadrature::new(0.0, 0.0));
+    }
+
+    let mut segments = vec![Segment::new(&mut f, lower, upper)?];
+    loop {
+        let value = segments.iter().map(|s| s.quadrature.value).sum::<f64>();
+        let error = segments.iter().map(|s| s.quadrature.error).sum::<f64>();
+        if error <= tolerance * value.abs().max(1.0) {
+            return Ok(Quadrature::new(value, error));
+        }
+
+        if !error.is_finite() || segments.len() >= MAX_SEGMENTS {
+            return Err(EvalError::ToleranceNotMet(error));
+        }
+
+        // Bisect the segment with the largest error.
+        let (index, _) = segments
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.quadrature.error.total_cmp(&b.quadrature.error))
+            .unwrap();
+        let Segment { lower, upper, .. } = segments.swap_remove(index);
+
+        // Too narrow to bisect any further.
+        let middle = lower + (upper - lower) / 2.0;
+        if middle == lower || middle == upper {
+            return Err(EvalError::ToleranceNotMet(error));
+        }
+
+        segments.push(Segment::new(&mut f, lower, middle)?);
+        segments.push(Segment::new(&mut f, middle, upper)?);
+    }
+}
+
+impl Quadrature {
+    /// Creates a [`Quadrature`]. The sign of the error is ignored.
+    pub fn new(value: f64, error: f64) -> Quadrature {
+        Quadrature {
+            value,
+            error: error.abs(),
+        }
+    }
+
+    /// Gets the value.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Gets the estimated absolute error.
+    pub fn error(&self) -> f64 {
+        self.error
+    }
+}
+
+impl Segment {
+    /// Integrates `f` over a segment with the Gauss–Kronrod rule. A value
+    /// that is not finite gives an infinite error.
+    fn new(f: &mut impl FnMut(f64) -> Result<f64>, lower: f64, upper: f64) -> Result<Segment> {
+        let center = lower + (upper - lower) / 2.0;
+        let half = (upper - lower) / 2.0;
+
+        let fc = f(center)?;
+        let mut kronrod = KRONROD_WEIGHTS[7] * fc;
+        let mut gauss = GAUSS_WEIGHTS[3] * fc;
+        for i in 0..7 {
+            let offset = half * KRONROD_NODES[i];
+            let pair = f(center - offset)? + f(center + offset)?;
+            kronrod += KRONROD_WEIGHTS[i] * pair;
+            if i % 2 == 1 {
+                gauss += GAUSS_WEIGHTS[i / 2] * pair;
+            }
+        }
+
+        let value = kronrod * half;
+        let error = match value.is_finite() {
+            true => ((kronrod - gauss) * half).abs(),
+            false => f64::INFINITY,
+        };
+
+        Ok(Segment {
+            lower,
+            upper,
+            quadrature: Quadrature::new(value, error),
+        })
+    }
+}
@@ -0,0 +1,146 @@
+//! This module implements the pseudo-random number generator for
+//! [`tilted`](crate).
+//!
+//! [`Rng`] is a SplitMix64 generator: its state is a counter advanced by a
+//! fixed odd constant, and each number is a mix of the bits of the counter.
+//! The same seed always gives the same numbers, on every platform, so that
+//! simulations can be reproduced. The generator is small and fast, but not
+//! cryptographically secure.
+//!
+//! Uniform floats take the top 53 bits of a number, integers in a range are
+//! drawn by rejection so that each is equally likely, and normal deviates are
+//! found with the Box–Muller transform.
+
+use std::{
+    f64::consts::TAU,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Increment of the state of SplitMix64, the odd integer closest to
+/// `2^64 / φ`.
+const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Seeded pseudo-random number generator.
+///
+/// An [`Rng`] draws numbers through a shared reference, so that it can be
+/// used while evaluating an AST. Clones of an [`Rng`] share its state and
+/// draw from the same stream of numbers: create a new [`Rng`] for an
+/// independent stream. It is serialised as its current state, from which it
+/// continues once deserialised.
+///
+/// ```
+/// use tilted::Rng;
+///
+/// let rng = Rng::new(42);
+/// let (a, b) = (rng.uniform(), rng.uniform());
+/// assert_ne!(a, b);
+///
+/// let again = Rng::new(42);
+/// assert_eq!((again.uniform(), again.uniform()), (a, b));
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(into = "u64", from = "u64")
+)]
+pub struct Rng {
+    /// Counter of SplitMix64.
+    state: Arc<AtomicU64>,
+}
+
+impl Rng {
+    /// Creates a new [`Rng`] from a seed.
+    pub fn new(seed: u64) -> Rng {
+        Self {
+            state: Arc::new(AtomicU64::new(seed)),
+        }
+    }
+
+    /// Gets the current state of this [`Rng`]. A new [`Rng`] seeded with it
+    /// draws the same numbers as this one from now on.
+    pub fn state(&self) -> u64 {
+        self.state.load(Ordering::Relaxed)
+    }
+
+    /// Draws a number with all 64 bits uniformly distributed.
+    pub fn next_u64(&self) -> u64 {
+        let z = self
+            .state
+            .fetch_add(GAMMA, Ordering::Relaxed)
+            .wrapping_add(GAMMA);
+        let z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        let z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Draws a float uniformly distributed in `[0, 1)`.
+    pub fn uniform(&self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Draws an integer uniformly distributed from `low` to `high`, both
+    /// included. Returns [`None`] if `low` is greater than `high`.
+    pub fn integer(&self, low: i128, high: i128) -> Option<i128> {
+        if low > high {
+            return None;
+        }
+
+        let next = || (self.next_u64() as u128) << 64 | self.next_u64() as u128;
+
+        // The whole range of i128.
+        let span = match (high.wrapping_sub(low) as u128).checked_add(1) {
+            Some(span) => span,
+            None => return Some(low.wrapping_add(next() as i128)),
+        };
+
+        // Numbers from the last, incomplete multiple of the span are redrawn,
+        // as they would favour the smallest integers.
+        let excess = span.wrapping_neg() % span;
+        loop {
+            let n = next();
+            if n <= u128::MAX - excess {
+                return Some(low.wrapping_add((n % span) as i128));
+            }
+        }
+    }
+
+    /// Draws a float from the normal distribution with mean `mu` and
+    /// standard deviation `sigma`, or NaN if `sigma` is negative.
+    pub fn normal(&self, mu: f64, sigma: f64) -> f64 {
+        if sigma < 0.0 {
+            return f64::NAN;
+        }
+
+        // 1 - u is in (0, 1], so its logarithm is finite.
+        let radius = (-2.0 * (1.0 - self.uniform()).ln()).sqrt();
+        let angle = TAU * self.uniform();
+        mu + sigma * radius * angle.cos()
+    }
+}
+
+impl PartialEq for Rng {
+    /// Compares the current states of two generators, which draw the same
+    /// numbers if they are equal.
+    fn eq(&self, other: &Self) -> bool {
+        self.state() == other.state()
+    }
+}
+
+impl From<u64> for Rng {
+    fn from(value: u64) -> Self {
+        Rng::new(value)
+    }
+}
+
+impl From<Rng> for u64 {
+    fn from(value: Rng) -> Self {
+        value.state()
+    }
+}
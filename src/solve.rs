@@ -0,0 +1,449 @@
+//! This module implements root finding for [`tilted`](crate).
+//!
+//! Polynomials of degree up to 4 are solved in closed form, and each root is
+//! then refined with Newton's method. Other equations are solved numerically:
+//! near a guess with Newton's method, falling back to Brent's method on an
+//! interval grown around the guess until the function changes sign, or over
+//! an interval by refining each sign change between evenly spaced points with
+//! Brent's method. Roots where the function touches zero without changing
+//! sign are only found by Newton's method.
+
+use std::f64::consts::TAU;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::EvalError;
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// Relative tolerance of roots found numerically.
+pub const TOLERANCE: f64 = 1e-12;
+
+/// Largest number of iterations of Newton's and Brent's methods.
+const MAX_ITERATIONS: usize = 100;
+
+/// Largest number of times the interval grown around a guess is doubled.
+const MAX_EXPANSIONS: usize = 64;
+
+/// Number of pieces an interval is split into when looking for sign changes.
+const PIECES: usize = 256;
+
+/// Where to look for the roots of an equation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Guess<T> {
+    /// A single root, looked for near zero.
+    None,
+
+    /// A single root, looked for near the value.
+    Point(T),
+
+    /// All roots from the lower to the upper bound.
+    Interval(T, T),
+}
+
+/// Finds the real roots of `f`, whose derivative is `df`, in ascending order.
+///
+/// If `f` is a polynomial with the given `coefficients`, constant first, of
+/// degree 4 or less, all of its roots are found in closed form, but only
+/// those within an [`Guess::Interval`] are returned. Otherwise, a single root
+/// is found near a guess, or every root where `f` changes sign within an
+/// interval.
+///
+/// Returns [`EvalError::InfinitelyManyRoots`] if every coefficient is zero,
+/// and [`EvalError::NotConverged`] if no root is found near a guess. Errors
+/// returned by `f` and `df` are returned as they are.
+pub fn solve(
+    mut f: impl FnMut(f64) -> Result<f64>,
+    mut df: impl FnMut(f64) -> Result<f64>,
+    coefficients: Option<&[f64]>,
+    guess: Guess<f64>,
+) -> Result<Vec<f64>> {
+    if let Some(mut roots) = coefficients.map(polynomial_roots).transpose()?.flatten() {
+        if let Guess::Interval(lower, upper) = guess {
+            let (lower, upper) = (lower.min(upper), lower.max(upper));
+            roots.retain(|&x| lower <= x && x <= upper);
+        }
+        return Ok(roots);
+    }
+
+    match guess {
+        Guess::None => Ok(vec![near(&mut f, &mut df, 0.0)?]),
+        Guess::Point(x) => Ok(vec![near(&mut f, &mut df, x)?]),
+        Guess::Interval(lower, upper) => within(&mut f, lower, upper),
+    }
+}
+
+/// Finds the real roots of a polynomial in closed form, in ascending order,
+/// given its coefficients, constant first. Multiple roots are returned once.
+///
+/// Returns [`None`] if the degree is more than 4 or a coefficient is not
+/// finite, and [`EvalError::InfinitelyManyRoots`] if every coefficient is
+/// zero.
+pub fn polynomial_roots(coefficients: &[f64]) -> Result<Option<Vec<f64>>> {
+    let degree = match coefficients.iter().rposition(|&c| c != 0.0) {
+        Some(degree) => degree,
+        None => return Err(EvalError::InfinitelyManyRoots),
+    };
+    let coefficients = &coefficients[..=degree];
+    if degree > 4 || coefficients.iter().any(|c| !c.is_finite()) {
+        return Ok(None);
+    }
+
+    // Monic, highest power first, e.g. x^2 + a[0] x + a[1].
+    let lead = coefficients[degree];
+    let a = coefficients[..degree]
+        .iter()
+        .rev()
+        .map(|c| c / lead)
+        .collect::<Vec<_>>();
+    let roots = match degree {
+        0 => vec![],
+        1 => vec![-a[0]],
+        2 => quadratic(a[0], a[1]),
+        3 => cubic(a[0], a[1], a[2]),
+        _ => quartic(a[0], a[1], a[2], a[3]),
+    };
+
+    let mut roots = roots
+        .into_iter()
+        .filter(|x| x.is_finite())
+        .map(|x| polish(coefficients, x))
+        .collect::<Vec<_>>();
+    roots.sort_by(f64::total_cmp);
+
+    // A multiple root may be found more than once, slightly apart. The
+    // polynomial is no larger between them, unlike between distinct roots.
+    roots.dedup_by(|b, a| {
+        let middle = horner(coefficients, (*a + *b) / 2.0).0.abs();
+        let ends = horner(coefficients, *a)
+            .0
+            .abs()
+            .max(horner(coefficients, *b).0.abs());
+        (*b - *a).abs() <= 1e-6 * a.abs().max(1.0) && middle <= ends
+    });
+
+    Ok(Some(roots))
+}
+
+/// Solves `x^2 + b x + c = 0`.
+fn quadratic(b: f64, c: f64) -> Vec<f64> {
+    let discriminant = b * b - 4.0 * c;
+    if discriminant < 0.0 {
+        return vec![];
+    }
+    if discriminant == 0.0 {
+        return vec![-b / 2.0];
+    }
+
+    // Avoids cancellation between -b and the square root.
+    let q = -0.5 * (b + discriminant.sqrt().copysign(b));
+    vec![q, c / q]
+}
+
+/// Solves `x^3 + a x^2 + b x + c = 0`.
+fn cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    // Depressed cubic t^3 + p t + q = 0, where x = t - a / 3.
+    let shift = a / 3.0;
+    let p = b - a * a / 3.0;
+    let q = 2.0 * a.powi(3) / 27.0 - a * b / 3.0 + c;
+
+    let discriminant = q * q / 4.0 + p.powi(3) / 27.0;
+    let roots = if p == 0.0 {
+        vec![(-q).cbrt()]
+    } else if discriminant > 0.0 {
+        // A single real root, by Cardano's formula.
+        let s = discriminant.sqrt();
+        vec![(-q / 2.0 + s).cbrt() + (-q / 2.0 - s).cbrt()]
+    } else {
+        // Three real roots, by the trigonometric method.
+        let m = 2.0 * (-p / 3.0).sqrt();
+        let theta = (3.0 * q / (p * m)).clamp(-1.0, 1.0).acos() / 3.0;
+        (0..3)
+            .map(|k| m * (theta - TAU * k as f64 / 3.0).cos())
+            .collect()
+    };
+
+    roots.into_iter().map(|t| t - shift).collect()
+}
+
+/// Solves `x^4 + a x^3 + b x^2 + c x + d = 0`.
+fn quartic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    // Depressed quartic y^4 + p y^2 + q y + r = 0, where x = y - a / 4.
+    let shift = a / 4.0;
+    let p = b - 3.0 * a * a / 8.0;
+    let q = c - a * b / 2.0 + a.powi(3) / 8.0;
+    let r = d - a * c / 4.0 + a * a * b / 16.0 - 3.0 * a.powi(4) / 256.0;
+
+    let roots = if q == 0.0 {
+        // Biquadratic, i.e. a quadratic in y^2.
+        quadratic(p, r)
+            .into_iter()
+            .filter(|&z| z >= 0.0)
+            .flat_map(|z| [-z.sqrt(), z.sqrt()])
+            .collect()
+    } else {
+        // Ferrari's method: for the positive root m of the resolvent cubic,
+        // the quartic is (y^2 + s y + p/2 + m - q/2s)(y^2 - s y + p/2 + m + q/2s)
+        // where s = (2m)^0.5.
+        let m = cubic(p, p * p / 4.0 - r, -q * q / 8.0)
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let s = (2.0 * m).sqrt();
+        let mut roots = quadratic(s, p / 2.0 + m - q / (2.0 * s));
+        roots.extend(quadratic(-s, p / 2.0 + m + q / (2.0 * s)));
+        roots
+    };
+
+    roots.into_iter().map(|y| y - shift).collect()
+}
+
+/// Refines a root of a polynomial with Newton's method, for as long as the
+/// value of the polynomial decreases.
+fn polish(coefficients: &[f64], mut x: f64) -> f64 {
+    let (mut value, mut slope) = horner(coefficients, x);
+    for _ in 0..MAX_ITERATIONS {
+        if value == 0.0 || slope == 0.0 {
+            break;
+        }
+
+        let next = x - value / slope;
+        let (next_value, next_slope) = horner(coefficients, next);
+        if next_value.is_nan() || next_value.abs() >= value.abs() {
+            break;
+        }
+        (x, value, slope) = (next, next_value, next_slope);
+    }
+    x
+}
+
+/// Evaluates a polynomial and its derivative with Horner's method.
+fn horner(coefficients: &[f64], x: f64) -> (f64, f64) {
+    coefficients
+        .iter()
+        .rev()
+        .fold((0.0, 0.0), |(value, slope), &c| {
+            (value * x + c, slope * x + value)
+        })
+}
+
+/// Finds a root near `x` with Newton's method, falling back to Brent's method
+/// where Newton's method fails.
+fn near(
+    f: &mut impl FnMut(f64) -> Result<f64>,
+    df: &mut impl FnMut(f64) -> Result<f64>,
+    x: f64,
+) -> Result<f64> {
+    match newton(f, df, x)? {
+        Some(root) => Ok(root),
+        None => {
+            let (lower, upper) = bracket(f, x)?;
+            brent(f, lower, upper)
+        }
+    }
+}
+
+/// Finds a root with Newton's method starting at `x`, returning [`None`] if
+/// it does not converge.
+fn newton(
+    f: &mut impl FnMut(f64) -> Result<f64>,
+    df: &mut impl FnMut(f64) -> Result<f64>,
+    mut x: f64,
+) -> Result<Option<f64>> {
+    for _ in 0..MAX_ITERATIONS {
+        let value = f(x)?;
+        if value == 0.0 {
+            return Ok(Some(x));
+        }
+
+        // A zero or non-finite derivative gives a non-finite step.
+        let step = value / df(x)?;
+        x -= step;
+        if !x.is_finite() {
+            return Ok(None);
+        }
+        if step.abs() <= TOLERANCE * x.abs().max(1.0) {
+            return Ok(Some(x));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Grows an interval around `x` on both sides until `f` changes sign within
+/// it, returning the part of the interval where it does.
+fn bracket(f: &mut impl FnMut(f64) -> Result<f64>, x: f64) -> Result<(f64, f64)> {
+    // The last point on each side where f is finite.
+    let fx = f(x)?;
+    let mut previous = [(x, fx), (x, fx)];
+
+    let mut step = 0.01 * x.abs().max(1.0);
+    for _ in 0..MAX_EXPANSIONS {
+        for (side, y) in [x - step, x + step].into_iter().enumerate() {
+            let fy = f(y)?;
+            if !fy.is_finite() {
+                continue;
+            }
+
+            let (p, fp) = previous[side];
+            if fp.is_finite() && fp * fy <= 0.0 {
+                return Ok((p.min(y), p.max(y)));
+            }
+            previous[side] = (y, fy);
+        }
+        step *= 2.0;
+    }
+
+    Err(EvalError::NotConverged(x))
+}
+
+/// Finds a root of `f` from `a` to `b`, where it changes sign, with Brent's
+/// method.
+fn brent(f: &mut impl FnMut(f64) -> Result<f64>, mut a: f64, mut b: f64) -> Result<f64> {
+    let (mut fa, mut fb) = (f(a)?, f(b)?);
+    if fa.abs() < fb.abs() {
+        (a, b, fa, fb) = (b, a, fb, fa);
+    }
+
+    // The previous two values of b, and whether the last step bisected.
+    let (mut c, mut fc, mut d) = (a, fa, a);
+    let mut bisected = true;
+
+    for _ in 0..MAX_ITERATIONS {
+        let tolerance = TOLERANCE * b.abs().max(1.0);
+        if fb == 0.0 || (b - a).abs() <= tolerance {
+            return Ok(b);
+        }
+
+        // Inverse quadratic interpolation, or else the secant method.
+        let mut s = if fa != fc && fb != fc {
+            a * fb * fc / ((fa - fb) * (fa - fc))
+                + b * fa * fc / ((fb - fa) * (fb - fc))
+                + c * fa * fb / ((fc - fa) * (fc - fb))
+        } else {
+            b - fb * (b - a) / (fb - fa)
+        };
+
+        // Bisect instead if the interpolation is out of range or too slow.
+        let m = (3.0 * a + b) / 4.0;
+        let last = match bisected {
+            true => (b - c).abs(),
+            false => (c - d).abs(),
+        };
+        let inside = m.min(b) < s && s < m.max(b);
+        if !inside || (s - b).abs() >= last / 2.0 || last < tolerance {
+            s = (a + b) / 2.0;
+            bisected = true;
+        } else {
+            bisected = false;
+        }
+
+        let fs = f(s)?;
+        if !fs.is_finite() {
+            return Err(EvalError::NotConverged(s));
+        }
+
+        (d, c, fc) = (c, b, fb);
+        if fa * fs < 0.0 {
+            (b, fb) = (s, fs);
+        } else {
+            (a, fa) = (s, fs);
+        }
+        if fa.abs() < fb.abs() {
+            (a, b, fa, fb) = (b, a, fb, fa);
+        }
+    }
+
+    Err(EvalError::NotConverged(b))
+}
+
+/// Finds the roots of `f` from `lower` to `upper`, at the sign changes
+/// between evenly spaced points.
+fn within(f: &mut impl FnMut(f64) -> Result<f64>, lower: f64, upper: f64) -> Result<Vec<f64>> {
+    let (lower, upper) = (lower.min(upper), lower.max(upper));
+    let mut roots = Vec::new();
+
+    let mut previous = (lower, f(lower)?);
+    if previous.1 == 0.0 {
+        roots.push(lower);
+    }
+    if lower == upper {
+        return Ok(roots);
+    }
+
+    for i in 1..=PIECES {
+        let x = match i {
+            PIECES => upper,
+            i => lower + (upper - lower) * (i as f64 / PIECES as f64),
+        };
+        let fx = f(x)?;
+
+        let (p, fp) = previous;
+        if fx == 0.0 {
+            roots.push(x);
+        } else if fp.is_finite() && fx.is_finite() && fp * fx < 0.0 {
+            // A pole also changes sign, but grows rather than vanishes.
+            let root = brent(f, p, x)?;
+            if f(root)?.abs() <= fp.abs().min(fx.abs()) {
+                roots.push(root);
+            }
+        }
+        previous = (x, fx);
+    }
+
+    Ok(roots)
+}
+
+impl<T> Guess<T> {
+    /// Borrows the values of this [`Guess`].
+    pub fn as_ref(&self) -> Guess<&T> {
+        match self {
+            Self::None => Guess::None,
+            Self::Point(x) => Guess::Point(x),
+            Self::Interval(a, b) => Guess::Interval(a, b),
+        }
+    }
+
+    /// Maps the values of this [`Guess`], stopping at the first error.
+    pub fn try_map<U, E>(
+        self,
+        mut f: impl FnMut(T) -> std::result::Result<U, E>,
+    ) -> std::result::Result<Guess<U>, E> {
+        Ok(match self {
+            Self::None => Guess::None,
+            Self::Point(x) => Guess::Point(f(x)?),
+            Self::Interval(a, b) => Guess::Interval(f(a)?, f(b)?),
+        })
+    }
+
+    /// Maps the values of this [`Guess`].
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Guess<U> {
+        match self {
+            Self::None => Guess::None,
+            Self::Point(x) => Guess::Point(f(x)),
+            Self::Interval(a, b) => Guess::Interval(f(a), f(b)),
+        }
+    }
+
+    /// Iterates over the values of this [`Guess`], in order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let (first, second) = match self {
+            Self::None => (None, None),
+            Self::Point(x) => (Some(x), None),
+            Self::Interval(a, b) => (Some(a), Some(b)),
+        };
+        first.into_iter().chain(second)
+    }
+
+    /// Gets the number of values of this [`Guess`].
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Checks if this [`Guess`] has no value.
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Self::None)
+    }
+}
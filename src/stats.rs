@@ -0,0 +1,421 @@
+//! This module implements statistics and probability for [`tilted`](crate).
+//!
+//! Sums are compensated with Neumaier's algorithm, and variances are found
+//! with two passes over the data, correcting the mean on the second, so
+//! neither loses precision to cancellation. The gamma function is computed
+//! with the Lanczos approximation for small arguments and Stirling's series
+//! for large ones, and the error function with a series of positive terms
+//! near zero and a continued fraction in the tails. Both are accurate to
+//! within a few units in the last place.
+//!
+//! Probabilities of the binomial distribution are the exact binomial
+//! coefficient times powers of `p` and `1 - p` where the coefficient fits in
+//! the mantissa of a float and `1 - p` is exact, e.g. for a fair coin, so
+//! that `binompdf(3, 10, 0.5)` is exactly `0.1171875`. Otherwise, and for the
+//! Poisson distribution, they are computed with Loader's saddle point
+//! expansion, which stays accurate for large counts where differences of
+//! logarithms of factorials would not. Their cumulative
+//! distribution functions add up the probabilities of each count up to the
+//! given one, leaving out those too far from the mean to matter. Parameters
+//! outside of a distribution's domain, e.g. a negative standard deviation,
+//! give NaN.
+
+use std::f64::consts::{FRAC_2_SQRT_PI, PI};
+
+use crate::Number;
+
+/// Parameter `g` of the Lanczos approximation.
+const LANCZOS_G: f64 = 7.0;
+
+/// Coefficients of the Lanczos approximation for `g = 7`.
+#[rustfmt::skip]
+const LANCZOS_COEFFICIENTS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+/// Point from which the gamma function is computed with Stirling's series.
+const STIRLING_SPLIT: f64 = 15.0;
+
+/// Point from which the error function is computed as the complement of its
+/// continued fraction.
+const ERF_SPLIT: f64 = 2.0;
+
+/// Number of standard deviations from the mean beyond which the probability
+/// of a count is negligible.
+const TAIL: f64 = 40.0;
+
+/// Largest number of terms of a series or a continued fraction.
+const MAX_TERMS: usize = 1000;
+
+/// Sums values with Neumaier's compensated summation.
+pub fn sum(data: &[f64]) -> f64 {
+    let mut sum = 0.0;
+    let mut compensation = 0.0;
+    for &x in data {
+        let t = sum + x;
+        compensation += match sum.abs() >= x.abs() {
+            true => (sum - t) + x,
+            false => (x - t) + sum,
+        };
+        sum = t;
+    }
+    sum + compensation
+}
+
+/// Arithmetic mean of values, or NaN if there are none.
+pub fn mean(data: &[f64]) -> f64 {
+    sum(data) / data.len() as f64
+}
+
+/// Sum of the squared deviations of values from their mean.
+fn squared_deviations(data: &[f64]) -> f64 {
+    let mean = mean(data);
+    let deviations: Vec<_> = data.iter().map(|x| x - mean).collect();
+    let squares: Vec<_> = deviations.iter().map(|d| d * d).collect();
+
+    // The deviations add up to zero, up to the rounding error of the mean.
+    let error = sum(&deviations);
+    sum(&squares) - error * error / data.len() as f64
+}
+
+/// Variance of a sample, which divides by one less than the number of
+/// values. Gives NaN for fewer than two values.
+pub fn sample_variance(data: &[f64]) -> f64 {
+    match data.len() {
+        0 | 1 => f64::NAN,
+        n => squared_deviations(data) / (n - 1) as f64,
+    }
+}
+
+/// Variance of a whole population. Gives NaN for no values.
+pub fn population_variance(data: &[f64]) -> f64 {
+    squared_deviations(data) / data.len() as f64
+}
+
+/// Percentile of sorted values, with `p` from 0 to 100, interpolating
+/// linearly between the nearest ranks. Gives NaN if `p` is out of range or
+/// there are no values.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() || !(0.0..=100.0).contains(&p) {
+        return f64::NAN;
+    }
+
+    let rank = (sorted.len() - 1) as f64 * p / 100.0;
+    let lower = rank.floor() as usize;
+    match sorted.get(lower + 1) {
+        Some(&upper) => sorted[lower] + (rank - lower as f64) * (upper - sorted[lower]),
+        None => sorted[lower],
+    }
+}
+
+/// Number of ways to choose `r` of `n` items, which is zero if `r` is out of
+/// range and NaN if `n` is negative. It is exact unless it overflows, in
+/// which case it is a float.
+pub fn choose(n: i128, r: i128) -> Number {
+    if n < 0 {
+        return Number::Flt(f64::NAN);
+    }
+    if r < 0 || r > n {
+        return Number::Int(0);
+    }
+
+    // Each partial product is itself a binomial coefficient, so the division
+    // is exact.
+    let r = r.min(n - r);
+    match (1..=r).try_fold(1i128, |c, i| c.checked_mul(n - r + i).map(|c| c / i)) {
+        Some(c) => Number::Int(c),
+        None => {
+            let (n, r) = (n as f64, r as f64);
+            Number::Flt((ln_gamma(n + 1.0) - ln_gamma(r + 1.0) - ln_gamma(n - r + 1.0)).exp())
+        }
+    }
+}
+
+/// Number of ways to arrange `r` of `n` items, which is zero if `r` is out of
+/// range and NaN if `n` is negative. It is exact unless it overflows, in
+/// which case it is a float.
+pub fn permutations(n: i128, r: i128) -> Number {
+    if n < 0 {
+        return Number::Flt(f64::NAN);
+    }
+    if r < 0 || r > n {
+        return Number::Int(0);
+    }
+    match (n - r + 1..=n).try_fold(1i128, |p, i| p.checked_mul(i)) {
+        Some(p) => Number::Int(p),
+        None => {
+            let (n, r) = (n as f64, r as f64);
+            Number::Flt((ln_gamma(n + 1.0) - ln_gamma(n - r + 1.0)).exp())
+        }
+    }
+}
+
+/// Gamma function, which is infinite at zero and NaN at negative integers.
+pub fn gamma(x: f64) -> f64 {
+    if x == 0.0 {
+        return f64::INFINITY.copysign(x);
+    }
+    if x < 0.0 && x.fract() == 0.0 {
+        return f64::NAN;
+    }
+
+    // Reflection formula.
+    if x < 0.5 {
+        return PI / ((PI * x).sin() * gamma(1.0 - x));
+    }
+
+    // The powers are split in two, so they do not overflow before they are
+    // multiplied by the exponentials.
+    if x >= STIRLING_SPLIT {
+        // Γ(x) = √(2π / x) (x / e)^x e^stirling_error(x).
+        let power = x.powf(x / 2.0);
+        let scale = (2.0 * PI / x).sqrt() * stirling_error(x).exp();
+        return scale * power * (power * (-x).exp());
+    }
+    let (t, a) = lanczos(x);
+    let power = t.powf((x - 0.5) / 2.0);
+    (2.0 * PI).sqrt() * power * (power * (-t).exp()) * a
+}
+
+/// Natural logarithm of the gamma function for positive `x`.
+pub fn ln_gamma(x: f64) -> f64 {
+    if x < 0.5 {
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    if x >= STIRLING_SPLIT {
+        return 0.5 * (2.0 * PI).ln() + (x - 0.5) * x.ln() - x + stirling_error(x);
+    }
+    let (t, a) = lanczos(x);
+    0.5 * (2.0 * PI).ln() + (x - 0.5) * t.ln() - t + a.ln()
+}
+
+/// Error of Stirling's approximation of `x!`, i.e.
+/// `ln(x!) - ln(√(2πx) (x / e)^x)`, for positive `x`.
+fn stirling_error(x: f64) -> f64 {
+    if x < STIRLING_SPLIT {
+        return ln_gamma(x + 1.0) - 0.5 * (2.0 * PI * x).ln() - x * x.ln() + x;
+    }
+
+    // Stirling's series, whose next term is below an ulp from here on.
+    let (s0, s1, s2, s3, s4) = (
+        1.0 / 12.0,
+        1.0 / 360.0,
+        1.0 / 1260.0,
+        1.0 / 1680.0,
+        1.0 / 1188.0,
+    );
+    let x2 = x * x;
+    (s0 - (s1 - (s2 - (s3 - s4 / x2) / x2) / x2) / x2) / x
+}
+
+/// Deviance `x ln(x / mean) + mean - x` of a count from a mean, without
+/// cancellation when they are close.
+fn deviance(x: f64, mean: f64) -> f64 {
+    if (x - mean).abs() >= 0.1 * (x + mean) {
+        return x * (x / mean).ln() + mean - x;
+    }
+
+    // Series in v = (x - mean) / (x + mean).
+    let v = (x - mean) / (x + mean);
+    let mut sum = (x - mean) * v;
+    let mut term = 2.0 * x * v;
+    for j in 1..MAX_TERMS {
+        term *= v * v;
+        let next = sum + term / (2 * j + 1) as f64;
+        if next == sum {
+            break;
+        }
+        sum = next;
+    }
+    sum
+}
+
+/// Gets the base `t` and the series `a` of the Lanczos approximation at `x`,
+/// where `Γ(x) = √(2π) t^(x - 1/2) e^(-t) a`.
+fn lanczos(x: f64) -> (f64, f64) {
+    let x = x - 1.0;
+    let a = LANCZOS_COEFFICIENTS
+        .iter()
+        .enumerate()
+        .skip(1)
+        .fold(LANCZOS_COEFFICIENTS[0], |a, (i, c)| a + c / (x + i as f64));
+    (x + LANCZOS_G + 0.5, a)
+}
+
+/// Error function.
+pub fn erf(x: f64) -> f64 {
+    match x.abs() < ERF_SPLIT {
+        true => erf_series(x),
+        false => (1.0 - erfc_fraction(x.abs())).copysign(x),
+    }
+}
+
+/// Complementary error function, `1 - erf(x)`, without cancellation for
+/// large `x`.
+pub fn erfc(x: f64) -> f64 {
+    match x < ERF_SPLIT {
+        true => 1.0 - erf(x),
+        false => erfc_fraction(x),
+    }
+}
+
+/// Error function by the series `2/√π e^(-x²) Σ (2x²)^n x / (2n + 1)!!`,
+/// whose terms are all of the same sign.
+fn erf_series(x: f64) -> f64 {
+    let ratio = 2.0 * x * x;
+    let mut term = x;
+    let mut sum = x;
+    for n in 1..MAX_TERMS {
+        term *= ratio / (2 * n + 1) as f64;
+        sum += term;
+        if term.abs() <= sum.abs() * f64::EPSILON {
+            break;
+        }
+    }
+    FRAC_2_SQRT_PI * (-x * x).exp() * sum
+}
+
+/// Complementary error function for positive `x` by the continued fraction
+/// `e^(-x²) / √π / (x + 1/2 / (x + 1 / (x + 3/2 / (x + ...))))`, evaluated
+/// with Lentz's method.
+fn erfc_fraction(x: f64) -> f64 {
+    let tiny = f64::MIN_POSITIVE;
+    let mut f = x;
+    let (mut c, mut d) = (x, 0.0);
+    for n in 1..MAX_TERMS {
+        let a = n as f64 / 2.0;
+        d = x + a * d;
+        d = if d == 0.0 { 1.0 / tiny } else { 1.0 / d };
+        c = x + a / c;
+        if c == 0.0 {
+            c = tiny;
+        }
+        let delta = c * d;
+        f *= delta;
+        if (delta - 1.0).abs() <= f64::EPSILON {
+            break;
+        }
+    }
+    FRAC_2_SQRT_PI / 2.0 * (-x * x).exp() / f
+}
+
+/// Probability density of the normal distribution with mean `mu` and
+/// standard deviation `sigma` at `x`.
+pub fn normal_pdf(x: f64, mu: f64, sigma: f64) -> f64 {
+    if sigma.is_nan() || sigma <= 0.0 {
+        return f64::NAN;
+    }
+    let z = (x - mu) / sigma;
+    (-z * z / 2.0).exp() / (sigma * (2.0 * PI).sqrt())
+}
+
+/// Cumulative distribution function of the normal distribution with mean
+/// `mu` and standard deviation `sigma` at `x`.
+pub fn normal_cdf(x: f64, mu: f64, sigma: f64) -> f64 {
+    if sigma.is_nan() || sigma <= 0.0 {
+        return f64::NAN;
+    }
+    erfc(-(x - mu) / (sigma * 2f64.sqrt())) / 2.0
+}
+
+/// Probability of `k` successes in `n` trials, each with probability `p`.
+pub fn binomial_pdf(k: i128, n: i128, p: f64) -> f64 {
+    if n < 0 || !(0.0..=1.0).contains(&p) {
+        return f64::NAN;
+    }
+    if k < 0 || k > n {
+        return 0.0;
+    }
+
+    // The product of exact factors is only rounded a few times.
+    let q = 1.0 - p;
+    if let Number::Int(c) = choose(n, k) {
+        if c < 1 << f64::MANTISSA_DIGITS && 1.0 - q == p {
+            return c as f64 * p.powf(k as f64) * q.powf((n - k) as f64);
+        }
+    }
+
+    // Zero to the power of zero is one.
+    let (k, n) = (k as f64, n as f64);
+    if k == 0.0 {
+        return (n * (-p).ln_1p()).exp();
+    }
+    if k == n {
+        return p.powf(n);
+    }
+    if p == 0.0 || p == 1.0 {
+        return 0.0;
+    }
+
+    let exponent = stirling_error(n)
+        - stirling_error(k)
+        - stirling_error(n - k)
+        - deviance(k, n * p)
+        - deviance(n - k, n * (1.0 - p));
+    exponent.exp() * (n / (2.0 * PI * k * (n - k))).sqrt()
+}
+
+/// Probability of at most `k` successes in `n` trials, each with
+/// probability `p`.
+pub fn binomial_cdf(k: i128, n: i128, p: f64) -> f64 {
+    if n < 0 || !(0.0..=1.0).contains(&p) {
+        return f64::NAN;
+    }
+    let mean = n as f64 * p;
+    let (start, end) = bulk(mean, (mean * (1.0 - p)).sqrt());
+    if k >= n.min(end) {
+        return 1.0;
+    }
+    let terms: Vec<_> = (start..=k).map(|i| binomial_pdf(i, n, p)).collect();
+    sum(&terms).min(1.0)
+}
+
+/// Probability of `k` events of a Poisson process with mean `lambda`.
+pub fn poisson_pdf(k: i128, lambda: f64) -> f64 {
+    if lambda.is_nan() || lambda < 0.0 {
+        return f64::NAN;
+    }
+    if k < 0 {
+        return 0.0;
+    }
+    if lambda == 0.0 {
+        return if k == 0 { 1.0 } else { 0.0 };
+    }
+    if k == 0 {
+        return (-lambda).exp();
+    }
+    let k = k as f64;
+    (-stirling_error(k) - deviance(k, lambda)).exp() / (2.0 * PI * k).sqrt()
+}
+
+/// Probability of at most `k` events of a Poisson process with mean
+/// `lambda`.
+pub fn poisson_cdf(k: i128, lambda: f64) -> f64 {
+    if lambda.is_nan() || lambda < 0.0 {
+        return f64::NAN;
+    }
+    let (start, end) = bulk(lambda, lambda.sqrt());
+    if k >= end {
+        return 1.0;
+    }
+    let terms: Vec<_> = (start..=k).map(|i| poisson_pdf(i, lambda)).collect();
+    sum(&terms).min(1.0)
+}
+
+/// Gets the first and last counts whose probabilities are not negligible,
+/// given the mean and standard deviation of a distribution. Counts more than
+/// [`TAIL`] standard deviations away from the mean are left out.
+fn bulk(mean: f64, deviation: f64) -> (i128, i128) {
+    let width = TAIL * (deviation + 1.0);
+    let start = (mean - width).floor().max(0.0) as i128;
+    let end = (mean + width).ceil() as i128;
+    (start, end)
+}
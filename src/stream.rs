@@ -0,0 +1,148 @@
+//! This module implements a stream of tokens for [`tilted`](crate).
+//!
+//! A [`TokenStream`] lexes [`Token`]s on demand from a [`Lexer`] and keeps
+//! them, so that any number of tokens can be looked ahead at with
+//! [`TokenStream::peek_nth`], and the stream can be rewound to a
+//! [`Checkpoint`] without lexing again. Unlike iterating over a [`Lexer`],
+//! which ends at the first error, it yields [`LexError`]s.
+//!
+//! ```
+//! use tilted::{Lexer, Operator, TokenKind, TokenStream};
+//!
+//! let mut tokens = TokenStream::new(Lexer::from_source_code("x -> x^2"));
+//! assert_eq!(tokens.peek_nth(1).unwrap().kind, TokenKind::Op(Operator::Arrow));
+//!
+//! let checkpoint = tokens.checkpoint();
+//! let x = tokens.next_token().unwrap();
+//! assert_eq!(&tokens[x.span], "x");
+//!
+//! tokens.rewind(checkpoint);
+//! assert_eq!(tokens.next_token().unwrap(), x);
+//! ```
+
+use std::ops::Index;
+
+use crate::{LexError, Lexer, Span, Token, TokenKind};
+
+/// Special [`Result`] type for the token stream.
+type Result<T> = std::result::Result<T, LexError>;
+
+/// Stream of [`Token`]s from a [`Lexer`], with any number of tokens of
+/// lookahead.
+#[derive(Debug, Clone)]
+pub struct TokenStream {
+    /// The [`Lexer`] that the tokens are lexed from.
+    lexer: Lexer,
+
+    /// Every token lexed so far, ending with EOF once it is reached.
+    tokens: Vec<Token>,
+
+    /// The error that stopped the lexer, if any, which is after every token
+    /// in `tokens`.
+    error: Option<LexError>,
+
+    /// The index in `tokens` of the next token.
+    position: usize,
+}
+
+/// Position in a [`TokenStream`], which it can be rewound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+impl TokenStream {
+    /// Creates a new [`TokenStream`] from a [`Lexer`].
+    pub fn new(lexer: Lexer) -> TokenStream {
+        TokenStream {
+            lexer,
+            tokens: Vec::new(),
+            error: None,
+            position: 0,
+        }
+    }
+
+    /// Consumes the next [`Token`]. At the end of the stream, EOF is returned
+    /// again and again, and so is an error, which is not consumed.
+    pub fn next_token(&mut self) -> Result<Token> {
+        let token = self.peek()?;
+        if token.kind != TokenKind::Eof {
+            self.position += 1;
+        }
+        Ok(token)
+    }
+
+    /// Gets the next [`Token`] without consuming it.
+    pub fn peek(&mut self) -> Result<Token> {
+        self.peek_nth(0)
+    }
+
+    /// Gets the token `k` places after the next one without consuming
+    /// anything, e.g. `peek_nth(0)` is the next token. Past the end of the
+    /// stream, EOF is returned, or the error that stopped the lexer.
+    pub fn peek_nth(&mut self, k: usize) -> Result<Token> {
+        let index = self.position + k;
+        while self.tokens.len() <= index {
+            match self.tokens.last() {
+                Some(token) if token.kind == TokenKind::Eof => return Ok(*token),
+                _ => (),
+            }
+            if let Some(ref error) = self.error {
+                return Err(error.clone());
+            }
+            match self.lexer.lex() {
+                Ok(token) => self.tokens.push(token),
+                Err(error) => self.error = Some(error),
+            }
+        }
+        Ok(self.tokens[index])
+    }
+
+    /// Gets the current position, to rewind to later.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.position)
+    }
+
+    /// Rewinds to a position given by [`TokenStream::checkpoint`], so that
+    /// the tokens after it are yielded again.
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.position = checkpoint.0;
+    }
+
+    /// Rewinds to the start of the stream.
+    pub fn reset(&mut self) {
+        self.rewind(Checkpoint(0));
+    }
+}
+
+impl Index<Span> for TokenStream {
+    type Output = str;
+
+    /// Looks up the source code of a [`Span`], as with a [`Lexer`].
+    fn index(&self, index: Span) -> &Self::Output {
+        &self.lexer[index]
+    }
+}
+
+impl From<Lexer> for TokenStream {
+    fn from(lexer: Lexer) -> Self {
+        TokenStream::new(lexer)
+    }
+}
+
+impl Iterator for TokenStream {
+    type Item = Result<Token>;
+
+    /// Consumes the next [`Token`], ending before EOF, or after yielding an
+    /// error.
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_token() {
+            Ok(token) if token.kind == TokenKind::Eof => None,
+            Err(_) if self.position > self.tokens.len() => None,
+            Err(error) => {
+                // Errors are yielded once, after which the stream ends.
+                self.position = self.tokens.len() + 1;
+                Some(Err(error))
+            }
+            result => Some(result),
+        }
+    }
+}
@@ -0,0 +1,264 @@
+//! This module implements physical units and their dimensions for
+//! [`tilted`](crate).
+//!
+//! Units are looked up in a built-in table, so that no network access or data
+//! file is needed. The table has the SI base and derived units, which take SI
+//! prefixes (e.g. `km`, `mA`, `kPa`), as well as a few other units in common
+//! use, such as `h`, `ft` and `lb`.
+
+use std::{
+    fmt::Display,
+    ops::{Div, Mul},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Dimension of a physical quantity, as the exponents of the SI base
+/// dimensions: length, mass, time, electric current, temperature, amount of
+/// substance and luminous intensity, in that order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Dimension([i8; 7]);
+
+/// A unit of measurement.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Unit {
+    /// Name of this [`Unit`] as written in source code.
+    name: String,
+
+    /// Value of this [`Unit`] in coherent SI units, e.g. 1000 for `km`.
+    scale: f64,
+
+    /// Dimension of this [`Unit`].
+    dimension: Dimension,
+}
+
+/// Symbols of the SI base units, by dimension.
+const BASE_UNITS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+/// Order in which the base units are written, e.g. `kg m/s`.
+const BASE_ORDER: [usize; 7] = [1, 0, 2, 3, 4, 5, 6];
+
+/// SI prefixes and their scales. `da` comes before `d`, so that the longest
+/// prefix is tried first.
+const PREFIXES: &[(&str, f64)] = &[
+    ("da", 1e1),
+    ("Y", 1e24),
+    ("Z", 1e21),
+    ("E", 1e18),
+    ("P", 1e15),
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+    ("a", 1e-18),
+    ("z", 1e-21),
+    ("y", 1e-24),
+];
+
+/// Built-in units: symbol, scale in coherent SI units, dimension, and whether
+/// SI prefixes apply.
+#[rustfmt::skip]
+const UNITS: &[(&str, f64, Dimension, bool)] = &[
+    // SI base units. The kilogram is the gram with a prefix.
+    ("m",   1.0,  Dimension([1, 0, 0, 0, 0, 0, 0]), true),
+    ("g",   1e-3, Dimension([0, 1, 0, 0, 0, 0, 0]), true),
+    ("s",   1.0,  Dimension([0, 0, 1, 0, 0, 0, 0]), true),
+    ("A",   1.0,  Dimension([0, 0, 0, 1, 0, 0, 0]), true),
+    ("K",   1.0,  Dimension([0, 0, 0, 0, 1, 0, 0]), true),
+    ("mol", 1.0,  Dimension([0, 0, 0, 0, 0, 1, 0]), true),
+    ("cd",  1.0,  Dimension([0, 0, 0, 0, 0, 0, 1]), true),
+
+    // SI derived units.
+    ("Hz",  1.0,  Dimension([0, 0, -1, 0, 0, 0, 0]), true),
+    ("N",   1.0,  Dimension([1, 1, -2, 0, 0, 0, 0]), true),
+    ("Pa",  1.0,  Dimension([-1, 1, -2, 0, 0, 0, 0]), true),
+    ("J",   1.0,  Dimension([2, 1, -2, 0, 0, 0, 0]), true),
+    ("W",   1.0,  Dimension([2, 1, -3, 0, 0, 0, 0]), true),
+    ("C",   1.0,  Dimension([0, 0, 1, 1, 0, 0, 0]), true),
+    ("V",   1.0,  Dimension([2, 1, -3, -1, 0, 0, 0]), true),
+    ("F",   1.0,  Dimension([-2, -1, 4, 2, 0, 0, 0]), true),
+    ("ohm", 1.0,  Dimension([2, 1, -3, -2, 0, 0, 0]), true),
+    ("S",   1.0,  Dimension([-2, -1, 3, 2, 0, 0, 0]), true),
+    ("Wb",  1.0,  Dimension([2, 1, -2, -1, 0, 0, 0]), true),
+    ("T",   1.0,  Dimension([0, 1, -2, -1, 0, 0, 0]), true),
+    ("H",   1.0,  Dimension([2, 1, -2, -2, 0, 0, 0]), true),
+
+    // Units accepted for use with the SI.
+    ("L",   1e-3, Dimension([3, 0, 0, 0, 0, 0, 0]), true),
+    ("min", 60.0, Dimension([0, 0, 1, 0, 0, 0, 0]), false),
+    ("h",   3600.0, Dimension([0, 0, 1, 0, 0, 0, 0]), false),
+
+    // Imperial and US customary units.
+    ("in",  0.0254, Dimension([1, 0, 0, 0, 0, 0, 0]), false),
+    ("ft",  0.3048, Dimension([1, 0, 0, 0, 0, 0, 0]), false),
+    ("yd",  0.9144, Dimension([1, 0, 0, 0, 0, 0, 0]), false),
+    ("mi",  1609.344, Dimension([1, 0, 0, 0, 0, 0, 0]), false),
+    ("lb",  0.45359237, Dimension([0, 1, 0, 0, 0, 0, 0]), false),
+    ("oz",  0.028349523125, Dimension([0, 1, 0, 0, 0, 0, 0]), false),
+    ("gal", 3.785411784e-3, Dimension([3, 0, 0, 0, 0, 0, 0]), false),
+];
+
+// -----------------------------------------------------------------------------
+// 1. impls for Dimension.
+// -----------------------------------------------------------------------------
+
+impl Dimension {
+    /// Dimension of pure numbers.
+    pub const NONE: Dimension = Dimension([0; 7]);
+
+    /// Creates a [`Dimension`] from the exponents of the SI base dimensions.
+    pub fn new(exponents: [i8; 7]) -> Dimension {
+        Self(exponents)
+    }
+
+    /// Gets the exponents of the SI base dimensions.
+    pub fn exponents(&self) -> [i8; 7] {
+        self.0
+    }
+
+    /// Checks if this is the dimension of pure numbers.
+    pub fn is_dimensionless(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    /// Raises this [`Dimension`] to a power, returning [`None`] if any
+    /// exponent of the result is not a whole number. The rounding error of
+    /// roots such as `1/3` is ignored.
+    pub fn powf(&self, exponent: f64) -> Option<Dimension> {
+        let mut result = Self::NONE;
+        for (r, d) in result.0.iter_mut().zip(self.0) {
+            let e = d as f64 * exponent;
+            if (e - e.round()).abs() > 1e-9 || e.abs() > i8::MAX as f64 {
+                return None;
+            }
+            *r = e.round() as i8;
+        }
+        Some(result)
+    }
+}
+
+impl Mul for Dimension {
+    type Output = Self;
+    fn mul(mut self, rhs: Self) -> Self::Output {
+        for (l, r) in self.0.iter_mut().zip(rhs.0) {
+            *l = l.saturating_add(r);
+        }
+        self
+    }
+}
+
+impl Div for Dimension {
+    type Output = Self;
+    fn div(mut self, rhs: Self) -> Self::Output {
+        for (l, r) in self.0.iter_mut().zip(rhs.0) {
+            *l = l.saturating_sub(r);
+        }
+        self
+    }
+}
+
+impl Display for Dimension {
+    /// Writes the SI unit of this [`Dimension`], e.g. `kg m/s^2`, or `s^-1`
+    /// without a numerator. Named derived units are preferred over products
+    /// of two or more base units, so the former is written `N`. Pure numbers
+    /// are written `1`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.iter().filter(|e| **e != 0).count() > 1 {
+            let derived = UNITS
+                .iter()
+                .find(|(_, scale, dimension, _)| *scale == 1.0 && dimension == self);
+            if let Some((symbol, ..)) = derived {
+                return write!(f, "{}", symbol);
+            }
+        }
+
+        let power = |i: usize, e: i8| match e {
+            1 => BASE_UNITS[i].to_string(),
+            e => format!("{}^{}", BASE_UNITS[i], e),
+        };
+
+        // Units with negative exponents are written both in the denominator,
+        // e.g. `m/s`, and as powers, e.g. `s^-1`.
+        let (mut numerator, mut denominator, mut reciprocal) = (vec![], vec![], vec![]);
+        for i in BASE_ORDER {
+            match self.0[i] {
+                0 => (),
+                e if e > 0 => numerator.push(power(i, e)),
+                e => {
+                    denominator.push(power(i, -e));
+                    reciprocal.push(power(i, e));
+                }
+            }
+        }
+
+        match (numerator.len(), denominator.len()) {
+            (0, 0) => write!(f, "1"),
+            (0, _) => write!(f, "{}", reciprocal.join(" ")),
+            (_, 0) => write!(f, "{}", numerator.join(" ")),
+            (_, 1) => write!(f, "{}/{}", numerator.join(" "), denominator[0]),
+            _ => write!(f, "{}/({})", numerator.join(" "), denominator.join(" ")),
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// 2. impls for Unit.
+// -----------------------------------------------------------------------------
+
+impl Unit {
+    /// Creates a new [`Unit`] named `name`, worth `scale` in coherent SI units.
+    pub fn new<T: Into<String>>(name: T, scale: f64, dimension: Dimension) -> Unit {
+        Self {
+            name: name.into(),
+            scale,
+            dimension,
+        }
+    }
+
+    /// Looks up a built-in unit by its symbol, possibly with an SI prefix.
+    pub fn lookup(name: &str) -> Option<Unit> {
+        let find = |symbol: &str| UNITS.iter().find(|(s, ..)| *s == symbol);
+
+        // Exact symbols take precedence, e.g. `min` is not a milli-inch.
+        if let Some(&(_, scale, dimension, _)) = find(name) {
+            return Some(Unit::new(name, scale, dimension));
+        }
+
+        PREFIXES.iter().find_map(|(prefix, factor)| {
+            let &(_, scale, dimension, prefixable) = find(name.strip_prefix(prefix)?)?;
+            prefixable.then(|| Unit::new(name, factor * scale, dimension))
+        })
+    }
+
+    /// Gets the name of this [`Unit`].
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the value of this [`Unit`] in coherent SI units.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Gets the dimension of this [`Unit`].
+    pub fn dimension(&self) -> Dimension {
+        self.dimension
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
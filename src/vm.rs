@@ -0,0 +1,602 @@
+//! This module implements a bytecode compiler and a stack-based virtual
+//! machine for [`tilted`](crate).
+//!
+//! Walking an AST costs a virtual call per node. When the same AST is
+//! evaluated many times with different values, it can instead be compiled
+//! once into a [`Program`], which a [`Vm`] runs without allocating after its
+//! first run. Both give exactly the same results, as the [`Vm`] evaluates
+//! every instruction with [`BinaryAction::evaluate`], [`UnaryAction::evaluate`]
+//! and [`BinderAction::evaluate`]. The body of a sum, product or integral is
+//! compiled into a [`Program`] of its own, which is run on an inner [`Vm`]
+//! that is kept between runs too. Equations are solved with [`solve::solve`]
+//! just like [`SolveNode::roots`](crate::SolveNode::roots) does, which
+//! allocates its roots on every run. Vectors, lists, ranges, lambdas and
+//! built-in functions are not compiled at all, but kept as ASTs, which are
+//! evaluated with [`Node::evaluate_with`] in a new [`Environment`], so these
+//! allocate on every run as well.
+//!
+//! Random functions draw from the [`Rng`] of the [`Vm`], which the
+//! sums, products, integrals and equations of a [`Program`] share, so a
+//! [`Program`] draws the same numbers as its AST does when both start from
+//! the same seed.
+
+use crate::{
+    ast::{self, NodeBox, SolveNode},
+    solve::{self, Guess},
+    BinaryAction, BinderAction, Environment, EvalError, Function, Node, NodeView, Number, Rng,
+    UnaryAction,
+};
+
+/// Special [`Result`] type for evaluation.
+type Result<T> = std::result::Result<T, EvalError>;
+
+/// A single instruction of a [`Program`], operating on a stack of [`Number`]s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    /// Pushes the constant at the index in [`Program::constants`].
+    Const(u32),
+
+    /// Pushes the value of the variable at the index in
+    /// [`Program::variables`].
+    Load(u32),
+
+    /// Pops the right-hand side, then the left-hand side operand, and pushes
+    /// the result of the action.
+    Binary(BinaryAction),
+
+    /// Negates the top of the stack.
+    Neg,
+
+    /// Applies a function to the top of the stack.
+    Call(Function),
+
+    /// Pops the upper, then the lower bound, and pushes the result of the
+    /// sum, product or integral at the index in the binders of the
+    /// [`Program`].
+    Bind(u32),
+
+    /// Pops the guess, if any, and pushes the smallest root of the equation
+    /// at the index in the solvers of the [`Program`].
+    Solve(u32),
+
+    /// Pushes the value of the AST at the index in the trees of the
+    /// [`Program`].
+    Tree(u32),
+}
+
+/// An AST compiled into bytecode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    /// Instructions in the order they are run.
+    instructions: Vec<Instruction>,
+
+    /// Constants referred to by [`Instruction::Const`].
+    constants: Vec<Number>,
+
+    /// Names of the variables referred to by [`Instruction::Load`], in the
+    /// order they first appear in the AST.
+    variables: Vec<String>,
+
+    /// Sums, products and integrals referred to by [`Instruction::Bind`].
+    binders: Vec<Binder>,
+
+    /// Equations referred to by [`Instruction::Solve`].
+    solvers: Vec<Solver>,
+
+    /// ASTs referred to by [`Instruction::Tree`].
+    trees: Vec<Tree>,
+
+    /// The largest number of values on the stack while running.
+    stack_size: usize,
+}
+
+/// A sum, product or integral compiled into bytecode.
+#[derive(Debug, Clone, PartialEq)]
+struct Binder {
+    actor: BinderAction,
+
+    /// The body, whose variables are bound by the [`Binder`].
+    body: Program,
+
+    /// Where the value of each variable of the body comes from: the index of
+    /// a variable of the enclosing [`Program`], or [`None`] for the bound
+    /// variable.
+    sources: Vec<Option<u32>>,
+}
+
+/// An equation compiled into bytecode. Its programs share their variables,
+/// the first of which is the variable solved for.
+#[derive(Debug, Clone, PartialEq)]
+struct Solver {
+    /// The equation, which evaluates to its residual.
+    equation: Program,
+
+    /// The derivative of the equation.
+    derivative: Program,
+
+    /// The coefficients of the equation, if it is a polynomial.
+    coefficients: Option<Vec<Program>>,
+
+    /// Number of values of the guess on the stack.
+    guesses: usize,
+
+    /// Where the value of each variable comes from: the index of a variable
+    /// of the enclosing [`Program`], or [`None`] for the variable solved for.
+    sources: Vec<Option<u32>>,
+}
+
+/// An AST that is evaluated as is.
+#[derive(Debug, Clone)]
+struct Tree {
+    node: NodeBox,
+
+    /// Names of the variables of the AST.
+    variables: Vec<String>,
+
+    /// The index of each variable in the enclosing [`Program`].
+    sources: Vec<u32>,
+}
+
+/// A stack-based virtual machine that runs [`Program`]s.
+///
+/// The stack is kept between runs, and so is the scratch space of sums,
+/// products, integrals and equations, so only the first run of a [`Program`]
+/// without equations or ASTs allocates. So is the [`Rng`], which is seeded
+/// with 0 unless it is seeded otherwise, so that each run draws new random
+/// numbers.
+#[derive(Debug, Clone, Default)]
+pub struct Vm {
+    stack: Vec<Number>,
+
+    /// Generator of random numbers.
+    rng: Rng,
+
+    /// Scratch space of the sums, products, integrals and equations run by
+    /// this [`Vm`], created when the first of them runs.
+    scratch: Option<Box<Scratch>>,
+}
+
+/// Scratch space for running the body of a sum, product or integral, or an
+/// equation and its derivative.
+#[derive(Debug, Clone, Default)]
+struct Scratch {
+    /// Values of the variables of a body or an equation.
+    values: Vec<Number>,
+
+    /// Values of the variables of a derivative.
+    derivative: Vec<Number>,
+
+    /// Runs a body, an equation or its coefficients.
+    vm: Vm,
+
+    /// Runs a derivative.
+    dvm: Vm,
+}
+
+impl Program {
+    /// Compiles an AST into a [`Program`].
+    pub fn compile(node: &dyn Node) -> Program {
+        Self::compile_with(node, Vec::new())
+    }
+
+    /// Compiles an AST into a [`Program`] whose variables start with
+    /// `variables`, whether it refers to them or not.
+    fn compile_with(node: &dyn Node, variables: Vec<String>) -> Program {
+        let mut program = Program {
+            instructions: Vec::new(),
+            constants: Vec::new(),
+            variables,
+            binders: Vec::new(),
+            solvers: Vec::new(),
+            trees: Vec::new(),
+            stack_size: 0,
+        };
+        program.emit(node, 0);
+        program
+    }
+
+    /// Gets the instructions of this [`Program`].
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Gets the constants of this [`Program`].
+    pub fn constants(&self) -> &[Number] {
+        &self.constants
+    }
+
+    /// Gets the names of the variables of this [`Program`], in the order
+    /// their values are passed to [`Vm::run`].
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// Runs this [`Program`] once, looking up variables in `env`.
+    pub fn evaluate_with(&self, env: &Environment) -> Result<Number> {
+        let values = self
+            .variables
+            .iter()
+            .map(|name| {
+                env.get(name)
+                    .ok_or_else(|| EvalError::UnboundVariable(name.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Vm::new().with_rng(env.rng().clone()).run(self, &values)
+    }
+
+    /// Emits the instructions of a node, given the stack size before it runs.
+    fn emit(&mut self, node: &dyn Node, depth: usize) {
+        // Symbolic functions are compiled as what they stand for, unless the
+        // call is invalid, which is left to the tree to report.
+        if let NodeView::Call(c) = node.view() {
+            if c.func().is_symbolic() {
+                if let Ok(form) = c.rewrite() {
+                    return self.emit(form.as_ref(), depth);
+                }
+            }
+        }
+
+        match node.view() {
+            NodeView::Plain(p) => self.emit_const(p.value(), depth),
+            NodeView::Unit(u) => self.emit_const(Number::Flt(u.unit().scale()), depth),
+            NodeView::Variable(v) => {
+                let index = self.variable(v.name());
+                self.push(Instruction::Load(index), depth + 1);
+            }
+            NodeView::Unary(u) => {
+                self.emit(u.operand(), depth);
+                match u.actor() {
+                    UnaryAction::Iden => (),
+                    UnaryAction::Neg => self.push(Instruction::Neg, depth + 1),
+                    UnaryAction::Func(func) => self.push(Instruction::Call(func), depth + 1),
+                }
+            }
+            NodeView::Binary(b) => {
+                self.emit(b.left(), depth);
+                self.emit(b.right(), depth + 1);
+                self.push(Instruction::Binary(b.actor()), depth + 1);
+            }
+            NodeView::Binder(b) => {
+                // The body comes first, so that variables keep the order they
+                // appear in.
+                let body = Program::compile(b.body());
+                let sources = body
+                    .variables
+                    .iter()
+                    .map(|name| (name != b.var()).then(|| self.variable(name)))
+                    .collect();
+                self.binders.push(Binder {
+                    actor: b.actor(),
+                    body,
+                    sources,
+                });
+
+                self.emit(b.lower(), depth);
+                self.emit(b.upper(), depth + 1);
+                let index = self.binders.len() - 1;
+                self.push(Instruction::Bind(index as u32), depth + 1);
+            }
+            NodeView::Solve(s) => {
+                let solver = Solver::compile(s);
+                let sources = solver.equation.variables[1..]
+                    .iter()
+                    .map(|name| Some(self.variable(name)));
+                let solver = Solver {
+                    sources: std::iter::once(None).chain(sources).collect(),
+                    ..solver
+                };
+                self.solvers.push(solver);
+
+                for (i, guess) in s.guess().iter().enumerate() {
+                    self.emit(*guess, depth + i);
+                }
+                let index = self.solvers.len() - 1;
+                self.push(Instruction::Solve(index as u32), depth + 1);
+            }
+            NodeView::Vector(_)
+            | NodeView::Call(_)
+            | NodeView::List(_)
+            | NodeView::Range(_)
+            | NodeView::Lambda(_) => {
+                let mut variables: Vec<String> = Vec::new();
+                for child in children(node) {
+                    for name in Program::compile(child).variables {
+                        if !variables.contains(&name) {
+                            variables.push(name);
+                        }
+                    }
+                }
+
+                // The parameters of a lambda are bound when it is called.
+                if let NodeView::Lambda(l) = node.view() {
+                    variables.retain(|name| !l.params().contains(name));
+                }
+                let sources = variables.iter().map(|name| self.variable(name)).collect();
+                self.trees.push(Tree {
+                    node: node.clone_box(),
+                    variables,
+                    sources,
+                });
+
+                let index = self.trees.len() - 1;
+                self.push(Instruction::Tree(index as u32), depth + 1);
+            }
+        }
+    }
+
+    /// Gets the index of a variable, adding it if it is new.
+    fn variable(&mut self, name: &str) -> u32 {
+        let index = match self.variables.iter().position(|n| n == name) {
+            Some(index) => index,
+            None => {
+                self.variables.push(name.to_string());
+                self.variables.len() - 1
+            }
+        };
+        index as u32
+    }
+
+    /// Emits a constant, given the stack size before it runs.
+    fn emit_const(&mut self, value: Number, depth: usize) {
+        let index = match self.constants.iter().position(|c| c.identical(&value)) {
+            Some(index) => index,
+            None => {
+                self.constants.push(value);
+                self.constants.len() - 1
+            }
+        };
+        self.push(Instruction::Const(index as u32), depth + 1);
+    }
+
+    /// Appends an instruction, given the stack size after it runs.
+    fn push(&mut self, instruction: Instruction, depth: usize) {
+        self.instructions.push(instruction);
+        self.stack_size = self.stack_size.max(depth);
+    }
+}
+
+impl Vm {
+    /// Creates a new [`Vm`] with an empty stack.
+    pub fn new() -> Vm {
+        Self::default()
+    }
+
+    /// Seeds the generator of random numbers, consuming and returning this
+    /// [`Vm`] for chaining.
+    pub fn with_seed(self, seed: u64) -> Vm {
+        self.with_rng(Rng::new(seed))
+    }
+
+    /// Sets the generator of random numbers, e.g. to share one with an
+    /// [`Environment`], consuming and returning this [`Vm`] for chaining.
+    pub fn with_rng(mut self, rng: Rng) -> Vm {
+        self.rng = rng;
+        self
+    }
+
+    /// Gets the generator of random numbers.
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    /// Runs a [`Program`], with `values` of its variables in the order of
+    /// [`Program::variables`].
+    pub fn run(&mut self, program: &Program, values: &[Number]) -> Result<Number> {
+        if let Some(name) = program.variables.get(values.len()) {
+            return Err(EvalError::UnboundVariable(name.clone()));
+        }
+
+        self.stack.clear();
+        self.stack.reserve(program.stack_size);
+
+        for instruction in &program.instructions {
+            match *instruction {
+                Instruction::Const(index) => self.stack.push(program.constants[index as usize]),
+                Instruction::Load(index) => self.stack.push(values[index as usize]),
+                Instruction::Binary(actor) => {
+                    let right = self.pop()?;
+                    let left = self.pop()?;
+                    self.stack.push(actor.evaluate(left, right));
+                }
+                Instruction::Neg => {
+                    let operand = self.pop()?;
+                    self.stack.push(UnaryAction::Neg.evaluate(operand));
+                }
+                Instruction::Call(func) => {
+                    let operand = self.pop()?;
+                    self.stack.push(UnaryAction::Func(func).evaluate(operand));
+                }
+                Instruction::Bind(index) => {
+                    let upper = self.pop()?;
+                    let lower = self.pop()?;
+                    let binder = &program.binders[index as usize];
+                    let scratch = self.scratch.get_or_insert_with(Default::default);
+                    let result = binder.run(lower, upper, values, &self.rng, scratch)?;
+                    self.stack.push(result);
+                }
+                Instruction::Solve(index) => {
+                    let solver = &program.solvers[index as usize];
+                    let guess = match solver.guesses {
+                        0 => Guess::None,
+                        1 => Guess::Point(self.pop()?),
+                        _ => {
+                            let upper = self.pop()?;
+                            Guess::Interval(self.pop()?, upper)
+                        }
+                    };
+                    let scratch = self.scratch.get_or_insert_with(Default::default);
+                    let result = solver.run(guess, values, &self.rng, scratch)?;
+                    self.stack.push(result);
+                }
+                Instruction::Tree(index) => {
+                    let result = program.trees[index as usize].run(values, &self.rng)?;
+                    self.stack.push(result);
+                }
+            }
+        }
+
+        self.pop()
+    }
+
+    /// Pops the top of the stack, which a compiled [`Program`] guarantees to
+    /// exist.
+    fn pop(&mut self) -> Result<Number> {
+        self.stack.pop().ok_or(EvalError::StackUnderflow)
+    }
+}
+
+impl Binder {
+    /// Evaluates this [`Binder`] with `values` of the variables of the
+    /// enclosing [`Program`].
+    fn run(
+        &self,
+        lower: Number,
+        upper: Number,
+        values: &[Number],
+        rng: &Rng,
+        scratch: &mut Scratch,
+    ) -> Result<Number> {
+        let Scratch {
+            values: inner, vm, ..
+        } = scratch;
+        fill(inner, &self.sources, values);
+        let slot = self.sources.iter().position(Option::is_none);
+
+        vm.rng = rng.clone();
+        self.actor.evaluate(lower, upper, |value| {
+            if let Some(slot) = slot {
+                inner[slot] = value;
+            }
+            vm.run(&self.body, inner)
+        })
+    }
+}
+
+impl PartialEq for Tree {
+    fn eq(&self, other: &Self) -> bool {
+        self.node.as_ref() == other.node.as_ref()
+            && self.variables == other.variables
+            && self.sources == other.sources
+    }
+}
+
+impl Tree {
+    /// Evaluates this [`Tree`] with `values` of the variables of the
+    /// enclosing [`Program`].
+    fn run(&self, values: &[Number], rng: &Rng) -> Result<Number> {
+        let mut env = Environment::new().with_rng(rng.clone());
+        for (name, &index) in self.variables.iter().zip(&self.sources) {
+            env.bind(name.as_str(), values[index as usize]);
+        }
+        self.node.evaluate_with(&env)
+    }
+}
+
+impl Solver {
+    /// Compiles an equation, leaving the sources to the enclosing [`Program`].
+    fn compile(s: &SolveNode) -> Solver {
+        let equation = Program::compile_with(s.equation(), vec![s.var().to_string()]);
+        let derivative = Program::compile_with(
+            ast::diff(s.equation(), s.var()).as_ref(),
+            equation.variables.clone(),
+        );
+
+        // The coefficients are free of the variable solved for, and of any
+        // variable the equation does not have.
+        let coefficients =
+            ast::coefficients(s.equation(), s.var(), SolveNode::MAX_DEGREE).map(|cs| {
+                cs.iter()
+                    .map(|c| Program::compile_with(c.as_ref(), equation.variables.clone()))
+                    .collect()
+            });
+
+        Solver {
+            equation,
+            derivative,
+            coefficients,
+            guesses: s.guess().len(),
+            sources: Vec::new(),
+        }
+    }
+
+    /// Finds the smallest root of this [`Solver`] with `values` of the
+    /// variables of the enclosing [`Program`].
+    fn run(
+        &self,
+        guess: Guess<Number>,
+        values: &[Number],
+        rng: &Rng,
+        scratch: &mut Scratch,
+    ) -> Result<Number> {
+        let Scratch {
+            values: inner,
+            derivative,
+            vm,
+            dvm,
+        } = scratch;
+        fill(inner, &self.sources, values);
+
+        vm.rng = rng.clone();
+        let coefficients = match &self.coefficients {
+            Some(cs) => Some(
+                cs.iter()
+                    .map(|c| vm.run(c, inner).map(float))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            None => None,
+        };
+
+        derivative.clone_from(inner);
+        dvm.rng = rng.clone();
+        let roots = solve::solve(
+            |x| {
+                inner[0] = Number::Flt(x);
+                vm.run(&self.equation, inner).map(float)
+            },
+            |x| {
+                derivative[0] = Number::Flt(x);
+                dvm.run(&self.derivative, derivative).map(float)
+            },
+            coefficients.as_deref(),
+            guess.map(float),
+        )?;
+
+        match roots.first() {
+            Some(&root) => Ok(Number::Flt(root)),
+            None => Err(EvalError::NoRealRoot),
+        }
+    }
+}
+
+/// Fills `inner` with the values of the variables of a body or an equation,
+/// given their `sources` in the enclosing [`Program`] and its `values`. The
+/// bound variable, or the variable solved for, is 0 until it is set.
+fn fill(inner: &mut Vec<Number>, sources: &[Option<u32>], values: &[Number]) {
+    inner.clear();
+    inner.extend(
+        sources
+            .iter()
+            .map(|source| source.map_or(Number::Int(0), |index| values[index as usize])),
+    );
+}
+
+/// Gets the sub-trees of a node that is kept as an AST, e.g. the elements of
+/// a vector or the arguments of a built-in function.
+fn children(node: &dyn Node) -> Vec<&dyn Node> {
+    match node.view() {
+        NodeView::Vector(v) => v.elements().collect(),
+        NodeView::Call(c) => c.args().collect(),
+        NodeView::List(l) => l.elements().collect(),
+        NodeView::Range(r) => vec![r.start(), r.end()],
+        NodeView::Lambda(l) => vec![l.body()],
+        _ => Vec::new(),
+    }
+}
+
+fn float(n: Number) -> f64 {
+    match n {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
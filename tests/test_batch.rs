@@ -0,0 +1,287 @@
+use proptest::prelude::*;
+use tilted::{
+    ast::{evaluate_batch, Column},
+    BinaryAction, BinaryNode, Environment, EvalError, Function, Lexer, NodeBox, Number, Parser,
+    PlainNode, UnaryAction, UnaryNode, VariableNode,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn same(a: Number, b: Number) -> bool {
+    match (a, b) {
+        (Number::Flt(a), Number::Flt(b)) if a.is_nan() && b.is_nan() => true,
+        _ => a.identical(&b),
+    }
+}
+
+fn batch(source: &str, columns: &[(&str, Column<'_>)], len: usize) -> Vec<Number> {
+    let mut output = vec![Number::Int(0); len];
+    evaluate_batch(parse(source).as_ref(), columns, &mut output).unwrap();
+    output
+}
+
+macro_rules! make_batch_test {
+    ($name: ident, $source: literal, [$(($var: literal, $values: expr)),*], $expected: expr) => {
+        #[test]
+        fn $name() {
+            let columns = [$(($var, Column::from(&$values[..]))),*];
+            let expected: Vec<Number> = $expected;
+
+            let actual = batch($source, &columns, expected.len());
+            assert_eq!(actual.len(), expected.len());
+            for (actual, expected) in actual.into_iter().zip(expected) {
+                assert!(same(actual, expected), "{} != {}", actual, expected);
+            }
+        }
+    };
+}
+
+make_batch_test!(
+    test_batch_int,
+    "x * 2 + y",
+    [("x", [1i128, 2, 3]), ("y", [10i128, 20, 30])],
+    vec![Number::Int(12), Number::Int(24), Number::Int(36)]
+);
+make_batch_test!(
+    test_batch_int_overflow,
+    "x + y",
+    [("x", [1i128, i128::MAX]), ("y", [2i128, 1])],
+    vec![Number::Int(3), Number::Flt(i128::MAX as f64 + 1.0)]
+);
+make_batch_test!(
+    test_batch_int_overflow_ops,
+    "x * x - y / (0 - 1)",
+    [("x", [2i128, 1i128 << 64]), ("y", [i128::MIN, 4])],
+    vec![
+        Number::Flt(4.0 + i128::MIN as f64),
+        Number::Flt(2f64.powi(128) + 4.0)
+    ]
+);
+make_batch_test!(
+    test_batch_neg_overflow,
+    "-x",
+    [("x", [1i128, i128::MIN])],
+    vec![Number::Int(-1), Number::Flt(-(i128::MIN as f64))]
+);
+make_batch_test!(
+    test_batch_flt,
+    "x / 2 - 0.5",
+    [("x", [1.0f64, 2.0, 3.0])],
+    vec![Number::Flt(0.0), Number::Flt(0.5), Number::Flt(1.0)]
+);
+make_batch_test!(
+    test_batch_mixed_columns,
+    "x + y",
+    [("x", [1i128, 2]), ("y", [0.5f64, 0.25])],
+    vec![Number::Flt(1.5), Number::Flt(2.25)]
+);
+make_batch_test!(
+    test_batch_binder,
+    "sum(k x, k, 1, 3) + y",
+    [("x", [1i128, 2]), ("y", [0.5f64, 1.0])],
+    vec![Number::Flt(6.5), Number::Flt(13.0)]
+);
+make_batch_test!(
+    test_batch_binder_int,
+    "prod(x, k, 1, 3)",
+    [("x", [2i128, 3])],
+    vec![Number::Int(8), Number::Int(27)]
+);
+make_batch_test!(
+    test_batch_solve,
+    "solve(t^2 = x, t, 0, 10)",
+    [("x", [4i128, 36])],
+    vec![Number::Flt(2.0), Number::Flt(6.0)]
+);
+make_batch_test!(
+    test_batch_int_div_zero,
+    "6 / x",
+    [("x", [2i128, 0, -3])],
+    vec![Number::Int(3), Number::Flt(f64::NAN), Number::Int(-2)]
+);
+make_batch_test!(
+    test_batch_flt_div_zero,
+    "1.0 / x",
+    [("x", [2.0f64, 0.0, -0.0])],
+    vec![
+        Number::Flt(0.5),
+        Number::Flt(f64::NAN),
+        Number::Flt(f64::NAN)
+    ]
+);
+make_batch_test!(
+    test_batch_pow_mixed_sign,
+    "2^x",
+    [("x", [3i128, -1, 0])],
+    vec![Number::Int(8), Number::Flt(0.5), Number::Int(1)]
+);
+make_batch_test!(
+    test_batch_pow_negative,
+    "2^x",
+    [("x", [-1i128, -2])],
+    vec![Number::Flt(0.5), Number::Flt(0.25)]
+);
+make_batch_test!(
+    test_batch_nan,
+    "x + 1",
+    [("x", [f64::NAN, 1.0])],
+    vec![Number::Flt(f64::NAN), Number::Flt(2.0)]
+);
+make_batch_test!(
+    test_batch_func,
+    "sin(x) + sec(x)",
+    [("x", [0i128, 0])],
+    vec![Number::Flt(1.0), Number::Flt(1.0)]
+);
+make_batch_test!(
+    test_batch_constant,
+    "-(2^3)",
+    [],
+    vec![Number::Int(-8), Number::Int(-8)]
+);
+
+#[test]
+fn test_batch_empty() {
+    let values: [f64; 0] = [];
+    assert!(batch("x + 1", &[("x", Column::from(&values[..]))], 0).is_empty());
+}
+
+#[test]
+fn test_batch_length_mismatch() {
+    let node = parse("x + y");
+    let columns = [
+        ("x", Column::from(&[1i128, 2, 3][..])),
+        ("y", Column::from(&[1.0f64, 2.0][..])),
+    ];
+    let mut output = [Number::Int(0); 3];
+
+    let err = evaluate_batch(node.as_ref(), &columns, &mut output).unwrap_err();
+    assert!(matches!(err, EvalError::ValueCountMismatch(3, 2)));
+}
+
+#[test]
+fn test_batch_unbound() {
+    let node = parse("x + y");
+    let columns = [("x", Column::from(&[1i128][..]))];
+    let mut output = [Number::Int(0)];
+
+    let err = evaluate_batch(node.as_ref(), &columns, &mut output).unwrap_err();
+    assert!(matches!(err, EvalError::UnboundVariable(ref name) if name == "y"));
+}
+
+// -----------------------------------------------------------------------------
+// Property tests.
+// -----------------------------------------------------------------------------
+
+/// Generates ASTs whose integer arithmetic cannot overflow: integers are at
+/// most 3 in magnitude, exponents are at most 3 and trees are at most 4 deep.
+fn arb_node() -> impl Strategy<Value = NodeBox> {
+    let leaf = prop_oneof![
+        (-3i128..=3).prop_map(|n| Box::new(PlainNode::new(Number::Int(n))) as NodeBox),
+        prop_oneof![
+            -10.0..10.0f64,
+            prop::sample::select(vec![0.0, -0.0, f64::NAN, f64::INFINITY]),
+        ]
+        .prop_map(|n| Box::new(PlainNode::new(Number::Flt(n))) as NodeBox),
+        prop::sample::select(vec!["x", "y"])
+            .prop_map(|n| Box::new(VariableNode::new(n)) as NodeBox),
+    ];
+    let exponent = prop_oneof![
+        (-2i128..=3).prop_map(|n| Box::new(PlainNode::new(Number::Int(n))) as NodeBox),
+        (-2.0..2.0f64).prop_map(|n| Box::new(PlainNode::new(Number::Flt(n))) as NodeBox),
+        prop::sample::select(vec!["x", "y"])
+            .prop_map(|n| Box::new(VariableNode::new(n)) as NodeBox),
+    ];
+    let binary = prop::sample::select(vec![
+        BinaryAction::Add,
+        BinaryAction::Sub,
+        BinaryAction::Mul,
+        BinaryAction::Div,
+    ]);
+    let unary = prop::sample::select(vec![
+        UnaryAction::Neg,
+        UnaryAction::Iden,
+        UnaryAction::Func(Function::Sin),
+        UnaryAction::Func(Function::Cos),
+        UnaryAction::Func(Function::Tan),
+        UnaryAction::Func(Function::Csc),
+        UnaryAction::Func(Function::Sec),
+        UnaryAction::Func(Function::Cot),
+        UnaryAction::Func(Function::Asin),
+        UnaryAction::Func(Function::Acos),
+        UnaryAction::Func(Function::Atan),
+        UnaryAction::Func(Function::Acsc),
+        UnaryAction::Func(Function::Asec),
+        UnaryAction::Func(Function::Acot),
+        UnaryAction::Func(Function::Ln),
+    ]);
+
+    leaf.prop_recursive(4, 32, 2, move |inner| {
+        prop_oneof![
+            (inner.clone(), binary.clone(), inner.clone())
+                .prop_map(|(l, a, r)| Box::new(BinaryNode::new(l, a, r)) as NodeBox),
+            (inner.clone(), exponent.clone()).prop_map(|(l, r)| {
+                Box::new(BinaryNode::new(l, BinaryAction::Pow, r)) as NodeBox
+            }),
+            (unary.clone(), inner).prop_map(|(a, o)| Box::new(UnaryNode::new(a, o)) as NodeBox),
+        ]
+    })
+}
+
+/// Generates a column of either kind, with `len` rows.
+fn arb_column(len: usize) -> impl Strategy<Value = Result<Vec<i128>, Vec<f64>>> {
+    prop_oneof![
+        prop::collection::vec(-3i128..=3, len).prop_map(Ok),
+        prop::collection::vec(
+            prop_oneof![
+                -10.0..10.0f64,
+                prop::sample::select(vec![0.0, -0.0, f64::NAN, f64::NEG_INFINITY]),
+            ],
+            len
+        )
+        .prop_map(Err),
+    ]
+}
+
+fn column(values: &Result<Vec<i128>, Vec<f64>>) -> Column<'_> {
+    match values {
+        Ok(values) => Column::Int(values),
+        Err(values) => Column::Flt(values),
+    }
+}
+
+fn row(values: &Result<Vec<i128>, Vec<f64>>, i: usize) -> Number {
+    match values {
+        Ok(values) => Number::Int(values[i]),
+        Err(values) => Number::Flt(values[i]),
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_batch_matches_scalar(
+        (node, x, y) in (0usize..8).prop_flat_map(|len| (arb_node(), arb_column(len), arb_column(len)))
+    ) {
+        let len = match &x {
+            Ok(values) => values.len(),
+            Err(values) => values.len(),
+        };
+        let columns = [("x", column(&x)), ("y", column(&y))];
+        let mut output = vec![Number::Int(0); len];
+        evaluate_batch(node.as_ref(), &columns, &mut output).unwrap();
+
+        for (i, actual) in output.into_iter().enumerate() {
+            let env = Environment::new().with("x", row(&x, i)).with("y", row(&y, i));
+            let expected = node.evaluate_with(&env).unwrap();
+
+            prop_assert!(
+                same(expected, actual),
+                "{:?} at row {}: scalar gave {}, batch gave {}", node, i, expected, actual
+            );
+        }
+    }
+}
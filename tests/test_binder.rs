@@ -0,0 +1,215 @@
+use proptest::prelude::*;
+use tilted::{
+    quadrature::{self, TOLERANCE},
+    Environment, EvalError, Expression, Lexer, NodeBox, Number, Parser, Program,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn evaluate(source: &str) -> Result<Number, EvalError> {
+    parse(source).evaluate_with(&Environment::new())
+}
+
+fn float(n: Number) -> f64 {
+    match n {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
+
+macro_rules! make_binder_test {
+    // Checks that the result is exactly `$expected`.
+    ($name: ident, $source: literal, $expected: expr) => {
+        #[test]
+        fn $name() {
+            let actual = evaluate($source).unwrap();
+            assert!(actual.identical(&$expected), "{} != {}", actual, $expected);
+        }
+    };
+    // Checks that the result is within the tolerance of `$expected`.
+    (~ $name: ident, $source: literal, $expected: expr) => {
+        #[test]
+        fn $name() {
+            let actual = float(evaluate($source).unwrap());
+            let expected: f64 = $expected;
+            assert!(
+                (expected - actual).abs() <= 10.0 * TOLERANCE * expected.abs().max(1.0),
+                "expected {}, found {}",
+                expected,
+                actual
+            );
+        }
+    };
+    // Checks the error.
+    (E: $name: ident, $source: literal, $expected: pat) => {
+        #[test]
+        fn $name() {
+            let err = evaluate($source).unwrap_err();
+            assert!(matches!(err, $expected), "{:?}", err);
+        }
+    };
+}
+
+make_binder_test!(test_binder_sum, "sum(k^2, k, 1, 100)", Number::Int(338350));
+make_binder_test!(test_binder_prod, "prod(k, k, 1, 10)", Number::Int(3628800));
+make_binder_test!(test_binder_sum_empty, "sum(k, k, 1, 0)", Number::Int(0));
+make_binder_test!(test_binder_prod_empty, "prod(k, k, 5, 4)", Number::Int(1));
+make_binder_test!(
+    test_binder_sum_flt,
+    "sum(1 / 2.0^k, k, 1, 3)",
+    Number::Flt(0.875)
+);
+make_binder_test!(
+    test_binder_whole_bounds,
+    "sum(k, k, 1.0, 4.0)",
+    Number::Int(10)
+);
+make_binder_test!(
+    test_binder_negative_bounds,
+    "sum(k, k, -3, 2)",
+    Number::Int(-3)
+);
+make_binder_test!(
+    test_binder_nested,
+    "sum(prod(j, j, 1, k), k, 1, 5)",
+    Number::Int(153)
+);
+make_binder_test!(
+    test_binder_shadowed,
+    "sum(prod(k, k, 1, k), k, 1, 3)",
+    Number::Int(9)
+);
+make_binder_test!(
+    test_binder_overflow,
+    "prod(k, k, 1, 40)",
+    Number::Flt((1..=40).map(|k| k as f64).product())
+);
+make_binder_test!(
+    test_binder_integral_empty,
+    "integrate(1 / x, x, 1, 1)",
+    Number::Flt(0.0)
+);
+make_binder_test!(test_binder_unit_name, "sum(m, m, 1, 3)", Number::Int(6));
+make_binder_test!(
+    test_binder_unit_name_coefficient,
+    "prod(2s, s, 1, 3)",
+    Number::Int(48)
+);
+
+make_binder_test!(~ test_binder_integral_sin, "integrate(sin(x), x, 0, pi)", 2.0);
+make_binder_test!(~ test_binder_integral_reversed, "integrate(x^2, x, 3, 0)", -9.0);
+make_binder_test!(~ test_binder_integral_singular, "integrate(1 / x^0.5, x, 0, 1)", 2.0);
+make_binder_test!(~ test_binder_integral_nested, "integrate(integrate(x y, y, 0, x), x, 0, 2)", 2.0);
+make_binder_test!(~ test_binder_integral_oscillating, "integrate(cos(50x), x, 0, 1)", 50.0f64.sin() / 50.0);
+make_binder_test!(~ test_binder_integral_unit_name, "integrate(s, s, 0, 1)", 0.5);
+
+make_binder_test!(E: test_binder_fractional_bound, "sum(k, k, 1, 2.5)", EvalError::IntegerExpected(_));
+make_binder_test!(E: test_binder_divergent, "integrate(1 / x, x, 0, 1)", EvalError::ToleranceNotMet(_));
+make_binder_test!(E: test_binder_not_finite, "integrate(x, x, 0, 1 / 0.0)", EvalError::ToleranceNotMet(_));
+make_binder_test!(E: test_binder_unbound, "sum(k n, k, 1, 3)", EvalError::UnboundVariable(_));
+
+#[test]
+fn test_binder_free_variable() {
+    let node = parse("sum(k x, k, 1, n)");
+    let env = Environment::new().with("x", 2).with("n", 4);
+
+    assert_eq!(node.evaluate_with(&env).unwrap(), Number::Int(20));
+    assert_eq!(Program::compile(node.as_ref()).variables(), ["x", "n"]);
+}
+
+#[test]
+fn test_binder_expression() {
+    let expression = Expression::compile("integrate(t^2, t, 0, x)").unwrap();
+    let actual = float(expression.eval(&[("x", 3)]).unwrap());
+
+    assert_eq!(expression.variables(), ["x"]);
+    assert!((actual - 9.0).abs() < 1e-9, "{}", actual);
+}
+
+#[test]
+fn test_quadrature_error_estimate() {
+    let actual = quadrature::integrate(|x| Ok(x.exp()), 0.0, 1.0, 1e-12).unwrap();
+    let expected = 1.0f64.exp() - 1.0;
+
+    assert!((actual.value() - expected).abs() <= actual.error() + f64::EPSILON);
+    assert!(actual.error() <= 1e-12 * expected);
+}
+
+#[test]
+fn test_quadrature_stops_at_error() {
+    let mut calls = 0;
+    let err = quadrature::integrate(
+        |x| {
+            calls += 1;
+            match x < 0.5 {
+                true => Ok(x),
+                false => Err(EvalError::UnboundVariable("y".to_string())),
+            }
+        },
+        0.0,
+        1.0,
+        TOLERANCE,
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, EvalError::UnboundVariable(_)));
+    assert!(calls <= 15, "{}", calls);
+}
+
+// -----------------------------------------------------------------------------
+// Property tests.
+// -----------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn test_binder_sum_closed_form(lower in -1000i128..1000, count in 0i128..1000) {
+        let upper = lower + count - 1;
+        let source = format!("sum(k^3, k, {}, {})", lower, upper);
+        let actual = evaluate(&source).unwrap();
+
+        // C(n) - C(n - 1) = n^3 for every integer n, where C(n) = (n (n + 1) / 2)^2.
+        let cubes = |n: i128| (n * (n + 1) / 2).pow(2);
+        let expected = cubes(upper) - cubes(lower - 1);
+        prop_assert_eq!(actual, Number::Int(expected));
+    }
+
+    #[test]
+    fn test_binder_integral_polynomial(
+        a in -5.0..5.0f64,
+        b in -5.0..5.0f64,
+        c in -5.0..5.0f64,
+    ) {
+        let source = format!("integrate({} x^2 + {} x, x, 0, {})", a, b, c);
+        let actual = float(evaluate(&source).unwrap());
+        let expected = a * c.powi(3) / 3.0 + b * c.powi(2) / 2.0;
+
+        prop_assert!(
+            (expected - actual).abs() <= 10.0 * TOLERANCE * expected.abs().max(1.0),
+            "{}: expected {}, found {}", source, expected, actual
+        );
+    }
+
+    #[test]
+    fn test_binder_vm_matches_tree(
+        x in -3i128..3,
+        n in -2i128..8,
+        source in prop::sample::select(vec![
+            "sum(k x, k, 1, n)",
+            "prod(x + k, k, 1, n) / 2",
+            "sum(prod(j - x, j, 0, k), k, 0, n)",
+            "integrate(sin(t x), t, 0, n)",
+            "sum(integrate(t^k, t, 0, x), k, 0, n)",
+        ]),
+    ) {
+        let node = parse(source);
+        let env = Environment::new().with("x", x).with("n", n);
+
+        let expected = node.evaluate_with(&env).unwrap();
+        let actual = Program::compile(node.as_ref()).evaluate_with(&env).unwrap();
+        prop_assert!(expected.identical(&actual), "{}: {} != {}", source, expected, actual);
+    }
+}
@@ -0,0 +1,190 @@
+use tilted::{
+    ast::{diff, rewrite, to_infix, Simplifier},
+    Builtin, CallNode, Environment, EvalError, Lexer, Node, NodeBox, Number, ParseError, Parser,
+    PlainNode, Program, TilError, VariableNode,
+};
+
+fn parse(source: &str) -> Result<NodeBox, TilError> {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse()
+}
+
+fn value(node: &NodeBox, x: f64) -> f64 {
+    let env = Environment::new().with("x", x);
+    match node.evaluate_with(&env).unwrap() {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
+
+macro_rules! make_diff_test {
+    ($name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            let actual = diff(parse($source).unwrap().as_ref(), "x");
+            let expected = Simplifier::new()
+                .assume_real(true)
+                .simplify(parse($expected).unwrap().as_ref());
+
+            assert_eq!(actual.as_ref(), expected.as_ref());
+        }
+    };
+    (N: $name: ident, $source: literal, [$($x: expr),*]) => {
+        #[test]
+        fn $name() {
+            let node = parse($source).unwrap();
+            let derivative = diff(node.as_ref(), "x");
+
+            $(
+                // Central difference.
+                let h = 1e-6;
+                let expected = (value(&node, $x + h) - value(&node, $x - h)) / (2.0 * h);
+                let actual = value(&derivative, $x);
+
+                assert!(
+                    (expected - actual).abs() < 1e-6 * expected.abs().max(1.0),
+                    "d/dx {} at {}: expected {}, found {}", $source, $x, expected, actual
+                );
+            )*
+        }
+    };
+}
+
+make_diff_test!(test_diff_constant, "5", "0");
+make_diff_test!(test_diff_other_var, "y^2", "0");
+make_diff_test!(test_diff_var, "x", "1.0");
+make_diff_test!(test_diff_linear, "3x + 2", "3.0");
+make_diff_test!(test_diff_div_constant, "x / 2", "0.5");
+make_diff_test!(test_diff_power, "x^3", "3x^2");
+make_diff_test!(test_diff_product, "x^2 * sin(x)", "2*x*sin(x) + x^2*cos(x)");
+make_diff_test!(test_diff_quotient, "1 / x", "-1 / x^2");
+make_diff_test!(test_diff_like_terms, "x * x", "2x");
+make_diff_test!(test_diff_chain, "sin(x^2)", "cos(x^2) * (2x)");
+make_diff_test!(test_diff_exponential, "2^x", "2^x ln(2)");
+make_diff_test!(test_diff_general_power, "x^x", "x^x (ln(x) + x / x)");
+make_diff_test!(test_diff_shadowed, "sum(x, x, 1, 3)", "0");
+make_diff_test!(test_diff_sum, "sum(k x, k, 1, n)", "sum(k * 1.0, k, 1, n)");
+make_diff_test!(test_diff_equation, "x^2 = 3x + y", "2x = 3.0");
+make_diff_test!(test_diff_equation_side, "y = x", "0 = 1.0");
+make_diff_test!(test_diff_solve_shadowed, "solve(x^2 = 2, x, 0, 2)", "0");
+make_diff_test!(test_diff_solve_guess, "solve(t = 1, t, x)", "0");
+make_diff_test!(test_diff_list, "{x^2, 3}", "{2x, 0 * 3}");
+make_diff_test!(
+    test_diff_map_lambda,
+    "map(t -> x t^2, {1, 2})",
+    "map(t -> 1.0 t^2, {1, 2})"
+);
+make_diff_test!(
+    test_diff_map_chain,
+    "map(t -> t^2, {x, 3})",
+    "map(t -> 2t * 1.0, {x, 3}) * {1.0, 0 * 3}"
+);
+make_diff_test!(test_diff_len, "len({x, 2x})", "0");
+
+make_diff_test!(N: test_diff_num_sin, "sin(3x + 1)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_cos, "cos(3x + 1)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_tan, "tan(0.5x + 1)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_sec, "sec(0.5x + 1)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_csc, "csc(0.5x + 1)", [0.0, 0.5, -1.0]);
+make_diff_test!(N: test_diff_num_cot, "cot(0.5x + 1)", [0.0, 0.5, -1.0]);
+make_diff_test!(N: test_diff_num_asin, "asin(0.5x)", [0.0, 0.5, -1.5]);
+make_diff_test!(N: test_diff_num_acos, "acos(0.5x)", [0.0, 0.5, -1.5]);
+make_diff_test!(N: test_diff_num_atan, "atan(3x)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_asec, "asec(x^2 + 1.5)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_sum, "sum(sin(k x), k, 1, 4)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_prod, "prod(x + k, k, 1, 3)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_integral, "integrate(x t, t, 0, x^2)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_integral_bound, "integrate(2^t, t, sin(x), 1)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_solve, "solve(t^3 + t = x, t)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_solve_newton, "solve(cos(t) = x t, t, 0.5)", [0.5, 1.0, 2.0]);
+make_diff_test!(N: test_diff_num_acsc, "acsc(x^2 + 1.5)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_acot, "acot(2x)", [0.3, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_ln, "ln(x^2 + 1)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_sqrt, "sqrt(x^2 + 1)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_quotient, "sin(x) / (x^2 + 1)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_var_power, "(x^2 + 1)^sin(x)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_neg, "-x^3 + --x", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_mean, "mean(x, x^2, 3)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_mean_list, "mean({sin(x), 2x})", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_erf, "erf(x^2)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_normpdf, "normpdf(2x, 1, 3)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_normcdf, "normcdf(x^2)", [0.0, 0.5, -2.0]);
+make_diff_test!(N: test_diff_num_call, "diff(t sin(x), t) + diff(x^3, x)", [0.0, 0.5, -2.0]);
+
+#[test]
+fn test_diff_form() {
+    let actual = rewrite(parse("diff(x^2 * sin(x), x) + 1").unwrap().as_ref());
+    let expected = parse("2*x*sin(x) + x^2*cos(x) + 1").unwrap();
+
+    assert_eq!(actual.as_ref(), expected.as_ref());
+}
+
+#[test]
+fn test_diff_form_nested() {
+    let actual = rewrite(parse("diff(diff(x^3, x), x)").unwrap().as_ref());
+    let expected = parse("6x").unwrap();
+
+    assert_eq!(actual.as_ref(), expected.as_ref());
+}
+
+#[test]
+fn test_diff_form_deferred() {
+    let node = parse("diff(x^3, x) + 1").unwrap();
+    let env = Environment::new().with("x", 2);
+
+    assert_eq!(to_infix(node.as_ref()), "diff(x^3, x) + 1");
+    assert_eq!(node.evaluate_with(&env).unwrap(), Number::Flt(13.0));
+    assert_eq!(
+        Program::compile(node.as_ref()).evaluate_with(&env).unwrap(),
+        Number::Flt(13.0)
+    );
+
+    let simplified = Simplifier::new().assume_real(true).simplify(node.as_ref());
+    assert_eq!(to_infix(simplified.as_ref()), "3 * x^2 + 1");
+}
+
+#[test]
+fn test_diff_form_in_binder() {
+    let node = parse("sum(diff(k x^2, x), k, 1, 3)").unwrap();
+    let env = Environment::new().with("x", 0.5);
+
+    assert_eq!(node.evaluate_with(&env).unwrap(), Number::Flt(6.0));
+}
+
+#[test]
+fn test_diff_form_value() {
+    let args: Vec<NodeBox> = vec![
+        Box::new(VariableNode::new("x")),
+        Box::new(PlainNode::new(Number::Int(2))),
+    ];
+    let node = CallNode::new(Builtin::Diff, args);
+    let err = node.evaluate_with(&Environment::new()).unwrap_err();
+    assert!(matches!(err, EvalError::VariableExpected("diff")));
+}
+
+#[test]
+fn test_diff_form_unit_name() {
+    let node = parse("diff(g^3, g)").unwrap();
+    let env = Environment::new().with("g", 2);
+
+    assert_eq!(node.evaluate_with(&env).unwrap(), Number::Flt(12.0));
+}
+
+#[test]
+fn test_diff_form_not_variable() {
+    let err = parse("diff(x^2, 2)").unwrap_err();
+    assert!(matches!(
+        err,
+        TilError::Parse(ParseError::VariableExpected(10))
+    ));
+}
+
+#[test]
+fn test_diff_form_argument_count() {
+    let err = parse("diff(x^2)").unwrap_err();
+    assert!(matches!(
+        err,
+        TilError::Parse(ParseError::ArgumentCountMismatch("diff", 2, 1))
+    ));
+}
@@ -0,0 +1,67 @@
+use tilted::{
+    ast::{to_dot, DotPrinter},
+    Environment, Lexer, NodeBox, Parser,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+#[test]
+fn test_dot_structure() {
+    let expected = "\
+digraph ast {
+    ordering=out;
+    n0 [label=\"-\"];
+    n1 [label=\"2\"];
+    n0 -> n1;
+    n2 [label=\"sin\"];
+    n3 [label=\"x\"];
+    n2 -> n3;
+    n0 -> n2;
+}
+";
+
+    assert_eq!(to_dot(parse("2 - sin(x)").as_ref()), expected);
+}
+
+#[test]
+fn test_dot_unary() {
+    let dot = to_dot(parse("-x^2").as_ref());
+
+    assert!(dot.contains("n0 [label=\"-\"];"));
+    assert!(dot.contains("n1 [label=\"^\"];"));
+    assert!(dot.contains("n0 -> n1;"));
+}
+
+#[test]
+fn test_dot_solve() {
+    let dot = to_dot(parse("solve(x = 1, x, 0)").as_ref());
+
+    assert!(dot.contains("n0 [label=\"solve x\"];"));
+    assert!(dot.contains("n1 [label=\"=\"];"));
+    assert!(dot.contains("n0 -> n1;"));
+    assert!(dot.contains("n4 [label=\"0\"];"));
+    assert!(dot.contains("n0 -> n4;"));
+}
+
+#[test]
+fn test_dot_annotate() {
+    let env = Environment::new().with("x", 3);
+    let dot = DotPrinter::new()
+        .annotate(&env)
+        .print(parse("2 * x").as_ref());
+
+    assert!(dot.contains("n0 [label=\"*\\nvalue: 6\\nspan: 0..=4\"];"));
+    assert!(dot.contains("n2 [label=\"x\\nvalue: 3\\nspan: 4..=4\"];"));
+}
+
+#[test]
+fn test_dot_annotate_unbound() {
+    let env = Environment::new();
+    let dot = DotPrinter::new().annotate(&env).print(parse("y").as_ref());
+
+    assert!(dot.contains("value: Variable 'y' is not bound to a value"));
+}
@@ -0,0 +1,176 @@
+use proptest::prelude::*;
+use tilted::{
+    ast::{diff, evaluate_dual},
+    Environment, EvalError, Lexer, NodeBox, Number, Parser,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn float(n: Number) -> f64 {
+    match n {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
+
+fn close(expected: f64, actual: f64) -> bool {
+    (expected.is_nan() && actual.is_nan())
+        || expected == actual
+        || (expected - actual).abs() < 1e-9 * expected.abs().max(1.0)
+}
+
+macro_rules! make_dual_test {
+    // Compares with the symbolic derivative, at each value of `x`.
+    ($name: ident, $source: literal, [$($x: expr),*]) => {
+        #[test]
+        fn $name() {
+            let node = parse($source);
+            let derivative = diff(node.as_ref(), "x");
+
+            $(
+                let env = Environment::new().with("x", $x);
+                let dual = evaluate_dual(node.as_ref(), &env, &["x"]).unwrap();
+
+                let expected = float(derivative.evaluate_with(&env).unwrap());
+                let actual = dual.derivatives()[0];
+                assert!(
+                    close(expected, actual),
+                    "d/dx {} at {}: expected {}, found {}", $source, $x, expected, actual
+                );
+            )*
+        }
+    };
+}
+
+make_dual_test!(test_dual_polynomial, "3x^3 - 2x + 1", [-2.0, 0.0, 1.5]);
+make_dual_test!(test_dual_quotient, "(x + 1) / (x^2 + 1)", [-1.0, 0.5, 3.0]);
+make_dual_test!(test_dual_exponential, "2^x", [-1.0, 0.0, 2.5]);
+make_dual_test!(test_dual_general_pow, "x^x", [0.5, 1.0, 2.0]);
+make_dual_test!(test_dual_sin, "sin(x^2)", [-1.0, 0.0, 2.0]);
+make_dual_test!(test_dual_cos, "cos(2x)", [-1.0, 0.0, 2.0]);
+make_dual_test!(test_dual_tan, "tan(x)", [-1.0, 0.0, 1.2]);
+make_dual_test!(test_dual_sec, "sec(x)", [-1.0, 0.3, 1.2]);
+make_dual_test!(test_dual_csc, "csc(x)", [-1.0, 0.3, 1.2]);
+make_dual_test!(test_dual_cot, "cot(x)", [-1.0, 0.3, 1.2]);
+make_dual_test!(test_dual_asin, "asin(x)", [-0.5, 0.0, 0.9]);
+make_dual_test!(test_dual_acos, "acos(x)", [-0.5, 0.0, 0.9]);
+make_dual_test!(test_dual_atan, "atan(x)", [-3.0, 0.0, 0.9]);
+make_dual_test!(test_dual_asec, "asec(x)", [-3.0, 1.5, 4.0]);
+make_dual_test!(test_dual_acsc, "acsc(x)", [-3.0, 1.5, 4.0]);
+make_dual_test!(test_dual_acot, "acot(x)", [-3.0, 0.5, 4.0]);
+make_dual_test!(test_dual_ln, "ln(x^2 + 1)", [-3.0, 0.0, 4.0]);
+make_dual_test!(test_dual_sqrt, "sqrt(x^2 + 1)", [-3.0, 0.0, 4.0]);
+make_dual_test!(test_dual_sum, "sum(x^k / k, k, 1, 5)", [-1.0, 0.0, 1.5]);
+make_dual_test!(test_dual_prod, "prod(x - k, k, 1, 3)", [-1.0, 0.0, 1.5]);
+make_dual_test!(test_dual_solve, "solve(t^2 = x, t, 0, 10)", [0.5, 1.0, 4.0]);
+make_dual_test!(
+    test_dual_solve_newton,
+    "solve(cos(t) = x t, t, 0.5)",
+    [0.5, 1.0, 2.0]
+);
+make_dual_test!(
+    test_dual_solve_shadowed,
+    "solve(x^3 = 2, x) * x",
+    [-1.0, 0.0, 1.5]
+);
+make_dual_test!(
+    test_dual_integral,
+    "integrate(sin(x t), t, 0, x)",
+    [-1.0, 0.0, 1.5]
+);
+
+#[test]
+fn test_dual_gradient() {
+    let node = parse("x^2 * y + sin(y) / x");
+    let env = Environment::new().with("x", 2.0).with("y", 3.0);
+
+    let dual = evaluate_dual(node.as_ref(), &env, &["x", "y", "z"]).unwrap();
+    assert_eq!(dual.value(), node.evaluate_with(&env).unwrap());
+
+    let (x, y) = (2.0f64, 3.0f64);
+    let expected = [2.0 * x * y - y.sin() / (x * x), x * x + y.cos() / x, 0.0];
+    for (expected, actual) in expected.into_iter().zip(dual.derivatives()) {
+        assert!(close(expected, *actual), "{} != {}", expected, actual);
+    }
+}
+
+#[test]
+fn test_dual_value_is_scalar() {
+    let node = parse("7 / x + x^2");
+    let env = Environment::new().with("x", 2);
+
+    let dual = evaluate_dual(node.as_ref(), &env, &["x"]).unwrap();
+    assert!(dual.value().identical(&Number::Int(7)));
+
+    // Derivatives are taken over the reals: -7/4 + 4.
+    assert_eq!(dual.derivatives(), [2.25]);
+}
+
+#[test]
+fn test_dual_div_zero() {
+    let node = parse("1 / x");
+    let env = Environment::new().with("x", 0.0);
+
+    let dual = evaluate_dual(node.as_ref(), &env, &["x"]).unwrap();
+    assert!(dual.derivatives()[0].is_nan());
+}
+
+#[test]
+fn test_dual_pow_zero() {
+    let node = parse("x^0");
+    let env = Environment::new().with("x", 0.0);
+
+    let dual = evaluate_dual(node.as_ref(), &env, &["x"]).unwrap();
+    assert_eq!(dual.value(), Number::Flt(1.0));
+    assert_eq!(dual.derivatives(), [0.0]);
+}
+
+#[test]
+fn test_dual_unbound() {
+    let node = parse("x + y");
+    let env = Environment::new().with("x", 1);
+
+    let err = evaluate_dual(node.as_ref(), &env, &["x"]).unwrap_err();
+    assert!(matches!(err, EvalError::UnboundVariable(ref name) if name == "y"));
+}
+
+// -----------------------------------------------------------------------------
+// Property tests.
+// -----------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn test_dual_matches_finite_difference(
+        a in -3.0..3.0f64,
+        b in -3.0..3.0f64,
+        x in 0.1..2.0f64,
+        source in prop::sample::select(vec![
+            "a*x^2 + b*x",
+            "sin(a*x) * cos(b + x)",
+            "ln(x) / (1 + a*a*x*x)",
+            "atan(a*x - b) + x^b",
+        ]),
+    ) {
+        let node = parse(source);
+        let value = |x: f64| {
+            let env = Environment::new().with("a", a).with("b", b).with("x", x);
+            float(node.evaluate_with(&env).unwrap())
+        };
+
+        let env = Environment::new().with("a", a).with("b", b).with("x", x);
+        let dual = evaluate_dual(node.as_ref(), &env, &["x"]).unwrap();
+
+        // Central difference.
+        let h = 1e-6;
+        let expected = (value(x + h) - value(x - h)) / (2.0 * h);
+        let actual = dual.derivatives()[0];
+        prop_assert!(
+            (expected - actual).abs() < 1e-5 * expected.abs().max(1.0),
+            "d/dx {} at {}: expected {}, found {}", source, x, expected, actual
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use tilted::{
+    ast::{evaluate_exact, to_latex, to_mathml},
+    Environment, EvalError, Lexer, NodeBox, Parser,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+macro_rules! make_exact_test {
+    // Checks the exact result.
+    ($name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            let exact = evaluate_exact(parse($source).as_ref(), &Environment::new()).unwrap();
+            assert!(exact.is_exact(), "{} is not exact", exact);
+            assert_eq!(exact.to_string(), $expected);
+        }
+    };
+    // Checks a result that falls back to a float.
+    (F: $name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            let exact = evaluate_exact(parse($source).as_ref(), &Environment::new()).unwrap();
+            assert!(!exact.is_exact(), "{} is exact", exact);
+            assert_eq!(exact.to_string(), $expected);
+        }
+    };
+    // Checks the error.
+    (E: $name: ident, $source: literal, $expected: pat) => {
+        #[test]
+        fn $name() {
+            let err = evaluate_exact(parse($source).as_ref(), &Environment::new()).unwrap_err();
+            assert!(matches!(err, $expected), "{:?}", err);
+        }
+    };
+}
+
+make_exact_test!(test_exact_sin_pi_6, "sin(pi / 6)", "1 / 2");
+make_exact_test!(test_exact_cos_pi_4, "cos(pi / 4)", "sqrt(2) / 2");
+make_exact_test!(test_exact_sin_pi_3, "sin(pi / 3)", "sqrt(3) / 2");
+make_exact_test!(test_exact_cos_pi, "cos(pi)", "-1");
+make_exact_test!(test_exact_sin_pi, "sin(pi)", "0");
+make_exact_test!(
+    test_exact_sin_pi_12,
+    "sin(pi / 12)",
+    "(sqrt(6) - sqrt(2)) / 4"
+);
+make_exact_test!(
+    test_exact_cos_pi_12,
+    "cos(pi / 12)",
+    "(sqrt(6) + sqrt(2)) / 4"
+);
+make_exact_test!(test_exact_tan_pi_12, "tan(pi / 12)", "2 - sqrt(3)");
+make_exact_test!(test_exact_tan_pi_3, "tan(pi / 3)", "sqrt(3)");
+make_exact_test!(test_exact_tan_pi_6, "tan(pi / 6)", "sqrt(3) / 3");
+make_exact_test!(test_exact_sec_pi_4, "sec(pi / 4)", "sqrt(2)");
+make_exact_test!(test_exact_cot_5pi_12, "cot(5 pi / 12)", "2 - sqrt(3)");
+make_exact_test!(test_exact_csc_7pi_6, "csc(7 pi / 6)", "-2");
+make_exact_test!(test_exact_sin_negative, "sin(-pi / 4)", "-sqrt(2) / 2");
+make_exact_test!(test_exact_cos_large, "cos(-17 pi / 3)", "1 / 2");
+make_exact_test!(test_exact_cos_sum, "cos(pi / 4 + pi / 2)", "-sqrt(2) / 2");
+make_exact_test!(test_exact_asin, "asin(1 / 2)", "pi / 6");
+make_exact_test!(test_exact_acos, "acos(-sqrt(2) / 2)", "3 * pi / 4");
+make_exact_test!(test_exact_atan, "atan(-1)", "-pi / 4");
+make_exact_test!(test_exact_atan_pi_12, "atan(2 - sqrt(3))", "pi / 12");
+make_exact_test!(test_exact_asec, "asec(2)", "pi / 3");
+make_exact_test!(
+    test_exact_round_trip,
+    "asin(sin(pi / 5 + pi / 20))",
+    "pi / 4"
+);
+make_exact_test!(test_exact_sqrt, "sqrt(8)", "2 * sqrt(2)");
+make_exact_test!(test_exact_sqrt_fraction, "sqrt(3 / 4)", "sqrt(3) / 2");
+make_exact_test!(test_exact_sqrt_square, "sqrt(0.25)", "1 / 2");
+make_exact_test!(test_exact_half_power, "12^(1 / 2)", "2 * sqrt(3)");
+make_exact_test!(test_exact_power, "(1 + sqrt(2))^2", "3 + 2 * sqrt(2)");
+make_exact_test!(
+    test_exact_negative_power,
+    "(1 + sqrt(2))^(-1)",
+    "sqrt(2) - 1"
+);
+make_exact_test!(test_exact_division, "1 / 3 + 1 / 6", "1 / 2");
+make_exact_test!(test_exact_vulgar_fraction, "⅔ + ¼", "11 / 12");
+make_exact_test!(test_exact_mixed_number, "3 ½", "7 / 2");
+make_exact_test!(test_exact_decimal, "0.1 + 0.2", "3 / 10");
+make_exact_test!(
+    test_exact_rationalise,
+    "1 / (sqrt(2) + sqrt(3) + sqrt(5))",
+    "(2 * sqrt(3) + 3 * sqrt(2) - sqrt(30)) / 12"
+);
+make_exact_test!(test_exact_pi_multiple, "2 pi / 3 - pi", "-pi / 3");
+make_exact_test!(test_exact_pi_ratio, "pi / (pi / 4)", "4");
+make_exact_test!(test_exact_ln_one, "ln(1)", "0");
+make_exact_test!(test_exact_integer_builtin, "gcd(12, 18) / 4", "3 / 2");
+make_exact_test!(test_exact_cos_pi_5, "cos(pi / 5)", "(1 + sqrt(5)) / 4");
+make_exact_test!(test_exact_sin_pi_10, "sin(pi / 10)", "(sqrt(5) - 1) / 4");
+make_exact_test!(test_exact_cos_3pi_5, "cos(3 pi / 5)", "(1 - sqrt(5)) / 4");
+make_exact_test!(test_exact_sec_2pi_5, "sec(2 pi / 5)", "1 + sqrt(5)");
+make_exact_test!(test_exact_acos_pi_5, "acos((1 + sqrt(5)) / 4)", "pi / 5");
+make_exact_test!(test_exact_asin_pi_10, "asin((sqrt(5) - 1) / 4)", "pi / 10");
+make_exact_test!(F: test_exact_tan_pi_10, "tan(pi / 10)", "0.3249196962329063");
+make_exact_test!(
+    test_exact_pi_decimal,
+    "3.141592653589793",
+    "3141592653589793 / 1000000000000000"
+);
+make_exact_test!(F: test_exact_pi_float_sin, "sin(3.141592653589793)", "0.00000000000000012246467991473532");
+make_exact_test!(F: test_exact_sin_one, "sin(1)", "0.8414709848078965");
+make_exact_test!(F: test_exact_sin_pi_5, "sin(pi / 5)", "0.5877852522924731");
+make_exact_test!(F: test_exact_sqrt_negative, "sqrt(-4)", "NaN");
+make_exact_test!(F: test_exact_pi_squared, "pi^2", "9.869604401089358");
+make_exact_test!(E: test_exact_tan_pole, "tan(pi / 2)", EvalError::Pole("tan", _));
+make_exact_test!(E: test_exact_sec_pole, "sec(-pi / 2)", EvalError::Pole("sec", _));
+make_exact_test!(E: test_exact_csc_pole, "csc(pi)", EvalError::Pole("csc", _));
+make_exact_test!(E: test_exact_cot_pole, "cot(0)", EvalError::Pole("cot", _));
+make_exact_test!(E: test_exact_tan_pole_turns, "tan(7 pi / 2)", EvalError::Pole("tan", _));
+make_exact_test!(F: test_exact_division_by_zero, "sqrt(2) / 0", "NaN");
+make_exact_test!(E: test_exact_unbound, "sin(x)", EvalError::UnboundVariable(_));
+
+#[test]
+fn test_exact_pole_message() {
+    let err = evaluate_exact(parse("sec(3 pi / 2)").as_ref(), &Environment::new()).unwrap_err();
+    assert_eq!(err.to_string(), "'sec' is undefined at 3 * pi / 2");
+}
+
+#[test]
+fn test_exact_variable() {
+    let env = Environment::new().with("x", 0.5);
+    let exact = evaluate_exact(parse("acos(x)").as_ref(), &env).unwrap();
+    assert_eq!(exact.to_string(), "pi / 3");
+}
+
+#[test]
+fn test_exact_render() {
+    let exact = evaluate_exact(parse("sin(pi / 12)").as_ref(), &Environment::new()).unwrap();
+    assert_eq!(
+        to_latex(exact.to_node().as_ref()),
+        r"\frac{\sqrt{6} - \sqrt{2}}{4}"
+    );
+
+    let exact = evaluate_exact(parse("acos(-1 / 2)").as_ref(), &Environment::new()).unwrap();
+    assert_eq!(to_latex(exact.to_node().as_ref()), r"\frac{2 \cdot \pi}{3}");
+    assert!(to_mathml(exact.to_node().as_ref()).contains(
+        "<mfrac><mrow><mn>2</mn><mo>\u{22C5}</mo><mi>\u{3C0}</mi></mrow><mn>3</mn></mfrac>"
+    ));
+}
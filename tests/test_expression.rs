@@ -0,0 +1,91 @@
+use std::{sync::Arc, thread};
+
+use tilted::{EvalError, Expression, Number, ParseError, TilError};
+
+#[test]
+fn test_expression_variables() {
+    let expr = Expression::compile("a*x^2 + b*x + a").unwrap();
+
+    assert_eq!(expr.variables(), ["a", "x", "b"]);
+    assert_eq!(expr.source(), "a*x^2 + b*x + a");
+}
+
+#[test]
+fn test_expression_eval_named() {
+    let expr = Expression::compile("a*x^2 + b").unwrap();
+
+    let value = expr.eval(&[("x", 2), ("b", 1), ("a", 3), ("unused", 0)]);
+    assert_eq!(value.unwrap(), Number::Int(13));
+}
+
+#[test]
+fn test_expression_eval_positional() {
+    let expr = Expression::compile("y / x").unwrap();
+
+    assert_eq!(
+        expr.eval_positional(&[1.0, 4.0]).unwrap(),
+        Number::Flt(0.25)
+    );
+    assert!(matches!(
+        expr.eval_positional(&[1.0]).unwrap_err(),
+        EvalError::ValueCountMismatch(2, 1)
+    ));
+}
+
+#[test]
+fn test_expression_eval_unbound() {
+    let expr = Expression::compile("x + y").unwrap();
+
+    assert!(matches!(
+        expr.eval(&[("x", 1)]).unwrap_err(),
+        EvalError::UnboundVariable(ref name) if name == "y"
+    ));
+}
+
+#[test]
+fn test_expression_constant() {
+    let expr = Expression::compile("2^10").unwrap();
+
+    assert!(expr.variables().is_empty());
+    assert_eq!(expr.eval::<i32>(&[]).unwrap(), Number::Int(1024));
+}
+
+#[test]
+fn test_expression_invalid() {
+    assert!(matches!(
+        Expression::compile("1 +").unwrap_err(),
+        TilError::Parse(ParseError::UnexpectedEOF)
+    ));
+}
+
+#[test]
+fn test_expression_threads() {
+    fn assert_shareable<T: Clone + Send + Sync>() {}
+    assert_shareable::<Expression>();
+
+    let expr = Arc::new(Expression::compile("x^2").unwrap());
+    let handles = (0..4)
+        .map(|i| {
+            let expr = Arc::clone(&expr);
+            thread::spawn(move || expr.eval(&[("x", i)]).unwrap())
+        })
+        .collect::<Vec<_>>();
+
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(handle.join().unwrap(), Number::Int((i * i) as i128));
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_expression_serde() {
+    let expr = Expression::compile("sin(x) + 1").unwrap();
+
+    let json = serde_json::to_string(&expr).unwrap();
+    assert_eq!(json, "\"sin(x) + 1\"");
+
+    let expr: Expression = serde_json::from_str(&json).unwrap();
+    assert_eq!(expr.eval(&[("x", 0.0)]).unwrap(), Number::Flt(1.0));
+
+    assert!(serde_json::from_str::<Expression>("\"1 +\"").is_err());
+}
@@ -0,0 +1,291 @@
+use tilted::{
+    ast::{evaluate_quantity, evaluate_value, Uncertain},
+    Environment, Lexer, NodeBox, Notation, Number, NumberFormat, Parser,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+macro_rules! make_format_test {
+    // Checks a number written in a notation.
+    ($name: ident, $notation: expr, $n: expr, $expected: literal) => {
+        #[test]
+        fn $name() {
+            let format = NumberFormat::new().notation($notation);
+            assert_eq!(format.format(Number::from($n)), $expected);
+        }
+    };
+    // Checks a number written with a format.
+    (F: $name: ident, $format: expr, $n: expr, $expected: literal) => {
+        #[test]
+        fn $name() {
+            assert_eq!($format.format(Number::from($n)), $expected);
+        }
+    };
+}
+
+make_format_test!(
+    test_format_auto_float,
+    Notation::Auto,
+    0.1 + 0.2,
+    "0.30000000000000004"
+);
+make_format_test!(test_format_auto_int, Notation::Auto, -42, "-42");
+make_format_test!(test_format_fixed, Notation::Fixed(2), 0.1 + 0.2, "0.30");
+make_format_test!(test_format_fixed_round, Notation::Fixed(1), 0.25, "0.2");
+make_format_test!(test_format_fixed_zero, Notation::Fixed(0), -2.5, "-2");
+make_format_test!(test_format_fixed_int, Notation::Fixed(3), 7, "7.000");
+make_format_test!(
+    test_format_fixed_negative_zero,
+    Notation::Fixed(2),
+    -0.0,
+    "-0.00"
+);
+make_format_test!(test_format_sig, Notation::Significant(3), 0.0015, "0.00150");
+make_format_test!(
+    test_format_sig_large,
+    Notation::Significant(2),
+    123456.0,
+    "120000"
+);
+make_format_test!(test_format_sig_carry, Notation::Significant(2), 9.96, "10");
+make_format_test!(
+    test_format_sig_int,
+    Notation::Significant(3),
+    123456,
+    "123000"
+);
+make_format_test!(
+    test_format_sig_int_carry,
+    Notation::Significant(2),
+    -999,
+    "-1000"
+);
+make_format_test!(
+    test_format_sig_int_pad,
+    Notation::Significant(4),
+    12,
+    "12.00"
+);
+make_format_test!(test_format_sig_zero, Notation::Significant(3), 0, "0.00");
+make_format_test!(test_format_sig_none, Notation::Significant(0), 17, "20");
+make_format_test!(
+    test_format_sci,
+    Notation::Scientific(None),
+    0.00015,
+    "1.5e-4"
+);
+make_format_test!(
+    test_format_sci_figures,
+    Notation::Scientific(Some(3)),
+    0.00015,
+    "1.50e-4"
+);
+make_format_test!(
+    test_format_sci_int,
+    Notation::Scientific(None),
+    1200,
+    "1.2e3"
+);
+make_format_test!(test_format_sci_zero, Notation::Scientific(None), 0.0, "0e0");
+make_format_test!(
+    test_format_sci_negative,
+    Notation::Scientific(Some(2)),
+    -5.55e100,
+    "-5.5e100"
+);
+make_format_test!(
+    test_format_sci_int_exact,
+    Notation::Scientific(None),
+    i128::MAX,
+    "1.70141183460469231731687303715884105727e38"
+);
+make_format_test!(
+    test_format_eng,
+    Notation::Engineering(None),
+    0.00015,
+    "150e-6"
+);
+make_format_test!(
+    test_format_eng_figures,
+    Notation::Engineering(Some(4)),
+    0.00015,
+    "150.0e-6"
+);
+make_format_test!(
+    test_format_eng_pad,
+    Notation::Engineering(Some(1)),
+    12345,
+    "10e3"
+);
+make_format_test!(
+    test_format_eng_int,
+    Notation::Engineering(None),
+    -1234567,
+    "-1.234567e6"
+);
+make_format_test!(
+    test_format_eng_small,
+    Notation::Engineering(None),
+    1.5,
+    "1.5e0"
+);
+make_format_test!(test_format_nan, Notation::Fixed(2), f64::NAN, "NaN");
+make_format_test!(
+    test_format_infinity,
+    Notation::Scientific(None),
+    f64::NEG_INFINITY,
+    "-inf"
+);
+make_format_test!(
+    F: test_format_thousands,
+    NumberFormat::new().thousands(true),
+    1234567.5,
+    "1,234,567.5"
+);
+make_format_test!(
+    F: test_format_thousands_short,
+    NumberFormat::new().thousands(true),
+    -123,
+    "-123"
+);
+make_format_test!(
+    F: test_format_thousands_int,
+    NumberFormat::new().thousands(true),
+    -1234,
+    "-1,234"
+);
+make_format_test!(
+    F: test_format_decimal_comma,
+    NumberFormat::new().decimal_comma(true),
+    3.25,
+    "3,25"
+);
+make_format_test!(
+    F: test_format_decimal_comma_thousands,
+    NumberFormat::new()
+        .notation(Notation::Fixed(2))
+        .thousands(true)
+        .decimal_comma(true),
+    1234567.891,
+    "1.234.567,89"
+);
+make_format_test!(
+    F: test_format_decimal_comma_sci,
+    NumberFormat::new()
+        .notation(Notation::Scientific(None))
+        .decimal_comma(true),
+    12500,
+    "1,25e4"
+);
+
+#[test]
+fn test_format_auto_display() {
+    // The default format agrees with `Display`.
+    let format = NumberFormat::default();
+    for n in [0.1, -0.0, 1e21, 1e-7, 123.456, f64::MAX, f64::MIN_POSITIVE] {
+        assert_eq!(format.format(Number::Flt(n)), Number::Flt(n).to_string());
+    }
+    for n in [0, -1, i128::MAX, i128::MIN] {
+        assert_eq!(format.format(Number::Int(n)), Number::Int(n).to_string());
+    }
+}
+
+#[test]
+fn test_format_value() {
+    let env = Environment::new();
+    let value = evaluate_value(parse("{[1, 2.5], [[1, 2], [3, 4]], 1..2}").as_ref(), &env);
+    let format = NumberFormat::new().notation(Notation::Fixed(1));
+    assert_eq!(
+        format.format_value(&value.unwrap()),
+        "{[1.0, 2.5], [[1.0, 2.0], [3.0, 4.0]], {1.0, 2.0}}"
+    );
+
+    let value = evaluate_value(parse("[1.5, 2]").as_ref(), &env).unwrap();
+    let format = NumberFormat::new().decimal_comma(true);
+    assert_eq!(format.format_value(&value), "[1,5; 2]");
+    assert_eq!(NumberFormat::new().format_value(&value), value.to_string());
+}
+
+#[test]
+fn test_format_quantity() {
+    let env = Environment::new();
+    let quantity = evaluate_quantity(parse("1.5 m/s to km/h").as_ref(), &env).unwrap();
+    let format = NumberFormat::new().notation(Notation::Fixed(2));
+    assert_eq!(format.format_quantity(&quantity), "5.40 km/h");
+    assert_eq!(
+        NumberFormat::new().format_quantity(&quantity),
+        quantity.to_string()
+    );
+
+    let quantity = evaluate_quantity(parse("3 km").as_ref(), &env).unwrap();
+    let format = NumberFormat::new().notation(Notation::Engineering(None));
+    assert_eq!(format.format_quantity(&quantity), "3e3 m");
+}
+
+#[test]
+fn test_format_notation_parse() {
+    let notations = [
+        ("auto", Notation::Auto),
+        ("fixed:2", Notation::Fixed(2)),
+        ("sig:3", Notation::Significant(3)),
+        ("sci", Notation::Scientific(None)),
+        ("sci:4", Notation::Scientific(Some(4))),
+        ("eng", Notation::Engineering(None)),
+        ("eng:5", Notation::Engineering(Some(5))),
+    ];
+    for (source, notation) in notations {
+        assert_eq!(source.parse::<Notation>(), Ok(notation));
+        assert_eq!(notation.to_string(), source);
+    }
+
+    for source in ["fixed", "sig", "auto:1", "sci:x", "sci:-1", "hex", ""] {
+        assert!(source.parse::<Notation>().is_err(), "{}", source);
+    }
+}
+
+#[test]
+fn test_format_notation_precision() {
+    for source in ["fixed:0", "fixed:40", "sig:1", "sig:40", "sci:40", "eng:1"] {
+        assert!(source.parse::<Notation>().is_ok(), "{}", source);
+    }
+
+    for source in ["sig:0", "sci:0", "eng:0"] {
+        let err = source.parse::<Notation>().unwrap_err();
+        assert!(err.contains("at least 1 significant figure"), "{}", err);
+    }
+    for source in ["fixed:41", "fixed:400", "sig:41", "eng:1000"] {
+        let err = source.parse::<Notation>().unwrap_err();
+        assert!(err.contains("at most 40"), "{}", err);
+    }
+}
+
+#[test]
+fn test_format_uncertain() {
+    let uncertain = Uncertain::new(12345.678, 0.21);
+    assert_eq!(
+        NumberFormat::new().format_uncertain(&uncertain),
+        uncertain.to_string()
+    );
+
+    let format = NumberFormat::new().thousands(true).decimal_comma(true);
+    assert_eq!(format.format_uncertain(&uncertain), "12.345,7 ± 0,2");
+    let format = NumberFormat::new().notation(Notation::Fixed(3));
+    assert_eq!(format.format_uncertain(&uncertain), "12345.678 ± 0.210");
+    let format = NumberFormat::new().notation(Notation::Scientific(Some(2)));
+    assert_eq!(format.format_uncertain(&uncertain), "1.2e4 ± 2.1e-1");
+
+    // Errors above 1 round the value left of the point.
+    let uncertain = Uncertain::new(123456.0, 1234.0);
+    let format = NumberFormat::new().thousands(true);
+    assert_eq!(format.format_uncertain(&uncertain), "123,500 ± 1,200");
+    for uncertain in [Uncertain::new(2.5, 0.0), Uncertain::new(f64::NAN, 1.0)] {
+        assert_eq!(
+            NumberFormat::new().format_uncertain(&uncertain),
+            uncertain.to_string()
+        );
+    }
+}
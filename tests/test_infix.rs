@@ -0,0 +1,209 @@
+use proptest::prelude::*;
+use tilted::{
+    ast::{to_infix, InfixPrinter},
+    BinaryAction, BinaryNode, BinderAction, BinderNode, Function, Guess, Lexer, NodeBox, Number,
+    Parser, PlainNode, SolveNode, UnaryAction, UnaryNode, Unit, UnitNode, VariableNode,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+macro_rules! make_infix_test {
+    ($name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            assert_eq!(to_infix(parse($source).as_ref()), $expected);
+        }
+    };
+    (I: $name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            let printer = InfixPrinter::new().implicit_mul(true);
+            assert_eq!(printer.print(parse($source).as_ref()), $expected);
+        }
+    };
+}
+
+make_infix_test!(test_infix_number, "3", "3");
+make_infix_test!(test_infix_float, "3.", "3.0");
+make_infix_test!(test_infix_negative, "-3.5", "-3.5");
+make_infix_test!(test_infix_pi, "-pi", "-pi");
+make_infix_test!(
+    test_infix_pi_float,
+    "3.141592653589793",
+    "3.141592653589793"
+);
+make_infix_test!(
+    test_infix_left_assoc,
+    "(1 - 2) - (3 - 4)",
+    "1 - 2 - (3 - 4)"
+);
+make_infix_test!(
+    test_infix_precedence,
+    "(1 + 2) * 3 / (4 * 5)",
+    "(1 + 2) * 3 / (4 * 5)"
+);
+make_infix_test!(test_infix_redundant_parens, "((x)) + (y * z)", "x + y * z");
+make_infix_test!(test_infix_neg, "-(x + 1) * -y", "-(x + 1) * -y");
+make_infix_test!(test_infix_neg_number, "-(2)", "-(2)");
+make_infix_test!(test_infix_neg_pow, "-2^x", "-2^x");
+make_infix_test!(test_infix_double_neg, "-(-x)", "-(-x)");
+make_infix_test!(test_infix_nested_neg, "- -x + --2", "-(-x) + -(-2)");
+make_infix_test!(test_infix_chained_pow, "x^y^2", "x^(y^2)");
+make_infix_test!(test_infix_pow, "(x^2)^(-1)", "(x^2)^(-1)");
+make_infix_test!(test_infix_func, "sin((x + 1)) ^ ln(2)", "sin(x + 1)^ln(2)");
+make_infix_test!(test_infix_implicit, "2x(y + 1)", "2 * x * (y + 1)");
+make_infix_test!(
+    test_infix_units,
+    "3 m / 2 s to km/h",
+    "3 * m / (2 * s) to km / h"
+);
+make_infix_test!(
+    test_infix_convert_nested,
+    "(x to m) + 1 m",
+    "(x to m) + 1 * m"
+);
+
+make_infix_test!(
+    test_infix_binder,
+    "integrate((sin(x)), x, 0, pi / 2)",
+    "integrate(sin(x), x, 0, pi / 2)"
+);
+make_infix_test!(
+    test_infix_binder_operand,
+    "2 * sum(k + 1, k, 1, n)^2",
+    "2 * sum(k + 1, k, 1, n)^2"
+);
+
+make_infix_test!(test_infix_equation, "(x + 1) = (2 to m)", "x + 1 = 2 to m");
+make_infix_test!(test_infix_equation_operand, "2 * (x = 1)", "2 * (x = 1)");
+make_infix_test!(
+    test_infix_equation_nested,
+    "(x = 1) = (y = 2)",
+    "(x = 1) = (y = 2)"
+);
+make_infix_test!(
+    test_infix_solve,
+    "solve((x^2 = 2), x, 0, (1 + 1))",
+    "solve(x^2 = 2, x, 0, 1 + 1)"
+);
+make_infix_test!(
+    test_infix_solve_operand,
+    "-solve(x = y, x)^2",
+    "-solve(x = y, x)^2"
+);
+
+make_infix_test!(I: test_infix_implicit_number, "2 * x", "2x");
+make_infix_test!(I: test_infix_implicit_names, "x * y1 * z", "x y1 z");
+make_infix_test!(I: test_infix_implicit_paren, "2 * x * (y + 1)", "2x * (y + 1)");
+make_infix_test!(I: test_infix_implicit_after_paren, "(x + 1) * sin(y)", "(x + 1)sin(y)");
+make_infix_test!(I: test_infix_implicit_number_right, "x * 2", "x * 2");
+make_infix_test!(I: test_infix_implicit_neg, "2 * -x", "2 * -x");
+make_infix_test!(I: test_infix_implicit_unit, "3 m / 2 s", "3m / (2s)");
+make_infix_test!(I: test_infix_implicit_binder, "2 * prod(k, k, 1, n)", "2prod(k, k, 1, n)");
+
+#[test]
+fn test_infix_iden() {
+    let node: NodeBox = Box::new(UnaryNode::new(
+        UnaryAction::Iden,
+        Box::new(VariableNode::new("x")),
+    ));
+
+    assert_eq!(to_infix(node.as_ref()), "x");
+}
+
+#[test]
+fn test_infix_extreme_numbers() {
+    for n in [i128::MIN, i128::MAX] {
+        let node: NodeBox = Box::new(PlainNode::new(Number::Int(n)));
+        assert_eq!(parse(&to_infix(node.as_ref())).as_ref(), node.as_ref());
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Property tests.
+// -----------------------------------------------------------------------------
+
+/// Generates ASTs without [`UnaryAction::Iden`] nodes and non-finite numbers,
+/// which do not survive a round trip.
+fn arb_node() -> impl Strategy<Value = NodeBox> {
+    let leaf = prop_oneof![
+        any::<i128>().prop_map(|n| Box::new(PlainNode::new(Number::Int(n))) as NodeBox),
+        prop_oneof![
+            any::<f64>().prop_filter("finite", |n| n.is_finite()),
+            prop::sample::select(vec![0.0, -0.0, 1.0, -1.5, 1e-300, 1e300]),
+        ]
+        .prop_map(|n| Box::new(PlainNode::new(Number::Flt(n))) as NodeBox),
+        "[a-z][a-z0-9_]{0,2}"
+            .prop_filter("not a function, unit or keyword", |n| Function::try_from(
+                n.as_str()
+            )
+            .is_err()
+                && Unit::lookup(n).is_none()
+                && !matches!(n.as_str(), "to" | "pi" | "sum" | "prod"))
+            .prop_map(|n| Box::new(VariableNode::new(n)) as NodeBox),
+        prop::sample::select(vec!["m", "km", "s", "h", "kg", "ohm", "ft"])
+            .prop_map(|n| Box::new(UnitNode::new(Unit::lookup(n).unwrap())) as NodeBox),
+    ];
+    let binary = prop::sample::select(vec![
+        BinaryAction::Add,
+        BinaryAction::Sub,
+        BinaryAction::Mul,
+        BinaryAction::Div,
+        BinaryAction::Pow,
+        BinaryAction::PlusMinus,
+        BinaryAction::Convert,
+        BinaryAction::Equate,
+    ]);
+    let unary = prop::sample::select(vec![
+        UnaryAction::Neg,
+        UnaryAction::Func(Function::Sin),
+        UnaryAction::Func(Function::Ln),
+    ]);
+    let binder = prop::sample::select(vec![
+        BinderAction::Integrate,
+        BinderAction::Sum,
+        BinderAction::Prod,
+    ]);
+
+    leaf.prop_recursive(5, 64, 2, move |inner| {
+        prop_oneof![
+            (inner.clone(), binary.clone(), inner.clone())
+                .prop_map(|(l, a, r)| Box::new(BinaryNode::new(l, a, r)) as NodeBox),
+            (unary.clone(), inner.clone())
+                .prop_map(|(a, o)| Box::new(UnaryNode::new(a, o)) as NodeBox),
+            (
+                binder.clone(),
+                prop::sample::select(vec!["k", "t"]),
+                inner.clone(),
+                inner.clone(),
+                inner.clone()
+            )
+                .prop_map(|(a, v, b, l, u)| Box::new(BinderNode::new(a, v, b, l, u)) as NodeBox),
+            (inner.clone(), 0..3usize, inner.clone(), inner).prop_map(|(e, n, a, b)| {
+                let guess = match n {
+                    0 => Guess::None,
+                    1 => Guess::Point(a),
+                    _ => Guess::Interval(a, b),
+                };
+                Box::new(SolveNode::new(e, "x", guess)) as NodeBox
+            }),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn test_infix_round_trip(node in arb_node(), implicit_mul in any::<bool>()) {
+        let source = InfixPrinter::new().implicit_mul(implicit_mul).print(node.as_ref());
+        let lexer = Lexer::from_source_code(&source);
+        let parsed = Parser::from_lexer(lexer).parse();
+
+        prop_assert!(parsed.is_ok(), "{:?} printed as {}: {:?}", node, source, parsed);
+        let parsed = parsed.unwrap();
+        prop_assert_eq!(parsed.as_ref(), node.as_ref(), "printed as {}", source);
+    }
+}
@@ -0,0 +1,344 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use proptest::prelude::*;
+use tilted::{
+    ast::{evaluate_interval, Interval},
+    BinaryAction, BinaryNode, Environment, EvalError, Function, Lexer, NodeBox, Number, Parser,
+    PlainNode, UnaryAction, UnaryNode, VariableNode,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn float(n: Number) -> f64 {
+    match n {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
+
+macro_rules! make_interval_test {
+    // Checks that the result is exactly `[lo, hi]`.
+    ($name: ident, $source: literal, [$(($var: literal, $lo: expr, $hi: expr)),*], [$elo: expr, $ehi: expr]) => {
+        #[test]
+        fn $name() {
+            let bindings = [$(($var, Interval::new($lo, $hi))),*];
+            let actual = evaluate_interval(parse($source).as_ref(), &bindings).unwrap();
+
+            assert_eq!(actual, Interval::new($elo, $ehi), "{}", actual);
+        }
+    };
+    // Checks that the result encloses `[lo, hi]`, within a relative tolerance.
+    (~ $name: ident, $source: literal, [$(($var: literal, $lo: expr, $hi: expr)),*], [$elo: expr, $ehi: expr]) => {
+        #[test]
+        fn $name() {
+            let bindings = [$(($var, Interval::new($lo, $hi))),*];
+            let actual = evaluate_interval(parse($source).as_ref(), &bindings).unwrap();
+
+            let (lo, hi): (f64, f64) = ($elo, $ehi);
+            assert!(actual.lo() <= lo && hi <= actual.hi(), "{} does not enclose [{}, {}]", actual, lo, hi);
+            assert!(actual.lo() == lo || lo - actual.lo() <= 1e-12 * lo.abs().max(1.0), "{} is too wide", actual);
+            assert!(actual.hi() == hi || actual.hi() - hi <= 1e-12 * hi.abs().max(1.0), "{} is too wide", actual);
+        }
+    };
+}
+
+make_interval_test!(
+    test_interval_example,
+    "x^2 - 2*x",
+    [("x", 0.0, 3.0)],
+    [-6.0, 9.0]
+);
+make_interval_test!(
+    test_interval_add,
+    "x + y",
+    [("x", 1.0, 2.0), ("y", -3.0, 0.5)],
+    [-2.0, 2.5]
+);
+make_interval_test!(
+    test_interval_mul,
+    "x * y",
+    [("x", -1.0, 2.0), ("y", -3.0, 0.5)],
+    [-6.0, 3.0]
+);
+make_interval_test!(
+    test_interval_div,
+    "x / y",
+    [("x", 1.0, 2.0), ("y", 4.0, 8.0)],
+    [0.125, 0.5]
+);
+make_interval_test!(test_interval_int_div, "7 / 2", [], [3.0, 3.0]);
+make_interval_test!(test_interval_int_div_neg, "-7 / 2", [], [-3.0, -3.0]);
+make_interval_test!(test_interval_flt_div, "7 / 2.0", [], [3.5, 3.5]);
+make_interval_test!(
+    test_interval_int_div_exponent,
+    "x^(1 / 2)",
+    [("x", 1.7, 1.7)],
+    [1.0, 1.0]
+);
+make_interval_test!(test_interval_either_div, "2^(0 - 1) / 2", [], [0.0, 0.25]);
+make_interval_test!(test_interval_neg, "-x", [("x", 1.0, 2.0)], [-2.0, -1.0]);
+make_interval_test!(
+    test_interval_dependency,
+    "x - x",
+    [("x", 0.0, 1.0)],
+    [-1.0, 1.0]
+);
+make_interval_test!(
+    test_interval_even_pow,
+    "x^2",
+    [("x", -2.0, 1.0)],
+    [0.0, 4.0]
+);
+make_interval_test!(
+    test_interval_odd_pow,
+    "x^3",
+    [("x", -2.0, 1.0)],
+    [-8.0, 1.0]
+);
+make_interval_test!(
+    test_interval_negative_pow,
+    "x^(-2)",
+    [("x", 2.0, 4.0)],
+    [0.0625, 0.25]
+);
+make_interval_test!(
+    test_interval_div_zero_right,
+    "1 / x",
+    [("x", 0.0, 2.0)],
+    [0.5, f64::INFINITY]
+);
+make_interval_test!(
+    test_interval_div_zero_left,
+    "1 / x",
+    [("x", -4.0, 0.0)],
+    [f64::NEG_INFINITY, -0.25]
+);
+make_interval_test!(
+    test_interval_div_zero_inside,
+    "1 / x",
+    [("x", -1.0, 1.0)],
+    [f64::NEG_INFINITY, f64::INFINITY]
+);
+make_interval_test!(
+    test_interval_div_zero_numerator,
+    "0 / x",
+    [("x", -1.0, 1.0)],
+    [0.0, 0.0]
+);
+
+make_interval_test!(~ test_interval_sin_peak, "sin(x)", [("x", 0.0, 2.0)], [0.0, 1.0]);
+make_interval_test!(~ test_interval_sin_full, "sin(x)", [("x", 0.0, 7.0)], [-1.0, 1.0]);
+make_interval_test!(~ test_interval_sin_far, "sin(x)", [("x", 100.0 * PI + 0.1, 100.0 * PI + 0.2)], [(100.0 * PI + 0.1).sin(), (100.0 * PI + 0.2).sin()]);
+make_interval_test!(~ test_interval_cos_trough, "cos(x)", [("x", 3.0, 4.0)], [-1.0, 3.0f64.cos().max(4.0f64.cos())]);
+make_interval_test!(~ test_interval_tan, "tan(x)", [("x", -1.0, 1.0)], [-(1.0f64.tan()), 1.0f64.tan()]);
+make_interval_test!(~ test_interval_tan_pole, "tan(x)", [("x", 1.0, 2.0)], [f64::NEG_INFINITY, f64::INFINITY]);
+make_interval_test!(~ test_interval_cot, "cot(x)", [("x", 1.0, 2.0)], [1.0 / 2.0f64.tan(), 1.0 / 1.0f64.tan()]);
+make_interval_test!(~ test_interval_cot_pole, "cot(x)", [("x", -1.0, 1.0)], [f64::NEG_INFINITY, f64::INFINITY]);
+make_interval_test!(~ test_interval_sec, "sec(x)", [("x", -1.0, 1.0)], [1.0, 1.0 / 1.0f64.cos()]);
+make_interval_test!(~ test_interval_sec_pole, "sec(x)", [("x", 1.0, 2.0)], [f64::NEG_INFINITY, f64::INFINITY]);
+make_interval_test!(~ test_interval_csc, "csc(x)", [("x", 1.0, 2.0)], [1.0, 1.0 / 1.0f64.sin()]);
+make_interval_test!(~ test_interval_asin_domain, "asin(x)", [("x", 0.0, 5.0)], [0.0, FRAC_PI_2]);
+make_interval_test!(~ test_interval_acos, "acos(x)", [("x", -1.0, 0.0)], [FRAC_PI_2, PI]);
+make_interval_test!(~ test_interval_atan, "atan(x)", [("x", -1.0, 1.0)], [-PI / 4.0, PI / 4.0]);
+make_interval_test!(~ test_interval_asec, "asec(x)", [("x", 1.0, 2.0)], [0.0, PI / 3.0]);
+make_interval_test!(~ test_interval_acot_zero, "acot(x)", [("x", 0.0, 0.0)], [-FRAC_PI_2, FRAC_PI_2]);
+make_interval_test!(~ test_interval_acot, "acot(x)", [("x", 1.0, 2.0)], [0.5f64.atan(), PI / 4.0]);
+make_interval_test!(~ test_interval_ln, "ln(x)", [("x", 0.0, 1.0)], [f64::NEG_INFINITY, 0.0]);
+make_interval_test!(~ test_interval_sqrt_func, "sqrt(x)", [("x", -1.0, 4.0)], [0.0, 2.0]);
+make_interval_test!(~ test_interval_sqrt, "x^0.5", [("x", -1.0, 4.0)], [0.0, 2.0]);
+make_interval_test!(
+    test_interval_sum,
+    "sum(k x, k, 1, 3)",
+    [("x", 1.0, 2.0)],
+    [6.0, 12.0]
+);
+make_interval_test!(
+    test_interval_sum_empty,
+    "sum(x, k, 1, 0)",
+    [("x", 1.0, 2.0)],
+    [0.0, 0.0]
+);
+make_interval_test!(
+    test_interval_sum_wide_bound,
+    "sum(k, k, 1, x)",
+    [("x", 1.0, 2.0)],
+    [f64::NEG_INFINITY, f64::INFINITY]
+);
+make_interval_test!(
+    test_interval_solve,
+    "solve(t^2 = 2, t, 0, x)",
+    [("x", 1.0, 2.0)],
+    [0.0, 2.0]
+);
+make_interval_test!(
+    test_interval_solve_point,
+    "solve(t^2 = 2, t, x)",
+    [("x", 1.0, 2.0)],
+    [f64::NEG_INFINITY, f64::INFINITY]
+);
+
+#[test]
+fn test_interval_outward_rounding() {
+    let actual = evaluate_interval(parse("0.1 + 0.2").as_ref(), &[]).unwrap();
+
+    assert!(actual.lo() < actual.hi());
+    assert!(actual.contains(0.1 + 0.2));
+    assert_eq!(actual.lo().next_up(), 0.1 + 0.2);
+}
+
+#[test]
+fn test_interval_empty() {
+    let node = parse("ln(x) + 1");
+    let actual = evaluate_interval(node.as_ref(), &[("x", Interval::new(-2.0, -1.0))]).unwrap();
+
+    assert!(actual.is_empty());
+    assert!(Interval::new(1.0, 0.0).is_empty());
+    assert_eq!(actual.to_string(), "[]");
+}
+
+#[test]
+fn test_interval_integral() {
+    let node = parse("integrate(x t^2, t, 0, 3)");
+    let actual = evaluate_interval(node.as_ref(), &[("x", Interval::new(1.0, 2.0))]).unwrap();
+
+    // Encloses the exact range [9, 18], without being much wider.
+    assert!(actual.lo() <= 9.0 && 18.0 <= actual.hi(), "{}", actual);
+    assert!(actual.hi() - actual.lo() < 9.0 * 1.1, "{}", actual);
+}
+
+#[test]
+fn test_interval_unbound() {
+    let node = parse("x + y");
+    let err = evaluate_interval(node.as_ref(), &[("x", Interval::point(1.0))]).unwrap_err();
+
+    assert!(matches!(err, EvalError::UnboundVariable(ref name) if name == "y"));
+}
+
+// -----------------------------------------------------------------------------
+// Property tests.
+// -----------------------------------------------------------------------------
+
+/// Generates ASTs over floats. Integer constants are left to
+/// [`arb_integer_node`], since they hit poles exactly, e.g. in `(1 - 1)^-1`.
+fn arb_node() -> impl Strategy<Value = NodeBox> {
+    let leaf = prop_oneof![
+        (-10.0..10.0f64).prop_map(|n| Box::new(PlainNode::new(Number::Flt(n))) as NodeBox),
+        prop::sample::select(vec!["x", "y"])
+            .prop_map(|n| Box::new(VariableNode::new(n)) as NodeBox),
+    ];
+    let exponent = prop_oneof![
+        (-2i128..=3).prop_map(|n| Box::new(PlainNode::new(Number::Int(n))) as NodeBox),
+        (-2.0..2.0f64).prop_map(|n| Box::new(PlainNode::new(Number::Flt(n))) as NodeBox),
+        prop::sample::select(vec!["x", "y"])
+            .prop_map(|n| Box::new(VariableNode::new(n)) as NodeBox),
+    ];
+    let binary = prop::sample::select(vec![
+        BinaryAction::Add,
+        BinaryAction::Sub,
+        BinaryAction::Mul,
+        BinaryAction::Div,
+    ]);
+    let unary = prop::sample::select(vec![
+        UnaryAction::Neg,
+        UnaryAction::Iden,
+        UnaryAction::Func(Function::Sin),
+        UnaryAction::Func(Function::Cos),
+        UnaryAction::Func(Function::Tan),
+        UnaryAction::Func(Function::Csc),
+        UnaryAction::Func(Function::Sec),
+        UnaryAction::Func(Function::Cot),
+        UnaryAction::Func(Function::Asin),
+        UnaryAction::Func(Function::Acos),
+        UnaryAction::Func(Function::Atan),
+        UnaryAction::Func(Function::Acsc),
+        UnaryAction::Func(Function::Asec),
+        UnaryAction::Func(Function::Acot),
+        UnaryAction::Func(Function::Ln),
+    ]);
+
+    leaf.prop_recursive(4, 32, 2, move |inner| {
+        prop_oneof![
+            (inner.clone(), binary.clone(), inner.clone())
+                .prop_map(|(l, a, r)| Box::new(BinaryNode::new(l, a, r)) as NodeBox),
+            (inner.clone(), exponent.clone()).prop_map(|(l, r)| {
+                Box::new(BinaryNode::new(l, BinaryAction::Pow, r)) as NodeBox
+            }),
+            (unary.clone(), inner).prop_map(|(a, o)| Box::new(UnaryNode::new(a, o)) as NodeBox),
+        ]
+    })
+}
+
+/// Generates sums, differences, products and quotients of integers, floats
+/// and a variable, so that integer division is mixed with division over the
+/// real numbers.
+fn arb_integer_node() -> impl Strategy<Value = NodeBox> {
+    let leaf = prop_oneof![
+        (-10i128..=10).prop_map(|n| Box::new(PlainNode::new(Number::Int(n))) as NodeBox),
+        (-10.0..10.0f64).prop_map(|n| Box::new(PlainNode::new(Number::Flt(n))) as NodeBox),
+        Just(Box::new(VariableNode::new("x")) as NodeBox),
+    ];
+    let binary = prop::sample::select(vec![
+        BinaryAction::Add,
+        BinaryAction::Sub,
+        BinaryAction::Mul,
+        BinaryAction::Div,
+        BinaryAction::LeftDiv,
+    ]);
+
+    leaf.prop_recursive(4, 32, 2, move |inner| {
+        prop_oneof![
+            (inner.clone(), binary.clone(), inner.clone())
+                .prop_map(|(l, a, r)| Box::new(BinaryNode::new(l, a, r)) as NodeBox),
+            inner.prop_map(|o| Box::new(UnaryNode::new(UnaryAction::Neg, o)) as NodeBox),
+        ]
+    })
+}
+
+/// Generates an interval and a point within it.
+fn arb_interval() -> impl Strategy<Value = (Interval, f64)> {
+    (-10.0..10.0f64, 0.0..5.0f64, 0.0..=1.0f64)
+        .prop_map(|(lo, width, t)| (Interval::new(lo, lo + width), lo + t * width))
+}
+
+proptest! {
+    #[test]
+    fn test_interval_encloses_scalar(
+        node in arb_node(),
+        (x, x0) in arb_interval(),
+        (y, y0) in arb_interval(),
+    ) {
+        let interval = evaluate_interval(node.as_ref(), &[("x", x), ("y", y)]).unwrap();
+
+        let env = Environment::new().with("x", x0).with("y", y0);
+        let value = float(node.evaluate_with(&env).unwrap());
+
+        // Undefined values, and infinities at poles, are not enclosed.
+        if value.is_finite() {
+            prop_assert!(
+                interval.contains(value),
+                "{:?} at x = {}, y = {}: {} does not contain {}", node, x0, y0, interval, value
+            );
+        }
+    }
+
+    #[test]
+    fn test_interval_encloses_integer_division(
+        node in arb_integer_node(),
+        (x, x0) in arb_interval(),
+    ) {
+        let interval = evaluate_interval(node.as_ref(), &[("x", x)]).unwrap();
+        let value = float(node.evaluate_with(&Environment::new().with("x", x0)).unwrap());
+
+        // Division by zero is undefined.
+        if value.is_finite() {
+            prop_assert!(
+                interval.contains(value),
+                "{:?} at x = {}: {} does not contain {}", node, x0, interval, value
+            );
+        }
+    }
+}
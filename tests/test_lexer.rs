@@ -38,6 +38,28 @@ make_lexer_test!(
     [Op(Plus), Op(Minus), Op(Star), Op(Slash), Op(Caret), Eof,]
 );
 
+make_lexer_test!(
+    test_lexer_plus_minus,
+    "9.81 ± 0.02 +/- 1+-1",
+    [
+        Flt(9.81),
+        Op(PlusMinus),
+        Flt(0.02),
+        Op(PlusMinus),
+        Int(1),
+        Op(Plus),
+        Op(Minus),
+        Int(1),
+        Eof,
+    ]
+);
+
+make_lexer_test!(
+    test_lexer_equal,
+    "x^2=2",
+    [Ident, Op(Caret), Int(2), Op(Equal), Int(2), Eof,]
+);
+
 make_lexer_test!(test_lexer_parens, "( )", [LeftParen, RightParen, Eof,]);
 
 make_lexer_test!(
@@ -89,5 +111,264 @@ make_lexer_test!(
     [Func(Sin), LeftParen, Flt(3.14), RightParen, Eof,]
 );
 
+make_lexer_test!(test_lexer_ident, "x y_1 sinx", [Ident, Ident, Ident, Eof,]);
+
+make_lexer_test!(
+    test_lexer_ident_with_expr,
+    "2x sin(y)",
+    [Int(2), Ident, Func(Sin), LeftParen, Ident, RightParen, Eof,]
+);
+
+make_lexer_test!(
+    test_lexer_units,
+    "3 km/h to m min^-1",
+    [
+        Int(3),
+        Unit,
+        Op(Slash),
+        Unit,
+        Op(To),
+        Unit,
+        Unit,
+        Op(Caret),
+        Op(Minus),
+        Int(1),
+        Eof,
+    ]
+);
+
+make_lexer_test!(
+    test_lexer_brackets,
+    "[[1, 2]] \\ x",
+    [
+        LeftBracket,
+        LeftBracket,
+        Int(1),
+        Comma,
+        Int(2),
+        RightBracket,
+        RightBracket,
+        Op(Backslash),
+        Ident,
+        Eof,
+    ]
+);
+
+make_lexer_test!(
+    test_lexer_lists,
+    "{1..5} x -> 2.5",
+    [
+        LeftBrace,
+        Int(1),
+        Op(DotDot),
+        Int(5),
+        RightBrace,
+        Ident,
+        Op(Arrow),
+        Flt(2.5),
+        Eof,
+    ]
+);
+
+make_lexer_test!(
+    test_lexer_unicode_ops,
+    "3 × 4 ÷ 2 − 1 · 5 ⋅ 6",
+    [
+        Int(3),
+        Op(Star),
+        Int(4),
+        Op(Slash),
+        Int(2),
+        Op(Minus),
+        Int(1),
+        Op(Star),
+        Int(5),
+        Op(Star),
+        Int(6),
+        Eof,
+    ]
+);
+
+make_lexer_test!(
+    test_lexer_radical_pi,
+    "√2π",
+    [Func(Sqrt), Int(2), Ident, Eof,]
+);
+
+make_lexer_test!(
+    test_lexer_superscript,
+    "x²⁰ + 2³",
+    [
+        Ident,
+        Op(Caret),
+        Int(20),
+        Op(Plus),
+        Int(2),
+        Op(Caret),
+        Int(3),
+        Eof,
+    ]
+);
+
+make_lexer_test!(
+    test_lexer_superscript_negative,
+    "x⁻¹",
+    [
+        Ident,
+        Op(Caret),
+        LeftParen,
+        Op(Minus),
+        Int(1),
+        RightParen,
+        Eof,
+    ]
+);
+
+make_lexer_test!(
+    test_lexer_vulgar_fraction,
+    "⅔x",
+    [
+        LeftParen,
+        Flt(2.0),
+        Op(Slash),
+        Int(3),
+        RightParen,
+        Ident,
+        Eof,
+    ]
+);
+
+make_lexer_test!(
+    test_lexer_mixed_number,
+    "3½",
+    [
+        LeftParen,
+        Int(3),
+        Op(Plus),
+        Flt(1.0),
+        Op(Slash),
+        Int(2),
+        RightParen,
+        Eof,
+    ]
+);
+
+make_lexer_test!(
+    test_lexer_mixed_number_space,
+    "3 ½",
+    [
+        LeftParen,
+        Int(3),
+        Op(Plus),
+        Flt(1.0),
+        Op(Slash),
+        Int(2),
+        RightParen,
+        Eof,
+    ]
+);
+
+make_lexer_test!(
+    test_lexer_fraction_product,
+    "3 × ½",
+    [
+        Int(3),
+        Op(Star),
+        LeftParen,
+        Flt(1.0),
+        Op(Slash),
+        Int(2),
+        RightParen,
+        Eof,
+    ]
+);
+
 make_lexer_test!(E: test_lexer_too_many_decimals, "9.0.0");
-make_lexer_test!(E: test_lexer_invalid_char, "a");
+make_lexer_test!(E: test_lexer_invalid_char, "$");
+make_lexer_test!(E: test_lexer_superscript_minus, "⁻ 1");
+
+#[test]
+fn test_lexer_plus_minus_spans() {
+    let mut lexer = Lexer::from_source_code("1 ± 2 +/- 3");
+    let spans = std::iter::from_fn(|| lexer.next())
+        .map(|token| (token.span.start_index, token.span.end_index))
+        .collect::<Vec<_>>();
+
+    // `±` is two bytes long in UTF-8.
+    assert_eq!(spans, [(0, 0), (2, 3), (5, 5), (7, 9), (11, 11)]);
+}
+
+#[test]
+fn test_lexer_unit_spans() {
+    let mut lexer = Lexer::from_source_code("3 km to mi");
+    let spans = std::iter::from_fn(|| lexer.next())
+        .map(|token| (token.kind, token.span.start_index, token.span.end_index))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        spans,
+        [(Int(3), 0, 0), (Unit, 2, 3), (Op(To), 5, 6), (Unit, 8, 9)]
+    );
+}
+
+#[test]
+fn test_lexer_unicode_spans() {
+    let source = "√π × x² − ½";
+    let mut lexer = Lexer::from_source_code(source);
+    let tokens = std::iter::from_fn(|| lexer.next()).collect::<Vec<_>>();
+    let spans = tokens
+        .iter()
+        .map(|token| (token.span.start_index, token.span.end_index))
+        .collect::<Vec<_>>();
+
+    // Spans are byte offsets, and every token a character stands for has
+    // its span.
+    assert_eq!(
+        spans,
+        [
+            (0, 2),
+            (3, 4),
+            (6, 7),
+            (9, 9),
+            (10, 11),
+            (10, 11),
+            (13, 15),
+            (17, 18),
+            (17, 18),
+            (17, 18),
+            (17, 18),
+            (17, 18),
+        ]
+    );
+    let sources = tokens
+        .iter()
+        .map(|token| &lexer[token.span])
+        .collect::<String>();
+    assert_eq!(sources, "√π×x²²−½½½½½");
+}
+
+#[test]
+fn test_lexer_reset_pending() {
+    let mut lexer = Lexer::from_source_code("x²");
+    lexer.lex().unwrap();
+    lexer.lex().unwrap();
+    lexer.reset();
+    assert_eq!(lexer.lex().unwrap().kind, Ident);
+    assert_eq!(lexer.lex().unwrap().kind, Op(Caret));
+    assert_eq!(lexer.lex().unwrap().kind, Int(2));
+}
+
+#[test]
+fn test_lexer_long_input() {
+    // A long input with multi-byte characters is lexed in full, with spans
+    // that slice it back together.
+    let source = "√x² − 1.5 + ".repeat(20_000) + "π";
+    let mut lexer = Lexer::from_source_code(&source);
+    let tokens = std::iter::from_fn(|| lexer.next()).collect::<Vec<_>>();
+    assert_eq!(tokens.len(), 20_000 * 7 + 1);
+
+    let last = tokens.last().unwrap();
+    assert_eq!(last.span.end_index, source.len() - 1);
+    assert_eq!(&lexer[last.span], "π");
+    assert_eq!(lexer.lex().unwrap().span.start_index, source.len());
+}
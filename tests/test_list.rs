@@ -0,0 +1,169 @@
+use tilted::{
+    ast::{evaluate_value, to_infix, Value},
+    Environment, EvalError, LambdaNode, Lexer, NodeBox, Number, Parser, Shape, VariableNode,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn value(source: &str) -> Result<Value, EvalError> {
+    evaluate_value(parse(source).as_ref(), &Environment::new())
+}
+
+macro_rules! make_list_test {
+    // Checks the formatted result.
+    ($name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            assert_eq!(value($source).unwrap().to_string(), $expected);
+        }
+    };
+    // Checks the error.
+    (E: $name: ident, $source: literal, $expected: pat) => {
+        #[test]
+        fn $name() {
+            let err = value($source).unwrap_err();
+            assert!(matches!(err, $expected), "{:?}", err);
+        }
+    };
+}
+
+make_list_test!(test_list_literal, "{1, 2.5, 3}", "{1, 2.5, 3}");
+make_list_test!(test_list_empty, "{}", "{}");
+make_list_test!(test_list_nested, "{1, {2, [3, 4]}}", "{1, {2, [3, 4]}}");
+make_list_test!(test_list_range, "1..5", "{1, 2, 3, 4, 5}");
+make_list_test!(test_list_range_empty, "3..1", "{}");
+make_list_test!(test_list_range_expr, "2 - 3..2 * 2", "{-1, 0, 1, 2, 3, 4}");
+make_list_test!(test_list_broadcast, "2 * {1, 2} + 1", "{3, 5}");
+make_list_test!(test_list_elementwise, "{1, 2} * {3, 4}", "{3, 8}");
+make_list_test!(test_list_negate, "-{1, [2, 3]}", "{-1, [-2, -3]}");
+make_list_test!(test_list_function, "ln({1})", "{0}");
+make_list_test!(test_list_map, "map(x -> x^2, 1..4)", "{1, 4, 9, 16}");
+make_list_test!(
+    test_list_filter,
+    "filter(k -> k - 2 * (k / 2), 1..6)",
+    "{1, 3, 5}"
+);
+make_list_test!(test_list_reduce, "reduce((a, b) -> a + b, 1..100)", "5050");
+make_list_test!(test_list_reduce_init, "reduce((a, b) -> a * b, {}, 7)", "7");
+make_list_test!(test_list_len, "len(1..10) + len([1, 2]) + len({})", "12");
+make_list_test!(test_list_lambda, "x -> x + 1", "x -> x + 1");
+make_list_test!(test_list_lambda_params, "(a, b) -> a b", "(a, b) -> a * b");
+make_list_test!(
+    test_list_capture,
+    "map(x -> reduce((a, b) -> a + b, map(y -> x y, 1..3)), {1, 10})",
+    "{6, 60}"
+);
+make_list_test!(
+    test_list_shadow,
+    "map(x -> map(x -> 2x, {x}), {1, 2})",
+    "{{2}, {4}}"
+);
+make_list_test!(test_list_unit_name, "map(s -> s^2, 1..3)", "{1, 4, 9}");
+make_list_test!(test_list_unit_params, "(s, m) -> s m", "(s, m) -> s * m");
+
+make_list_test!(
+    E: test_list_mismatch,
+    "{1, 2} + {1, 2, 3}",
+    EvalError::ShapeMismatch(Shape::List(2), Shape::List(3))
+);
+make_list_test!(
+    E: test_list_range_integer,
+    "1..2.5",
+    EvalError::IntegerExpected(_)
+);
+make_list_test!(
+    E: test_list_reduce_empty,
+    "reduce((a, b) -> a + b, {})",
+    EvalError::InvalidShape("reduce", Shape::List(0))
+);
+make_list_test!(
+    E: test_list_lambda_arity,
+    "map((a, b) -> a, {1})",
+    EvalError::ValueCountMismatch(2, 1)
+);
+make_list_test!(
+    E: test_list_not_lambda,
+    "map(1, {1})",
+    EvalError::InvalidShape("map", Shape::Scalar)
+);
+make_list_test!(
+    E: test_list_not_list,
+    "filter(x -> x, 1)",
+    EvalError::InvalidShape("filter", Shape::Scalar)
+);
+make_list_test!(
+    E: test_list_lambda_vector,
+    "map(v -> norm(v), {[3, 4]})",
+    EvalError::ScalarExpected(Shape::Vector(2))
+);
+make_list_test!(
+    E: test_list_lambda_arithmetic,
+    "(x -> x) + 1",
+    EvalError::ScalarExpected(Shape::Lambda(1))
+);
+make_list_test!(
+    E: test_list_in_vector,
+    "[{1}]",
+    EvalError::InvalidShape("[]", Shape::List(1))
+);
+
+#[test]
+fn test_list_scalar_evaluation() {
+    let node = parse("reduce((a, b) -> a + b, map(k -> k^2, 1..3))");
+    assert_eq!(node.evaluate(), Number::Int(14));
+
+    let node = parse("1..3");
+    let err = node.evaluate_with(&Environment::new()).unwrap_err();
+    assert!(matches!(err, EvalError::ScalarExpected(Shape::List(3))));
+}
+
+#[test]
+fn test_list_variables() {
+    let mut env = Environment::new();
+    env.bind("n", Number::Int(4));
+    let node = parse("map(k -> n k, 1..n)");
+    let value = evaluate_value(node.as_ref(), &env).unwrap();
+    assert_eq!(value.to_string(), "{4, 8, 12, 16}");
+}
+
+#[test]
+fn test_list_lambda_node() {
+    let node = parse("(a, b) -> a");
+    let expected = LambdaNode::new(["a", "b"], Box::new(VariableNode::new("a")));
+    assert_eq!(node.as_ref(), &expected as &dyn tilted::Node);
+}
+
+#[test]
+fn test_list_round_trip() {
+    for source in [
+        "map(x -> x^2, 1..n)",
+        "{1, {2}, [3]}",
+        "reduce((a, b) -> a + b, filter(x -> x = 1, {}), 0)",
+        "(x -> x) + 1",
+        "1..3 = (x -> x)",
+        "x -> y -> x + y",
+        "1 + 2..3 to 4",
+    ] {
+        assert_eq!(to_infix(parse(source).as_ref()), source);
+    }
+}
+
+#[test]
+fn test_list_tree() {
+    let tree = parse("map(x -> x, 1..2)").to_tree().join("\n");
+    assert!(tree.contains("Lambda(x)"), "{}", tree);
+    assert!(tree.contains("Range"), "{}", tree);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_list_serde() {
+    let node = parse("reduce((a, b) -> a + b, map(x -> x^2, {1, 2..4}))");
+    let json = serde_json::to_string(&node).unwrap();
+    let back: NodeBox = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.as_ref(), node.as_ref());
+}
@@ -0,0 +1,194 @@
+use tilted::{
+    ast::{evaluate_value, to_infix, Value},
+    Environment, EvalError, Lexer, Matrix, NodeBox, Number, Parser, Shape,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn value(source: &str) -> Result<Value, EvalError> {
+    evaluate_value(parse(source).as_ref(), &Environment::new())
+}
+
+macro_rules! make_matrix_test {
+    // Checks the formatted result.
+    ($name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            assert_eq!(value($source).unwrap().to_string(), $expected);
+        }
+    };
+    // Checks the error.
+    (E: $name: ident, $source: literal, $expected: pat) => {
+        #[test]
+        fn $name() {
+            let err = value($source).unwrap_err();
+            assert!(matches!(err, $expected), "{:?}", err);
+        }
+    };
+}
+
+make_matrix_test!(test_matrix_vector, "[1, 2, 3]", "[1, 2, 3]");
+make_matrix_test!(test_matrix_empty, "[]", "[]");
+make_matrix_test!(test_matrix_literal, "[[1, 2], [3, 4]]", "[[1, 2], [3, 4]]");
+make_matrix_test!(test_matrix_add, "[1, 2] + [3, 4]", "[4, 6]");
+make_matrix_test!(test_matrix_broadcast, "2 * [1, 2] + 1", "[3, 5]");
+make_matrix_test!(test_matrix_elementwise, "[1, 2] * [3, 4]", "[3, 8]");
+make_matrix_test!(
+    test_matrix_negate,
+    "-[[1, 2], [3, 4]]",
+    "[[-1, -2], [-3, -4]]"
+);
+make_matrix_test!(
+    test_matrix_product,
+    "[[1, 2], [3, 4]] * [[5, 6], [7, 8]]",
+    "[[19, 22], [43, 50]]"
+);
+make_matrix_test!(
+    test_matrix_times_vector,
+    "[[1, 2], [3, 4]] * [1, 1]",
+    "[3, 7]"
+);
+make_matrix_test!(
+    test_vector_times_matrix,
+    "[1, 1] * [[1, 2], [3, 4]]",
+    "[4, 6]"
+);
+make_matrix_test!(
+    test_matrix_pow,
+    "[[1, 1], [1, 0]]^10",
+    "[[89, 55], [55, 34]]"
+);
+make_matrix_test!(test_matrix_det, "det([[1, 2], [3, 4]])", "-2");
+make_matrix_test!(
+    test_matrix_det_exact,
+    "det([[2, 0, 1], [1, 3, 2], [1, 1, 2]])",
+    "6"
+);
+make_matrix_test!(test_matrix_det_flt, "det([[0.5, 1], [1, 4]])", "1");
+make_matrix_test!(
+    test_matrix_inv,
+    "inv([[2, 0], [0, 4]])",
+    "[[0.5, 0], [0, 0.25]]"
+);
+make_matrix_test!(
+    test_matrix_transpose,
+    "transpose([[1, 2, 3], [4, 5, 6]])",
+    "[[1, 4], [2, 5], [3, 6]]"
+);
+make_matrix_test!(test_matrix_dot, "dot([1, 2, 3], [4, 5, 6])", "32");
+make_matrix_test!(
+    test_matrix_cross,
+    "cross([1, 0, 0], [0, 1, 0])",
+    "[0, 0, 1]"
+);
+make_matrix_test!(test_matrix_norm, "norm([3, 4])", "5");
+make_matrix_test!(test_matrix_solve, "[[2, 1], [1, 3]] \\ [3, 4]", "[1, 1]");
+make_matrix_test!(test_matrix_left_div_scalar, "2 \\ [4, 6]", "[2, 3]");
+make_matrix_test!(
+    test_matrix_of_vectors,
+    "[[1, 2], 2 * [1, 2]]",
+    "[[1, 2], [2, 4]]"
+);
+
+make_matrix_test!(
+    E: test_matrix_add_mismatch,
+    "[1, 2] + [1, 2, 3]",
+    EvalError::ShapeMismatch(Shape::Vector(2), Shape::Vector(3))
+);
+make_matrix_test!(
+    E: test_matrix_ragged,
+    "[[1, 2], [3]]",
+    EvalError::ShapeMismatch(Shape::Vector(2), Shape::Vector(1))
+);
+make_matrix_test!(
+    E: test_matrix_mixed,
+    "[1, [2]]",
+    EvalError::ShapeMismatch(Shape::Scalar, Shape::Vector(1))
+);
+make_matrix_test!(
+    E: test_matrix_product_mismatch,
+    "[[1, 2]] * [[1, 2]]",
+    EvalError::ShapeMismatch(Shape::Matrix(1, 2), Shape::Matrix(1, 2))
+);
+make_matrix_test!(
+    E: test_matrix_det_not_square,
+    "det([[1, 2]])",
+    EvalError::InvalidShape("det", Shape::Matrix(1, 2))
+);
+make_matrix_test!(
+    E: test_matrix_det_vector,
+    "det([1, 2])",
+    EvalError::InvalidShape("det", Shape::Vector(2))
+);
+make_matrix_test!(
+    E: test_matrix_singular,
+    "inv([[1, 2], [2, 4]])",
+    EvalError::SingularMatrix
+);
+make_matrix_test!(
+    E: test_matrix_cross_length,
+    "cross([1, 2], [3, 4])",
+    EvalError::InvalidShape("cross", Shape::Vector(2))
+);
+make_matrix_test!(
+    E: test_matrix_divide_by_matrix,
+    "[[1]] / [[1]]",
+    EvalError::ScalarExpected(Shape::Matrix(1, 1))
+);
+
+#[test]
+fn test_matrix_scalar_evaluation() {
+    let node = parse("dot([1, 2], [3, 4]) + 1");
+    assert_eq!(node.evaluate(), Number::Int(12));
+
+    let node = parse("[1, 2]");
+    let err = node.evaluate_with(&Environment::new()).unwrap_err();
+    assert!(matches!(err, EvalError::ScalarExpected(Shape::Vector(2))));
+}
+
+#[test]
+fn test_matrix_variables() {
+    let mut env = Environment::new();
+    env.bind("x", Number::Int(3));
+    let node = parse("[[x, 1], [0, x]]^2");
+    let value = evaluate_value(node.as_ref(), &env).unwrap();
+
+    let expected = Matrix::from_rows(vec![
+        vec![Number::Int(9), Number::Int(6)],
+        vec![Number::Int(0), Number::Int(9)],
+    ])
+    .unwrap();
+    assert_eq!(value, Value::Matrix(expected));
+}
+
+#[test]
+fn test_matrix_inverse_product() {
+    let a = Matrix::from_rows(vec![
+        vec![Number::Int(4), Number::Int(7), Number::Int(2)],
+        vec![Number::Int(3), Number::Int(6), Number::Int(1)],
+        vec![Number::Int(2), Number::Int(5), Number::Int(3)],
+    ])
+    .unwrap();
+    let product = a.product(&a.inverse().unwrap()).unwrap();
+
+    for i in 0..3 {
+        for j in 0..3 {
+            let expected = (i == j) as i32 as f64;
+            let actual = match product.get(i, j) {
+                Number::Int(n) => n as f64,
+                Number::Flt(n) => n,
+            };
+            assert!((actual - expected).abs() < 1e-12, "{}", product);
+        }
+    }
+}
+
+#[test]
+fn test_matrix_round_trip() {
+    let source = "[[1, 2], [3, 4]] \\ [det(a), norm(v)]";
+    assert_eq!(to_infix(parse(source).as_ref()), source);
+}
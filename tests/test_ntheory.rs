@@ -0,0 +1,207 @@
+use tilted::{
+    ast::{evaluate_value, simplify, to_infix, Value},
+    ntheory, Environment, EvalError, Lexer, NodeBox, Number, Parser,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn value(source: &str) -> Result<Value, EvalError> {
+    evaluate_value(parse(source).as_ref(), &Environment::new())
+}
+
+macro_rules! make_ntheory_test {
+    // Checks the formatted result.
+    ($name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            assert_eq!(value($source).unwrap().to_string(), $expected);
+        }
+    };
+    // Checks the error.
+    (E: $name: ident, $source: literal, $expected: pat) => {
+        #[test]
+        fn $name() {
+            let err = value($source).unwrap_err();
+            assert!(matches!(err, $expected), "{:?}", err);
+        }
+    };
+    // Checks the factorisation, simplified and printed back to source code.
+    (F: $name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            let simplified = simplify(parse($source).as_ref());
+            assert_eq!(to_infix(simplified.as_ref()), $expected);
+        }
+    };
+}
+
+make_ntheory_test!(test_ntheory_gcd, "gcd(12, 18)", "6");
+make_ntheory_test!(test_ntheory_gcd_negative, "gcd(-12, 18)", "6");
+make_ntheory_test!(test_ntheory_gcd_zero, "gcd(0, 0)", "0");
+make_ntheory_test!(
+    test_ntheory_gcd_min,
+    "gcd(-170141183460469231731687303715884105728, 0)",
+    "170141183460469230000000000000000000000"
+);
+make_ntheory_test!(test_ntheory_lcm, "lcm(4, 6)", "12");
+make_ntheory_test!(test_ntheory_lcm_zero, "lcm(0, 6)", "0");
+make_ntheory_test!(
+    test_ntheory_lcm_overflow,
+    "lcm(2^100 + 1, 2^100 - 1)",
+    "1606938044258990300000000000000000000000000000000000000000000"
+);
+make_ntheory_test!(test_ntheory_isprime, "isprime(97)", "1");
+make_ntheory_test!(test_ntheory_isprime_one, "isprime(1)", "0");
+make_ntheory_test!(test_ntheory_isprime_negative, "isprime(-7)", "0");
+make_ntheory_test!(
+    test_ntheory_isprime_mersenne,
+    "isprime(170141183460469231731687303715884105727)",
+    "1"
+);
+make_ntheory_test!(test_ntheory_isprime_carmichael, "isprime(561)", "0");
+make_ntheory_test!(test_ntheory_nextprime, "nextprime(13)", "17");
+make_ntheory_test!(test_ntheory_nextprime_negative, "nextprime(-5)", "2");
+make_ntheory_test!(test_ntheory_totient, "totient(36)", "12");
+make_ntheory_test!(test_ntheory_totient_one, "totient(1)", "1");
+make_ntheory_test!(test_ntheory_totient_zero, "totient(0)", "NaN");
+make_ntheory_test!(test_ntheory_powmod, "powmod(4, 13, 497)", "445");
+make_ntheory_test!(test_ntheory_powmod_negative_base, "powmod(-2, 3, 5)", "2");
+make_ntheory_test!(test_ntheory_powmod_inverse, "powmod(3, -1, 7)", "5");
+make_ntheory_test!(test_ntheory_powmod_no_inverse, "powmod(2, -1, 4)", "NaN");
+make_ntheory_test!(test_ntheory_powmod_zero, "powmod(2, 3, 0)", "NaN");
+make_ntheory_test!(
+    test_ntheory_powmod_fermat,
+    "powmod(3, 170141183460469231731687303715884105726, 170141183460469231731687303715884105727)",
+    "1"
+);
+make_ntheory_test!(test_ntheory_modinv, "modinv(3, 11)", "4");
+make_ntheory_test!(test_ntheory_modinv_none, "modinv(6, 9)", "NaN");
+make_ntheory_test!(
+    test_ntheory_pow_overflow,
+    "2^127",
+    "170141183460469230000000000000000000000"
+);
+make_ntheory_test!(
+    test_ntheory_add_overflow,
+    "2^126 + 2^126",
+    "170141183460469230000000000000000000000"
+);
+make_ntheory_test!(
+    test_ntheory_add_max,
+    "170141183460469231731687303715884105727 + 1",
+    "170141183460469230000000000000000000000"
+);
+make_ntheory_test!(
+    test_ntheory_sub_min,
+    "-170141183460469231731687303715884105727 - 2",
+    "-170141183460469230000000000000000000000"
+);
+make_ntheory_test!(
+    test_ntheory_sub_exact,
+    "-170141183460469231731687303715884105727 - 1",
+    "-170141183460469231731687303715884105728"
+);
+make_ntheory_test!(
+    test_ntheory_mul_overflow,
+    "2^100 * 2^100",
+    "1606938044258990300000000000000000000000000000000000000000000"
+);
+make_ntheory_test!(
+    test_ntheory_div_overflow,
+    "(-170141183460469231731687303715884105727 - 1) / (-1)",
+    "170141183460469230000000000000000000000"
+);
+make_ntheory_test!(
+    test_ntheory_neg_overflow,
+    "-(-170141183460469231731687303715884105727 - 1)",
+    "170141183460469230000000000000000000000"
+);
+make_ntheory_test!(
+    E: test_ntheory_float,
+    "gcd(2.0, 4)",
+    EvalError::IntegerExpected(Number::Flt(_))
+);
+make_ntheory_test!(
+    E: test_ntheory_float_result,
+    "isprime(2^0.5)",
+    EvalError::IntegerExpected(_)
+);
+make_ntheory_test!(F: test_ntheory_factorint, "factorint(360)", "2^3 * 3^2 * 5");
+make_ntheory_test!(F: test_ntheory_factorint_prime, "factorint(97)", "97");
+make_ntheory_test!(F: test_ntheory_factorint_negative, "factorint(-12)", "-1 * 2^2 * 3");
+make_ntheory_test!(F: test_ntheory_factorint_one, "factorint(1)", "1");
+make_ntheory_test!(
+    F: test_ntheory_factorint_large,
+    "factorint(2^64 + 1)",
+    "274177 * 67280421310721"
+);
+
+make_ntheory_test!(test_ntheory_factorint_value, "factorint(360)", "360");
+make_ntheory_test!(
+    E: test_ntheory_factorint_float,
+    "factorint(1.5)",
+    EvalError::IntegerExpected(Number::Flt(_))
+);
+
+#[test]
+fn test_ntheory_float_message() {
+    let err = value("gcd(2.0, 4)").unwrap_err();
+    assert_eq!(err.to_string(), "Expected an integer, found the float 2.0");
+
+    let err = value("gcd(5, 1.5)").unwrap_err();
+    assert_eq!(err.to_string(), "Expected an integer, found the float 1.5");
+}
+
+#[test]
+fn test_ntheory_factorint_variable() {
+    // The argument is evaluated with the environment, not when parsed.
+    let node = parse("factorint(n)");
+    let env = Environment::new().with("n", Number::Int(84));
+    assert_eq!(
+        evaluate_value(node.as_ref(), &env).unwrap(),
+        Value::Scalar(Number::Int(84))
+    );
+    assert_eq!(to_infix(simplify(node.as_ref()).as_ref()), "factorint(n)");
+}
+
+#[test]
+fn test_ntheory_is_prime() {
+    let primes: Vec<u128> = (0..100).filter(|&n| ntheory::is_prime(n)).collect();
+    assert_eq!(primes.len(), 25);
+    assert_eq!(primes.last(), Some(&97));
+
+    // Strong pseudoprime to the first 13 primes as bases.
+    assert!(!ntheory::is_prime(3_317_044_064_679_887_385_961_981));
+    assert!(ntheory::is_prime(u128::MAX - 158));
+    assert!(!ntheory::is_prime(u128::MAX));
+}
+
+#[test]
+fn test_ntheory_factorise() {
+    // Two primes near 10^10, whose product is above 2^64.
+    let (p, q) = (9_999_999_967, 10_000_000_019);
+    assert_eq!(ntheory::factorise(p * q), vec![(p, 1), (q, 1)]);
+    assert_eq!(ntheory::factorise(1 << 127), vec![(2, 127)]);
+    assert_eq!(ntheory::factorise(0), vec![]);
+
+    for n in 1..2000u128 {
+        let product: u128 = ntheory::factorise(n)
+            .into_iter()
+            .map(|(p, e)| p.pow(e))
+            .product();
+        assert_eq!(product, n);
+    }
+}
+
+#[test]
+fn test_ntheory_inverse_mod() {
+    assert_eq!(ntheory::inverse_mod(0, 1), Some(0));
+    assert_eq!(ntheory::inverse_mod(3, 0), None);
+    let m = u128::MAX - 158;
+    let inverse = ntheory::inverse_mod(2, m).unwrap();
+    assert_eq!(ntheory::mul_mod(inverse, 2, m), 1);
+}
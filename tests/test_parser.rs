@@ -1,4 +1,4 @@
-use tilted::{Lexer, Number, Parser};
+use tilted::{Environment, Lexer, Node, NodeView, Number, ParseError, Parser, TilError};
 
 macro_rules! make_parser_test {
     ($name: ident, $source: literal, $expected: literal) => {
@@ -31,3 +31,259 @@ make_parser_test!(test_parser_impl_mul, "5(5)", 25);
 make_parser_test!(test_parser_impl_mul_expr, "5(5 + 5)", 50);
 make_parser_test!(test_parser_complex_expr, "2*-(3*(1+-(2)))^2", -18);
 make_parser_test!(test_parser_impl_mul_func, "5sin(0)", 0.0);
+make_parser_test!(test_parser_func_expr, "sin(0) + 2", 2.0);
+make_parser_test!(test_parser_neg_pow, "-2^2", -4);
+make_parser_test!(test_parser_chained_pow, "2^3^2", 512);
+make_parser_test!(test_parser_chained_neg_pow, "-2^1^3 + 1", -1);
+make_parser_test!(test_parser_plus_minus, "2 ± 0.1 * 3 + 1", 3);
+make_parser_test!(test_parser_units, "3 m / 2 s", 1.5);
+make_parser_test!(test_parser_unit_pow, "2 km^2", 2e6);
+make_parser_test!(test_parser_convert, "1.5 m/s to km/h", 5.4);
+make_parser_test!(test_parser_sum, "sum(k^2, k, 1, 100)", 338350);
+make_parser_test!(test_parser_prod, "2prod(k, k, 1, 5)", 240);
+make_parser_test!(test_parser_pi, "2pi / pi", 2.0);
+make_parser_test!(test_parser_unicode_pi, "2π / pi", 2.0);
+make_parser_test!(test_parser_unicode_ops, "3 × 4 ÷ 2 − 1", 5);
+make_parser_test!(test_parser_radical, "√16 · 2", 8.0);
+make_parser_test!(test_parser_radical_paren, "√(12 + 4)", 4.0);
+make_parser_test!(test_parser_superscript, "2³ − 3²", -1);
+make_parser_test!(test_parser_superscript_negative, "2.0⁻¹", 0.5);
+make_parser_test!(test_parser_vulgar_fraction, "¾ × 4", 3.0);
+make_parser_test!(test_parser_mixed_number, "2 × 3½", 7.0);
+make_parser_test!(test_parser_mixed_number_space, "2 × 3 ½", 7.0);
+make_parser_test!(test_parser_mixed_number_negative, "-3 ¼", -3.25);
+make_parser_test!(test_parser_fraction_product, "3 × ½", 1.5);
+make_parser_test!(test_parser_equation, "2 + 3 = 1 + 1", 3);
+make_parser_test!(test_parser_equation_paren, "2(1 = 3)", -4);
+make_parser_test!(test_parser_solve, "solve(2x = 3, x)", 1.5);
+make_parser_test!(test_parser_solve_guess, "solve(x^2 = 4, x, 0, 10) + 1", 3.0);
+make_parser_test!(
+    test_parser_min_int,
+    "-170141183460469231731687303715884105728",
+    -170141183460469231731687303715884105728i128
+);
+
+macro_rules! make_parser_error_test {
+    ($name: ident, $source: literal, $expected: pat) => {
+        #[test]
+        fn $name() {
+            let lexer = Lexer::from_source_code($source);
+            let mut parser = Parser::from_lexer(lexer);
+            let err = parser.parse().unwrap_err();
+
+            assert!(matches!(err, TilError::Parse($expected)), "{:?}", err);
+        }
+    };
+}
+
+make_parser_error_test!(
+    test_parser_trailing_token,
+    "1 2",
+    ParseError::OperatorExpected(_)
+);
+make_parser_error_test!(
+    test_parser_trailing_paren,
+    "1)",
+    ParseError::MismatchRightParen(1)
+);
+make_parser_error_test!(
+    test_parser_convert_missing_unit,
+    "3 m to",
+    ParseError::UnexpectedEOF
+);
+make_parser_error_test!(
+    test_parser_binder_variable,
+    "sum(k, 1, 1, 2)",
+    ParseError::VariableExpected(7)
+);
+make_parser_error_test!(
+    test_parser_binder_arguments,
+    "integrate(x, x, 0)",
+    ParseError::ArgumentCountMismatch("integrate", 4, 3)
+);
+make_parser_error_test!(
+    test_parser_chained_equation,
+    "1 = 2 = 3",
+    ParseError::OperatorExpected(_)
+);
+make_parser_error_test!(
+    test_parser_solve_variable,
+    "solve(x = 1, 2x)",
+    ParseError::VariableExpected(13)
+);
+make_parser_error_test!(
+    test_parser_solve_few_arguments,
+    "solve(x = 1)",
+    ParseError::ArgumentCountMismatch("solve", 2, 1)
+);
+make_parser_error_test!(
+    test_parser_solve_many_arguments,
+    "solve(x = 1, x, 0, 1, 2)",
+    ParseError::ArgumentCountMismatch("solve", 4, 5)
+);
+make_parser_error_test!(
+    test_parser_collect_variable,
+    "collect(x, 2)",
+    ParseError::VariableExpected(11)
+);
+make_parser_error_test!(
+    test_parser_expand_arguments,
+    "expand(x, y)",
+    ParseError::ArgumentCountMismatch("expand", 1, 2)
+);
+make_parser_error_test!(
+    test_parser_vector_bracket,
+    "[1, 2",
+    ParseError::RightBracketExpected(_)
+);
+make_parser_error_test!(
+    test_parser_mismatch_bracket,
+    "1 + 2]",
+    ParseError::MismatchRightBracket(5)
+);
+make_parser_error_test!(
+    test_parser_builtin_arguments,
+    "cross([1, 0, 0])",
+    ParseError::ArgumentCountMismatch("cross", 2, 1)
+);
+make_parser_error_test!(
+    test_parser_builtin_no_arguments,
+    "det()",
+    ParseError::ArgumentCountMismatch("det", 1, 0)
+);
+make_parser_error_test!(
+    test_parser_builtin_trailing_comma,
+    "dot([1], )",
+    ParseError::MismatchRightParen(9)
+);
+make_parser_error_test!(
+    test_parser_list_brace,
+    "{1, 2",
+    ParseError::RightBraceExpected(_)
+);
+make_parser_error_test!(
+    test_parser_mismatch_brace,
+    "{1} + 2}",
+    ParseError::MismatchRightBrace(7)
+);
+make_parser_error_test!(
+    test_parser_lambda_params,
+    "(a, 1) -> a",
+    ParseError::VariableExpected(4)
+);
+make_parser_error_test!(
+    test_parser_lambda_arrow,
+    "(a, b) + 1",
+    ParseError::ArrowExpected(_)
+);
+make_parser_error_test!(
+    test_parser_lambda_eof,
+    "(a, b)",
+    ParseError::ArrowExpected(_)
+);
+make_parser_error_test!(
+    test_parser_lambda_trailing_comma,
+    "(a,) -> a",
+    ParseError::VariableExpected(3)
+);
+make_parser_error_test!(
+    test_parser_int_overflow,
+    "170141183460469231731687303715884105728",
+    ParseError::IntegerOverflow(_)
+);
+
+#[test]
+fn test_parser_int_limits() {
+    // Arithmetic on the largest literals is exact while it fits in `i128`,
+    // and gives floats beyond.
+    let cases = [
+        (
+            "170141183460469231731687303715884105727",
+            Number::Int(i128::MAX),
+        ),
+        (
+            "170141183460469231731687303715884105727 - 1 + 1",
+            Number::Int(i128::MAX),
+        ),
+        (
+            "170141183460469231731687303715884105727 * 2",
+            Number::Flt(i128::MAX as f64 * 2.0),
+        ),
+        (
+            "-170141183460469231731687303715884105728 - 1",
+            Number::Flt(i128::MIN as f64),
+        ),
+        (
+            "-170141183460469231731687303715884105728 / -1",
+            Number::Flt(-(i128::MIN as f64)),
+        ),
+    ];
+    for (source, expected) in cases {
+        let lexer = Lexer::from_source_code(source);
+        let actual = Parser::from_lexer(lexer).parse().unwrap().evaluate();
+        assert!(actual.identical(&expected), "{}: {}", source, actual);
+    }
+}
+
+#[test]
+fn test_parser_variables() {
+    let lexer = Lexer::from_source_code("2x + y(x - 1)");
+    let mut parser = Parser::from_lexer(lexer);
+    let node = parser.parse().unwrap();
+
+    let env = Environment::new().with("x", 3).with("y", 0.5);
+    assert_eq!(node.evaluate_with(&env).unwrap(), Number::from(7.0));
+    assert!(node.evaluate_with(&Environment::new()).is_err());
+}
+
+#[test]
+fn test_parser_spans() {
+    let lexer = Lexer::from_source_code("-(1 + 2) * sin(x)");
+    let mut parser = Parser::from_lexer(lexer);
+    let node = parser.parse().unwrap();
+
+    let span = |node: &dyn Node| {
+        let span = node.span().unwrap();
+        (span.start_index, span.end_index)
+    };
+
+    let NodeView::Binary(mul) = node.view() else {
+        panic!("expected a binary node");
+    };
+    assert_eq!(span(node.as_ref()), (0, 16));
+    assert_eq!(span(mul.left()), (0, 7));
+    assert_eq!(span(mul.right()), (11, 16));
+
+    let NodeView::Unary(neg) = mul.left().view() else {
+        panic!("expected a unary node");
+    };
+    assert_eq!(span(neg.operand()), (1, 7));
+}
+
+#[test]
+fn test_parser_lambda_lookahead() {
+    // Parameters are told apart from parenthesised expressions by looking
+    // ahead for the arrow, and are not parsed as expressions first.
+    let cases = [
+        ("(a, b, c) -> a b c", vec!["a", "b", "c"], (0, 17)),
+        ("(a) -> a + 1", vec!["a"], (0, 11)),
+        ("(x, y) -> (x) -> x y", vec!["x", "y"], (0, 19)),
+    ];
+    for (source, params, expected) in cases {
+        let lexer = Lexer::from_source_code(source);
+        let node = Parser::from_lexer(lexer).parse().unwrap();
+
+        let NodeView::Lambda(lambda) = node.view() else {
+            panic!("{} is not a lambda", source);
+        };
+        assert_eq!(lambda.params(), params);
+        let span = node.span().unwrap();
+        assert_eq!((span.start_index, span.end_index), expected, "{}", source);
+    }
+
+    // A single parenthesised variable without an arrow is an expression.
+    let lexer = Lexer::from_source_code("(x) (y)");
+    let node = Parser::from_lexer(lexer).parse().unwrap();
+    let env = Environment::new().with("x", 3).with("y", 4);
+    assert_eq!(node.evaluate_with(&env).unwrap(), Number::Int(12));
+}
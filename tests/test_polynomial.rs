@@ -0,0 +1,383 @@
+use proptest::prelude::*;
+use tilted::{
+    ast::{from_polynomial, rewrite, simplify, to_infix, to_polynomial},
+    Environment, Lexer, NodeBox, Number, Parser, Polynomial, Program, Rational,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn polynomial(source: &str) -> Polynomial {
+    to_polynomial(parse(source).as_ref()).unwrap()
+}
+
+fn rational(numer: i128, denom: i128) -> Rational {
+    Rational::new(numer, denom).unwrap()
+}
+
+fn value(node: &NodeBox, x: f64) -> f64 {
+    let env = Environment::new().with("x", x);
+    match node.evaluate_with(&env).unwrap() {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
+
+macro_rules! make_polynomial_test {
+    // Checks that the rewritten call is the AST of `$expected`.
+    ($name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            let actual = rewrite(parse($source).as_ref());
+            let expected = parse($expected);
+            assert_eq!(
+                actual.as_ref(),
+                expected.as_ref(),
+                "{} != {}",
+                to_infix(actual.as_ref()),
+                to_infix(expected.as_ref())
+            );
+        }
+    };
+}
+
+make_polynomial_test!(test_expand_cube, "expand((x + 1)^3)", "x^3 + 3x^2 + 3x + 1");
+make_polynomial_test!(
+    test_expand_binomial,
+    "expand((x + y)^2)",
+    "x^2 + 2x y + y^2"
+);
+make_polynomial_test!(test_expand_negative, "expand(-(x - 1)^2)", "-x^2 + 2x - 1");
+make_polynomial_test!(test_expand_cancel, "expand((x + 1)(x - 1) - x^2)", "-1.0");
+make_polynomial_test!(test_expand_zero, "expand(x - x)", "0.0");
+make_polynomial_test!(test_expand_number, "expand(2 * (3 + 4))", "2 * (3 + 4)");
+make_polynomial_test!(
+    test_expand_exact_division,
+    "expand((x + 1) / 2)",
+    "0.5x + 0.5"
+);
+make_polynomial_test!(
+    test_expand_thirds,
+    "expand((2x - 1)^2 / 3)",
+    "4x^2 / 3.0 - 4x / 3.0 + 1 / 3.0"
+);
+make_polynomial_test!(test_expand_integer_division, "expand(1 / 2 * x + x)", "x");
+make_polynomial_test!(
+    test_expand_float,
+    "expand(x^2.0 * (x + 1.5))",
+    "x^3 + 1.5x^2"
+);
+make_polynomial_test!(
+    test_expand_inside,
+    "expand(sin((x + 1)^2) * (x + 1))",
+    "sin(x^2 + 2x + 1) * (x + 1)"
+);
+make_polynomial_test!(
+    test_expand_equation,
+    "expand((x + 1)^2 = 0)",
+    "x^2 + 2x + 1 = 0"
+);
+make_polynomial_test!(
+    test_expand_binder,
+    "expand(sum(k (x + 1), k, 1, n))",
+    "sum(k x + k, k, 1, n)"
+);
+make_polynomial_test!(test_expand_variable_power, "expand((x + 1)^n)", "(x + 1)^n");
+
+make_polynomial_test!(
+    test_factor_quadratic,
+    "factor(x^2 - 5x + 6)",
+    "(x - 2)(x - 3)"
+);
+make_polynomial_test!(
+    test_factor_content,
+    "factor(2x^3 - 2x)",
+    "2x (x + 1)(x - 1)"
+);
+make_polynomial_test!(test_factor_negative, "factor(1 - x^2)", "-(x + 1)(x - 1)");
+make_polynomial_test!(
+    test_factor_rational_roots,
+    "factor(6x^2 + 5x + 1)",
+    "(2x + 1)(3x + 1)"
+);
+make_polynomial_test!(
+    test_factor_fraction,
+    "factor(x^2 / 4 - 1)",
+    "0.25 (x + 2)(x - 2)"
+);
+make_polynomial_test!(
+    test_factor_repeated,
+    "factor(expand((x - 1)^2 (x + 2)^3))",
+    "(x + 2)^3 (x - 1)^2"
+);
+make_polynomial_test!(
+    test_factor_quartic,
+    "factor(x^4 + 4)",
+    "(x^2 + 2x + 2)(x^2 - 2x + 2)"
+);
+make_polynomial_test!(
+    test_factor_cyclotomic,
+    "factor(x^6 - 1)",
+    "(x + 1)(x - 1)(x^2 + x + 1)(x^2 - x + 1)"
+);
+make_polynomial_test!(
+    test_factor_kronecker,
+    "factor(x^5 + x + 1)",
+    "(x^2 + x + 1)(x^3 - x^2 + 1)"
+);
+make_polynomial_test!(
+    test_factor_repeated_quadratic,
+    "factor(expand((x^2 + 1)^2 (x^3 - x - 1)))",
+    "(x^2 + 1)^2 (x^3 - x - 1)"
+);
+make_polynomial_test!(test_factor_irreducible, "factor(x^2 + 1)", "x^2 + 1");
+make_polynomial_test!(
+    test_factor_several_variables,
+    "factor(3x^2 y - 6x y)",
+    "3x y (x - 2)"
+);
+make_polynomial_test!(
+    test_factor_inside,
+    "factor(sin(x^2 - 1))",
+    "sin((x + 1)(x - 1))"
+);
+
+make_polynomial_test!(test_collect_sum, "collect(a*x + b*x, x)", "(a + b) x");
+make_polynomial_test!(
+    test_collect_powers,
+    "collect(a x^2 + b x + c x + a b + 2x^2 - 1, x)",
+    "(a + 2) x^2 + (b + c) x + a b - 1"
+);
+make_polynomial_test!(
+    test_collect_single,
+    "collect(x y - 2x y^2, y)",
+    "-2x y^2 + x y"
+);
+make_polynomial_test!(
+    test_collect_negative,
+    "collect(-a x - b x, x)",
+    "(-a - b) x"
+);
+
+#[test]
+fn test_polynomial_form_deferred() {
+    let node = parse("expand((x + 1)^2) - factor(x^2 - 1) + collect(a x + x, x)");
+    let env = Environment::new().with("x", 3).with("a", 2);
+
+    assert_eq!(
+        to_infix(node.as_ref()),
+        "expand((x + 1)^2) - factor(x^2 - 1) + collect(a * x + x, x)"
+    );
+    assert_eq!(node.evaluate_with(&env).unwrap(), Number::Int(17));
+    assert_eq!(
+        Program::compile(node.as_ref()).evaluate_with(&env).unwrap(),
+        Number::Int(17)
+    );
+    assert_eq!(
+        to_infix(simplify(parse("expand((x + 1)^2)").as_ref()).as_ref()),
+        "x^2 + 2 * x + 1"
+    );
+}
+
+#[test]
+fn test_rational_arithmetic() {
+    let half = rational(1, 2);
+    let third = rational(-2, -6);
+
+    assert_eq!(half.checked_add(third), Some(rational(5, 6)));
+    assert_eq!(half.checked_sub(third), Some(rational(1, 6)));
+    assert_eq!(half.checked_mul(third), Some(rational(1, 6)));
+    assert_eq!(half.checked_div(third), Some(rational(3, 2)));
+    assert_eq!(rational(4, -6).to_string(), "-2/3");
+    assert_eq!(Rational::ZERO.recip(), None);
+}
+
+#[test]
+fn test_rational_overflow() {
+    let large = Rational::integer(i128::MAX).unwrap();
+
+    assert_eq!(Rational::integer(i128::MIN), None);
+    assert_eq!(large.checked_add(Rational::ONE), None);
+    assert_eq!(large.checked_mul(rational(2, 3)), None);
+    assert_eq!(
+        large.checked_mul(rational(1, 3)),
+        Some(rational(i128::MAX, 3))
+    );
+    assert_eq!(rational(1, 2).checked_pow(200), None);
+}
+
+#[test]
+fn test_rational_from_f64() {
+    assert_eq!(Rational::from_f64(0.1), Some(rational(1, 10)));
+    assert_eq!(Rational::from_f64(-2.5), Some(rational(-5, 2)));
+    assert_eq!(Rational::from_f64(3.0), Some(rational(3, 1)));
+    assert_eq!(Rational::from_f64(1e300), None);
+    assert_eq!(Rational::from_f64(f64::NAN), None);
+}
+
+#[test]
+fn test_polynomial_div_rem() {
+    let (quotient, remainder) = polynomial("x^3 - 2x + 1")
+        .checked_div_rem(&polynomial("2x - 2"))
+        .unwrap();
+
+    assert_eq!(quotient, polynomial("x^2 / 2 + x / 2 - 1 / 2.0"));
+    assert_eq!(remainder, Polynomial::zero());
+    assert_eq!(polynomial("x").checked_div_rem(&Polynomial::zero()), None);
+    assert_eq!(polynomial("x").checked_div_rem(&polynomial("y")), None);
+}
+
+#[test]
+fn test_polynomial_gcd() {
+    let a = polynomial("(x - 1)^2 (x + 3)");
+    let b = polynomial("(x - 1)(x + 2) * 4");
+
+    assert_eq!(a.gcd(&b), Some(polynomial("x - 1")));
+    assert_eq!(
+        a.gcd(&Polynomial::zero()),
+        Some(polynomial("x^3 + x^2 - 5x + 3"))
+    );
+    assert_eq!(polynomial("2").gcd(&polynomial("x")), Some(polynomial("1")));
+    assert_eq!(
+        Polynomial::zero().gcd(&Polynomial::zero()),
+        Some(Polynomial::zero())
+    );
+}
+
+#[test]
+fn test_polynomial_factorisation() {
+    let p = polynomial("-6x^5 + 6x");
+    let factorisation = p.factor().unwrap();
+    let factors: Vec<_> = factorisation
+        .factors()
+        .iter()
+        .map(|(f, m)| (to_infix(from_polynomial(f).as_ref()), *m))
+        .collect();
+
+    assert_eq!(factorisation.constant(), rational(-6, 1));
+    assert_eq!(
+        factors,
+        [("x", 1), ("x + 1", 1), ("x - 1", 1), ("x^2 + 1", 1)].map(|(f, m)| (f.to_string(), m))
+    );
+    assert_eq!(factorisation.expand(), Some(p));
+}
+
+#[test]
+fn test_polynomial_terms() {
+    let p = polynomial("y^2 + x + 2x y + x^2 - 3");
+    let terms: Vec<_> = p.terms().map(|(m, c)| (m.degree(), c)).collect();
+
+    assert_eq!(p.variables(), ["x", "y"]);
+    assert_eq!(p.degree("y"), 2);
+    assert_eq!(
+        to_infix(from_polynomial(&p).as_ref()),
+        "x^2 + 2 * x * y + y^2 + x - 3"
+    );
+    assert_eq!(terms[4], (0, rational(-3, 1)));
+    assert_eq!(
+        p.coefficients("y"),
+        [polynomial("x^2 + x - 3"), polynomial("2x"), polynomial("1")]
+    );
+}
+
+#[test]
+fn test_polynomial_not_polynomial() {
+    for source in [
+        "sin(x)", "x^y", "1 / x", "x^(-1)", "x m", "x ± 1", "x^0.5", "x / 0",
+    ] {
+        assert_eq!(to_polynomial(parse(source).as_ref()), None, "{}", source);
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Property tests.
+// -----------------------------------------------------------------------------
+
+/// Generates a polynomial in `x` with small integer coefficients, constant
+/// first.
+fn dense() -> impl Strategy<Value = Vec<i128>> {
+    prop::collection::vec(-5i128..=5, 1..=4)
+}
+
+fn from_dense(coefficients: &[i128]) -> Polynomial {
+    let terms = coefficients
+        .iter()
+        .enumerate()
+        .map(|(power, c)| format!("{} x^{}", c, power));
+    polynomial(&terms.collect::<Vec<_>>().join(" + "))
+}
+
+proptest! {
+    #[test]
+    fn test_expand_preserves_value(
+        factors in prop::collection::vec((-5i128..=5, -5i128..=5), 1..=4),
+        x in -3.0..3.0f64,
+    ) {
+        let product: Vec<_> = factors.iter().map(|(a, b)| format!("({} x + {})", a, b)).collect();
+        let node = parse(&product.join(" * "));
+        let expanded = parse(&format!("expand({})", product.join(" * ")));
+
+        let (expected, actual) = (value(&node, x), value(&expanded, x));
+        prop_assert!((expected - actual).abs() <= 1e-9 * expected.abs().max(1.0), "{} != {}", expected, actual);
+    }
+
+    #[test]
+    fn test_factor_expands_back(factors in prop::collection::vec(dense(), 1..=3)) {
+        let mut p = polynomial("1");
+        for factor in &factors {
+            p = p.checked_mul(&from_dense(factor)).unwrap();
+        }
+
+        let factorisation = p.factor().unwrap();
+        prop_assert_eq!(factorisation.expand(), Some(p));
+        for (factor, _) in factorisation.factors() {
+            prop_assert!(factor.as_constant().is_none());
+        }
+    }
+
+    #[test]
+    fn test_factor_finds_linear_factors(roots in prop::collection::vec((prop_oneof![-6i128..=-1, 1i128..=6], 1i128..=3), 1..=4)) {
+        let mut p = polynomial("1");
+        for (numer, denom) in &roots {
+            p = p.checked_mul(&polynomial(&format!("{} x - {}", denom, numer))).unwrap();
+        }
+
+        // Every factor is linear, with the expected roots in ascending order.
+        let factorisation = p.factor().unwrap();
+        let mut expected: Vec<_> = roots.iter().map(|&(n, d)| rational(n, d)).collect();
+        expected.sort_by(|a, b| a.to_f64().total_cmp(&b.to_f64()));
+        expected.dedup();
+
+        let mut actual = vec![];
+        for (factor, _) in factorisation.factors() {
+            prop_assert_eq!(factor.degree("x"), 1);
+            let coefficients = factor.coefficients("x");
+            let root = coefficients[0].as_constant().unwrap().checked_div(coefficients[1].as_constant().unwrap());
+            actual.push(-root.unwrap());
+        }
+        prop_assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_div_rem_identity(a in dense(), b in dense()) {
+        let (a, b) = (from_dense(&a), from_dense(&b));
+        prop_assume!(!b.is_zero());
+
+        let (quotient, remainder) = a.checked_div_rem(&b).unwrap();
+        prop_assert_eq!(quotient.checked_mul(&b).unwrap().checked_add(&remainder).unwrap(), a);
+        prop_assert!(remainder.is_zero() || remainder.degree("x") < b.degree("x"));
+    }
+
+    #[test]
+    fn test_gcd_common_factor(a in dense(), b in dense(), c in dense()) {
+        let (a, b, c) = (from_dense(&a), from_dense(&b), from_dense(&c));
+        prop_assume!(!c.is_zero());
+
+        let gcd = a.checked_mul(&c).unwrap().gcd(&b.checked_mul(&c).unwrap()).unwrap();
+        let (_, remainder) = gcd.checked_div_rem(&c).unwrap();
+        prop_assert!(remainder.is_zero());
+    }
+}
@@ -0,0 +1,190 @@
+use proptest::prelude::*;
+use tilted::{
+    ast::{evaluate_quantity, Quantity},
+    Dimension, Environment, EvalError, Lexer, NodeBox, Number, Parser, Unit,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn quantity(source: &str) -> Result<Quantity, EvalError> {
+    evaluate_quantity(parse(source).as_ref(), &Environment::new())
+}
+
+macro_rules! make_quantity_test {
+    // Checks the formatted result.
+    ($name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            assert_eq!(quantity($source).unwrap().to_string(), $expected);
+        }
+    };
+    // Checks the error.
+    (E: $name: ident, $source: literal, $expected: pat) => {
+        #[test]
+        fn $name() {
+            let err = quantity($source).unwrap_err();
+            assert!(matches!(err, $expected), "{:?}", err);
+        }
+    };
+}
+
+make_quantity_test!(test_quantity_number, "7 / 2", "3");
+make_quantity_test!(test_quantity_speed, "3 m / 2 s", "1.5 m/s");
+make_quantity_test!(test_quantity_si, "3 km", "3000 m");
+make_quantity_test!(test_quantity_convert, "3 m / 2 s to km/h", "5.4 km/h");
+make_quantity_test!(test_quantity_imperial, "5 ft to m", "1.524 m");
+make_quantity_test!(test_quantity_same_unit, "60 min to h", "1 h");
+make_quantity_test!(test_quantity_derived, "2 kg * 9.81 m/s^2", "19.62 N");
+make_quantity_test!(test_quantity_charge, "2 A * 3 s", "6 C");
+make_quantity_test!(test_quantity_sum, "(3 km to m) + 1 m", "3001 m");
+make_quantity_test!(test_quantity_negative, "-3 m / 2 s", "-1.5 m/s");
+make_quantity_test!(test_quantity_sum_binder, "sum(k m, k, 1, 3)", "6 m");
+make_quantity_test!(test_quantity_prod_binder, "prod(2 m, k, 1, 3)", "8 m^3");
+make_quantity_test!(test_quantity_integral, "integrate(2 m/s, t, 0, 3)", "6 m/s");
+make_quantity_test!(test_quantity_solve, "solve(x m = 5 km, x) m", "5000 m");
+make_quantity_test!(test_quantity_reciprocal, "1 / (2 s)", "0.5 s^-1");
+make_quantity_test!(test_quantity_root, "(4 m^2)^0.5", "2 m");
+make_quantity_test!(test_quantity_ratio, "3 km / 1 m", "3000");
+make_quantity_test!(
+    test_quantity_denominator,
+    "3 m^2 / (2 s^2 A)",
+    "1.5 m^2/(s^2 A)"
+);
+make_quantity_test!(
+    test_quantity_convert_product,
+    "2 kW * 3 h to kW h",
+    "6 kW h"
+);
+
+make_quantity_test!(
+    E: test_quantity_add_mismatch,
+    "3 m + 2 s",
+    EvalError::DimensionMismatch(..)
+);
+make_quantity_test!(
+    E: test_quantity_convert_mismatch,
+    "3 m to kg",
+    EvalError::DimensionMismatch(..)
+);
+make_quantity_test!(
+    E: test_quantity_sum_mismatch,
+    "sum(m^k, k, 1, 2)",
+    EvalError::DimensionMismatch(..)
+);
+make_quantity_test!(
+    E: test_quantity_binder_bound,
+    "integrate(x, x, 0, 2 m)",
+    EvalError::DimensionlessExpected(..)
+);
+make_quantity_test!(
+    E: test_quantity_equation_mismatch,
+    "solve(x m = 5 s, x)",
+    EvalError::DimensionMismatch(..)
+);
+make_quantity_test!(
+    E: test_quantity_solve_guess,
+    "solve(x = 1, x, 1 m)",
+    EvalError::DimensionlessExpected(..)
+);
+make_quantity_test!(
+    E: test_quantity_func_dimension,
+    "sin(3 m)",
+    EvalError::DimensionlessExpected(_)
+);
+make_quantity_test!(
+    E: test_quantity_exponent_dimension,
+    "2^(1 s)",
+    EvalError::DimensionlessExpected(_)
+);
+make_quantity_test!(
+    E: test_quantity_fractional,
+    "(2 m)^0.5",
+    EvalError::FractionalDimension(_)
+);
+make_quantity_test!(test_quantity_sqrt, "sqrt(4 m^2)", "2 m");
+make_quantity_test!(test_quantity_radical, "√(9 m^2 / s^2)", "3 m/s");
+make_quantity_test!(test_quantity_cube_root, "(8 m^3)^(1 / 3.0)", "2 m");
+make_quantity_test!(
+    E: test_quantity_sqrt_fractional,
+    "sqrt(2 m)",
+    EvalError::FractionalDimension(_)
+);
+
+#[test]
+fn test_quantity_magnitude() {
+    let node = parse("1.5 m/s to km/h");
+    let actual = evaluate_quantity(node.as_ref(), &Environment::new()).unwrap();
+
+    assert_eq!(actual.unit().map(Unit::name), Some("km/h"));
+    assert_eq!(
+        actual.magnitude(),
+        node.evaluate_with(&Environment::new()).unwrap()
+    );
+}
+
+#[test]
+fn test_quantity_variables() {
+    let env = Environment::new().with("x", 4);
+    let actual = evaluate_quantity(parse("x m").as_ref(), &env).unwrap();
+
+    assert_eq!(actual.value(), Number::Flt(4.0));
+    assert_eq!(actual.dimension(), Dimension::new([1, 0, 0, 0, 0, 0, 0]));
+}
+
+#[test]
+fn test_unit_lookup() {
+    let scale = |name| Unit::lookup(name).map(|u| u.scale());
+
+    assert_eq!(scale("km"), Some(1e3));
+    assert_eq!(scale("kg"), Some(1.0));
+    assert_eq!(scale("mA"), Some(1e-3));
+    assert_eq!(scale("dam"), Some(10.0));
+    assert_eq!(scale("min"), Some(60.0));
+    assert_eq!(scale("mL"), Some(1e-6));
+    assert_eq!(scale("kft"), None);
+    assert_eq!(scale("x"), None);
+}
+
+#[test]
+fn test_dimension_display() {
+    let display = |exponents| Dimension::new(exponents).to_string();
+
+    assert_eq!(display([0, 0, 0, 0, 0, 0, 0]), "1");
+    assert_eq!(display([1, 1, -2, 0, 0, 0, 0]), "N");
+    assert_eq!(display([1, 1, 0, 0, 0, 0, 0]), "kg m");
+    assert_eq!(display([0, 0, -1, 0, 0, 0, 0]), "s^-1");
+    assert_eq!(display([-1, 0, -1, 0, 0, 0, 0]), "m^-1 s^-1");
+    assert_eq!(display([3, 0, -1, 0, 0, 0, 0]), "m^3/s");
+}
+
+// -----------------------------------------------------------------------------
+// Property tests.
+// -----------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn test_quantity_round_trip(
+        x in -1e6..1e6f64,
+        units in prop::sample::select(vec![
+            ("km", "mi"),
+            ("m/s", "km/h"),
+            ("kg", "lb"),
+            ("L", "gal"),
+            ("kW h", "MJ"),
+        ]),
+    ) {
+        let (from, to) = units;
+        let env = Environment::new().with("x", x);
+        let source = format!("(x {}) to {} to {}", from, to, from);
+        let actual = evaluate_quantity(parse(&source).as_ref(), &env).unwrap();
+
+        let Number::Flt(magnitude) = actual.magnitude() else {
+            panic!("expected a float");
+        };
+        prop_assert!((magnitude - x).abs() <= 1e-9 * x.abs().max(1.0));
+    }
+}
@@ -0,0 +1,240 @@
+use tilted::{
+    ast::{evaluate_batch, evaluate_value, is_pure, Value},
+    stats, Environment, EvalError, Lexer, NodeBox, Number, ParseError, Parser, Program, Rng, Shape,
+    TilError, Vm,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn value(source: &str, seed: u64) -> Result<Value, EvalError> {
+    evaluate_value(parse(source).as_ref(), &Environment::new().with_seed(seed))
+}
+
+fn scalar(source: &str, seed: u64) -> f64 {
+    match value(source, seed).unwrap() {
+        Value::Scalar(Number::Flt(n)) => n,
+        Value::Scalar(Number::Int(n)) => n as f64,
+        value => panic!("{} is not a scalar", value),
+    }
+}
+
+macro_rules! make_random_test {
+    // Checks that every draw is within the bounds, for several seeds.
+    ($name: ident, $source: literal, $low: expr, $high: expr) => {
+        #[test]
+        fn $name() {
+            for seed in 0..100 {
+                let n = scalar($source, seed);
+                assert!(($low..=$high).contains(&n), "{} is out of bounds", n);
+            }
+        }
+    };
+    // Checks the formatted result.
+    ($name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            assert_eq!(value($source, 0).unwrap().to_string(), $expected);
+        }
+    };
+    // Checks the error.
+    (E: $name: ident, $source: literal, $expected: pat) => {
+        #[test]
+        fn $name() {
+            let err = value($source, 0).unwrap_err();
+            assert!(matches!(err, $expected), "{:?}", err);
+        }
+    };
+}
+
+make_random_test!(test_random_rand, "rand()", 0.0, 0.999_999_999_999_999_9);
+make_random_test!(test_random_randint, "randint(1, 6)", 1.0, 6.0);
+make_random_test!(test_random_randint_negative, "randint(-3, -3)", -3.0, -3.0);
+make_random_test!(test_random_randn_sigma_zero, "randn(5, 0)", 5.0, 5.0);
+make_random_test!(test_random_choose, "choose({2, 3, 5})", 2.0, 5.0);
+make_random_test!(test_random_choose_vector, "choose([7, 7])", 7.0, 7.0);
+make_random_test!(test_random_randint_reversed, "randint(6, 1)", "NaN");
+make_random_test!(test_random_randn_negative, "randn(0, -1)", "NaN");
+make_random_test!(test_random_choose_single, "choose({[1, 2]})", "[1, 2]");
+make_random_test!(
+    E: test_random_randint_float,
+    "randint(1, 6.5)",
+    EvalError::IntegerExpected(_)
+);
+make_random_test!(
+    E: test_random_choose_empty,
+    "choose({})",
+    EvalError::InvalidShape("choose", Shape::List(0))
+);
+make_random_test!(
+    E: test_random_choose_scalar,
+    "choose(1)",
+    EvalError::InvalidShape("choose", Shape::Scalar)
+);
+
+#[test]
+fn test_random_arguments() {
+    let lexer = Lexer::from_source_code("rand(1)");
+    let err = Parser::from_lexer(lexer).parse().unwrap_err();
+    assert!(
+        matches!(
+            err,
+            TilError::Parse(ParseError::ArgumentCountMismatch("rand", 0, 1))
+        ),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn test_random_splitmix() {
+    // First outputs of SplitMix64 from a seed of 0.
+    let rng = Rng::new(0);
+    assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+    assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+    assert_eq!(rng.next_u64(), 0x06C4_5D18_8009_454F);
+}
+
+#[test]
+fn test_random_reproducible() {
+    let source = "{rand(), randint(1, 100), randn(), choose(1..10)}";
+    assert_eq!(value(source, 42).unwrap(), value(source, 42).unwrap());
+    assert_ne!(value(source, 42).unwrap(), value(source, 43).unwrap());
+}
+
+#[test]
+fn test_random_shared_stream() {
+    // Every draw advances the same generator, even within lambdas.
+    let env = Environment::new().with_seed(1);
+    let node = parse("map(x -> rand(), 1..3)");
+    let first = evaluate_value(node.as_ref(), &env).unwrap();
+    let second = evaluate_value(node.as_ref(), &env).unwrap();
+    assert_ne!(first, second);
+
+    let rng = Rng::new(1);
+    let expected = Value::List((0..3).map(|_| Number::Flt(rng.uniform()).into()).collect());
+    assert_eq!(first, expected);
+}
+
+#[test]
+fn test_random_clone_shares_state() {
+    let rng = Rng::new(7);
+    let clone = rng.clone();
+    let first = rng.next_u64();
+    assert_ne!(clone.next_u64(), first);
+    assert_eq!(rng, clone);
+    assert_eq!(Rng::new(rng.state()).next_u64(), rng.next_u64());
+}
+
+#[test]
+fn test_random_set_seed() {
+    let mut env = Environment::new();
+    let clone = env.clone();
+    env.set_seed(5);
+    assert_eq!(*env.rng(), Rng::new(5));
+    assert_eq!(*clone.rng(), Rng::new(0));
+}
+
+#[test]
+fn test_random_integer_uniform() {
+    let rng = Rng::new(3);
+    let mut counts = [0; 6];
+    for _ in 0..60000 {
+        counts[rng.integer(1, 6).unwrap() as usize - 1] += 1;
+    }
+    for count in counts {
+        assert!((9500..10500).contains(&count), "{:?}", counts);
+    }
+}
+
+#[test]
+fn test_random_integer_extremes() {
+    let rng = Rng::new(3);
+    assert_eq!(rng.integer(i128::MAX, i128::MAX), Some(i128::MAX));
+    assert_eq!(rng.integer(1, 0), None);
+    assert!(rng.integer(i128::MIN, i128::MAX).is_some());
+    let n = rng.integer(i128::MIN, i128::MIN + 1).unwrap();
+    assert!(n == i128::MIN || n == i128::MIN + 1);
+}
+
+#[test]
+fn test_random_uniform_moments() {
+    let rng = Rng::new(11);
+    let data: Vec<f64> = (0..100000).map(|_| rng.uniform()).collect();
+    let mean = stats::mean(&data);
+    let variance = stats::population_variance(&data);
+    assert!((mean - 0.5).abs() < 0.005, "{}", mean);
+    assert!((variance - 1.0 / 12.0).abs() < 0.002, "{}", variance);
+}
+
+#[test]
+fn test_random_normal_moments() {
+    let rng = Rng::new(13);
+    let data: Vec<f64> = (0..100000).map(|_| rng.normal(10.0, 2.0)).collect();
+    let mean = stats::mean(&data);
+    let stdev = stats::sample_variance(&data).sqrt();
+    assert!((mean - 10.0).abs() < 0.03, "{}", mean);
+    assert!((stdev - 2.0).abs() < 0.03, "{}", stdev);
+}
+
+#[test]
+fn test_random_is_pure() {
+    assert!(is_pure(parse("x + sin(1) + mean(1..3)").as_ref()));
+    assert!(!is_pure(parse("1 + rand()").as_ref()));
+    assert!(!is_pure(parse("map(x -> x + randn(), 1..3)").as_ref()));
+    assert!(!is_pure(parse("sum(randint(0, i), i, 1, 3)").as_ref()));
+}
+
+#[test]
+fn test_random_program() {
+    // A program draws the same numbers as its AST.
+    let node = parse("rand() + sum(i randint(1, 6), i, 1, 3) - choose({1, 2})");
+    let env = Environment::new().with_seed(9);
+    let expected = node.evaluate_with(&env).unwrap();
+    let program = Program::compile(node.as_ref());
+    let actual = program
+        .evaluate_with(&Environment::new().with_seed(9))
+        .unwrap();
+    assert!(
+        actual.identical(&expected),
+        "{} is not {}",
+        actual,
+        expected
+    );
+
+    // A reused machine keeps drawing new numbers.
+    let mut vm = Vm::new().with_seed(9);
+    assert!(vm.run(&program, &[]).unwrap().identical(&expected));
+    assert!(!vm.run(&program, &[]).unwrap().identical(&expected));
+}
+
+#[test]
+fn test_random_batch() {
+    // Rows draw in turn from one generator seeded with 0.
+    let node = parse("x + rand()");
+    let x = [1.0, 2.0, 3.0];
+    let mut output = [Number::Int(0); 3];
+    evaluate_batch(node.as_ref(), &[("x", x[..].into())], &mut output).unwrap();
+
+    let rng = Rng::default();
+    for (actual, x) in output.iter().zip(x) {
+        assert!(actual.identical(&Number::Flt(x + rng.uniform())));
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_random_serde() {
+    let env = Environment::new().with_seed(21);
+    env.rng().next_u64();
+    let json = serde_json::to_string(&env).unwrap();
+    let copy: Environment = serde_json::from_str(&json).unwrap();
+    assert_eq!(copy.rng().next_u64(), env.rng().next_u64());
+
+    // Environments serialised without a generator get the default one.
+    let copy: Environment = serde_json::from_str(r#"{"variables":{}}"#).unwrap();
+    assert_eq!(*copy.rng(), Rng::default());
+}
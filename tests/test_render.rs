@@ -0,0 +1,96 @@
+use tilted::{
+    ast::{to_latex, to_mathml},
+    Lexer, NodeBox, Number, Parser, PlainNode,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+macro_rules! make_render_test {
+    (L: $name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            assert_eq!(to_latex(parse($source).as_ref()), $expected);
+        }
+    };
+    (M: $name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            let expected = format!(
+                "<math xmlns=\"http://www.w3.org/1998/Math/MathML\">{}</math>",
+                $expected
+            );
+            assert_eq!(to_mathml(parse($source).as_ref()), expected);
+        }
+    };
+}
+
+make_render_test!(L: test_latex_sum, "1 + x - 2.5", r"1 + x - 2.5");
+make_render_test!(L: test_latex_plus_minus, "2a ± (b - c)", r"2 \cdot a \pm \left(b - c\right)");
+make_render_test!(L: test_latex_sum_right, "a - (b - c)", r"a - \left(b - c\right)");
+make_render_test!(L: test_latex_product, "2x(y + 1)", r"2 \cdot x \cdot \left(y + 1\right)");
+make_render_test!(L: test_latex_product_right, "a(b * c)", r"a \cdot \left(b \cdot c\right)");
+make_render_test!(L: test_latex_sum_product, "a + b * c", r"a + b \cdot c");
+make_render_test!(L: test_latex_frac, "(a + b) / (c * d)", r"\frac{a + b}{c \cdot d}");
+make_render_test!(L: test_latex_frac_pow, "(1 / x)^2", r"\left(\frac{1}{x}\right)^{2}");
+make_render_test!(L: test_latex_pow, "(x + 1)^(2y)", r"\left(x + 1\right)^{2 \cdot y}");
+make_render_test!(L: test_latex_pow_neg, "(-2)^x - -2^x", r"\left(-2\right)^{x} - \left(-2^{x}\right)");
+make_render_test!(L: test_latex_neg, "-(a + b) * -c", r"\left(-\left(a + b\right)\right) \cdot \left(-c\right)");
+make_render_test!(L: test_latex_leading_neg, "-a + b", r"-a + b");
+make_render_test!(L: test_latex_func, "sin(x)^2", r"\sin\left(x\right)^{2}");
+make_render_test!(L: test_latex_func_arc, "asin(x) + acot(x)", r"\arcsin\left(x\right) + \operatorname{arccot}\left(x\right)");
+make_render_test!(L: test_latex_sqrt, "sqrt(2) / 2 + pi", r"\frac{\sqrt{2}}{2} + \pi");
+make_render_test!(L: test_latex_long_variable, "x_1 + ab", r"\mathit{x\_1} + \mathit{ab}");
+make_render_test!(L: test_latex_units, "3 m / 2 s to km/h", r"\frac{3 \cdot \mathrm{m}}{2 \cdot \mathrm{s}} \to \frac{\mathrm{km}}{\mathrm{h}}");
+make_render_test!(L: test_latex_integral, "integrate(x + 1, x, 0, 1) * 2", r"\int_{0}^{1} x + 1 \, \mathrm{d}x \cdot 2");
+make_render_test!(L: test_latex_binder_operand, "sum(k^2, k, 1, n) + 1", r"\left(\sum_{k = 1}^{n} k^{2}\right) + 1");
+make_render_test!(L: test_latex_prod, "prod(k + 1, k, 1, n)", r"\prod_{k = 1}^{n} \left(k + 1\right)");
+
+make_render_test!(L: test_latex_equation, "x^2 = 2 + 1", r"x^{2} = 2 + 1");
+make_render_test!(L: test_latex_equation_operand, "2 * (x = 1)", r"2 \cdot \left(x = 1\right)");
+make_render_test!(L: test_latex_solve, "solve(x^2 = 2, x, 0, 1)", r"\operatorname{solve}\left(x^{2} = 2, x, 0, 1\right)");
+make_render_test!(L: test_latex_map, "map((a, b) -> a b, 1..n)", r"\operatorname{map}\left(\left(a, b\right) \mapsto a \cdot b, \left\{1, \ldots, n\right\}\right)");
+make_render_test!(L: test_latex_stats, "nCr(n, 2) gamma(x) + mean(1, 2)", r"\binom{n}{2} \cdot \Gamma\left(x\right) + \operatorname{mean}\left(1, 2\right)");
+make_render_test!(L: test_latex_lambda_operand, "{x -> x} + 1", r"\left\{x \mapsto x\right\} + 1");
+make_render_test!(L: test_latex_diff, "diff(x^2, x)", r"\frac{\mathrm{d}}{\mathrm{d}x}\left(x^{2}\right)");
+make_render_test!(M: test_mathml_sum, "1 + x", "<mrow><mn>1</mn><mo>+</mo><mi>x</mi></mrow>");
+make_render_test!(M: test_mathml_negative, "-2", "<mrow><mo>\u{2212}</mo><mn>2</mn></mrow>");
+make_render_test!(M: test_mathml_units, "2 kg to lb", "<mrow><mrow><mn>2</mn><mo>\u{22c5}</mo><mi mathvariant=\"normal\">kg</mi></mrow><mo>\u{2192}</mo><mi mathvariant=\"normal\">lb</mi></mrow>");
+make_render_test!(M: test_mathml_sum_binder, "sum(k, k, 1, n)", "<mrow><munderover><mo>\u{2211}</mo><mrow><mi>k</mi><mo>=</mo><mn>1</mn></mrow><mi>n</mi></munderover><mi>k</mi></mrow>");
+make_render_test!(M: test_mathml_equation, "a = b", "<mrow><mi>a</mi><mo>=</mo><mi>b</mi></mrow>");
+make_render_test!(M: test_mathml_sqrt, "sqrt(pi)", "<msqrt><mi>\u{3c0}</mi></msqrt>");
+make_render_test!(M: test_mathml_lambda, "x -> {x}", "<mrow><mi>x</mi><mo>\u{21A6}</mo><mrow><mo>{</mo><mi>x</mi><mo>}</mo></mrow></mrow>");
+make_render_test!(M: test_mathml_solve, "solve(a, x)", "<mrow><mi>solve</mi><mo>\u{2061}</mo><mrow><mo>(</mo><mi>a</mi><mo>,</mo><mi>x</mi><mo>)</mo></mrow></mrow>");
+make_render_test!(M: test_mathml_integral, "integrate(x, x, 0, 1)", "<mrow><msubsup><mo>\u{222B}</mo><mn>0</mn><mn>1</mn></msubsup><mi>x</mi><mrow><mi mathvariant=\"normal\">d</mi><mi>x</mi></mrow></mrow>");
+make_render_test!(
+    M: test_mathml_frac,
+    "1 / (x - 1)",
+    "<mfrac><mn>1</mn><mrow><mi>x</mi><mo>\u{2212}</mo><mn>1</mn></mrow></mfrac>"
+);
+make_render_test!(
+    M: test_mathml_pow,
+    "(x * y)^2",
+    "<msup><mrow><mo>(</mo><mrow><mi>x</mi><mo>\u{22C5}</mo><mi>y</mi></mrow><mo>)</mo></mrow><mn>2</mn></msup>"
+);
+make_render_test!(
+    M: test_mathml_func,
+    "acot(x)",
+    "<mrow><mi>arccot</mi><mo>\u{2061}</mo><mrow><mo>(</mo><mi>x</mi><mo>)</mo></mrow></mrow>"
+);
+make_render_test!(
+    M: test_mathml_diff,
+    "diff(y, x)",
+    "<mrow><mfrac><mi mathvariant=\"normal\">d</mi><mrow><mi mathvariant=\"normal\">d</mi><mi>x</mi></mrow></mfrac><mrow><mo>(</mo><mi>y</mi><mo>)</mo></mrow></mrow>"
+);
+
+#[test]
+fn test_render_non_finite() {
+    let node: NodeBox = Box::new(PlainNode::new(Number::Flt(f64::NEG_INFINITY)));
+    assert_eq!(to_latex(node.as_ref()), r"-\infty");
+
+    let node: NodeBox = Box::new(PlainNode::new(Number::Flt(f64::NAN)));
+    assert!(to_mathml(node.as_ref()).contains("<mi>NaN</mi>"));
+}
@@ -0,0 +1,229 @@
+use proptest::prelude::*;
+use tilted::{
+    ast::{simplify, Simplifier},
+    BinaryAction, BinaryNode, Environment, Function, Lexer, NodeBox, Number, Parser, PlainNode,
+    UnaryAction, UnaryNode, VariableNode,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+macro_rules! make_simplify_test {
+    ($name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            let actual = simplify(parse($source).as_ref());
+            let expected = parse($expected);
+
+            assert_eq!(actual.as_ref(), expected.as_ref());
+        }
+    };
+    (R: $name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            let simplifier = Simplifier::new().assume_real(true);
+            let actual = simplifier.simplify(parse($source).as_ref());
+            let expected = parse($expected);
+
+            assert_eq!(actual.as_ref(), expected.as_ref());
+        }
+    };
+}
+
+make_simplify_test!(test_simplify_fold_int, "2 + 3 * 4", "14");
+make_simplify_test!(test_simplify_fold_flt, "2.5 * 2", "5.0");
+make_simplify_test!(test_simplify_fold_func, "x + sin(0)", "x + 0.0");
+make_simplify_test!(test_simplify_fold_subtree, "x * (2 ^ 3)", "x * 8");
+make_simplify_test!(test_simplify_mul_one, "x * 1", "x");
+make_simplify_test!(test_simplify_one_mul, "1 * x", "x");
+make_simplify_test!(test_simplify_mul_minus_one, "x * (0 - 1)", "-x");
+make_simplify_test!(test_simplify_div_one, "x / 1", "x");
+make_simplify_test!(test_simplify_pow_one, "x ^ 1", "x");
+make_simplify_test!(test_simplify_sub_zero, "x - 0", "x");
+make_simplify_test!(test_simplify_double_neg, "-(-x)", "x");
+make_simplify_test!(test_simplify_add_neg_const, "x + -3", "x - 3");
+make_simplify_test!(test_simplify_add_neg, "x + -sin(y)", "x - sin(y)");
+make_simplify_test!(test_simplify_sub_neg, "x - -sin(y)", "x + sin(y)");
+make_simplify_test!(test_simplify_add_self, "x + x", "2x");
+make_simplify_test!(test_simplify_nested, "(x * 1 - 0 * 2) ^ (3 - 2)", "x");
+
+// Identities that do not hold for every float are kept.
+make_simplify_test!(test_simplify_keep_add_zero, "x + 0", "x + 0");
+make_simplify_test!(test_simplify_keep_mul_zero, "0 * x", "0 * x");
+make_simplify_test!(test_simplify_keep_sub_self, "x - x", "x - x");
+make_simplify_test!(test_simplify_keep_mul_flt_one, "x * 1.0", "x * 1.0");
+make_simplify_test!(test_simplify_keep_like_terms, "2x + 3x", "2x + 3x");
+make_simplify_test!(test_simplify_keep_add_neg, "x + -y", "x + -y");
+
+// Float sub-trees allow more identities.
+make_simplify_test!(test_simplify_flt_add_neg_zero, "sin(x) + -0.0", "sin(x)");
+make_simplify_test!(test_simplify_flt_mul_one, "sin(x) * 1.0", "sin(x)");
+make_simplify_test!(test_simplify_flt_integral, "sin(x) * 2.0", "sin(x) * 2");
+
+// Identities for real numbers.
+make_simplify_test!(R: test_simplify_real_add_zero, "x + 0", "x");
+make_simplify_test!(R: test_simplify_real_mul_zero, "0 * x", "0.0");
+make_simplify_test!(R: test_simplify_real_sub_self, "x - x", "0.0");
+make_simplify_test!(R: test_simplify_real_like_terms, "2x + 3x", "5x");
+make_simplify_test!(R: test_simplify_real_like_terms_cancel, "2x + 1 - x - x", "1.0");
+make_simplify_test!(
+    R: test_simplify_real_like_terms_mixed,
+    "3sin(x) + y - 2 - sin(x) * 2 + 2y + 5",
+    "sin(x) + 3y + 3"
+);
+make_simplify_test!(R: test_simplify_real_like_terms_neg, "x - 3x", "-(2x)");
+make_simplify_test!(R: test_simplify_real_merge_factors, "3(x * 2)", "6x");
+make_simplify_test!(
+    test_simplify_random_args,
+    "randint(1 + 1, 6)",
+    "randint(2, 6)"
+);
+make_simplify_test!(
+    test_simplify_random_double,
+    "rand() + rand()",
+    "rand() + rand()"
+);
+make_simplify_test!(R: test_simplify_random_cancel, "rand() - rand()", "rand() - rand()");
+make_simplify_test!(R: test_simplify_random_mul_zero, "0 * randn()", "0 * randn()");
+make_simplify_test!(R: test_simplify_random_pow_zero, "randn()^0", "randn()^0");
+make_simplify_test!(R: test_simplify_random_like_terms, "x + rand() - x", "x + rand() - x");
+
+#[test]
+fn test_simplify_removes_iden() {
+    let node: NodeBox = Box::new(UnaryNode::new(
+        UnaryAction::Iden,
+        Box::new(VariableNode::new("x")),
+    ));
+
+    assert_eq!(simplify(node.as_ref()).as_ref(), parse("x").as_ref());
+}
+
+#[test]
+fn test_simplify_keeps_overflow() {
+    let source = "x + 2 ^ 127";
+    assert_eq!(
+        simplify(parse(source).as_ref()).as_ref(),
+        parse(source).as_ref()
+    );
+}
+
+// -----------------------------------------------------------------------------
+// Property tests.
+// -----------------------------------------------------------------------------
+
+/// Generates ASTs whose integer arithmetic cannot overflow: integers are at
+/// most 2 in magnitude, exponents are at most 3 and trees are at most 4 deep.
+fn arb_node() -> impl Strategy<Value = NodeBox> {
+    let leaf = prop_oneof![
+        (-2i128..=2).prop_map(|n| Box::new(PlainNode::new(Number::Int(n))) as NodeBox),
+        prop::sample::select(vec![
+            0.0,
+            -0.0,
+            0.5,
+            1.0,
+            -1.0,
+            2.0,
+            f64::NAN,
+            f64::INFINITY
+        ])
+        .prop_map(|n| Box::new(PlainNode::new(Number::Flt(n))) as NodeBox),
+        prop::sample::select(vec!["x", "y"])
+            .prop_map(|n| Box::new(VariableNode::new(n)) as NodeBox),
+    ];
+    let exponent = prop_oneof![
+        (-1i128..=3).prop_map(|n| Box::new(PlainNode::new(Number::Int(n))) as NodeBox),
+        prop::sample::select(vec![0.5, 1.0, -0.0])
+            .prop_map(|n| Box::new(PlainNode::new(Number::Flt(n))) as NodeBox),
+        prop::sample::select(vec!["x", "y"])
+            .prop_map(|n| Box::new(VariableNode::new(n)) as NodeBox),
+    ];
+    let binary = prop::sample::select(vec![
+        BinaryAction::Add,
+        BinaryAction::Sub,
+        BinaryAction::Mul,
+        BinaryAction::Div,
+    ]);
+    let unary = prop::sample::select(vec![
+        UnaryAction::Neg,
+        UnaryAction::Iden,
+        UnaryAction::Func(Function::Sin),
+        UnaryAction::Func(Function::Atan),
+    ]);
+
+    leaf.prop_recursive(4, 32, 2, move |inner| {
+        prop_oneof![
+            (inner.clone(), binary.clone(), inner.clone())
+                .prop_map(|(l, a, r)| { Box::new(BinaryNode::new(l, a, r)) as NodeBox }),
+            (inner.clone(), exponent.clone()).prop_map(|(l, r)| {
+                Box::new(BinaryNode::new(l, BinaryAction::Pow, r)) as NodeBox
+            }),
+            (unary.clone(), inner).prop_map(|(a, o)| Box::new(UnaryNode::new(a, o)) as NodeBox),
+        ]
+    })
+}
+
+fn arb_number() -> impl Strategy<Value = Number> {
+    prop_oneof![
+        (-2i128..=2).prop_map(Number::Int),
+        prop::sample::select(vec![
+            0.0,
+            -0.0,
+            1.5,
+            -2.5,
+            f64::NAN,
+            f64::INFINITY,
+            f64::NEG_INFINITY
+        ])
+        .prop_map(Number::Flt),
+    ]
+}
+
+fn same(a: Number, b: Number) -> bool {
+    match (a, b) {
+        (Number::Flt(a), Number::Flt(b)) if a.is_nan() && b.is_nan() => true,
+        _ => a.identical(&b),
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_simplify_keeps_value(node in arb_node(), x in arb_number(), y in arb_number()) {
+        let env = Environment::new().with("x", x).with("y", y);
+        let simplified = simplify(node.as_ref());
+
+        let expected = node.evaluate_with(&env).unwrap();
+        let actual = simplified.evaluate_with(&env).unwrap();
+
+        prop_assert!(
+            same(expected, actual),
+            "{:?} became {:?}, {} != {}", node, simplified, expected, actual
+        );
+    }
+
+    #[test]
+    fn test_simplify_real_keeps_value(node in arb_node(), x in -2.0..2.0f64, y in -2.0..2.0f64) {
+        let env = Environment::new().with("x", x).with("y", y);
+        let simplified = Simplifier::new().assume_real(true).simplify(node.as_ref());
+
+        let expected = match node.evaluate_with(&env).unwrap() {
+            Number::Int(n) => n as f64,
+            Number::Flt(n) => n,
+        };
+        let actual = match simplified.evaluate_with(&env).unwrap() {
+            Number::Int(n) => n as f64,
+            Number::Flt(n) => n,
+        };
+
+        // Only finite results are comparable, e.g. 0 * inf is NaN but 0 is 0.
+        if !expected.is_finite() {
+            return Ok(());
+        }
+        prop_assert!(
+            (expected - actual).abs() <= 1e-9 * expected.abs().max(1.0),
+            "{:?} became {:?}, {} != {}", node, simplified, expected, actual
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use proptest::prelude::*;
+use tilted::{
+    solve::{self, TOLERANCE},
+    Environment, EvalError, Guess, Lexer, NodeBox, NodeView, Number, Parser, Program,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn evaluate(source: &str) -> Result<Number, EvalError> {
+    parse(source).evaluate_with(&Environment::new())
+}
+
+fn roots(source: &str) -> Result<Vec<f64>, EvalError> {
+    match parse(source).view() {
+        NodeView::Solve(s) => s.roots(&Environment::new()),
+        _ => panic!("{} is not a solve", source),
+    }
+}
+
+fn float(n: Number) -> f64 {
+    match n {
+        Number::Int(n) => n as f64,
+        Number::Flt(n) => n,
+    }
+}
+
+fn close(expected: f64, actual: f64) -> bool {
+    (expected - actual).abs() <= 10.0 * TOLERANCE * expected.abs().max(1.0)
+}
+
+macro_rules! make_solve_test {
+    // Checks that the roots are within the tolerance of `$expected`.
+    ($name: ident, $source: literal, $expected: expr) => {
+        #[test]
+        fn $name() {
+            let actual = roots($source).unwrap();
+            let expected: Vec<f64> = $expected;
+            assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+            for (e, a) in expected.iter().zip(&actual) {
+                assert!(close(*e, *a), "expected {:?}, found {:?}", expected, actual);
+            }
+        }
+    };
+    // Checks that the result is within the tolerance of `$expected`.
+    (~ $name: ident, $source: literal, $expected: expr) => {
+        #[test]
+        fn $name() {
+            let actual = float(evaluate($source).unwrap());
+            let expected: f64 = $expected;
+            assert!(
+                close(expected, actual),
+                "expected {}, found {}",
+                expected,
+                actual
+            );
+        }
+    };
+    // Checks the error.
+    (E: $name: ident, $source: literal, $expected: pat) => {
+        #[test]
+        fn $name() {
+            let err = evaluate($source).unwrap_err();
+            assert!(matches!(err, $expected), "{:?}", err);
+        }
+    };
+}
+
+make_solve_test!(test_solve_linear, "solve(2x + 1 = 4, x)", vec![1.5]);
+make_solve_test!(
+    test_solve_quadratic,
+    "solve(x^2 = 2, x)",
+    vec![-2.0f64.sqrt(), 2.0f64.sqrt()]
+);
+make_solve_test!(test_solve_double_root, "solve((x - 1)^2 = 0, x)", vec![1.0]);
+make_solve_test!(
+    test_solve_cubic,
+    "solve(x^3 - 2*x = 1, x)",
+    vec![
+        -1.0,
+        (1.0 - 5.0f64.sqrt()) / 2.0,
+        (1.0 + 5.0f64.sqrt()) / 2.0
+    ]
+);
+make_solve_test!(test_solve_cubic_single, "solve(x^3 = 8, x)", vec![2.0]);
+make_solve_test!(
+    test_solve_quartic,
+    "solve(x^4 - 5x^2 + 4 = 0, x)",
+    vec![-2.0, -1.0, 1.0, 2.0]
+);
+make_solve_test!(test_solve_quartic_none, "solve(x^4 + 1 = 0, x)", vec![]);
+make_solve_test!(
+    test_solve_polynomial_interval,
+    "solve(x^2 = 4, x, 0, 10)",
+    vec![2.0]
+);
+make_solve_test!(
+    test_solve_interval,
+    "solve(sin(x) = 0, x, 1, 10)",
+    vec![
+        std::f64::consts::PI,
+        2.0 * std::f64::consts::PI,
+        3.0 * std::f64::consts::PI
+    ]
+);
+make_solve_test!(
+    test_solve_interval_reversed,
+    "solve(x^5 = 32, x, 10, 0)",
+    vec![2.0]
+);
+make_solve_test!(test_solve_unit_name, "solve(h^2 = 4, h)", vec![-2.0, 2.0]);
+
+make_solve_test!(~ test_solve_newton, "solve(cos(x) = x, x, 0.5)", 0.7390851332151607);
+make_solve_test!(~ test_solve_logarithm, "solve(ln(x) = 2, x, 1)", 2.0f64.exp());
+make_solve_test!(~ test_solve_no_guess, "solve(sin(x) = 0.5, x)", std::f64::consts::FRAC_PI_6);
+make_solve_test!(~ test_solve_brent, "solve(x^5 = 32, x, 0, 10) + 1", 3.0);
+make_solve_test!(~ test_solve_degree_five, "solve(x^5 + x = 3, x, 1)", 1.1329975658850653);
+make_solve_test!(~ test_solve_nested, "solve(t = solve(u^2 = 9, u, 0, 10), t)", 3.0);
+
+make_solve_test!(E: test_solve_no_real_root, "solve(x^2 = -1, x)", EvalError::NoRealRoot);
+make_solve_test!(E: test_solve_empty_interval, "solve(sin(x) = 2, x, 0, 10)", EvalError::NoRealRoot);
+make_solve_test!(E: test_solve_identity, "solve(x = x, x)", EvalError::InfinitelyManyRoots);
+make_solve_test!(E: test_solve_not_converged, "solve(1/x = 0, x, 1)", EvalError::NotConverged(_));
+make_solve_test!(E: test_solve_unbound, "solve(x = y, x)", EvalError::UnboundVariable(_));
+
+#[test]
+fn test_solve_free_variable() {
+    let node = parse("solve(t^2 = x, t, 0, n)");
+    let env = Environment::new().with("x", 2).with("n", 4);
+
+    assert!(close(
+        2.0f64.sqrt(),
+        float(node.evaluate_with(&env).unwrap())
+    ));
+    assert_eq!(Program::compile(node.as_ref()).variables(), ["x", "n"]);
+}
+
+#[test]
+fn test_solve_polynomial_roots() {
+    assert_eq!(
+        solve::polynomial_roots(&[-6.0, 11.0, -6.0, 1.0])
+            .unwrap()
+            .map(|r| r.len()),
+        Some(3)
+    );
+    assert_eq!(
+        solve::polynomial_roots(&[1.0, 0.0, 1.0]).unwrap(),
+        Some(vec![])
+    );
+    assert_eq!(solve::polynomial_roots(&[1.0; 6]).unwrap(), None);
+    assert_eq!(solve::polynomial_roots(&[f64::NAN, 1.0]).unwrap(), None);
+    assert!(matches!(
+        solve::polynomial_roots(&[0.0, 0.0]),
+        Err(EvalError::InfinitelyManyRoots)
+    ));
+}
+
+#[test]
+fn test_solve_stops_at_error() {
+    let err = solve::solve(
+        |x| match x < 2.0 {
+            true => Ok(x - 3.0),
+            false => Err(EvalError::UnboundVariable("y".to_string())),
+        },
+        |_| Ok(1.0),
+        None,
+        Guess::Point(0.0),
+    )
+    .unwrap_err();
+
+    assert!(matches!(err, EvalError::UnboundVariable(_)));
+}
+
+// -----------------------------------------------------------------------------
+// Property tests.
+// -----------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn test_solve_roots_satisfy_polynomial(
+        a in -5.0..5.0f64,
+        b in -5.0..5.0f64,
+        c in -5.0..5.0f64,
+        cubic in any::<bool>(),
+    ) {
+        let degree = if cubic { 3 } else { 2 };
+        let source = format!("solve(x^{} + {} x^2 + {} x = {}, x)", degree, a, b, c);
+        let f = |x: f64| x.powi(degree) + a * x * x + b * x - c;
+        let df = |x: f64| degree as f64 * x.powi(degree - 1) + 2.0 * a * x + b;
+
+        let actual = roots(&source).unwrap();
+        prop_assert!(cubic || actual.len() != 1 || df(actual[0]).abs() < 1e-6);
+        for x in actual {
+            // The residual is bounded by the rounding error of evaluating f.
+            let scale = x.abs().max(1.0).powi(degree) * (1.0 + a.abs() + b.abs() + c.abs());
+            prop_assert!(f(x).abs() <= 1e-9 * scale, "{}: f({}) = {}", source, x, f(x));
+        }
+    }
+
+    #[test]
+    fn test_solve_vm_matches_tree(
+        x in 1i128..5,
+        y in 0.1..3.0f64,
+        source in prop::sample::select(vec![
+            "solve(t^2 = x, t, 0, 10)",
+            "solve(t^3 + y t = x, t)",
+            "solve(cos(t) = y t, t, x)",
+            "solve(t = sum(k y, k, 1, x), t) / 2",
+            "solve(ln(t) = x - y, t, 0.1, 100) + y",
+        ]),
+    ) {
+        let node = parse(source);
+        let env = Environment::new().with("x", x).with("y", y);
+
+        let expected = node.evaluate_with(&env).unwrap();
+        let actual = Program::compile(node.as_ref()).evaluate_with(&env).unwrap();
+        prop_assert!(expected.identical(&actual), "{}: {} != {}", source, expected, actual);
+    }
+}
@@ -0,0 +1,309 @@
+use tilted::{
+    ast::{evaluate_value, Value},
+    stats, Builtin, Environment, EvalError, Lexer, NodeBox, Number, Parser, Shape,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn value(source: &str) -> Result<Value, EvalError> {
+    evaluate_value(parse(source).as_ref(), &Environment::new())
+}
+
+/// Checks that `actual` is within a relative `tolerance` of `expected`.
+fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    let error = ((actual - expected) / expected).abs();
+    assert!(
+        error <= tolerance,
+        "{} is not {}, relative error {}",
+        actual,
+        expected,
+        error
+    );
+}
+
+macro_rules! make_stats_test {
+    // Checks the result against a reference value, to a relative tolerance.
+    ($name: ident, $source: literal, $expected: expr, $tolerance: expr) => {
+        #[test]
+        fn $name() {
+            let actual = match value($source).unwrap() {
+                Value::Scalar(Number::Flt(n)) => n,
+                Value::Scalar(Number::Int(n)) => n as f64,
+                value => panic!("{} is not a scalar", value),
+            };
+            assert_close(actual, $expected, $tolerance);
+        }
+    };
+    // Checks the formatted result.
+    ($name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            assert_eq!(value($source).unwrap().to_string(), $expected);
+        }
+    };
+    // Checks the error.
+    (E: $name: ident, $source: literal, $expected: pat) => {
+        #[test]
+        fn $name() {
+            let err = value($source).unwrap_err();
+            assert!(matches!(err, $expected), "{:?}", err);
+        }
+    };
+}
+
+make_stats_test!(test_stats_mean, "mean(1, 2, 3, 4)", "2.5");
+make_stats_test!(test_stats_mean_list, "mean(1..100)", "50.5");
+make_stats_test!(test_stats_mean_vector, "mean([0.1, 0.2, 0.3])", 0.2, 1e-15);
+make_stats_test!(test_stats_median_odd, "median(3, 1, 2)", "2");
+make_stats_test!(test_stats_median_even, "median({4, 1, 3, 2})", "2.5");
+make_stats_test!(test_stats_mode, "mode(3, 1, 3, 2, 2)", "2");
+make_stats_test!(test_stats_mode_single, "mode({1.5})", "1.5");
+make_stats_test!(
+    test_stats_variance,
+    "variance(2, 4, 4, 4, 5, 5, 7, 9)",
+    32.0 / 7.0,
+    1e-15
+);
+make_stats_test!(
+    test_stats_pvariance,
+    "pvariance(2, 4, 4, 4, 5, 5, 7, 9)",
+    "4"
+);
+make_stats_test!(
+    test_stats_stdev,
+    "stdev(2, 4, 4, 4, 5, 5, 7, 9)",
+    2.138089935299395,
+    1e-15
+);
+make_stats_test!(test_stats_pstdev, "pstdev({2, 4, 4, 4, 5, 5, 7, 9})", "2");
+make_stats_test!(
+    test_stats_variance_offset,
+    "variance(1000000004.0, 1000000007.0, 1000000013.0, 1000000016.0)",
+    30.0,
+    1e-15
+);
+make_stats_test!(
+    test_stats_percentile,
+    "percentile({1, 2, 3, 4}, 25)",
+    "1.75"
+);
+make_stats_test!(test_stats_percentile_max, "percentile(1..9, 100)", "9");
+make_stats_test!(test_stats_percentile_range, "percentile(1..9, 101)", "NaN");
+
+make_stats_test!(test_stats_ncr, "nCr(52, 5)", "2598960");
+make_stats_test!(test_stats_ncr_out_of_range, "nCr(5, 6)", "0");
+make_stats_test!(
+    test_stats_ncr_overflow,
+    "nCr(200, 100)",
+    9.054851465610328e58,
+    1e-12
+);
+make_stats_test!(test_stats_npr, "nPr(10, 3)", "720");
+make_stats_test!(test_stats_npr_all, "nPr(20.0, 20)", "2432902008176640000");
+
+make_stats_test!(test_stats_gamma_int, "gamma(5)", "24");
+make_stats_test!(
+    test_stats_gamma_overflow,
+    "gamma(171)",
+    7.257415615307999e306,
+    1e-14
+);
+make_stats_test!(
+    test_stats_gamma_half,
+    "gamma(0.5)",
+    1.772453850905516,
+    1e-15
+);
+make_stats_test!(
+    test_stats_gamma_flt,
+    "gamma(4.5)",
+    11.631728396567448,
+    1e-15
+);
+make_stats_test!(
+    test_stats_gamma_small,
+    "gamma(0.1)",
+    9.513507698668732,
+    1e-15
+);
+make_stats_test!(
+    test_stats_gamma_neg,
+    "gamma(-0.5)",
+    -3.5449077018110318,
+    1e-15
+);
+make_stats_test!(test_stats_gamma_pole, "gamma(-2)", "NaN");
+make_stats_test!(test_stats_gamma_list, "gamma({1, 2, 3, 4})", "{1, 1, 2, 6}");
+
+make_stats_test!(test_stats_erf_small, "erf(0.1)", 0.1124629160182849, 1e-15);
+make_stats_test!(test_stats_erf_half, "erf(0.5)", 0.5204998778130465, 1e-15);
+make_stats_test!(test_stats_erf_one, "erf(1)", 0.8427007929497149, 1e-15);
+make_stats_test!(test_stats_erf_neg, "erf(-1)", -0.8427007929497149, 1e-15);
+make_stats_test!(test_stats_erf_two, "erf(2)", 0.9953222650189527, 1e-15);
+make_stats_test!(test_stats_erf_three, "erf(3)", 0.9999779095030014, 1e-15);
+make_stats_test!(test_stats_erf_zero, "erf(0)", "0");
+
+make_stats_test!(test_stats_normpdf, "normpdf(0)", 0.3989422804014327, 1e-15);
+make_stats_test!(
+    test_stats_normpdf_scaled,
+    "normpdf(1, 1, 2)",
+    0.19947114020071635,
+    1e-15
+);
+make_stats_test!(
+    test_stats_normcdf,
+    "normcdf(1.96)",
+    0.9750021048517795,
+    1e-15
+);
+make_stats_test!(
+    test_stats_normcdf_tail,
+    "normcdf(-3)",
+    0.0013498980316300946,
+    1e-15
+);
+make_stats_test!(
+    test_stats_normcdf_far_tail,
+    "normcdf(-10)",
+    7.619853024160527e-24,
+    1e-14
+);
+make_stats_test!(
+    test_stats_normcdf_scaled,
+    "normcdf(130, 100, 15)",
+    0.9772498680518208,
+    1e-15
+);
+make_stats_test!(test_stats_normcdf_sigma, "normcdf(0, 0, -1)", "NaN");
+
+make_stats_test!(test_stats_binompdf, "binompdf(3, 10, 0.5)", "0.1171875");
+make_stats_test!(
+    test_stats_binompdf_quarter,
+    "binompdf(2, 5, 0.25)",
+    "0.263671875"
+);
+make_stats_test!(
+    test_stats_binompdf_inexact,
+    "binompdf(2, 5, 0.3)",
+    0.3087,
+    1e-14
+);
+make_stats_test!(test_stats_binompdf_edge, "binompdf(0, 10, 0)", "1");
+make_stats_test!(test_stats_binomcdf, "binomcdf(3, 10, 0.5)", "0.171875");
+make_stats_test!(
+    test_stats_binomcdf_quarter,
+    "binomcdf(2, 5, 0.25)",
+    "0.896484375"
+);
+make_stats_test!(test_stats_binomcdf_all, "binomcdf(10, 10, 0.3)", "1");
+make_stats_test!(
+    test_stats_binomcdf_large,
+    "binomcdf(500000, 1000000, 0.5)",
+    0.5003989421806659,
+    1e-14
+);
+make_stats_test!(
+    test_stats_poissonpdf,
+    "poissonpdf(2, 3)",
+    0.22404180765538775,
+    1e-14
+);
+make_stats_test!(
+    test_stats_poissoncdf,
+    "poissoncdf(2, 3)",
+    0.42319008112684353,
+    1e-14
+);
+make_stats_test!(
+    test_stats_poissoncdf_large,
+    "poissoncdf(1000000000000, 2.5)",
+    "1"
+);
+
+make_stats_test!(
+    E: test_stats_empty,
+    "mean({})",
+    EvalError::InvalidShape("mean", Shape::List(0))
+);
+make_stats_test!(
+    E: test_stats_variance_single,
+    "variance(1)",
+    EvalError::InvalidShape("variance", Shape::List(1))
+);
+make_stats_test!(
+    E: test_stats_matrix,
+    "median([[1, 2], [3, 4]])",
+    EvalError::InvalidShape("median", Shape::Matrix(2, 2))
+);
+make_stats_test!(
+    E: test_stats_nested,
+    "mean({1, [2, 3]})",
+    EvalError::ScalarExpected(Shape::Vector(2))
+);
+make_stats_test!(
+    E: test_stats_ncr_integer,
+    "nCr(5.5, 2)",
+    EvalError::IntegerExpected(_)
+);
+make_stats_test!(
+    E: test_stats_binompdf_integer,
+    "binompdf(1, 2.5, 0.5)",
+    EvalError::IntegerExpected(_)
+);
+
+#[test]
+fn test_stats_argument_count() {
+    // Called directly rather than parsed, the arity is checked when evaluated.
+    let err = Builtin::Mean.evaluate(vec![]).unwrap_err();
+    assert!(
+        matches!(err, EvalError::ArgumentCountMismatch("mean", 1, 0)),
+        "{:?}",
+        err
+    );
+
+    let args = vec![Value::Scalar(Number::Int(1)); 4];
+    let err = Builtin::NormPdf.evaluate(args).unwrap_err();
+    assert!(
+        matches!(err, EvalError::ArgumentCountMismatch("normpdf", 3, 4)),
+        "{:?}",
+        err
+    );
+
+    let args = vec![Value::Scalar(Number::Int(5)), Value::Scalar(Number::Int(2))];
+    assert_eq!(
+        Builtin::Ncr.evaluate(args).unwrap(),
+        Value::Scalar(Number::Int(10))
+    );
+}
+
+#[test]
+fn test_stats_sum_compensated() {
+    let data = [1.0, 1e100, 1.0, -1e100];
+    assert_eq!(stats::sum(&data), 2.0);
+}
+
+#[test]
+fn test_stats_ln_gamma() {
+    assert_close(stats::ln_gamma(100.0), 359.1342053695754, 1e-15);
+    assert_close(stats::ln_gamma(0.5), 0.5723649429247001, 1e-15);
+}
+
+#[test]
+fn test_stats_erfc() {
+    assert_close(stats::erfc(3.0), 2.209049699858544e-5, 1e-14);
+    assert_close(stats::erfc(5.0), 1.537459794428035e-12, 1e-14);
+    assert_close(stats::erfc(-1.0), 1.8427007929497148, 1e-15);
+}
+
+#[test]
+fn test_stats_pdf_sums_to_one() {
+    let binomial: Vec<_> = (0..=30).map(|k| stats::binomial_pdf(k, 30, 0.2)).collect();
+    assert_close(stats::sum(&binomial), 1.0, 1e-14);
+
+    let poisson: Vec<_> = (0..=100).map(|k| stats::poisson_pdf(k, 7.5)).collect();
+    assert_close(stats::sum(&poisson), 1.0, 1e-14);
+}
@@ -0,0 +1,168 @@
+use tilted::{
+    ast::to_infix, Function::*, LexError, Lexer, Number, Operator::*, Parser, TilError,
+    TokenKind::*, TokenStream,
+};
+
+fn stream(source: &str) -> TokenStream {
+    TokenStream::new(Lexer::from_source_code(source))
+}
+
+macro_rules! make_stream_test {
+    // Checks the kinds of the tokens yielded.
+    ($name: ident, $source: literal, [$($token_kind: expr,)*]) => {
+        #[test]
+        fn $name() {
+            let kinds = stream($source)
+                .map(|token| token.unwrap().kind)
+                .collect::<Vec<_>>();
+            assert_eq!(kinds, [$($token_kind,)*]);
+        }
+    };
+    // Checks the error yielded after some tokens.
+    (E: $name: ident, $source: literal, $count: literal, $expected: pat) => {
+        #[test]
+        fn $name() {
+            let results = stream($source).collect::<Vec<_>>();
+            assert_eq!(results.len(), $count + 1);
+            assert!(results[..$count].iter().all(|token| token.is_ok()));
+            let err = results[$count].as_ref().unwrap_err();
+            assert!(matches!(err, $expected), "{:?}", err);
+        }
+    };
+}
+
+make_stream_test!(test_stream_empty, "", []);
+make_stream_test!(
+    test_stream_tokens,
+    "sin(x) + 1",
+    [Func(Sin), LeftParen, Ident, RightParen, Op(Plus), Int(1),]
+);
+make_stream_test!(
+    test_stream_pending,
+    "x⁻¹",
+    [Ident, Op(Caret), LeftParen, Op(Minus), Int(1), RightParen,]
+);
+make_stream_test!(
+    E: test_stream_error,
+    "1 + $ 2",
+    2,
+    LexError::UnrecognisedCharacter('$', 4)
+);
+make_stream_test!(
+    E: test_stream_error_first,
+    "9.0.0",
+    0,
+    LexError::UnrecognisedCharacter('.', 3)
+);
+
+#[test]
+fn test_stream_peek_nth() {
+    let mut tokens = stream("a * b");
+    assert_eq!(tokens.peek_nth(2).unwrap().kind, Ident);
+    assert_eq!(tokens.peek_nth(1).unwrap().kind, Op(Star));
+    assert_eq!(tokens.peek().unwrap().kind, Ident);
+
+    // Past the end of the stream is EOF.
+    assert_eq!(tokens.peek_nth(3).unwrap().kind, Eof);
+    assert_eq!(tokens.peek_nth(100).unwrap().kind, Eof);
+
+    // Peeking consumes nothing.
+    assert_eq!(tokens.next_token().unwrap().span.start_index, 0);
+    assert_eq!(tokens.next_token().unwrap().kind, Op(Star));
+    assert_eq!(tokens.peek_nth(1).unwrap().kind, Eof);
+}
+
+#[test]
+fn test_stream_eof_repeats() {
+    let mut tokens = stream("1");
+    assert_eq!(tokens.next_token().unwrap().kind, Int(1));
+    for _ in 0..3 {
+        let eof = tokens.next_token().unwrap();
+        assert_eq!(eof.kind, Eof);
+        assert_eq!(eof.span.start_index, 1);
+    }
+}
+
+#[test]
+fn test_stream_error_repeats() {
+    let mut tokens = stream("x $");
+    assert_eq!(tokens.peek_nth(0).unwrap().kind, Ident);
+    assert!(tokens.peek_nth(1).is_err());
+    assert!(tokens.peek_nth(5).is_err());
+
+    tokens.next_token().unwrap();
+    assert!(tokens.next_token().is_err());
+    assert!(tokens.next_token().is_err());
+}
+
+#[test]
+fn test_stream_checkpoint() {
+    let mut tokens = stream("f(x) = x²");
+    tokens.next_token().unwrap();
+    let checkpoint = tokens.checkpoint();
+    let consumed = (0..5)
+        .map(|_| tokens.next_token().unwrap())
+        .collect::<Vec<_>>();
+
+    // Tokens after a checkpoint are yielded again, with the same spans.
+    tokens.rewind(checkpoint);
+    assert_eq!(tokens.checkpoint(), checkpoint);
+    for token in consumed {
+        assert_eq!(tokens.next_token().unwrap(), token);
+    }
+    assert_eq!(tokens.next_token().unwrap().kind, Op(Caret));
+
+    tokens.reset();
+    assert_eq!(
+        tokens.map(|token| token.unwrap().kind).collect::<Vec<_>>(),
+        [
+            Ident,
+            LeftParen,
+            Ident,
+            RightParen,
+            Op(Equal),
+            Ident,
+            Op(Caret),
+            Int(2)
+        ]
+    );
+}
+
+#[test]
+fn test_stream_source() {
+    let mut tokens = stream("2π · km");
+    let spans = tokens
+        .by_ref()
+        .map(|token| token.unwrap().span)
+        .collect::<Vec<_>>();
+    let sources = spans.iter().map(|span| &tokens[*span]).collect::<Vec<_>>();
+    assert_eq!(sources, ["2", "π", "·", "km"]);
+}
+
+#[test]
+fn test_stream_parser() {
+    // A parser starts at the current position of the stream, and peeking
+    // before does not consume anything.
+    let mut tokens = stream("x -> x + 1");
+    assert_eq!(tokens.peek_nth(1).unwrap().kind, Op(Arrow));
+    tokens.next_token().unwrap();
+    tokens.next_token().unwrap();
+    let node = Parser::from_tokens(tokens).parse().unwrap();
+    assert_eq!(to_infix(node.as_ref()), "x + 1");
+
+    let mut tokens = stream("(1 + 2) * 3");
+    tokens.peek_nth(4).unwrap();
+    let node = Parser::from_tokens(tokens).parse().unwrap();
+    assert_eq!(node.evaluate(), Number::Int(9));
+}
+
+#[test]
+fn test_stream_parser_error() {
+    let lexer = Lexer::from_source_code("1 + $");
+    let err = Parser::from_lexer(lexer).parse().unwrap_err();
+    assert!(
+        matches!(err, TilError::Lex(LexError::UnrecognisedCharacter('$', 4))),
+        "{:?}",
+        err
+    );
+}
@@ -0,0 +1,212 @@
+use proptest::prelude::*;
+use tilted::{
+    ast::{evaluate_dual, evaluate_uncertain, simplify, to_infix, Uncertain},
+    Environment, EvalError, Lexer, NodeBox, Number, Parser,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn close(expected: f64, actual: f64) -> bool {
+    (expected - actual).abs() <= 1e-12 * expected.abs().max(1.0)
+}
+
+macro_rules! make_uncertain_test {
+    // Checks the value and error, with variables bound to uncertain values.
+    ($name: ident, $source: literal, [$(($var: literal, $value: expr, $error: expr)),*], ($evalue: expr, $eerror: expr)) => {
+        #[test]
+        fn $name() {
+            let bindings = [$(($var, Uncertain::new($value, $error))),*];
+            let actual = evaluate_uncertain(parse($source).as_ref(), &bindings).unwrap();
+
+            let (value, error): (f64, f64) = ($evalue, $eerror);
+            assert!(close(value, actual.value()), "{}: expected value {}", actual, value);
+            assert!(close(error, actual.error()), "{}: expected error {}", actual, error);
+        }
+    };
+    // Checks the formatted result.
+    (D: $name: ident, $source: literal, $expected: literal) => {
+        #[test]
+        fn $name() {
+            let actual = evaluate_uncertain(parse($source).as_ref(), &[]).unwrap();
+            assert_eq!(actual.to_string(), $expected);
+        }
+    };
+}
+
+make_uncertain_test!(test_uncertain_literal, "9.81 ± 0.02", [], (9.81, 0.02));
+make_uncertain_test!(test_uncertain_ascii, "9.81 +/- -0.02", [], (9.81, 0.02));
+make_uncertain_test!(test_uncertain_exact, "2 * 3", [], (6.0, 0.0));
+make_uncertain_test!(test_uncertain_scale, "3 * (2 ± 0.1)", [], (6.0, 0.3));
+make_uncertain_test!(
+    test_uncertain_independent,
+    "(1 ± 0.3) + (2 ± 0.4)",
+    [],
+    (3.0, 0.5)
+);
+make_uncertain_test!(
+    test_uncertain_product,
+    "(10 ± 0.3) * (20 ± 0.8)",
+    [],
+    (200.0, (6.0f64 * 6.0 + 8.0 * 8.0).sqrt())
+);
+make_uncertain_test!(
+    test_uncertain_quotient,
+    "(10 ± 0.3) / (20 ± 0.8)",
+    [],
+    (0.5, 0.5 * (0.03f64.powi(2) + 0.04f64.powi(2)).sqrt())
+);
+make_uncertain_test!(test_uncertain_pow, "(2 ± 0.1)^3", [], (8.0, 12.0 * 0.1));
+make_uncertain_test!(
+    test_uncertain_exponential,
+    "2^(3 ± 0.1)",
+    [],
+    (8.0, 8.0 * 2.0f64.ln() * 0.1)
+);
+make_uncertain_test!(
+    test_uncertain_sin,
+    "sin(0.5 ± 0.01)",
+    [],
+    (0.5f64.sin(), 0.5f64.cos() * 0.01)
+);
+make_uncertain_test!(
+    test_uncertain_sec,
+    "sec(x)",
+    [("x", 0.5, 0.01)],
+    (
+        1.0 / 0.5f64.cos(),
+        0.5f64.sin() / 0.5f64.cos().powi(2) * 0.01
+    )
+);
+make_uncertain_test!(
+    test_uncertain_acsc,
+    "acsc(x)",
+    [("x", 2.0, 0.1)],
+    (0.5f64.asin(), 0.1 / (4.0 * 0.75f64.sqrt()))
+);
+make_uncertain_test!(
+    test_uncertain_correlated_sub,
+    "x - x",
+    [("x", 2.0, 0.1)],
+    (0.0, 0.0)
+);
+make_uncertain_test!(
+    test_uncertain_correlated_mul,
+    "x * x",
+    [("x", 2.0, 0.1)],
+    (4.0, 0.4)
+);
+make_uncertain_test!(
+    test_uncertain_correlated_mixed,
+    "x * y + x",
+    [("x", 2.0, 0.1), ("y", 3.0, 0.2)],
+    (8.0, (0.4f64 * 0.4 + 0.4 * 0.4).sqrt())
+);
+make_uncertain_test!(
+    test_uncertain_nested,
+    "(1 ± 0.1) ± 0.2",
+    [],
+    (1.0, (0.01f64 + 0.04).sqrt())
+);
+make_uncertain_test!(
+    test_uncertain_sum_binder,
+    "sum(k ± 0.1, k, 1, 3)",
+    [],
+    (6.0, 0.3)
+);
+make_uncertain_test!(
+    test_uncertain_integral,
+    "integrate(x t, t, 0, 1)",
+    [("x", 2.0, 0.1)],
+    (1.0, 0.05)
+);
+make_uncertain_test!(
+    test_uncertain_integral_bound,
+    "integrate(t, t, 0, x)",
+    [("x", 2.0, 0.1)],
+    (2.0, 0.2)
+);
+
+make_uncertain_test!(
+    test_uncertain_solve,
+    "solve(t^2 = x, t, 0, 10)",
+    [("x", 4.0, 0.4)],
+    (2.0, 0.1)
+);
+make_uncertain_test!(
+    test_uncertain_solve_plus_minus,
+    "solve(2t = 6 ± 0.2, t) + (1 ± 0.1)",
+    [],
+    (4.0, 0.1 * 2.0f64.sqrt())
+);
+
+make_uncertain_test!(D: test_uncertain_display, "9.81 ± 0.02", "9.81 ± 0.02");
+make_uncertain_test!(D: test_uncertain_display_two_figures, "9.8123 ± 0.0149", "9.812 ± 0.015");
+make_uncertain_test!(D: test_uncertain_display_carry, "1.234 ± 0.096", "1.23 ± 0.10");
+make_uncertain_test!(D: test_uncertain_display_large, "1234 ± 56", "1230 ± 60");
+make_uncertain_test!(D: test_uncertain_display_exact, "1 ± 0", "1 ± 0");
+make_uncertain_test!(D: test_uncertain_display_sum, "2*(9.81 +/- 0.02)", "19.62 ± 0.04");
+
+#[test]
+fn test_uncertain_scalar() {
+    let node = parse("2 ± 0.1 + 1");
+
+    assert_eq!(
+        node.evaluate_with(&Environment::new()).unwrap(),
+        Number::Int(3)
+    );
+}
+
+#[test]
+fn test_uncertain_kept_by_simplify() {
+    let node = simplify(parse("(1 + 1) ± (0.1 * 2)").as_ref());
+
+    assert_eq!(to_infix(node.as_ref()), "2 ± 0.2");
+}
+
+#[test]
+fn test_uncertain_unbound() {
+    let node = parse("x ± 0.1");
+    let err = evaluate_uncertain(node.as_ref(), &[]).unwrap_err();
+
+    assert!(matches!(err, EvalError::UnboundVariable(ref name) if name == "x"));
+}
+
+// -----------------------------------------------------------------------------
+// Property tests.
+// -----------------------------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn test_uncertain_matches_gradient(
+        x in 0.1..3.0f64,
+        y in 0.1..3.0f64,
+        dx in 0.0..0.5f64,
+        dy in 0.0..0.5f64,
+        source in prop::sample::select(vec![
+            "x*y + x^2",
+            "sin(x) / (1 + y)",
+            "ln(x*y) - atan(x/y)",
+            "x^y + acot(x)",
+        ]),
+    ) {
+        let node = parse(source);
+        let bindings = [("x", Uncertain::new(x, dx)), ("y", Uncertain::new(y, dy))];
+        let actual = evaluate_uncertain(node.as_ref(), &bindings).unwrap();
+
+        // First order: the error is the norm of the gradient scaled by the
+        // errors of the variables.
+        let env = Environment::new().with("x", x).with("y", y);
+        let dual = evaluate_dual(node.as_ref(), &env, &["x", "y"]).unwrap();
+        let gradient = dual.derivatives();
+        let expected = (gradient[0] * dx).hypot(gradient[1] * dy);
+
+        prop_assert!(
+            (expected - actual.error()).abs() <= 1e-9 * expected.max(1.0),
+            "{}: expected error {}, found {}", source, expected, actual.error()
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use proptest::prelude::*;
+use tilted::{
+    BinaryAction, BinaryNode, Environment, EvalError, Function, Instruction, Lexer, NodeBox,
+    Number, Parser, PlainNode, Program, UnaryAction, UnaryNode, VariableNode, Vm,
+};
+
+fn parse(source: &str) -> NodeBox {
+    let lexer = Lexer::from_source_code(source);
+    let mut parser = Parser::from_lexer(lexer);
+    parser.parse().unwrap()
+}
+
+fn same(a: Number, b: Number) -> bool {
+    match (a, b) {
+        (Number::Flt(a), Number::Flt(b)) if a.is_nan() && b.is_nan() => true,
+        _ => a.identical(&b),
+    }
+}
+
+macro_rules! make_vm_test {
+    ($name: ident, $source: literal, [$(($var: literal, $value: expr)),*]) => {
+        #[test]
+        fn $name() {
+            let node = parse($source);
+            let env = Environment::new()$(.with($var, $value))*;
+
+            let expected = node.evaluate_with(&env).unwrap();
+            let actual = Program::compile(node.as_ref()).evaluate_with(&env).unwrap();
+
+            assert!(same(expected, actual), "{} != {}", expected, actual);
+        }
+    };
+}
+
+make_vm_test!(test_vm_int, "7 + 6 * 2 - 4 * (8 + 3)", []);
+make_vm_test!(test_vm_int_div, "7 / 2", []);
+make_vm_test!(test_vm_flt, "5.0 / 2 + 0.5", []);
+make_vm_test!(test_vm_pow, "2^10 - 2^(-1)", []);
+make_vm_test!(test_vm_div_zero, "1 / 0", []);
+make_vm_test!(test_vm_func, "sin(1) + ln(2) * acot(3)", []);
+make_vm_test!(test_vm_neg, "-x^2 * -(y - 1)", [("x", 3), ("y", 0.5)]);
+make_vm_test!(
+    test_vm_binder,
+    "sum(k x, k, 1, y) + integrate(t x, t, 0, 1)",
+    [("x", 2), ("y", 4)]
+);
+make_vm_test!(
+    test_vm_binder_nested,
+    "sum(prod(k, k, 1, k), k, 1, x)",
+    [("x", 6)]
+);
+make_vm_test!(
+    test_vm_solve,
+    "solve(t^3 - y t = x, t) + solve(cos(t) = x t, t, y) + solve(sin(t) = 0, t, x, 10)",
+    [("x", 2), ("y", 0.5)]
+);
+make_vm_test!(
+    test_vm_solve_nested,
+    "solve(t = sum(solve(u^2 = k x, u, 0, x), k, 1, 3), t)",
+    [("x", 3.0)]
+);
+make_vm_test!(
+    test_vm_variables,
+    "a x^2 + b x + c",
+    [("a", 2), ("b", -3.0), ("c", 1), ("x", 4)]
+);
+
+#[test]
+fn test_vm_bytecode() {
+    let program = Program::compile(parse("x * 2 + sin(x)").as_ref());
+
+    assert_eq!(
+        program.instructions(),
+        [
+            Instruction::Load(0),
+            Instruction::Const(0),
+            Instruction::Binary(BinaryAction::Mul),
+            Instruction::Load(0),
+            Instruction::Call(Function::Sin),
+            Instruction::Binary(BinaryAction::Add),
+        ]
+    );
+    assert_eq!(program.variables(), ["x"]);
+}
+
+#[test]
+fn test_vm_constants_shared() {
+    let program = Program::compile(parse("1 + 1.0 + 1").as_ref());
+
+    assert_eq!(program.constants().len(), 2);
+}
+
+#[test]
+fn test_vm_reuse() {
+    let program = Program::compile(parse("y - x").as_ref());
+    let mut vm = Vm::new();
+
+    assert_eq!(program.variables(), ["y", "x"]);
+    for x in 0..10 {
+        let value = vm.run(&program, &[Number::Int(10), Number::Int(x)]);
+        assert_eq!(value.unwrap(), Number::Int(10 - x));
+    }
+}
+
+#[test]
+fn test_vm_reuse_scratch() {
+    // Sums and equations reuse their scratch space between runs.
+    let node = parse("sum(prod(j, j, 1, k) x, k, 1, n) + solve(t^2 = x, t, 0, 10)");
+    let program = Program::compile(node.as_ref());
+    let mut vm = Vm::new();
+
+    assert_eq!(program.variables(), ["x", "n"]);
+    for n in [5, 2, 7, 0, 3] {
+        let env = Environment::new().with("x", 4).with("n", n);
+        let expected = node.evaluate_with(&env).unwrap();
+        let actual = vm.run(&program, &[Number::Int(4), Number::Int(n)]).unwrap();
+        assert!(same(expected, actual), "{} != {}", expected, actual);
+    }
+}
+
+#[test]
+fn test_vm_unbound() {
+    let program = Program::compile(parse("x + y").as_ref());
+
+    let err = Vm::new().run(&program, &[Number::Int(1)]).unwrap_err();
+    assert!(matches!(err, EvalError::UnboundVariable(ref name) if name == "y"));
+
+    let env = Environment::new().with("y", 1);
+    let err = program.evaluate_with(&env).unwrap_err();
+    assert!(matches!(err, EvalError::UnboundVariable(ref name) if name == "x"));
+}
+
+// -----------------------------------------------------------------------------
+// Property tests.
+// -----------------------------------------------------------------------------
+
+/// Generates ASTs whose integer arithmetic cannot overflow: integers are at
+/// most 3 in magnitude, exponents are at most 3 and trees are at most 4 deep.
+fn arb_node() -> impl Strategy<Value = NodeBox> {
+    let leaf = prop_oneof![
+        (-3i128..=3).prop_map(|n| Box::new(PlainNode::new(Number::Int(n))) as NodeBox),
+        prop_oneof![
+            -10.0..10.0f64,
+            prop::sample::select(vec![0.0, -0.0, f64::NAN, f64::INFINITY]),
+        ]
+        .prop_map(|n| Box::new(PlainNode::new(Number::Flt(n))) as NodeBox),
+        prop::sample::select(vec!["x", "y"])
+            .prop_map(|n| Box::new(VariableNode::new(n)) as NodeBox),
+    ];
+    let exponent = prop_oneof![
+        (-2i128..=3).prop_map(|n| Box::new(PlainNode::new(Number::Int(n))) as NodeBox),
+        (-2.0..2.0f64).prop_map(|n| Box::new(PlainNode::new(Number::Flt(n))) as NodeBox),
+        prop::sample::select(vec!["x", "y"])
+            .prop_map(|n| Box::new(VariableNode::new(n)) as NodeBox),
+    ];
+    let binary = prop::sample::select(vec![
+        BinaryAction::Add,
+        BinaryAction::Sub,
+        BinaryAction::Mul,
+        BinaryAction::Div,
+    ]);
+    let unary = prop::sample::select(vec![
+        UnaryAction::Neg,
+        UnaryAction::Iden,
+        UnaryAction::Func(Function::Sin),
+        UnaryAction::Func(Function::Cos),
+        UnaryAction::Func(Function::Tan),
+        UnaryAction::Func(Function::Csc),
+        UnaryAction::Func(Function::Sec),
+        UnaryAction::Func(Function::Cot),
+        UnaryAction::Func(Function::Asin),
+        UnaryAction::Func(Function::Acos),
+        UnaryAction::Func(Function::Atan),
+        UnaryAction::Func(Function::Acsc),
+        UnaryAction::Func(Function::Asec),
+        UnaryAction::Func(Function::Acot),
+        UnaryAction::Func(Function::Ln),
+    ]);
+
+    leaf.prop_recursive(4, 32, 2, move |inner| {
+        prop_oneof![
+            (inner.clone(), binary.clone(), inner.clone())
+                .prop_map(|(l, a, r)| Box::new(BinaryNode::new(l, a, r)) as NodeBox),
+            (inner.clone(), exponent.clone()).prop_map(|(l, r)| {
+                Box::new(BinaryNode::new(l, BinaryAction::Pow, r)) as NodeBox
+            }),
+            (unary.clone(), inner).prop_map(|(a, o)| Box::new(UnaryNode::new(a, o)) as NodeBox),
+        ]
+    })
+}
+
+fn arb_number() -> impl Strategy<Value = Number> {
+    prop_oneof![
+        (-3i128..=3).prop_map(Number::Int),
+        prop_oneof![
+            -10.0..10.0f64,
+            prop::sample::select(vec![0.0, -0.0, f64::NAN, f64::NEG_INFINITY]),
+        ]
+        .prop_map(Number::Flt),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn test_vm_matches_tree(node in arb_node(), x in arb_number(), y in arb_number()) {
+        let env = Environment::new().with("x", x).with("y", y);
+
+        let expected = node.evaluate_with(&env).unwrap();
+        let actual = Program::compile(node.as_ref()).evaluate_with(&env).unwrap();
+
+        prop_assert!(
+            same(expected, actual),
+            "{:?}: tree gave {}, VM gave {}", node, expected, actual
+        );
+    }
+}